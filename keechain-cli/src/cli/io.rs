@@ -5,6 +5,9 @@ use console::Term;
 use dialoguer::{Confirm, Input, Password, Select};
 use keechain_core::Result;
 
+/// Max attempts to confirm a new password before aborting
+const PASSWORD_CONFIRMATION_ATTEMPTS: usize = 3;
+
 pub fn get_input<S>(prompt: S) -> Result<String>
 where
     S: Into<String>,
@@ -20,10 +23,44 @@ pub fn get_new_password() -> Result<String> {
     Ok(Password::new().with_prompt("New password").interact()?)
 }
 
+pub fn get_export_password() -> Result<String> {
+    Ok(Password::new().with_prompt("Export password").interact()?)
+}
+
 pub fn get_confirmation_password() -> Result<String> {
     Ok(Password::new().with_prompt("Confirm password").interact()?)
 }
 
+/// Ask to confirm `password`, retrying on mismatch (up to [`PASSWORD_CONFIRMATION_ATTEMPTS`] times)
+pub fn confirm_password(password: &str) -> Result<String> {
+    for attempt in 1..=PASSWORD_CONFIRMATION_ATTEMPTS {
+        let confirm_password: String = get_confirmation_password()?;
+        if confirm_password == password {
+            return Ok(confirm_password);
+        }
+        if attempt < PASSWORD_CONFIRMATION_ATTEMPTS {
+            println!("Passwords don't match, try again");
+        }
+    }
+    Err("Passwords don't match".into())
+}
+
+pub fn get_mnemonic() -> Result<String> {
+    Ok(Password::new().with_prompt("Seed").interact()?)
+}
+
+pub fn get_optional_passphrase() -> Result<Option<String>> {
+    let passphrase: String = Password::new()
+        .with_prompt("Passphrase (leave empty if none)")
+        .allow_empty_password(true)
+        .interact()?;
+    if passphrase.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(passphrase))
+    }
+}
+
 pub fn ask<S>(prompt: S) -> Result<bool>
 where
     S: Into<String> + std::marker::Copy,
@@ -3,25 +3,56 @@
 
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use keechain_core::bdk::miniscript::Descriptor;
+use keechain_core::bips::bip32::{DerivationPath, Fingerprint};
+use keechain_core::psbt::{DEFAULT_MAX_FEE_RATE, DEFAULT_MAX_INPUTS};
 use keechain_core::types::Index;
 
 pub mod io;
 
-use crate::types::{CliElectrumSupportedScripts, CliNetwork, CliWordCount};
+use crate::types::{
+    CliBip85Type, CliElectrumSupportedScripts, CliKdfProfile, CliNetwork, CliPsbtFormat,
+    CliPsbtSource, CliScript, CliSecretField, CliSeedQrFormat, CliSighash, CliWordCount,
+};
 
 #[derive(Debug, Parser)]
 #[command(name = "keechain")]
 #[clap(author, version, about, long_about = None)]
 pub struct Cli {
-    /// Network
-    #[clap(short, long, value_enum, default_value_t = CliNetwork::Bitcoin)]
-    pub network: CliNetwork,
+    /// Network (default: config file or bitcoin)
+    #[clap(short, long, value_enum)]
+    pub network: Option<CliNetwork>,
+    /// Data directory (default: config file or `~/.keechain`)
+    #[clap(long, global = true)]
+    pub datadir: Option<PathBuf>,
+    /// Rewrite keychains using a legacy encryption format with the current one when opened
+    #[clap(long, global = true)]
+    pub reencrypt_on_open: bool,
     #[command(subcommand)]
     pub command: Command,
 }
 
+/// Keychain selected by name or by master fingerprint
+#[derive(Debug, Clone, Args)]
+#[group(required = true, multiple = false)]
+pub struct KeychainArg {
+    /// Keychain name
+    pub name: Option<String>,
+    /// Select the keychain by master fingerprint instead of the name (see `list`)
+    #[arg(long, value_name = "FP")]
+    pub fingerprint: Option<Fingerprint>,
+}
+
+impl From<String> for KeychainArg {
+    fn from(name: String) -> Self {
+        Self {
+            name: Some(name),
+            fingerprint: None,
+        }
+    }
+}
+
 #[derive(Debug, Subcommand)]
 pub enum Command {
     /// Generate mnemonic (BIP39)
@@ -36,6 +67,18 @@ pub enum Command {
         /// Add entropy from dice roll
         #[arg(long, default_value_t = false)]
         dice_roll: bool,
+        /// Use ONLY the dice rolls as entropy (reproducible mnemonic, for auditing)
+        #[arg(long, default_value_t = false, requires = "dice_roll")]
+        dice_only_deterministic: bool,
+        /// Refuse to generate without enough dice rolls to cover the mnemonic entropy (mixed with OS and system entropy)
+        #[arg(long, conflicts_with = "dice_only_deterministic")]
+        require_dice: bool,
+        /// Generate `count` keychains named `<name>-0`..`<name>-<count - 1>`, with the same password (for test wallets)
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..), conflicts_with_all = ["dice_roll", "require_dice"])]
+        count: Option<u32>,
+        /// Key derivation strength of the keychain encryption (default: `KEECHAIN_KDF`, config file or balanced)
+        #[arg(long, visible_alias = "kdf-strength", value_enum)]
+        kdf_profile: Option<CliKdfProfile>,
     },
     /// Restore mnemonic (BIP39)
     #[command(arg_required_else_help = true)]
@@ -43,15 +86,37 @@ pub enum Command {
         /// Keychain name
         #[arg(required = true)]
         name: String,
+        /// Restore from hex encoded entropy (16, 24 or 32 bytes) instead of the mnemonic
+        #[arg(long)]
+        entropy: Option<String>,
+        /// Key derivation strength of the keychain encryption (default: `KEECHAIN_KDF`, config file or balanced)
+        #[arg(long, visible_alias = "kdf-strength", value_enum)]
+        kdf_profile: Option<CliKdfProfile>,
+    },
+    /// Check a written mnemonic (BIP39) without saving anything
+    CheckMnemonic {
+        /// Refuse weak passphrases (too short or a single character class, i.e. all lowercase)
+        #[arg(long)]
+        strict_passphrase: bool,
+    },
+    /// Check the password of a keychain, without opening it (exit code 3 if invalid)
+    ///
+    /// Exit codes: 0 if the password is valid, 3 if invalid, 1 on other errors (2 is used for usage errors).
+    #[command(arg_required_else_help = true)]
+    Verify {
+        #[command(flatten)]
+        keychain: KeychainArg,
     },
     /// List keychains
     List,
     /// View master fingerprint
     #[command(arg_required_else_help = true)]
     Identity {
-        /// Keychain name
-        #[arg(required = true)]
-        name: String,
+        #[command(flatten)]
+        keychain: KeychainArg,
+        /// Print only the fingerprint (for scripts)
+        #[arg(long)]
+        raw: bool,
     },
     /// Export
     #[command(arg_required_else_help = true)]
@@ -60,15 +125,123 @@ pub enum Command {
         #[command(subcommand)]
         export_type: ExportTypes,
     },
-    /// Decode PSBT
+    /// Import
     #[command(arg_required_else_help = true)]
-    Decode {
-        /// PSBT file
+    Import {
+        /// Type
+        #[command(subcommand)]
+        import_type: ImportTypes,
+    },
+    /// Decrypt a file exported with `--encrypt`
+    #[command(arg_required_else_help = true)]
+    DecryptExport {
+        /// Encrypted file
         #[arg(required = true)]
         file: PathBuf,
+        /// Output file (default: print the JSON)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Derive addresses
+    #[command(arg_required_else_help = true)]
+    Addresses {
+        #[command(flatten)]
+        keychain: KeychainArg,
+        /// Account number (default: keychain default or 0)
+        account: Option<u32>,
+        /// Number of addresses to derive (for each script type)
+        #[arg(long, default_value_t = 20)]
+        count: u32,
+        /// File with candidate addresses (one per line): print only the ones derivable from this keychain
+        #[arg(long)]
+        only_mine: Option<PathBuf>,
+        /// Non-standard coin type (recovery of wallets not using 0' for mainnet and 1' for test networks)
+        #[arg(long, value_parser = clap::value_parser!(u32).range(..0x8000_0000))]
+        coin_type: Option<u32>,
+    },
+    /// Show an address with its QR code, or list a range of addresses (`--count`)
+    #[command(arg_required_else_help = true)]
+    Address {
+        #[command(flatten)]
+        keychain: KeychainArg,
+        /// Address index (first index of the range with `--count`)
+        #[arg(long, visible_alias = "start", default_value_t = 0)]
+        index: u32,
+        /// Number of addresses to derive (the QR code is shown only for a single address)
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+        count: u32,
+        /// Account number (default: keychain default or 0)
+        #[arg(long)]
+        account: Option<u32>,
+        /// Script (default: keychain default or native-segwit)
+        #[arg(long, value_enum)]
+        script: Option<CliScript>,
+        /// Derive change addresses
+        #[arg(long)]
+        change: bool,
+    },
+    /// Verify if an address belongs to the keychain
+    #[command(arg_required_else_help = true)]
+    VerifyAddress {
+        /// Keychain name
+        #[arg(required = true)]
+        name: String,
+        /// Address
+        #[arg(required = true)]
+        address: String,
+        /// Account number (default: keychain default or 0)
+        account: Option<u32>,
+        /// Number of addresses to scan (for each script type and chain)
+        #[arg(long, default_value_t = 1000)]
+        max_index: u32,
+        /// Print the derivation path, script type and chain of the address (or the scanned range if not found)
+        #[arg(long)]
+        show_derivation_for_address: bool,
+        /// Print the result as JSON
+        #[arg(long)]
+        json: bool,
+        /// Non-standard coin type (recovery of wallets not using 0' for mainnet and 1' for test networks)
+        #[arg(long, value_parser = clap::value_parser!(u32).range(..0x8000_0000))]
+        coin_type: Option<u32>,
+    },
+    /// Show the standard derivations (account xpubs and first addresses)
+    #[command(arg_required_else_help = true)]
+    Tree {
+        #[command(flatten)]
+        keychain: KeychainArg,
+        /// Account number (default: keychain default or 0)
+        account: Option<u32>,
+    },
+    /// Decode PSBT
+    #[command(arg_required_else_help = true)]
+    Decode {
+        /// PSBT file (`-` for stdin, `file://` URLs are accepted)
+        #[arg(required_unless_present = "psbt")]
+        file: Option<CliPsbtSource>,
+        /// PSBT as base64, hex or UR string (i.e. scanned from a QR code), instead of the file
+        #[arg(long, conflicts_with_all = ["file", "ur"])]
+        psbt: Option<String>,
         /// Print base64
         #[clap(long)]
         base64: bool,
+        /// Print the predicted txid of the final transaction
+        #[arg(long)]
+        txid: bool,
+        /// Refuse PSBTs with more inputs than this
+        #[arg(long, default_value_t = DEFAULT_MAX_INPUTS)]
+        max_inputs: usize,
+        /// The file contains `ur:crypto-psbt` parts (separated by whitespaces or concatenated)
+        #[arg(long)]
+        ur: bool,
+        /// Warn if the fee rate (sat/vB) is higher than this
+        #[arg(long, default_value_t = DEFAULT_MAX_FEE_RATE)]
+        max_fee_rate: f64,
+        /// Keychain name, to mark the outputs as change (derived from the keychain) or payment
+        #[arg(long, conflicts_with = "base64")]
+        name: Option<String>,
+        /// Keychain master fingerprint, instead of the name
+        #[arg(long, value_name = "FP", conflicts_with_all = ["base64", "name"])]
+        fingerprint: Option<Fingerprint>,
     },
     /// Sign PSBT
     #[command(arg_required_else_help = true)]
@@ -76,11 +249,84 @@ pub enum Command {
         /// Keychain name
         #[arg(required = true)]
         name: String,
-        /// PSBT file
-        #[arg(required = true)]
-        file: PathBuf,
+        /// PSBT file (`-` for stdin, `file://` URLs are accepted: the signed PSBT of stdin is printed)
+        #[arg(required_unless_present = "psbt")]
+        file: Option<CliPsbtSource>,
         /// Descriptor (optional)
         descriptor: Option<Descriptor<String>>,
+        /// PSBT as base64, hex or UR string (i.e. scanned from a QR code), instead of the file (the signed PSBT is printed)
+        #[arg(long, conflicts_with_all = ["file", "no_rename"])]
+        psbt: Option<String>,
+        /// Sighash type (default: SIGHASH_ALL, requires `--allow-all-sighashes` for the others)
+        #[arg(long, value_enum)]
+        sighash: Option<CliSighash>,
+        /// Allow signing with sighash types other than SIGHASH_ALL (signatures may be reused in a different transaction)
+        #[arg(long)]
+        allow_all_sighashes: bool,
+        /// Signed PSBT encoding (default: binary for files, base64 for stdout)
+        #[arg(long, value_enum)]
+        output_format: Option<CliPsbtFormat>,
+        /// Print the signed PSBT instead of saving it to a file
+        #[arg(long)]
+        stdout: bool,
+        /// Overwrite the original file instead of saving to a new `-part-N`/`-finalized` file
+        #[arg(long, conflicts_with = "stdout")]
+        no_rename: bool,
+        /// Print the predicted txid of the final transaction
+        #[arg(long)]
+        txid: bool,
+        /// Refuse PSBTs with more inputs than this
+        #[arg(long, default_value_t = DEFAULT_MAX_INPUTS)]
+        max_inputs: usize,
+        /// Sign segwit v0 inputs without the previous transaction (exposes to fee-inflation attacks: use only if you trust the PSBT creator)
+        #[arg(long)]
+        trust_witness_utxo: bool,
+        /// Script type to use instead of inferring it from the derivation path purpose (for non-standard paths)
+        #[arg(long, value_enum)]
+        assume_script: Option<CliScript>,
+        /// Sign taproot inputs with the script path even if the key path is possible (reveals the spent script)
+        #[arg(long)]
+        force_script_path: bool,
+    },
+    /// Sign a message with the key of a taproot or native segwit address (BIP322)
+    #[command(arg_required_else_help = true)]
+    SignMessage {
+        /// Keychain name
+        #[arg(required = true)]
+        name: String,
+        /// Message
+        #[arg(required = true)]
+        message: String,
+        /// Account number (default: keychain default or 0)
+        #[arg(long)]
+        account: Option<u32>,
+        /// Address index
+        #[arg(long, default_value_t = 0)]
+        index: u32,
+        /// Use the change address at `index`
+        #[arg(long)]
+        change: bool,
+        /// Derivation path of the key (P2TR `m/86'/..` or P2WPKH `m/84'/..`)
+        #[arg(long, conflicts_with_all = ["account", "index", "change"])]
+        path: Option<DerivationPath>,
+    },
+    /// Verify a BIP322 message signature (P2TR or P2WPKH addresses)
+    #[command(arg_required_else_help = true)]
+    VerifyMessage {
+        /// Address
+        #[arg(required = true)]
+        address: String,
+        /// Message
+        #[arg(required = true)]
+        message: String,
+        /// Signature (base64)
+        #[arg(required = true)]
+        signature: String,
+    },
+    /// PSBT utilities
+    Psbt {
+        #[command(subcommand)]
+        command: PsbtCommand,
     },
     /// Advanced
     Advanced {
@@ -92,6 +338,86 @@ pub enum Command {
         #[command(subcommand)]
         command: SettingCommand,
     },
+    /// Config file (`~/.keechain/config.json`)
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Run known-answer tests (encryption, BIP39, BIP32 and BIP85), without touching the keychains
+    SelfTest,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PsbtCommand {
+    /// Join PSBTs with different inputs and outputs (BIP174 joiner)
+    #[command(arg_required_else_help = true)]
+    Join {
+        /// PSBT files
+        #[arg(required = true, num_args = 2..)]
+        files: Vec<PathBuf>,
+        /// Output file
+        #[arg(short, long, required = true)]
+        output: PathBuf,
+    },
+    /// Combine PSBTs of the same transaction signed by different cosigners (BIP174 combiner)
+    #[command(arg_required_else_help = true)]
+    Combine {
+        /// PSBT files
+        #[arg(required = true, num_args = 2..)]
+        files: Vec<PathBuf>,
+        /// Output file
+        #[arg(short, long, required = true)]
+        output: PathBuf,
+    },
+    /// Finalize a fully signed PSBT and save the raw transaction (hex)
+    #[command(arg_required_else_help = true)]
+    Finalize {
+        /// PSBT file
+        #[arg(required = true)]
+        file: PathBuf,
+        /// Output file (default: PSBT file with `.tx` extension)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Finalize (if needed) a fully signed PSBT and print the raw transaction (hex), ready to broadcast
+    #[command(arg_required_else_help = true)]
+    ExtractTx {
+        /// PSBT file or PSBT as base64, hex or UR string
+        #[arg(required = true)]
+        psbt: String,
+        /// Print also the txid
+        #[arg(long)]
+        txid: bool,
+    },
+    /// Show which roles (BIP174) acted on a PSBT and what comes next
+    #[command(arg_required_else_help = true)]
+    Role {
+        /// PSBT file or PSBT as base64, hex or UR string
+        #[arg(required = true)]
+        psbt: String,
+    },
+    /// Add the missing UTXOs of the inputs spending a previous transaction
+    #[command(arg_required_else_help = true)]
+    AddUtxo {
+        /// PSBT file
+        #[arg(required = true)]
+        psbt_file: PathBuf,
+        /// Previous transaction (hex or file)
+        #[arg(long, required = true)]
+        prev_tx: String,
+        /// Output file (default: overwrite the PSBT file)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Describe a multisig descriptor (threshold, cosigners and position of this keychain)
+    #[command(arg_required_else_help = true)]
+    DescribeMultisig {
+        /// Descriptor file
+        #[arg(required = true)]
+        descriptor_file: PathBuf,
+        #[command(flatten)]
+        keychain: KeychainArg,
+    },
 }
 
 #[derive(Debug, Subcommand)]
@@ -102,12 +428,27 @@ pub enum AdvancedCommand {
         /// Keychain name
         #[arg(required = true)]
         name: String,
-        /// Word count
+        /// Word count (only for mnemonic, ignored by the other applications)
         #[arg(required = true, value_enum)]
         word_count: CliWordCount,
         /// Index (must be between 0 and 2^31 - 1)
         #[arg(required = true)]
         index: Index,
+        /// Application
+        #[arg(long = "type", value_enum, default_value_t = CliBip85Type::Mnemonic)]
+        bip85_type: CliBip85Type,
+        /// Number of bytes, between 16 and 64 (only for hex)
+        #[arg(long, default_value_t = 64)]
+        num_bytes: usize,
+        /// Print also the BIP85 derivation path (to reproduce it with other BIP85 tools)
+        #[arg(long)]
+        print_path: bool,
+    },
+    /// Export the raw 64-byte BIP39 seed (hex), for tools that don't import a mnemonic
+    #[command(arg_required_else_help = true)]
+    ExportSeed {
+        #[command(flatten)]
+        keychain: KeychainArg,
     },
     /// Danger
     Danger {
@@ -121,16 +462,17 @@ pub enum DangerCommand {
     /// View secrets
     #[command(arg_required_else_help = true)]
     ViewSecrets {
-        /// Keychain name
-        #[arg(required = true)]
-        name: String,
+        #[command(flatten)]
+        keychain: KeychainArg,
+        /// Fields to show (comma separated, default: all)
+        #[arg(long, value_enum, value_delimiter = ',')]
+        fields: Vec<CliSecretField>,
     },
     /// Delete keychain
     #[command(arg_required_else_help = true)]
     Wipe {
-        /// Keychain name
-        #[arg(required = true)]
-        name: String,
+        #[command(flatten)]
+        keychain: KeychainArg,
     },
 }
 
@@ -149,10 +491,33 @@ pub enum SettingCommand {
     /// Change keychain password
     #[command(arg_required_else_help = true)]
     ChangePassword {
-        /// Keychain name
-        #[arg(required = true)]
-        name: String,
+        #[command(flatten)]
+        keychain: KeychainArg,
+        /// Re-encrypt with another key derivation strength (default: `KEECHAIN_KDF`, config file or keep the current one)
+        #[arg(long, visible_alias = "kdf-strength", value_enum)]
+        kdf_profile: Option<CliKdfProfile>,
     },
+    /// View or set the default account and script (used when not specified)
+    #[command(arg_required_else_help = true)]
+    Defaults {
+        #[command(flatten)]
+        keychain: KeychainArg,
+        /// Default account
+        #[arg(long)]
+        account: Option<u32>,
+        /// Default script
+        #[arg(long, value_enum)]
+        script: Option<CliScript>,
+        /// Remove the defaults
+        #[arg(long, conflicts_with_all = ["account", "script"])]
+        clear: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Print the effective config (config file, environment variables and flags)
+    Show,
 }
 
 #[derive(Debug, Subcommand)]
@@ -160,41 +525,276 @@ pub enum ExportTypes {
     /// Export descriptors
     #[command(arg_required_else_help = true)]
     Descriptors {
-        /// Keychain name
-        #[arg(required = true)]
-        name: String,
-        /// Account number
-        #[arg(default_value_t = 0)]
-        account: u32,
+        #[command(flatten)]
+        keychain: KeychainArg,
+        /// Account number (default: keychain default or 0)
+        account: Option<u32>,
+        /// Export a single multipath descriptor (`<0;1>`) for each script, instead of separate receive and change descriptors
+        #[arg(long)]
+        combine_into_descriptor_wallet: bool,
+        /// Export only the receive (external) descriptors
+        #[arg(long, conflicts_with_all = ["internal_only", "combine_into_descriptor_wallet"])]
+        external_only: bool,
+        /// Export only the change (internal) descriptors
+        #[arg(long, conflicts_with = "combine_into_descriptor_wallet")]
+        internal_only: bool,
+        /// Print a summary of the derivation used
+        #[arg(long)]
+        derivation_summary: bool,
+        /// Non-standard coin type (recovery of wallets not using 0' for mainnet and 1' for test networks)
+        #[arg(long, value_parser = clap::value_parser!(u32).range(..0x8000_0000), conflicts_with = "derivation_summary")]
+        coin_type: Option<u32>,
+        /// Export only the descriptors of this script (default: all)
+        #[arg(long, value_enum)]
+        script: Option<CliScript>,
+        /// Copy the descriptors to the clipboard (cleared after 30 seconds) instead of printing them
+        #[arg(long)]
+        to_clipboard: bool,
+        /// Print also the account as `ur:crypto-account` parts (for animated QR codes)
+        #[arg(long, conflicts_with_all = ["coin_type", "to_clipboard"])]
+        ur: bool,
+        /// Print a QR code of each descriptor
+        #[arg(long, conflicts_with = "to_clipboard")]
+        qr: bool,
     },
     /// Export Bitcoin Core descriptors
     #[command(arg_required_else_help = true)]
     BitcoinCore {
-        /// Keychain name
-        #[arg(required = true)]
-        name: String,
-        /// Account number
-        #[arg(default_value_t = 0)]
-        account: u32,
+        #[command(flatten)]
+        keychain: KeychainArg,
+        /// Account number (default: keychain default or 0)
+        account: Option<u32>,
+        /// Print a summary of the derivation used
+        #[arg(long)]
+        derivation_summary: bool,
+        /// Copy the descriptors to the clipboard (cleared after 30 seconds) instead of printing them
+        #[arg(long)]
+        to_clipboard: bool,
+    },
+    /// Export BIP48 P2WSH multisig descriptors (`wsh(sortedmulti(..))`) with the cosigners keys
+    #[command(arg_required_else_help = true)]
+    Multisig {
+        #[command(flatten)]
+        keychain: KeychainArg,
+        /// Account number (default: keychain default or 0)
+        account: Option<u32>,
+        /// Required signatures
+        #[arg(long, required = true)]
+        threshold: usize,
+        /// Cosigner account key with origin (`[fingerprint/48'/0'/0'/2']xpub...`)
+        #[arg(long = "cosigner", required = true)]
+        cosigners: Vec<String>,
+    },
+    /// Export BSMS signer key record (BIP129), for multisig coordinators (i.e. Nunchuk)
+    #[command(arg_required_else_help = true)]
+    Bsms {
+        #[command(flatten)]
+        keychain: KeychainArg,
+        /// Account number (default: keychain default or 0)
+        account: Option<u32>,
+        /// Token provided by the coordinator (hex, 8 or 16 bytes)
+        #[arg(long)]
+        token: Option<String>,
+        /// Key description (default: keychain name)
+        #[arg(long)]
+        description: Option<String>,
+        /// Output directory (default: `KEECHAIN_EXPORT_DIR` or home directory)
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// Print the file content instead of saving it
+        #[arg(long, conflicts_with = "out")]
+        stdout: bool,
     },
     /// Export Electrum file
     #[command(arg_required_else_help = true)]
     Electrum {
-        /// Keychain name
-        #[arg(required = true)]
-        name: String,
-        /// Script
-        #[arg(value_enum, default_value_t = CliElectrumSupportedScripts::NativeSegwit)]
-        script: CliElectrumSupportedScripts,
-        /// Account number
-        #[arg(default_value_t = 0)]
-        account: u32,
+        #[command(flatten)]
+        keychain: KeychainArg,
+        /// Script (default: keychain default or native-segwit)
+        #[arg(value_enum)]
+        script: Option<CliElectrumSupportedScripts>,
+        /// Account number (default: keychain default or 0)
+        account: Option<u32>,
+        /// Print a summary of the derivation used
+        #[arg(long)]
+        derivation_summary: bool,
+        /// Output directory (default: `KEECHAIN_EXPORT_DIR` or home directory)
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// Encrypt the file with a new password (saved with the `.enc` extension, see `decrypt-export`)
+        #[arg(long)]
+        encrypt: bool,
+        /// Print the file content instead of saving it
+        #[arg(long, conflicts_with_all = ["out", "derivation_summary", "encrypt"])]
+        stdout: bool,
+    },
+    /// Export Sparrow Wallet file (watch-only)
+    #[command(arg_required_else_help = true)]
+    Sparrow {
+        #[command(flatten)]
+        keychain: KeychainArg,
+        /// Script (default: keychain default or native-segwit)
+        #[arg(value_enum)]
+        script: Option<CliScript>,
+        /// Account number (default: keychain default or 0)
+        account: Option<u32>,
+        /// Print a summary of the derivation used
+        #[arg(long)]
+        derivation_summary: bool,
+        /// Output directory (default: `KEECHAIN_EXPORT_DIR` or home directory)
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// Print the file content instead of saving it
+        #[arg(long, conflicts_with_all = ["out", "derivation_summary"])]
+        stdout: bool,
+    },
+    /// Export Passport file (Foundation)
+    #[command(arg_required_else_help = true)]
+    Passport {
+        #[command(flatten)]
+        keychain: KeychainArg,
+        /// Account number (default: keychain default or 0)
+        account: Option<u32>,
+        /// Print a summary of the derivation used
+        #[arg(long)]
+        derivation_summary: bool,
+        /// Output directory (default: `KEECHAIN_EXPORT_DIR` or home directory)
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// Print the file content instead of saving it
+        #[arg(long, conflicts_with_all = ["out", "derivation_summary"])]
+        stdout: bool,
+    },
+    /// Print a summary of the account xpubs (Coldcard style), to store alongside the seed backup
+    #[command(arg_required_else_help = true)]
+    Summary {
+        #[command(flatten)]
+        keychain: KeychainArg,
+        /// Account number (default: keychain default or 0)
+        account: Option<u32>,
+    },
+    /// Print the mnemonic as SeedQR payload (SeedSigner)
+    #[command(name = "seedqr", arg_required_else_help = true)]
+    SeedQr {
+        #[command(flatten)]
+        keychain: KeychainArg,
+        /// SeedQR format (not interchangeable between devices)
+        #[arg(long, value_enum, default_value_t = CliSeedQrFormat::Standard)]
+        format: CliSeedQrFormat,
+    },
+    /// Print the silent payment address (BIP352)
+    #[command(arg_required_else_help = true)]
+    SilentPayment {
+        #[command(flatten)]
+        keychain: KeychainArg,
+        /// Account number (default: keychain default or 0)
+        account: Option<u32>,
+        /// Also print the scan private key (lets a watch-only wallet detect incoming payments, not spend them)
+        #[arg(long)]
+        scan_key: bool,
+    },
+    /// Check that the Electrum and Bitcoin Core exports describe the same keys
+    #[command(arg_required_else_help = true)]
+    Verify {
+        #[command(flatten)]
+        keychain: KeychainArg,
+        /// Account number (default: keychain default or 0)
+        account: Option<u32>,
     },
     /// Export Wasabi file
     #[command(arg_required_else_help = true)]
     Wasabi {
-        /// Keychain name
+        #[command(flatten)]
+        keychain: KeychainArg,
+        /// Account number (default: keychain default or 0)
+        account: Option<u32>,
+        /// Print a summary of the derivation used
+        #[arg(long)]
+        derivation_summary: bool,
+        /// Output directory (default: `KEECHAIN_EXPORT_DIR` or home directory)
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// Encrypt the file with a new password (saved with the `.enc` extension, see `decrypt-export`)
+        #[arg(long)]
+        encrypt: bool,
+        /// Print the file content instead of saving it
+        #[arg(long, conflicts_with_all = ["out", "derivation_summary", "encrypt"])]
+        stdout: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ImportTypes {
+    /// Read the account key of an Electrum wallet file and print its descriptors (watch-only)
+    #[command(arg_required_else_help = true)]
+    Electrum {
+        /// Electrum wallet file (`standard` wallet, not encrypted)
         #[arg(required = true)]
-        name: String,
+        file: PathBuf,
     },
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_advanced_derive_word_count_positional() {
+        let cli =
+            Cli::try_parse_from(["keechain", "advanced", "derive", "name", "12", "5"]).unwrap();
+        match cli.command {
+            Command::Advanced {
+                command:
+                    AdvancedCommand::Derive {
+                        word_count,
+                        index,
+                        bip85_type,
+                        ..
+                    },
+            } => {
+                assert!(matches!(word_count, CliWordCount::W12));
+                assert_eq!(index.as_u32(), 5);
+                assert_eq!(bip85_type, CliBip85Type::Mnemonic);
+            }
+            command => panic!("Unexpected command: {command:?}"),
+        }
+
+        // The word count can't be omitted
+        assert!(Cli::try_parse_from(["keechain", "advanced", "derive", "name", "5"]).is_err());
+    }
+
+    #[test]
+    fn test_keychain_by_fingerprint() {
+        let cli =
+            Cli::try_parse_from(["keechain", "identity", "--fingerprint", "73c5da0a"]).unwrap();
+        match cli.command {
+            Command::Identity { keychain, .. } => {
+                assert_eq!(keychain.name, None);
+                assert_eq!(
+                    keychain.fingerprint,
+                    Some(Fingerprint::from_str("73c5da0a").unwrap())
+                );
+            }
+            command => panic!("Unexpected command: {command:?}"),
+        }
+
+        let cli = Cli::try_parse_from(["keechain", "identity", "name"]).unwrap();
+        match cli.command {
+            Command::Identity { keychain, .. } => {
+                assert_eq!(keychain.name.as_deref(), Some("name"));
+                assert_eq!(keychain.fingerprint, None);
+            }
+            command => panic!("Unexpected command: {command:?}"),
+        }
+
+        // Name and fingerprint conflict, and one of them is required
+        assert!(
+            Cli::try_parse_from(["keechain", "identity", "name", "--fingerprint", "73c5da0a"])
+                .is_err()
+        );
+        assert!(Cli::try_parse_from(["keechain", "identity", "--raw"]).is_err());
+        assert!(Cli::try_parse_from(["keechain", "identity", "--fingerprint", "name"]).is_err());
+    }
+}
@@ -0,0 +1,319 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Config file (`~/.keechain/config.json`)
+//!
+//! Precedence: CLI flags, then environment variables, then the config file.
+//!
+//! There is no auto-lock timeout: each command opens the keychain and exits, so no unlocked
+//! session is left to lock.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use clap::ValueEnum;
+use keechain_common::EXPORT_DIR_ENV;
+use keechain_core::bitcoin::Network;
+use keechain_core::crypto::{KdfParams, KdfProfile};
+use keechain_core::types::KeychainDefaults;
+use keechain_core::{Purpose, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::types::{CliKdfProfile, CliNetwork, CliScript};
+
+/// Environment variable with the default network
+pub const NETWORK_ENV: &str = "KEECHAIN_NETWORK";
+/// Environment variable with the data directory (where the `keychains` directory lives)
+pub const DATADIR_ENV: &str = "KEECHAIN_DATADIR";
+/// Environment variable with the default script type
+pub const SCRIPT_ENV: &str = "KEECHAIN_SCRIPT";
+/// Environment variable with the key derivation of the new encryptions (`<memory_kib>,<iterations>,<parallelism>`)
+pub const KDF_ENV: &str = "KEECHAIN_KDF";
+
+/// Argon2id parameters used to create keychains and change their password
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KdfConfig {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl From<KdfConfig> for KdfParams {
+    fn from(value: KdfConfig) -> Self {
+        Self {
+            memory_kib: value.memory_kib,
+            iterations: value.iterations,
+            parallelism: value.parallelism,
+        }
+    }
+}
+
+/// `<memory_kib>,<iterations>,<parallelism>`
+impl FromStr for KdfConfig {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values: Vec<u32> = s
+            .split(',')
+            .map(|value| value.trim().parse::<u32>())
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.to_string())?;
+        match values.as_slice() {
+            [memory_kib, iterations, parallelism] => Ok(Self {
+                memory_kib: *memory_kib,
+                iterations: *iterations,
+                parallelism: *parallelism,
+            }),
+            _ => Err(String::from(
+                "expected `<memory_kib>,<iterations>,<parallelism>`",
+            )),
+        }
+    }
+}
+
+/// Settings from a single source (missing values are taken from the next one)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PartialConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<CliNetwork>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub datadir: Option<PathBuf>,
+    /// Used when the keychain has no default script
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub script: Option<CliScript>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub export_dir: Option<PathBuf>,
+    /// Used when `--kdf-profile` is not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kdf: Option<KdfConfig>,
+}
+
+impl PartialConfig {
+    /// Read the config file, if exists
+    pub fn from_file<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path: &Path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content: String = fs::read_to_string(path)?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid config file {}: {e}", path.display()).into())
+    }
+
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|key| std::env::var(key).ok())
+    }
+
+    fn from_vars<F>(var: F) -> Result<Self>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        // Empty variables are considered unset
+        let var = |key: &str| var(key).filter(|value| !value.is_empty());
+        Ok(Self {
+            network: var(NETWORK_ENV)
+                .map(|value| parse_value(NETWORK_ENV, &value))
+                .transpose()?,
+            datadir: var(DATADIR_ENV).map(PathBuf::from),
+            script: var(SCRIPT_ENV)
+                .map(|value| parse_value(SCRIPT_ENV, &value))
+                .transpose()?,
+            export_dir: var(EXPORT_DIR_ENV).map(PathBuf::from),
+            kdf: var(KDF_ENV)
+                .map(|value| {
+                    KdfConfig::from_str(&value)
+                        .map_err(|e| format!("Invalid {KDF_ENV} value: {value} ({e})"))
+                })
+                .transpose()?,
+        })
+    }
+
+    /// Fill the missing values with the ones of `other`
+    pub fn or(self, other: Self) -> Self {
+        Self {
+            network: self.network.or(other.network),
+            datadir: self.datadir.or(other.datadir),
+            script: self.script.or(other.script),
+            export_dir: self.export_dir.or(other.export_dir),
+            kdf: self.kdf.or(other.kdf),
+        }
+    }
+}
+
+fn parse_value<T>(key: &str, value: &str) -> Result<T>
+where
+    T: ValueEnum,
+{
+    T::from_str(value, true).map_err(|_| format!("Invalid {key} value: {value}").into())
+}
+
+/// Account and script used by a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Settings {
+    pub account: u32,
+    /// `None` if set nowhere (each command has its own fallback)
+    pub script: Option<Purpose>,
+}
+
+/// Effective config
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Config {
+    pub network: CliNetwork,
+    pub datadir: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub script: Option<CliScript>,
+    pub export_dir: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kdf: Option<KdfConfig>,
+}
+
+impl Config {
+    /// Merge the CLI `flags` with the environment variables and the config file
+    pub fn load(flags: PartialConfig) -> Result<Self> {
+        let file = PartialConfig::from_file(keechain_common::config_file()?)?;
+        let partial: PartialConfig = flags.or(PartialConfig::from_env()?).or(file);
+        Ok(Self {
+            network: partial.network.unwrap_or(CliNetwork::Bitcoin),
+            datadir: match partial.datadir {
+                Some(datadir) => datadir,
+                None => keechain_common::keechain()?,
+            },
+            script: partial.script,
+            export_dir: partial.export_dir.unwrap_or_else(keechain_common::home),
+            kdf: partial.kdf,
+        })
+    }
+
+    pub fn network(&self) -> Network {
+        self.network.into()
+    }
+
+    pub fn script(&self) -> Option<Purpose> {
+        self.script.map(|s| s.into())
+    }
+
+    /// Account and script: the flags, then the keychain `defaults`, then the config (account `0` if not set)
+    pub fn settings(
+        &self,
+        defaults: KeychainDefaults,
+        account: Option<u32>,
+        script: Option<Purpose>,
+    ) -> Settings {
+        Settings {
+            account: account.or(defaults.account).unwrap_or_default(),
+            script: script.or(defaults.purpose).or_else(|| self.script()),
+        }
+    }
+
+    /// Key derivation of the new encryptions: the `profile` flag, then the config `kdf`
+    pub fn kdf_params(&self, profile: Option<CliKdfProfile>) -> Option<KdfParams> {
+        match profile {
+            Some(profile) => Some(KdfProfile::from(profile).params()),
+            None => self.kdf.map(KdfParams::from),
+        }
+    }
+
+    /// Keychains directory inside the data directory
+    pub fn keychains(&self) -> Result<PathBuf> {
+        Ok(keechain_common::keychains_in(&self.datadir)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_config_precedence() {
+        let flags = PartialConfig {
+            network: Some(CliNetwork::Testnet),
+            ..Default::default()
+        };
+        let env = PartialConfig::from_vars(|key| match key {
+            NETWORK_ENV => Some(String::from("signet")),
+            SCRIPT_ENV => Some(String::from("tr")),
+            DATADIR_ENV => Some(String::new()),
+            KDF_ENV => Some(String::from("65536, 3, 1")),
+            _ => None,
+        })
+        .unwrap();
+        let file: PartialConfig = serde_json::from_str(
+            r#"{"network": "regtest", "datadir": "/tmp/keechain", "script": "wpkh", "export_dir": "/tmp/exports", "kdf": {"memory_kib": 8192, "iterations": 1, "parallelism": 1}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            flags.or(env).or(file),
+            PartialConfig {
+                network: Some(CliNetwork::Testnet),
+                datadir: Some(PathBuf::from("/tmp/keechain")),
+                script: Some(CliScript::Taproot),
+                export_dir: Some(PathBuf::from("/tmp/exports")),
+                kdf: Some(KdfConfig {
+                    memory_kib: 65536,
+                    iterations: 3,
+                    parallelism: 1,
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn test_settings_precedence() {
+        let config = Config {
+            network: CliNetwork::Bitcoin,
+            datadir: PathBuf::from("/tmp/keechain"),
+            script: Some(CliScript::Taproot),
+            export_dir: PathBuf::from("/tmp/exports"),
+            kdf: None,
+        };
+        let defaults = KeychainDefaults {
+            account: Some(2),
+            purpose: Some(Purpose::BIP49),
+        };
+
+        assert_eq!(
+            config.settings(defaults, Some(1), Some(Purpose::BIP44)),
+            Settings {
+                account: 1,
+                script: Some(Purpose::BIP44)
+            }
+        );
+        assert_eq!(
+            config.settings(defaults, None, None),
+            Settings {
+                account: 2,
+                script: Some(Purpose::BIP49)
+            }
+        );
+        assert_eq!(
+            config.settings(KeychainDefaults::default(), None, None),
+            Settings {
+                account: 0,
+                script: Some(Purpose::BIP86)
+            }
+        );
+    }
+
+    #[test]
+    fn test_partial_config_invalid() {
+        assert!(PartialConfig::from_vars(|key| match key {
+            NETWORK_ENV => Some(String::from("mainnet")),
+            _ => None,
+        })
+        .is_err());
+        assert!(PartialConfig::from_vars(|key| match key {
+            KDF_ENV => Some(String::from("65536,3")),
+            _ => None,
+        })
+        .is_err());
+        assert!(serde_json::from_str::<PartialConfig>(r#"{"netwrok": "testnet"}"#).is_err());
+        assert!(serde_json::from_str::<PartialConfig>(r#"{"kdf": {"memory_kib": 8192}}"#).is_err());
+    }
+}
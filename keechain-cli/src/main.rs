@@ -1,46 +1,143 @@
 // Copyright (c) 2022-2023 Yuki Kishimoto
 // Distributed under the MIT software license
 
+use std::fs;
 use std::path::PathBuf;
 use std::str::FromStr;
 
 use clap::Parser;
-use console::Term;
-use keechain_core::bips::bip39::Mnemonic;
+use console::{style, Term};
+use keechain_core::bips::bip32::{
+    Bip32, DerivationPath, ExtendedPrivKey, ExtendedPubKey, Fingerprint,
+};
+use keechain_core::bips::bip352::Bip352;
+use keechain_core::bips::bip39::{self, EntropyMode, Language, Mnemonic};
+use keechain_core::bips::bip48::ScriptType;
+use keechain_core::bips::bip85::{self, Bip85};
 use keechain_core::bitcoin::psbt::PartiallySignedTransaction;
 use keechain_core::bitcoin::secp256k1::Secp256k1;
-use keechain_core::bitcoin::Network;
-use keechain_core::util::dir;
-use keechain_core::{BitcoinCore, Electrum, KeeChain, PsbtUtility, Result, Wasabi};
+use keechain_core::bitcoin::{consensus, Address, Network, Transaction};
+use keechain_core::crypto::KdfParams;
+use keechain_core::descriptors::{
+    self, summarize_multisig, DerivedAddress, MultisigInfo, ScanResult,
+};
+use keechain_core::export::{self, Bsms, SeedQr};
+use keechain_core::miniscript::descriptor::{Descriptor, DescriptorPublicKey};
+use keechain_core::psbt::{
+    self, PsbtFormat, PsbtRole, PsbtRoles, SigningOptions, DEFAULT_MAX_FEE_RATE,
+};
+use keechain_core::self_test;
+use keechain_core::types::{seed, KeychainDefaults};
+use keechain_core::ur;
+use keechain_core::util::passphrase::{self, PassphraseStrength, MIN_PASSPHRASE_LEN};
+use keechain_core::util::{dir, hex};
+use keechain_core::{
+    BitcoinCore, Electrum, ElectrumSupportedScripts, KeeChain, Passport, PsbtUtility, Purpose,
+    Result, Seed, Sparrow, SparrowScriptType, Summary, WalletExport, Wasabi, WordCount,
+};
 
 mod cli;
+mod config;
 mod types;
 mod util;
 
 use self::cli::io;
-use self::cli::{AdvancedCommand, Cli, Command, DangerCommand, ExportTypes, SettingCommand};
+use self::cli::{
+    AdvancedCommand, Cli, Command, ConfigCommand, DangerCommand, ExportTypes, ImportTypes,
+    KeychainArg, PsbtCommand, SettingCommand,
+};
+use self::config::{Config, PartialConfig, Settings};
+use self::types::{CliBip85Type, CliPsbtFormat, CliPsbtSource};
+use self::util::Session;
+
+/// Exit code of `verify` for an invalid password (`2` is used by clap for usage errors)
+const INVALID_PASSWORD_EXIT_CODE: i32 = 3;
+
+const DESCRIPTORS_PURPOSES: [Purpose; 4] = [
+    Purpose::BIP44,
+    Purpose::BIP49,
+    Purpose::BIP84,
+    Purpose::BIP86,
+];
 
 fn main() -> Result<()> {
     let args = Cli::parse();
     let secp = Secp256k1::new();
-    let network: Network = args.network.into();
-    let keychain_path: PathBuf = keechain_common::keychains()?;
+    let config = Config::load(PartialConfig {
+        network: args.network,
+        datadir: args.datadir,
+        ..Default::default()
+    })?;
+    let network: Network = config.network();
+    let keychain_path: PathBuf = config.keychains()?;
+    let reencrypt_on_open: bool = args.reencrypt_on_open;
+    let open = |keychain: KeychainArg| -> Result<Session> {
+        Session::open(&keychain_path, keychain, network, reencrypt_on_open, &secp)
+    };
 
     match args.command {
         Command::Generate {
-            name,
+            keychain,
             word_count,
             dice_roll,
+            dice_only_deterministic,
+            require_dice,
+            count,
+            kdf_profile,
         } => {
+            keechain_common::ensure_writable(&keychain_path)?;
+            let word_count: WordCount = word_count.into();
+            let kdf_params: KdfParams = config.kdf_params(kdf_profile).unwrap_or_default();
+
+            if let Some(count) = count {
+                let names: Vec<String> = (0..count).map(|i| format!("{name}-{i}")).collect();
+                let existing: Vec<String> = dir::get_keychains_list(&keychain_path)?;
+                if let Some(name) = names.iter().find(|name| existing.contains(name)) {
+                    return Err(format!("Keychain `{name}` already exists").into());
+                }
+
+                let password: String = io::get_password()?;
+                let confirm_password: String = io::confirm_password(&password)?;
+                let mut generated: Vec<(String, Fingerprint)> = Vec::with_capacity(names.len());
+                for name in names.into_iter() {
+                    let keechain = KeeChain::generate_with_kdf_params(
+                        &keychain_path,
+                        name.clone(),
+                        || Ok(password.clone()),
+                        || Ok(confirm_password.clone()),
+                        word_count,
+                        || Ok(None),
+                        EntropyMode::Mixed,
+                        kdf_params,
+                        network,
+                        &secp,
+                    )?;
+                    generated.push((name, keechain.identity()));
+                }
+                util::print_generated_keychains(&generated);
+                return Ok(());
+            }
+
             let password: String = io::get_password()?;
-            let keechain = KeeChain::generate(
-                keychain_path,
+            let keechain = KeeChain::generate_with_kdf_params(
+                &keychain_path,
                 name,
                 || Ok(password.clone()),
-                io::get_confirmation_password,
-                word_count.into(),
+                || io::confirm_password(&password),
+                word_count,
                 || {
-                    if dice_roll {
+                    if dice_roll || require_dice {
+                        if dice_only_deterministic {
+                            println!(
+                                "Deterministic mode: roll the dice at least {} times",
+                                bip39::min_dice_rolls(word_count)
+                            );
+                        } else if require_dice {
+                            println!(
+                                "Dice required: roll the dice at least {} times",
+                                bip39::min_dice_rolls(word_count)
+                            );
+                        }
                         let term = Term::stdout();
                         let mut rolls: Vec<u8> = Vec::new();
                         io::select_dice_roll(term, &mut rolls)?;
@@ -49,105 +146,707 @@ fn main() -> Result<()> {
                         Ok(None)
                     }
                 },
+                if dice_only_deterministic {
+                    EntropyMode::Deterministic
+                } else if require_dice {
+                    EntropyMode::RequireCustom
+                } else {
+                    EntropyMode::Mixed
+                },
+                kdf_params,
                 network,
                 &secp,
             )?;
 
+            let mnemonic: Mnemonic = keechain.keychain(password)?.mnemonic();
             println!("\n!!! WRITE DOWN YOUT SEED PHRASE !!!");
             println!("\n################################################################\n");
-            println!("{}", keechain.keychain(password)?.seed.mnemonic());
+            println!("{mnemonic}");
             println!("\n################################################################\n");
+            println!("Language: {}", mnemonic.language());
+            println!(
+                "Word count: {} ({} bits of entropy)",
+                mnemonic.word_count(),
+                bip39::entropy_bits(&mnemonic)
+            );
+            println!("Write down the language too: it's required to restore the keychain");
+            println!("Encryption key derivation: {}", keechain.kdf_params());
 
             Ok(())
         }
-        Command::Restore { name } => {
-            KeeChain::restore(
-                keychain_path,
+        Command::Restore {
+            keychain,
+            entropy,
+            kdf_profile,
+        } => {
+            keechain_common::ensure_writable(&keychain_path)?;
+            // Check the entropy before asking anything
+            let mnemonic: Option<Mnemonic> =
+                entropy.map(seed::mnemonic_from_hex_entropy).transpose()?;
+            let password: String = io::get_password()?;
+            let keechain = KeeChain::restore_with_kdf_params(
+                &keychain_path,
                 name,
-                io::get_password,
-                io::get_confirmation_password,
-                || Ok(Mnemonic::from_str(&io::get_input("Seed")?)?),
+                || Ok(password.clone()),
+                || io::confirm_password(&password),
+                || match mnemonic {
+                    Some(mnemonic) => Ok(mnemonic),
+                    None => Ok(seed::parse_mnemonic(
+                        io::get_input("Seed")?,
+                        Language::English,
+                    )?),
+                },
+                config.kdf_params(kdf_profile).unwrap_or_default(),
                 network,
                 &secp,
             )?;
+            println!("Encryption key derivation: {}", keechain.kdf_params());
+            Ok(())
+        }
+        Command::CheckMnemonic { strict_passphrase } => {
+            let mnemonic = seed::parse_mnemonic(io::get_mnemonic()?, Language::English)?;
+            let passphrase: Option<String> = io::get_optional_passphrase()?;
+            let strength: Option<PassphraseStrength> =
+                passphrase.as_ref().map(passphrase::strength);
+            if strict_passphrase && strength == Some(PassphraseStrength::Weak) {
+                return Err(format!("Weak passphrase: use at least {MIN_PASSPHRASE_LEN} characters and mix lowercase, uppercase, digits or symbols").into());
+            }
+            let seed = Seed::new(mnemonic, passphrase);
+            println!("Valid mnemonic");
+            match strength {
+                Some(PassphraseStrength::Weak) => println!(
+                    "{}",
+                    style("Passphrase strength: weak (too short or a single character class)")
+                        .red()
+                ),
+                Some(strength) => println!("Passphrase strength: {strength}"),
+                None => (),
+            }
+            println!("Language: {}", seed.mnemonic().language());
+            println!("Word count: {}", seed.mnemonic().word_count());
+            println!("Fingerprint: {}", seed.fingerprint(network, &secp)?);
             Ok(())
         }
+        Command::Verify { keychain } => {
+            let name: String = util::keychain_name(&keychain_path, keychain)?;
+            if KeeChain::verify_password(&keychain_path, name, io::get_password)? {
+                println!("Password OK");
+                Ok(())
+            } else {
+                println!("Invalid password");
+                std::process::exit(INVALID_PASSWORD_EXIT_CODE);
+            }
+        }
         Command::List => {
-            let names = dir::get_keychains_list(keychain_path)?;
+            let names = dir::get_keychains_list(&keychain_path)?;
             for (index, name) in names.iter().enumerate() {
-                println!("{}. {name}", index + 1);
+                // The fingerprint is not shown for unreadable files
+                match KeeChain::fingerprint_in_header(&keychain_path, name) {
+                    Ok(Some(fingerprint)) => println!("{}. {name} ({fingerprint})", index + 1),
+                    _ => println!("{}. {name}", index + 1),
+                }
             }
             Ok(())
         }
-        Command::Identity { name } => {
-            let keechain = KeeChain::open(keychain_path, name, io::get_password, network, &secp)?;
-            let fingerprint = keechain.identity();
+        Command::Identity { keychain, raw } => {
+            let session = open(keychain)?;
+            let fingerprint = session.keechain.identity();
+            if raw {
+                println!("{fingerprint}");
+                return Ok(());
+            }
             println!("Fingerprint: {fingerprint}");
+            util::print_defaults(&session.defaults()?);
             Ok(())
         }
         Command::Export { export_type } => match export_type {
-            ExportTypes::Descriptors { name, account } => {
-                let password: String = io::get_password()?;
-                let keechain =
-                    KeeChain::open(keychain_path, name, || Ok(password.clone()), network, &secp)?;
-                let descriptors =
-                    keechain
-                        .keychain(password)?
-                        .descriptors(network, Some(account), &secp)?;
-                println!("Extenrals:");
-                for desc in descriptors.external().iter() {
-                    println!("- {desc}");
+            ExportTypes::Descriptors {
+                keychain,
+                account,
+                combine_into_descriptor_wallet,
+                external_only,
+                internal_only,
+                derivation_summary,
+                coin_type,
+                script,
+                to_clipboard,
+                ur,
+                qr,
+            } => {
+                let session = open(keychain)?;
+                let account: u32 = session.settings(&config, account, None)?.account;
+                let keychain = session.keychain()?;
+                let descriptors = keychain.descriptors_with_coin_type(
+                    network,
+                    Some(account),
+                    coin_type,
+                    &secp,
+                )?;
+                util::print_coin_type_warning(coin_type, network);
+                let purposes: Vec<Purpose> = match script {
+                    Some(script) => vec![script.into()],
+                    None => DESCRIPTORS_PURPOSES.to_vec(),
+                };
+                let mut externals: Vec<String> = Vec::with_capacity(purposes.len());
+                let mut internals: Vec<String> = Vec::with_capacity(purposes.len());
+                for purpose in purposes.iter() {
+                    if combine_into_descriptor_wallet {
+                        externals.push(descriptors.get_multipath_by_purpose(*purpose)?.to_string());
+                    } else {
+                        if !internal_only {
+                            externals
+                                .push(descriptors.get_by_purpose(*purpose, false)?.to_string());
+                        }
+                        if !external_only {
+                            internals.push(descriptors.get_by_purpose(*purpose, true)?.to_string());
+                        }
+                    }
+                }
+                if !to_clipboard {
+                    if !internal_only {
+                        if combine_into_descriptor_wallet {
+                            println!("Multipath:");
+                        } else {
+                            println!("Extenrals:");
+                        }
+                        for desc in externals.iter() {
+                            println!("- {desc}");
+                            if qr {
+                                println!("{}", util::qr::render(desc)?);
+                            }
+                        }
+                    }
+                    if !combine_into_descriptor_wallet && !external_only {
+                        println!("Internals:");
+                        for desc in internals.iter() {
+                            println!("- {desc}");
+                            if qr {
+                                println!("{}", util::qr::render(desc)?);
+                            }
+                        }
+                    }
                 }
-                println!("Internals:");
-                for desc in descriptors.internal().iter() {
-                    println!("- {desc}");
+                if derivation_summary {
+                    util::print_derivation_summary(
+                        &purposes,
+                        account,
+                        network,
+                        session.keechain.identity(),
+                    )?;
+                }
+                if ur {
+                    println!("UR:");
+                    for part in ur::encode_account(
+                        &keychain.seed(),
+                        network,
+                        Some(account),
+                        &purposes,
+                        ur::DEFAULT_MAX_FRAGMENT_LEN,
+                        &secp,
+                    )?
+                    .iter()
+                    {
+                        println!("{part}");
+                    }
+                }
+                if to_clipboard {
+                    externals.extend(internals);
+                    util::copy_to_clipboard(externals.join("\n"), session.keechain.identity())?;
                 }
                 Ok(())
             }
-            ExportTypes::BitcoinCore { name, account } => {
-                let password: String = io::get_password()?;
-                let keechain =
-                    KeeChain::open(keychain_path, name, || Ok(password.clone()), network, &secp)?;
+            ExportTypes::BitcoinCore {
+                keychain,
+                account,
+                derivation_summary,
+                to_clipboard,
+            } => {
+                let session = open(keychain)?;
+                let account: u32 = session.settings(&config, account, None)?.account;
                 let descriptors =
-                    BitcoinCore::new(&keechain.seed(password)?, network, Some(account), &secp)?;
-                println!("{}", descriptors.to_string());
+                    BitcoinCore::new(&session.seed()?, network, Some(account), &secp)?;
+                if !to_clipboard {
+                    println!("{}", descriptors.to_string());
+                }
+                if derivation_summary {
+                    util::print_derivation_summary(
+                        &DESCRIPTORS_PURPOSES,
+                        account,
+                        network,
+                        session.keechain.identity(),
+                    )?;
+                }
+                if to_clipboard {
+                    util::copy_to_clipboard(descriptors.to_string(), session.keechain.identity())?;
+                }
                 Ok(())
             }
-            ExportTypes::Electrum {
-                name,
-                script,
+            ExportTypes::Multisig {
+                keychain,
                 account,
+                threshold,
+                cosigners,
             } => {
-                let password: String = io::get_password()?;
-                let keechain =
-                    KeeChain::open(keychain_path, name, || Ok(password.clone()), network, &secp)?;
-                let electrum_json_wallet = Electrum::new(
-                    &keechain.seed(password)?,
+                let cosigners = cosigners
+                    .iter()
+                    .map(|key| util::parse_cosigner(key))
+                    .collect::<Result<Vec<_>>>()?;
+                let session = open(keychain)?;
+                let account: u32 = session.settings(&config, account, None)?.account;
+                let (external, internal) = descriptors::multisig_descriptors(
+                    &session.seed()?,
+                    threshold,
+                    cosigners,
                     network,
-                    script.into(),
                     Some(account),
                     &secp,
                 )?;
-                let path = electrum_json_wallet.save_to_file(keechain_common::home())?;
+                println!("External: {external}");
+                println!("Internal: {internal}");
+                Ok(())
+            }
+            ExportTypes::Bsms {
+                keychain,
+                account,
+                token,
+                description,
+                out,
+                stdout,
+            } => {
+                let token: Option<Vec<u8>> = token.map(hex::decode).transpose()?;
+                let session = open(keychain)?;
+                let account: u32 = session.settings(&config, account, None)?.account;
+                let description: String = match description {
+                    Some(description) => description,
+                    None => session.keechain.name().unwrap_or_default(),
+                };
+                let bsms = Bsms::new(
+                    &session.seed()?,
+                    network,
+                    ScriptType::P2WSH,
+                    Some(account),
+                    token.as_deref(),
+                    description,
+                    &secp,
+                )?;
+                if stdout {
+                    println!("{bsms}");
+                    return Ok(());
+                }
+                let path = bsms.save_to_file(out.unwrap_or_else(|| config.export_dir.clone()))?;
+                println!("BSMS record exported to {}", path.display());
+                Ok(())
+            }
+            ExportTypes::Electrum {
+                keychain,
+                script,
+                account,
+                derivation_summary,
+                out,
+                encrypt,
+                stdout,
+            } => {
+                let session = open(keychain)?;
+                let settings: Settings = session.settings(
+                    &config,
+                    account,
+                    script.map(|script| ElectrumSupportedScripts::from(script).into()),
+                )?;
+                let account: u32 = settings.account;
+                let script: ElectrumSupportedScripts = settings
+                    .script
+                    .and_then(ElectrumSupportedScripts::from_purpose)
+                    .unwrap_or_default();
+                let electrum_json_wallet =
+                    Electrum::new(&session.seed()?, network, script, Some(account), &secp)?;
+                if stdout {
+                    println!("{}", electrum_json_wallet.to_json());
+                    return Ok(());
+                }
+                let dir: PathBuf = out.unwrap_or_else(|| config.export_dir.clone());
+                let path = if encrypt {
+                    let export_password: String = io::get_export_password()?;
+                    io::confirm_password(&export_password)?;
+                    electrum_json_wallet.save_encrypted_to_file(dir, export_password)?
+                } else {
+                    electrum_json_wallet.save_to_file(dir)?
+                };
                 println!("Electrum file exported to {}", path.display());
+                if derivation_summary {
+                    util::print_derivation_summary(
+                        &[script.into()],
+                        account,
+                        network,
+                        session.keechain.identity(),
+                    )?;
+                }
                 Ok(())
             }
-            ExportTypes::Wasabi { name } => {
-                let password: String = io::get_password()?;
-                let keechain =
-                    KeeChain::open(keychain_path, name, || Ok(password.clone()), network, &secp)?;
-                let wasabi_json_wallet = Wasabi::new(&keechain.seed(password)?, network, &secp)?;
-                let path = wasabi_json_wallet.save_to_file(keechain_common::home())?;
+            ExportTypes::Sparrow {
+                keychain,
+                script,
+                account,
+                derivation_summary,
+                out,
+                stdout,
+            } => {
+                let session = open(keychain)?;
+                let settings: Settings =
+                    session.settings(&config, account, script.map(Purpose::from))?;
+                let account: u32 = settings.account;
+                let script: SparrowScriptType = settings
+                    .script
+                    .and_then(SparrowScriptType::from_purpose)
+                    .unwrap_or_default();
+                let sparrow =
+                    Sparrow::new(&session.seed()?, network, script, Some(account), &secp)?;
+                if stdout {
+                    println!("{}", sparrow.to_json());
+                    return Ok(());
+                }
+                let path =
+                    sparrow.save_to_file(out.unwrap_or_else(|| config.export_dir.clone()))?;
+                println!("Sparrow file exported to {}", path.display());
+                if derivation_summary {
+                    util::print_derivation_summary(
+                        &[script.into()],
+                        account,
+                        network,
+                        session.keechain.identity(),
+                    )?;
+                }
+                Ok(())
+            }
+            ExportTypes::Passport {
+                keychain,
+                account,
+                derivation_summary,
+                out,
+                stdout,
+            } => {
+                let session = open(keychain)?;
+                let account: u32 = session.settings(&config, account, None)?.account;
+                let passport = Passport::new(&session.seed()?, network, Some(account), &secp)?;
+                if stdout {
+                    println!("{}", passport.to_json());
+                    return Ok(());
+                }
+                let path =
+                    passport.save_to_file(out.unwrap_or_else(|| config.export_dir.clone()))?;
+                println!("Passport file exported to {}", path.display());
+                if derivation_summary {
+                    util::print_derivation_summary(
+                        &[Purpose::BIP44, Purpose::BIP49, Purpose::BIP84],
+                        account,
+                        network,
+                        session.keechain.identity(),
+                    )?;
+                }
+                Ok(())
+            }
+            ExportTypes::Summary { keychain, account } => {
+                let session = open(keychain)?;
+                let account: u32 = session.settings(&config, account, None)?.account;
+                let summary = Summary::new(&session.seed()?, network, Some(account), &secp)?;
+                print!("{summary}");
+                Ok(())
+            }
+            ExportTypes::SeedQr { keychain, format } => {
+                if io::ask(
+                    "The SeedQR gives full access to your funds. Are you sure you want to print it?",
+                )? {
+                    let session = open(keychain)?;
+                    let seed: Seed = session.seed()?;
+                    let seed_qr = SeedQr::new(&seed.mnemonic(), format.into())?;
+                    println!("{seed_qr}");
+                    if seed.passphrase().is_some() {
+                        println!("WARNING: the passphrase is not included in the SeedQR");
+                    }
+                } else {
+                    println!("Aborted.");
+                }
+                Ok(())
+            }
+            ExportTypes::SilentPayment {
+                keychain,
+                account,
+                scan_key,
+            } => {
+                let session = open(keychain)?;
+                let account: u32 = session.settings(&config, account, None)?.account;
+                let keys = session
+                    .seed()?
+                    .derive_silent_payment_keys(network, account, &secp)?;
+                println!("Address: {}", keys.address(network, &secp)?);
+                println!("Spend public key: {}", keys.spend_public_key(&secp));
+                if scan_key {
+                    println!("Scan private key: {}", keys.scan_key.display_secret());
+                }
+                Ok(())
+            }
+            ExportTypes::Verify { keychain, account } => {
+                let session = open(keychain)?;
+                let account: u32 = session.settings(&config, account, None)?.account;
+                let seed: Seed = session.seed()?;
+                let bitcoin_core = BitcoinCore::new(&seed, network, Some(account), &secp)?;
+                for script in [
+                    ElectrumSupportedScripts::Legacy,
+                    ElectrumSupportedScripts::Segwit,
+                    ElectrumSupportedScripts::NativeSegwit,
+                ] {
+                    Electrum::new(&seed, network, script, Some(account), &secp)?
+                        .verify(&bitcoin_core)?;
+                    println!("{script}: OK");
+                }
+                Ok(())
+            }
+            ExportTypes::Wasabi {
+                keychain,
+                account,
+                derivation_summary,
+                out,
+                encrypt,
+                stdout,
+            } => {
+                let session = open(keychain)?;
+                let account: u32 = session.settings(&config, account, None)?.account;
+                let wasabi_json_wallet =
+                    Wasabi::new(&session.seed()?, network, Some(account), &secp)?;
+                if stdout {
+                    println!("{}", wasabi_json_wallet.to_json());
+                    return Ok(());
+                }
+                let dir: PathBuf = out.unwrap_or_else(|| config.export_dir.clone());
+                let path = if encrypt {
+                    let export_password: String = io::get_export_password()?;
+                    io::confirm_password(&export_password)?;
+                    wasabi_json_wallet.save_encrypted_to_file(dir, export_password)?
+                } else {
+                    wasabi_json_wallet.save_to_file(dir)?
+                };
                 println!("Wasabi file exported to {}", path.display());
+                if derivation_summary {
+                    util::print_derivation_summary(
+                        &[Purpose::BIP84],
+                        account,
+                        network,
+                        session.keechain.identity(),
+                    )?;
+                }
+                Ok(())
+            }
+        },
+        Command::Import { import_type } => match import_type {
+            ImportTypes::Electrum { file } => {
+                let electrum = Electrum::from_file(file)?;
+                let script: ElectrumSupportedScripts = electrum.script();
+                let wallet_network: Network = electrum.network()?;
+                if wallet_network != network {
+                    println!(
+                        "{}",
+                        style(format!(
+                            "Warning: the wallet is for {wallet_network}, not {network}"
+                        ))
+                        .red()
+                    );
+                }
+                let (external, internal) = electrum.descriptors()?;
+                println!("Script: {script} ({})", script.xpub_type());
+                println!("Root fingerprint: {}", electrum.root_fingerprint());
+                println!("Derivation: {}", electrum.derivation());
+                println!("Xpub: {}", electrum.xpub());
+                println!("External: {external}");
+                println!("Internal: {internal}");
+                if electrum.xprv()?.is_some() {
+                    println!(
+                        "The file contains the private keys: only the xpub has been read (keechain stores only seeds)"
+                    );
+                }
                 Ok(())
             }
         },
-        Command::Decode { file, base64 } => {
-            let psbt = PartiallySignedTransaction::from_file(file)?;
+        Command::DecryptExport { file, output } => {
+            let password: String = io::get_export_password()?;
+            let json: String = export::decrypt_export(file, password)?;
+            match output {
+                Some(output) => {
+                    fs::write(&output, json)?;
+                    println!("Decrypted file saved to {}", output.display());
+                }
+                None => println!("{json}"),
+            }
+            Ok(())
+        }
+        Command::Addresses {
+            keychain,
+            account,
+            count,
+            only_mine,
+            coin_type,
+        } => {
+            let session = open(keychain)?;
+            let settings: Settings = session.settings(&config, account, None)?;
+            let account: u32 = settings.account;
+            let descriptors = session.keychain()?.descriptors_with_coin_type(
+                network,
+                Some(account),
+                coin_type,
+                &secp,
+            )?;
+            util::print_coin_type_warning(coin_type, network);
+            match only_mine {
+                Some(path) => {
+                    let candidates: Vec<Address> = util::read_addresses(path, network)?;
+                    let result: ScanResult = descriptors.scan_addresses(
+                        &candidates,
+                        network,
+                        &DESCRIPTORS_PURPOSES,
+                        0..count,
+                    )?;
+                    util::print_derived_addresses(&result.matches);
+                    println!(
+                        "{} of {} addresses are mine ({} addresses scanned)",
+                        result.matches.len(),
+                        candidates.len(),
+                        result.scanned
+                    );
+                }
+                None => {
+                    let purposes: Vec<Purpose> = match settings.script {
+                        Some(purpose) => vec![purpose],
+                        None => DESCRIPTORS_PURPOSES.to_vec(),
+                    };
+                    let mut addresses = Vec::new();
+                    for purpose in purposes.into_iter() {
+                        addresses.extend(descriptors.derive_addresses(
+                            purpose,
+                            false,
+                            0..count,
+                            network,
+                        )?);
+                    }
+                    util::print_derived_addresses(&addresses);
+                }
+            }
+            Ok(())
+        }
+        Command::Address {
+            keychain,
+            index,
+            count,
+            account,
+            script,
+            change,
+        } => {
+            let session = open(keychain)?;
+            let settings: Settings =
+                session.settings(&config, account, script.map(Purpose::from))?;
+            let account: u32 = settings.account;
+            let purpose: Purpose = settings.script.unwrap_or(Purpose::BIP84);
+            let keychain = session.keychain()?;
+            let descriptors = keychain.descriptors(network, Some(account), &secp)?;
+            if count == 1 {
+                let derived = descriptors.derive_address(purpose, change, index, network)?;
+                println!("{}", util::qr::render(derived.address.to_string())?);
+                println!("Address: {}", derived.address);
+                println!("Path: {}", derived.path);
+            } else {
+                let addresses = descriptors.derive_addresses(
+                    purpose,
+                    change,
+                    index..index.saturating_add(count),
+                    network,
+                )?;
+                util::print_derived_addresses(&addresses);
+            }
+            Ok(())
+        }
+        Command::VerifyAddress {
+            name,
+            address,
+            account,
+            max_index,
+            show_derivation_for_address,
+            json,
+            coin_type,
+        } => {
+            let address: Address = Address::from_str(&address)?.require_network(network)?;
+            let session = open(name.into())?;
+            let account: u32 = session.settings(&config, account, None)?.account;
+            let descriptors = session.keychain()?.descriptors_with_coin_type(
+                network,
+                Some(account),
+                coin_type,
+                &secp,
+            )?;
+            if !json {
+                util::print_coin_type_warning(coin_type, network);
+            }
+            let verification = descriptors.verify_address(&address, network, max_index)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&verification)?);
+            } else {
+                util::print_address_verification(
+                    &address,
+                    &verification,
+                    show_derivation_for_address,
+                );
+            }
+            Ok(())
+        }
+        Command::Tree { keychain, account } => {
+            let session = open(keychain)?;
+            let account: u32 = session.settings(&config, account, None)?.account;
+            let keychain = session.keychain()?;
+            let root: ExtendedPrivKey = keychain.seed().to_bip32_root_key(network)?;
+            let descriptors = keychain.descriptors(network, Some(account), &secp)?;
+            let mut accounts: Vec<(DerivationPath, ExtendedPubKey, DerivedAddress)> = Vec::new();
+            for purpose in DESCRIPTORS_PURPOSES.into_iter() {
+                let path: DerivationPath =
+                    purpose.to_account_extended_path(network, Some(account))?;
+                let xpub: ExtendedPubKey =
+                    ExtendedPubKey::from_priv(&secp, &root.derive_priv(&secp, &path)?);
+                let first: DerivedAddress =
+                    descriptors.derive_address(purpose, false, 0, network)?;
+                accounts.push((path, xpub, first));
+            }
+            util::print_derivation_tree(root.fingerprint(&secp), &accounts);
+            Ok(())
+        }
+        Command::Decode {
+            file,
+            psbt,
+            base64,
+            txid,
+            max_inputs,
+            ur,
+            max_fee_rate,
+            name,
+            fingerprint,
+        } => {
+            let psbt = match (psbt, file) {
+                (Some(psbt), _) => PartiallySignedTransaction::from_text(psbt)?,
+                (None, Some(file)) if ur => {
+                    PartiallySignedTransaction::from_ur(String::from_utf8(file.read()?)?)?
+                }
+                (None, Some(file)) => PartiallySignedTransaction::from_bytes(file.read()?)?,
+                (None, None) => return Err("PSBT file or string required".into()),
+            };
+            psbt.ensure_max_inputs(max_inputs)?;
+            if txid {
+                println!("{}", util::format_predicted_txid(&psbt));
+            }
             if base64 {
                 println!("{}", psbt.as_base64());
             } else {
-                util::print_psbt(psbt, network);
+                let seed: Option<Seed> = match (name, fingerprint) {
+                    (None, None) => None,
+                    (name, fingerprint) => {
+                        let session = open(KeychainArg { name, fingerprint })?;
+                        Some(session.seed()?)
+                    }
+                };
+                util::print_psbt(&psbt, network, max_fee_rate, seed.as_ref(), &secp)?;
             }
             Ok(())
         }
@@ -155,63 +854,289 @@ fn main() -> Result<()> {
             name,
             file,
             descriptor,
+            psbt,
+            sighash,
+            trust_witness_utxo,
+            allow_all_sighashes,
+            output_format,
+            stdout,
+            no_rename,
+            txid,
+            max_inputs,
+            assume_script,
+            force_script_path,
         } => {
-            let password: String = io::get_password()?;
-            let keechain =
-                KeeChain::open(keychain_path, name, || Ok(password.clone()), network, &secp)?;
-            let seed = &keechain.seed(password)?;
-            let mut psbt: PartiallySignedTransaction =
-                PartiallySignedTransaction::from_file(&file)?;
-            let finalized = match descriptor {
-                Some(descriptor) => psbt.sign_with_descriptor(seed, descriptor, network, &secp)?,
-                None => psbt.sign_with_seed(seed, network, &secp)?,
+            // PSBT strings and stdin can't be renamed and saved: print the signed one
+            let stdout: bool =
+                stdout || psbt.is_some() || matches!(file, Some(CliPsbtSource::Stdin));
+            if stdout && matches!(output_format, Some(CliPsbtFormat::Binary)) {
+                return Err("Binary format can't be printed: save it to a file".into());
+            }
+            let mut psbt: PartiallySignedTransaction = match (psbt, &file) {
+                (Some(psbt), _) => PartiallySignedTransaction::from_text(psbt)?,
+                (None, Some(file)) => PartiallySignedTransaction::from_bytes(file.read()?)?,
+                (None, None) => return Err("PSBT file or string required".into()),
+            };
+            psbt.ensure_max_inputs(max_inputs)?;
+            let session = open(name.into())?;
+            let seed = &session.seed()?;
+            // Review before signing (not when the signed PSBT is printed)
+            if !stdout {
+                util::print_psbt(&psbt, network, DEFAULT_MAX_FEE_RATE, Some(seed), &secp)?;
+            }
+            let options = SigningOptions {
+                sighash: sighash.map(|s| s.into()),
+                trust_witness_utxo,
+                allow_all_sighashes,
+                assume_script: assume_script.map(|s| s.into()),
+                force_script_path,
             };
-            println!("Signed.");
-            let mut renamed_file: PathBuf = file;
-            dir::rename_psbt(&mut renamed_file, finalized)?;
-            psbt.save_to_file(renamed_file)?;
-            if finalized {
-                println!("PSBT finalized");
+            let finalized = psbt.sign_custom_with_options(
+                seed,
+                descriptor,
+                Vec::new(),
+                options,
+                network,
+                &secp,
+            )?;
+            let status: &str = if finalized {
+                "PSBT finalized"
+            } else {
+                "PSBT signing not finalized"
+            };
+            if stdout {
+                eprintln!("Signed.");
+                eprintln!("{status}");
+                if txid {
+                    eprintln!("{}", util::format_predicted_txid(&psbt));
+                }
+                let format: PsbtFormat = output_format.map_or(PsbtFormat::Base64, |f| f.into());
+                println!("{}", String::from_utf8(psbt.encode(format)?)?);
             } else {
-                println!("PSBT signing not finalized");
+                println!("Signed.");
+                let mut path: PathBuf = file
+                    .as_ref()
+                    .and_then(CliPsbtSource::path)
+                    .ok_or("PSBT file required")?
+                    .to_path_buf();
+                if !no_rename {
+                    dir::rename_psbt(&mut path, finalized)?;
+                }
+                psbt.save_to_file_with_format(
+                    &path,
+                    output_format.map_or(PsbtFormat::Binary, |f| f.into()),
+                )?;
+                println!("{status}: saved to {}", path.display());
+                if txid {
+                    println!("{}", util::format_predicted_txid(&psbt));
+                }
+                if no_rename && !finalized {
+                    println!(
+                        "WARNING: the original file has been overwritten with a NOT finalized PSBT"
+                    );
+                }
             }
             Ok(())
         }
+        Command::SignMessage {
+            name,
+            message,
+            account,
+            index,
+            change,
+            path,
+        } => {
+            let session = open(name.into())?;
+            let account: u32 = session.settings(&config, account, None)?.account;
+            let keychain = session.keychain()?;
+            let path: DerivationPath = match path {
+                Some(path) => path,
+                None => {
+                    let derived = keychain
+                        .descriptors(network, Some(account), &secp)?
+                        .derive_address(Purpose::BIP86, change, index, network)?;
+                    println!("Address: {}", derived.address);
+                    derived.path
+                }
+            };
+            let signature: String = keychain.sign_message(&path, &message, network, &secp)?;
+            println!("Path: {path}");
+            println!("Signature: {signature}");
+            Ok(())
+        }
+        Command::VerifyMessage {
+            address,
+            message,
+            signature,
+        } => {
+            let address: Address = Address::from_str(&address)?.require_network(network)?;
+            psbt::verify_message(&address, &message, &signature, &secp)?;
+            println!("Valid signature for {address}");
+            Ok(())
+        }
+        Command::Psbt { command } => match command {
+            PsbtCommand::Join { files, output } => {
+                let mut files = files.into_iter();
+                let mut psbt: PartiallySignedTransaction = match files.next() {
+                    Some(file) => PartiallySignedTransaction::from_file(file)?,
+                    None => return Err("No PSBT provided".into()),
+                };
+                for file in files {
+                    psbt.join(PartiallySignedTransaction::from_file(file)?)?;
+                }
+                psbt.save_to_file(&output)?;
+                println!("Joined PSBT saved to {}", output.display());
+                Ok(())
+            }
+            PsbtCommand::Combine { files, output } => {
+                let mut psbts: Vec<PartiallySignedTransaction> = Vec::with_capacity(files.len());
+                for file in files {
+                    psbts.push(PartiallySignedTransaction::from_file(file)?);
+                }
+                let psbt: PartiallySignedTransaction = psbt::combine(psbts)?;
+                psbt.save_to_file(&output)?;
+                println!("Combined PSBT saved to {}", output.display());
+                Ok(())
+            }
+            PsbtCommand::Finalize { file, output } => {
+                let mut psbt: PartiallySignedTransaction =
+                    PartiallySignedTransaction::from_file(&file)?;
+                let tx: Transaction = psbt.finalize(&secp)?;
+                let output: PathBuf = output.unwrap_or_else(|| file.with_extension("tx"));
+                fs::write(&output, consensus::encode::serialize_hex(&tx))?;
+                println!("Transaction {} saved to {}", tx.txid(), output.display());
+                Ok(())
+            }
+            PsbtCommand::ExtractTx { psbt, txid } => {
+                let mut psbt: PartiallySignedTransaction = util::read_psbt(&psbt)?;
+                let tx: Transaction = psbt.finalize(&secp)?;
+                println!("{}", consensus::encode::serialize_hex(&tx));
+                if txid {
+                    println!("Txid: {}", tx.txid());
+                }
+                Ok(())
+            }
+            PsbtCommand::Role { psbt } => {
+                let psbt: PartiallySignedTransaction = util::read_psbt(&psbt)?;
+                let roles: PsbtRoles = psbt.roles();
+                println!(
+                    "Inputs: {} ({} signed, {} finalized)",
+                    roles.inputs, roles.signed_inputs, roles.finalized_inputs
+                );
+                let acted: Vec<String> = roles.acted().iter().map(|r| r.to_string()).collect();
+                println!("Roles: {}", acted.join(", "));
+                let next: PsbtRole = roles.next();
+                let hint: &str = match next {
+                    PsbtRole::Creator => "add the inputs",
+                    PsbtRole::Updater => "add the UTXOs with `psbt add-utxo`",
+                    PsbtRole::Signer | PsbtRole::Combiner => {
+                        "sign with `sign` (and `psbt combine` the PSBTs of the other cosigners)"
+                    }
+                    PsbtRole::Finalizer => "finalize with `psbt finalize`",
+                    PsbtRole::Extractor => "extract the transaction with `psbt extract-tx`",
+                };
+                println!("Next: {next} ({hint})");
+                Ok(())
+            }
+            PsbtCommand::AddUtxo {
+                psbt_file,
+                prev_tx,
+                output,
+            } => {
+                let mut psbt: PartiallySignedTransaction =
+                    PartiallySignedTransaction::from_file(&psbt_file)?;
+                let prev_tx: Transaction = util::read_transaction(&prev_tx)?;
+                let updated: usize = psbt.add_missing_utxo(&prev_tx)?;
+                let output: PathBuf = output.unwrap_or(psbt_file);
+                psbt.save_to_file(&output)?;
+                println!(
+                    "UTXOs added to {updated} inputs, PSBT saved to {}",
+                    output.display()
+                );
+                Ok(())
+            }
+            PsbtCommand::DescribeMultisig {
+                descriptor_file,
+                keychain,
+            } => {
+                let descriptor: Descriptor<DescriptorPublicKey> =
+                    util::read_descriptor(descriptor_file)?;
+                let info: MultisigInfo = summarize_multisig(&descriptor)?;
+                info.validate_key_origins()?;
+                let session = open(keychain)?;
+                let position: Option<usize> =
+                    info.cosigner_position(&session.seed()?, network, &secp)?;
+                util::print_multisig_info(&info, position);
+                Ok(())
+            }
+        },
         Command::Advanced { command } => match command {
             AdvancedCommand::Derive {
                 name,
-                word_count,
                 index,
+                bip85_type,
+                word_count,
+                num_bytes,
+                print_path,
             } => {
-                let password: String = io::get_password()?;
-                let keechain =
-                    KeeChain::open(keychain_path, name, || Ok(password.clone()), network, &secp)?;
-                let mnemonic: Mnemonic = keechain.keychain(password)?.deterministic_entropy(
-                    word_count.into(),
-                    index,
-                    &secp,
-                )?;
-                println!("Mnemonic: {mnemonic}");
+                let session = open(name.into())?;
+                let seed: Seed = session.seed()?;
+                match bip85_type {
+                    CliBip85Type::Mnemonic => {
+                        let word_count: WordCount = word_count.into();
+                        let mnemonic: Mnemonic =
+                            seed.derive_bip85_mnemonic(word_count, index, &secp)?;
+                        if print_path {
+                            println!("Path: {}", bip85::mnemonic_path(word_count, index)?);
+                        }
+                        println!("Mnemonic: {mnemonic}");
+                    }
+                    CliBip85Type::Hex => {
+                        let entropy: Vec<u8> = seed.derive_bip85_hex(num_bytes, index, &secp)?;
+                        if print_path {
+                            println!("Path: {}", bip85::hex_path(num_bytes, index)?);
+                        }
+                        println!("Hex: {}", hex::encode(entropy));
+                    }
+                    CliBip85Type::Wif => {
+                        let key = seed.derive_bip85_wif(index, network, &secp)?;
+                        if print_path {
+                            println!("Path: {}", bip85::wif_path(index)?);
+                        }
+                        println!("WIF: {}", key.to_wif());
+                    }
+                    CliBip85Type::Xprv => {
+                        let xprv: ExtendedPrivKey =
+                            seed.derive_bip85_xprv(index, network, &secp)?;
+                        if print_path {
+                            println!("Path: {}", bip85::xprv_path(index)?);
+                        }
+                        println!("XPRV: {xprv}");
+                    }
+                }
+                Ok(())
+            }
+            AdvancedCommand::ExportSeed { keychain } => {
+                if io::ask(
+                    "The seed gives full access to your funds. Are you sure you want to print it?",
+                )? {
+                    let session = open(keychain)?;
+                    println!("{}", session.seed()?.to_hex());
+                } else {
+                    println!("Aborted.");
+                }
                 Ok(())
             }
             AdvancedCommand::Danger { command } => match command {
-                DangerCommand::ViewSecrets { name } => {
-                    let password: String = io::get_password()?;
-                    let keechain = KeeChain::open(
-                        keychain_path,
-                        name,
-                        || Ok(password.clone()),
-                        network,
-                        &secp,
-                    )?;
-                    let secrets = keechain.keychain(password)?.secrets(network, &secp)?;
-                    util::print_secrets(secrets);
+                DangerCommand::ViewSecrets { keychain, fields } => {
+                    let session = open(keychain)?;
+                    let secrets = session.keychain()?.secrets(network, &secp)?;
+                    util::print_secrets(secrets, &fields);
                     Ok(())
                 }
-                DangerCommand::Wipe { name } => {
+                DangerCommand::Wipe { keychain } => {
                     if io::ask("Are you really sure? This action is permanent!")? && io::ask("Again, are you really sure? THIS ACTION IS PERMANENT AND YOU MAY LOSE ALL YOUR FUNDS!")? {
-                        let keechain = KeeChain::open(keychain_path, name, io::get_password, network, &secp)?;
-                        keechain.wipe()?;
+                        open(keychain)?.keechain.wipe()?;
                     } else {
                         println!("Aborted.");
                     }
@@ -221,19 +1146,75 @@ fn main() -> Result<()> {
         },
         Command::Setting { command } => match command {
             SettingCommand::Rename { name, new_name } => {
-                let mut keechain =
-                    KeeChain::open(keychain_path, name, io::get_password, network, &secp)?;
-                Ok(keechain.rename(new_name)?)
-            }
-            SettingCommand::ChangePassword { name } => {
-                let mut keechain =
-                    KeeChain::open(keychain_path, name, io::get_password, network, &secp)?;
-                Ok(keechain.change_password(
-                    io::get_password,
-                    io::get_new_password,
-                    io::get_confirmation_password,
-                )?)
+                let mut session = open(name.into())?;
+                Ok(session.keechain.rename(new_name)?)
+            }
+            SettingCommand::ChangePassword {
+                keychain,
+                kdf_profile,
+            } => {
+                let Session {
+                    mut keechain,
+                    password,
+                } = open(keychain)?;
+                let new_password: String = io::get_new_password()?;
+                let confirm_password: String = io::confirm_password(&new_password)?;
+                let fingerprint = keechain.change_password_with_kdf_params(
+                    || Ok(password),
+                    || Ok(new_password),
+                    || Ok(confirm_password),
+                    config.kdf_params(kdf_profile),
+                    &secp,
+                )?;
+                println!("Password changed; fingerprint unchanged: {fingerprint}");
+                println!("Encryption key derivation: {}", keechain.kdf_params());
+                Ok(())
+            }
+            SettingCommand::Defaults {
+                keychain,
+                account,
+                script,
+                clear,
+            } => {
+                let mut session = open(keychain)?;
+                let mut defaults: KeychainDefaults = session.defaults()?;
+                if clear || account.is_some() || script.is_some() {
+                    if clear {
+                        defaults = KeychainDefaults::default();
+                    }
+                    if let Some(account) = account {
+                        defaults.account = Some(account);
+                    }
+                    if let Some(script) = script {
+                        defaults.purpose = Some(script.into());
+                    }
+                    session.keechain.set_defaults(&session.password, defaults)?;
+                    session.keechain.save()?;
+                }
+                util::print_defaults(&defaults);
+                Ok(())
             }
         },
+        Command::Config { command } => match command {
+            ConfigCommand::Show => {
+                println!("{}", serde_json::to_string_pretty(&config)?);
+                Ok(())
+            }
+        },
+        Command::SelfTest => {
+            let checks: Vec<self_test::Check> = self_test::run(&secp);
+            for check in checks.iter() {
+                match &check.result {
+                    Ok(()) => println!("{} {}", style("PASS").green(), check.name),
+                    Err(e) => println!("{} {}: {e}", style("FAIL").red(), check.name),
+                }
+            }
+            let failed: usize = checks.iter().filter(|c| !c.passed()).count();
+            if failed > 0 {
+                return Err(format!("{failed} of {} self-tests failed", checks.len()).into());
+            }
+            println!("All self-tests passed");
+            Ok(())
+        }
     }
 }
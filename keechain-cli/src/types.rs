@@ -1,11 +1,22 @@
 // Copyright (c) 2022-2023 Yuki Kishimoto
 // Distributed under the MIT software license
 
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
 use clap::ValueEnum;
+use keechain_core::bitcoin::sighash::EcdsaSighashType;
 use keechain_core::bitcoin::Network;
-use keechain_core::{ElectrumSupportedScripts, WordCount};
+use keechain_core::crypto::KdfProfile;
+use keechain_core::export::SeedQrFormat;
+use keechain_core::psbt::PsbtFormat;
+use keechain_core::{ElectrumSupportedScripts, Purpose, WordCount};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, ValueEnum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum CliNetwork {
     Bitcoin,
     Testnet,
@@ -24,6 +35,26 @@ impl From<CliNetwork> for Network {
     }
 }
 
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum CliKdfProfile {
+    /// 8 MiB, 1 iteration (low-end devices)
+    Fast,
+    /// 19 MiB, 2 iterations
+    Balanced,
+    /// 256 MiB, 4 iterations
+    Paranoid,
+}
+
+impl From<CliKdfProfile> for KdfProfile {
+    fn from(value: CliKdfProfile) -> Self {
+        match value {
+            CliKdfProfile::Fast => Self::Fast,
+            CliKdfProfile::Balanced => Self::Balanced,
+            CliKdfProfile::Paranoid => Self::Paranoid,
+        }
+    }
+}
+
 #[derive(Debug, Clone, ValueEnum)]
 pub enum CliElectrumSupportedScripts {
     Legacy,
@@ -41,6 +72,111 @@ impl From<CliElectrumSupportedScripts> for ElectrumSupportedScripts {
     }
 }
 
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum CliPsbtFormat {
+    Base64,
+    Hex,
+    Binary,
+}
+
+impl From<CliPsbtFormat> for PsbtFormat {
+    fn from(value: CliPsbtFormat) -> Self {
+        match value {
+            CliPsbtFormat::Base64 => Self::Base64,
+            CliPsbtFormat::Hex => Self::Hex,
+            CliPsbtFormat::Binary => Self::Binary,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum CliSeedQrFormat {
+    /// Word indexes as digits
+    Standard,
+    /// Entropy bytes (hex)
+    Compact,
+}
+
+impl From<CliSeedQrFormat> for SeedQrFormat {
+    fn from(value: CliSeedQrFormat) -> Self {
+        match value {
+            CliSeedQrFormat::Standard => Self::Standard,
+            CliSeedQrFormat::Compact => Self::Compact,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CliScript {
+    #[value(alias = "pkh")]
+    #[serde(alias = "pkh")]
+    Legacy,
+    #[value(alias = "sh-wpkh")]
+    #[serde(alias = "sh-wpkh")]
+    NestedSegwit,
+    #[value(alias = "wpkh")]
+    #[serde(alias = "wpkh")]
+    NativeSegwit,
+    #[value(alias = "tr")]
+    #[serde(alias = "tr")]
+    Taproot,
+}
+
+impl From<CliScript> for Purpose {
+    fn from(value: CliScript) -> Self {
+        match value {
+            CliScript::Legacy => Self::BIP44,
+            CliScript::NestedSegwit => Self::BIP49,
+            CliScript::NativeSegwit => Self::BIP84,
+            CliScript::Taproot => Self::BIP86,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum CliSighash {
+    All,
+    None,
+    Single,
+    AllAnyoneCanPay,
+    NoneAnyoneCanPay,
+    SingleAnyoneCanPay,
+}
+
+impl From<CliSighash> for EcdsaSighashType {
+    fn from(value: CliSighash) -> Self {
+        match value {
+            CliSighash::All => Self::All,
+            CliSighash::None => Self::None,
+            CliSighash::Single => Self::Single,
+            CliSighash::AllAnyoneCanPay => Self::AllPlusAnyoneCanPay,
+            CliSighash::NoneAnyoneCanPay => Self::NonePlusAnyoneCanPay,
+            CliSighash::SingleAnyoneCanPay => Self::SinglePlusAnyoneCanPay,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CliSecretField {
+    Entropy,
+    Mnemonic,
+    Passphrase,
+    SeedHex,
+    Network,
+    RootKey,
+    Fingerprint,
+}
+
+/// BIP85 application
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CliBip85Type {
+    Mnemonic,
+    Hex,
+    Wif,
+    Xprv,
+}
+
 #[derive(Debug, Clone, ValueEnum)]
 pub enum CliWordCount {
     #[clap(name = "12")]
@@ -60,3 +196,69 @@ impl From<CliWordCount> for WordCount {
         }
     }
 }
+
+/// PSBT argument: `-` (stdin), `file://` URL or path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliPsbtSource {
+    Stdin,
+    File(PathBuf),
+}
+
+impl CliPsbtSource {
+    /// Path of the file (`None` for stdin)
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Stdin => None,
+            Self::File(path) => Some(path.as_path()),
+        }
+    }
+
+    pub fn read(&self) -> io::Result<Vec<u8>> {
+        match self {
+            Self::Stdin => {
+                let mut content: Vec<u8> = Vec::new();
+                io::stdin().read_to_end(&mut content)?;
+                Ok(content)
+            }
+            Self::File(path) => fs::read(path),
+        }
+    }
+}
+
+impl FromStr for CliPsbtSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "-" {
+            return Ok(Self::Stdin);
+        }
+        match s.split_once("://") {
+            Some(("file", "")) => Err(String::from("Empty `file://` URL")),
+            Some(("file", path)) => Ok(Self::File(PathBuf::from(path))),
+            Some((scheme, _)) => Err(format!(
+                "Unsupported scheme `{scheme}`: use a path, a `file://` URL or `-` for stdin"
+            )),
+            None => Ok(Self::File(PathBuf::from(s))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_psbt_source() {
+        assert_eq!(CliPsbtSource::from_str("-"), Ok(CliPsbtSource::Stdin));
+        assert_eq!(
+            CliPsbtSource::from_str("file:///tmp/tx.psbt"),
+            Ok(CliPsbtSource::File(PathBuf::from("/tmp/tx.psbt")))
+        );
+        assert_eq!(
+            CliPsbtSource::from_str("tx.psbt"),
+            Ok(CliPsbtSource::File(PathBuf::from("tx.psbt")))
+        );
+        assert!(CliPsbtSource::from_str("file://").is_err());
+        assert!(CliPsbtSource::from_str("https://example.com/tx.psbt").is_err());
+    }
+}
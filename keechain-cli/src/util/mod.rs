@@ -1,55 +1,425 @@
 // Copyright (c) 2022-2023 Yuki Kishimoto
 // Distributed under the MIT software license
 
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+
+use arboard::Clipboard;
+use console::style;
+
+use keechain_core::bips::bip32::{DerivationPath, ExtendedPubKey, Fingerprint};
+use keechain_core::bips::bip44;
+use keechain_core::bitcoin::consensus;
 use keechain_core::bitcoin::psbt::PartiallySignedTransaction;
-use keechain_core::bitcoin::{Address, Network, TxOut};
-use keechain_core::types::Secrets;
+use keechain_core::bitcoin::script::Instruction;
+use keechain_core::bitcoin::secp256k1::{Secp256k1, Signing};
+use keechain_core::bitcoin::{Address, Network, Script, Transaction, TxOut};
+use keechain_core::descriptors::{AddressVerification, DerivedAddress, MultisigInfo};
+use keechain_core::miniscript::descriptor::{Descriptor, DescriptorPublicKey, Wildcard};
+use keechain_core::types::{KeychainDefaults, Secrets, Seed};
+use keechain_core::util::hex;
+use keechain_core::{KeeChain, Keychain, PsbtUtility, Purpose, Result};
 use prettytable::format::FormatBuilder;
 use prettytable::{row, Table};
 
 mod format;
+pub mod qr;
+
+use crate::cli::{io, KeychainArg};
+use crate::config::{Config, Settings};
+use crate::types::CliSecretField;
+
+/// Seconds before clearing the clipboard
+const CLIPBOARD_CLEAR_AFTER: u64 = 30;
+
+/// Keychain opened by a command, with its password
+pub struct Session {
+    pub keechain: KeeChain,
+    pub password: String,
+}
+
+impl Session {
+    /// Ask the password and open the keychain, rewriting it with the current encryption format if requested
+    ///
+    /// The re-encryption notice goes to stderr, to not break the scripts capturing stdout (i.e. `identity --raw`).
+    pub fn open<P, K, C>(
+        base_path: P,
+        keychain: K,
+        network: Network,
+        reencrypt_on_open: bool,
+        secp: &Secp256k1<C>,
+    ) -> Result<Self>
+    where
+        P: AsRef<Path>,
+        K: Into<KeychainArg>,
+        C: Signing,
+    {
+        let name: String = keychain_name(&base_path, keychain)?;
+        let password: String = io::get_password()?;
+        let mut keechain = KeeChain::open(base_path, name, || Ok(password.clone()), network, secp)?;
+        if reencrypt_on_open && keechain.reencrypt_if_legacy()? {
+            eprintln!("Keychain re-encrypted with the current format");
+        }
+        Ok(Self { keechain, password })
+    }
+
+    pub fn keychain(&self) -> Result<Keychain> {
+        Ok(self.keechain.keychain(&self.password)?)
+    }
+
+    pub fn seed(&self) -> Result<Seed> {
+        Ok(self.keechain.seed(&self.password)?)
+    }
+
+    pub fn defaults(&self) -> Result<KeychainDefaults> {
+        Ok(self.keechain.defaults(&self.password)?)
+    }
+
+    /// Account and script of the command (see [`Config::settings`])
+    pub fn settings(
+        &self,
+        config: &Config,
+        account: Option<u32>,
+        script: Option<Purpose>,
+    ) -> Result<Settings> {
+        Ok(config.settings(self.defaults()?, account, script))
+    }
+}
+
+/// Name of the keychain selected by name or by master fingerprint
+pub fn keychain_name<P, K>(base_path: P, keychain: K) -> Result<String>
+where
+    P: AsRef<Path>,
+    K: Into<KeychainArg>,
+{
+    let keychain: KeychainArg = keychain.into();
+    match (keychain.name, keychain.fingerprint) {
+        (_, Some(fingerprint)) => Ok(KeeChain::find_by_fingerprint(base_path, fingerprint)?),
+        (Some(name), None) => Ok(name),
+        (None, None) => Err("Keychain name or fingerprint required".into()),
+    }
+}
+
+pub fn print_defaults(defaults: &KeychainDefaults) {
+    match defaults.account {
+        Some(account) => println!("Default account: {account}"),
+        None => println!("Default account: not set"),
+    }
+    match defaults.purpose {
+        Some(purpose) => println!("Default script: {}", purpose.script_name()),
+        None => println!("Default script: not set"),
+    }
+}
 
-pub fn print_secrets(secrets: Secrets) {
+/// Print secrets (all of them if `fields` is empty)
+pub fn print_secrets(secrets: Secrets, fields: &[CliSecretField]) {
+    let show = |field: CliSecretField| fields.is_empty() || fields.contains(&field);
     let mut table = Table::new();
 
-    table.add_row(row![
-        format!("Entropy ({} bits)", secrets.entropy.len() / 2 * 8),
-        secrets.entropy
-    ]);
-    table.add_row(row!["Mnemonic (BIP39)", secrets.mnemonic]);
+    if show(CliSecretField::Entropy) {
+        table.add_row(row![
+            format!("Entropy ({} bits)", secrets.entropy.len() / 2 * 8),
+            secrets.entropy
+        ]);
+    }
+
+    if show(CliSecretField::Mnemonic) {
+        table.add_row(row!["Mnemonic (BIP39)", secrets.mnemonic]);
+    }
+
+    if show(CliSecretField::Passphrase) {
+        if let Some(passphrase) = &secrets.passphrase {
+            table.add_row(row!["Passphrase (BIP39)", passphrase]);
+        }
+    }
+
+    if show(CliSecretField::SeedHex) {
+        table.add_row(row!["Seed HEX (BIP39)", secrets.seed_hex]);
+    }
+
+    if show(CliSecretField::Network) {
+        table.add_row(row!["Network", secrets.network]);
+    }
+
+    if show(CliSecretField::RootKey) {
+        table.add_row(row!["Root Key (BIP32)", secrets.root_key]);
+    }
+
+    if show(CliSecretField::Fingerprint) {
+        table.add_row(row!["Fingerprint (BIP32)", secrets.fingerprint]);
+    }
+
+    table.printstd();
+}
+
+pub fn print_derivation_summary(
+    purposes: &[Purpose],
+    account: u32,
+    network: Network,
+    root_fingerprint: Fingerprint,
+) -> Result<()> {
+    println!("Derivation summary:");
+    for purpose in purposes.iter() {
+        println!(
+            "- {}",
+            purpose.derivation_summary(account, network, root_fingerprint)?
+        );
+    }
+    Ok(())
+}
+
+/// Warn if `coin_type` isn't the standard one of `network`
+pub fn print_coin_type_warning(coin_type: Option<u32>, network: Network) {
+    if let Some(coin_type) = coin_type {
+        let standard: u32 = bip44::coin_type(network);
+        if coin_type != standard {
+            println!("WARNING: using the non-standard coin type {coin_type}' (standard for {network} is {standard}'): only for the recovery of funds of non-standard wallets");
+        }
+    }
+}
+
+pub fn read_addresses<P>(path: P, network: Network) -> Result<Vec<Address>>
+where
+    P: AsRef<Path>,
+{
+    let content: String = fs::read_to_string(path)?;
+    let mut addresses: Vec<Address> = Vec::new();
+    for line in content.lines().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+        addresses.push(Address::from_str(line)?.require_network(network)?);
+    }
+    Ok(addresses)
+}
+
+/// Read a raw transaction, as hex string or from a file (binary or hex)
+pub fn read_transaction(value: &str) -> Result<Transaction> {
+    let path: &Path = Path::new(value);
+    if !path.is_file() {
+        return Ok(consensus::deserialize(&hex::decode(value.trim())?)?);
+    }
+
+    let content: Vec<u8> = fs::read(path)?;
+    // Hex or binary
+    let decoded: Option<Vec<u8>> = std::str::from_utf8(&content)
+        .ok()
+        .and_then(|content| hex::decode(content.trim()).ok());
+    let bytes: Vec<u8> = decoded.unwrap_or(content);
+    Ok(consensus::deserialize(&bytes)?)
+}
+
+/// Read a PSBT from a file or, if `value` is not a file, from a base64, hex or UR string
+pub fn read_psbt(value: &str) -> Result<PartiallySignedTransaction> {
+    let path: &Path = Path::new(value);
+    if path.is_file() {
+        Ok(PartiallySignedTransaction::from_file(path)?)
+    } else {
+        Ok(PartiallySignedTransaction::from_text(value)?)
+    }
+}
+
+pub fn read_descriptor<P>(path: P) -> Result<Descriptor<DescriptorPublicKey>>
+where
+    P: AsRef<Path>,
+{
+    let content: String = fs::read_to_string(path)?;
+    Ok(Descriptor::from_str(content.trim())?)
+}
 
-    if let Some(passphrase) = &secrets.passphrase {
-        table.add_row(row!["Passphrase (BIP39)", passphrase]);
+/// Parse a cosigner account key with its origin (`[fingerprint/48'/0'/0'/2']xpub...`)
+pub fn parse_cosigner(key: &str) -> Result<(Fingerprint, DerivationPath, ExtendedPubKey)> {
+    match DescriptorPublicKey::from_str(key.trim())? {
+        DescriptorPublicKey::XPub(xkey)
+            if xkey.derivation_path.is_empty() && xkey.wildcard == Wildcard::None =>
+        {
+            match xkey.origin {
+                Some((fingerprint, path)) => Ok((fingerprint, path, xkey.xkey)),
+                None => Err(format!("Cosigner key without origin: {key}").into()),
+            }
+        }
+        _ => Err(format!("Invalid cosigner key (expected `[fingerprint/path]xpub`): {key}").into()),
+    }
+}
+
+pub fn print_multisig_info(info: &MultisigInfo, position: Option<usize>) {
+    println!("Policy: {info}");
+    println!("Cosigners:");
+    for (index, fingerprint) in info.fingerprints().into_iter().enumerate() {
+        if position == Some(index) {
+            println!("  #{} {fingerprint} (this keychain)", index + 1);
+        } else {
+            println!("  #{} {fingerprint}", index + 1);
+        }
     }
+    match position {
+        Some(index) => println!("This keychain is cosigner #{}", index + 1),
+        None => println!("This keychain is not a cosigner"),
+    }
+}
 
-    table.add_row(row!["Seed HEX (BIP39)", secrets.seed_hex]);
-    table.add_row(row!["Network", secrets.network]);
-    table.add_row(row!["Root Key (BIP32)", secrets.root_key]);
-    table.add_row(row!["Fingerprint (BIP32)", secrets.fingerprint]);
+pub fn print_generated_keychains(keychains: &[(String, Fingerprint)]) {
+    let mut table = Table::new();
+
+    table.set_titles(row!["Name", "Fingerprint"]);
+
+    for (name, fingerprint) in keychains.iter() {
+        table.add_row(row![name, fingerprint]);
+    }
 
     table.printstd();
 }
 
-fn output_table_row(network: Network, output: &TxOut) -> String {
+/// Print the account xpubs and first receive addresses as a tree, under the master fingerprint
+pub fn print_derivation_tree(
+    fingerprint: Fingerprint,
+    accounts: &[(DerivationPath, ExtendedPubKey, DerivedAddress)],
+) {
+    println!("[{fingerprint}] m");
+    for (index, (path, xpub, first)) in accounts.iter().enumerate() {
+        let (branch, indent) = if index + 1 == accounts.len() {
+            ("└──", "    ")
+        } else {
+            ("├──", "│   ")
+        };
+        println!("{branch} {path} ({})", first.purpose.script_name());
+        println!("{indent}├── {xpub}");
+        println!("{indent}└── {} {}", first.path, first.address);
+    }
+}
+
+pub fn print_derived_addresses(addresses: &[DerivedAddress]) {
+    let mut table = Table::new();
+
+    table.set_titles(row!["Address", "Path", "Script"]);
+
+    for derived in addresses.iter() {
+        table.add_row(row![
+            derived.address,
+            derived.path,
+            derived.purpose.script_name()
+        ]);
+    }
+
+    table.printstd();
+}
+
+pub fn print_address_verification(
+    address: &Address,
+    verification: &AddressVerification,
+    show_derivation: bool,
+) {
+    if verification.matched {
+        println!("Address {address} belongs to the keychain");
+    } else {
+        println!("Address {address} not found");
+    }
+
+    if show_derivation {
+        if let Some(path) = &verification.path {
+            println!("Path: {path}");
+        }
+        if let Some(script_type) = &verification.script_type {
+            println!("Script type: {script_type}");
+        }
+        if let Some(chain) = &verification.chain {
+            println!("Chain: {chain}");
+        }
+        if let Some(scanned) = &verification.scanned {
+            let chains: Vec<String> = scanned.chains.iter().map(|c| c.to_string()).collect();
+            println!(
+                "Scanned: indexes 0..{} of the {} chains of {}",
+                scanned.max_index,
+                chains.join(" and "),
+                scanned.script_types.join(", ")
+            );
+        }
+    }
+}
+
+fn output_table_row(network: Network, output: &TxOut, ownership: Option<&str>) -> String {
     let mut table = Table::new();
     let format = FormatBuilder::new()
         .column_separator('|')
         .padding(0, 0)
         .build();
     table.set_format(format);
-    table.add_row(row![
-        format!(
-            "{} ",
-            Address::from_script(&output.script_pubkey, network)
-                .expect("Impossible to construct address from output script")
-        ),
-        format!(" {} sat", format::number(output.value as usize))
-    ]);
+    // Non-address scripts (i.e. `OP_RETURN`) are shown as hex, without the ownership
+    let (address, ownership) = match Address::from_script(&output.script_pubkey, network) {
+        Ok(address) => (format!("{address} "), ownership),
+        Err(_) => (format!("{} ", format_script(&output.script_pubkey)), None),
+    };
+    let value = format!(" {} sat", format::number(output.value as usize));
+    match ownership {
+        Some(ownership) => table.add_row(row![address, value, format!(" {ownership}")]),
+        None => table.add_row(row![address, value]),
+    };
     table.to_string()
 }
 
-pub fn print_psbt(psbt: PartiallySignedTransaction, network: Network) {
-    let tx = psbt.extract_tx();
+/// `OP_RETURN <hex>` (data of the pushes) or the hex of the script
+fn format_script(script: &Script) -> String {
+    if script.is_op_return() {
+        let data: Vec<u8> = script
+            .instructions()
+            .skip(1)
+            .filter_map(|instruction| match instruction {
+                Ok(Instruction::PushBytes(push)) => Some(push.as_bytes().to_vec()),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        format!("OP_RETURN {}", hex::encode(data))
+    } else {
+        hex::encode(script.as_bytes())
+    }
+}
+
+/// Predicted txid of the final transaction, with a warning if it may still change
+pub fn format_predicted_txid(psbt: &PartiallySignedTransaction) -> String {
+    let txid = psbt.predicted_txid();
+    if psbt.txid_may_change() {
+        format!("Predicted txid: {txid}\nWARNING: the transaction has legacy inputs, the txid may change until finalized")
+    } else {
+        format!("Predicted txid: {txid}")
+    }
+}
+
+/// Print inputs, outputs and fee, with a warning if the fee rate is higher than `max_fee_rate` (sat/vB)
+///
+/// With the `seed`, the outputs are marked as change or payment. An output is change only if its script
+/// is derived from the seed at the path of its key origin (see [`PsbtUtility::is_own_output`]).
+pub fn print_psbt<C>(
+    psbt: &PartiallySignedTransaction,
+    network: Network,
+    max_fee_rate: f64,
+    seed: Option<&Seed>,
+    secp: &Secp256k1<C>,
+) -> Result<()>
+where
+    C: Signing,
+{
+    // Missing if the inputs have no UTXOs
+    let fee: Option<(u64, u64, f64)> = match (
+        psbt.total_input_value(),
+        psbt.absolute_fee(),
+        psbt.fee_rate(),
+    ) {
+        (Ok(total_input), Ok(fee), Ok(fee_rate)) => Some((total_input, fee, fee_rate)),
+        _ => None,
+    };
+    let total_output: u64 = psbt.total_output_value();
+    // Best-effort: no label if the ownership can't be checked
+    let ownership = |index: usize| -> Option<&'static str> {
+        let seed: &Seed = seed?;
+        match psbt.is_own_output(index, seed, network, secp) {
+            Ok(true) => Some("(mine/change)"),
+            Ok(false) => Some("(payment)"),
+            Err(_) => None,
+        }
+    };
+
+    let tx: &Transaction = &psbt.unsigned_tx;
     let inputs_len: usize = tx.input.len();
     let outputs_len: usize = tx.output.len();
 
@@ -64,14 +434,17 @@ pub fn print_psbt(psbt: PartiallySignedTransaction, network: Network) {
         for (index, input) in tx.input.iter().enumerate() {
             let input = format!("{}", input.previous_output);
             if let Some(output) = tx.output.get(index) {
-                table.add_row(row![input, output_table_row(network, output)]);
+                table.add_row(row![
+                    input,
+                    output_table_row(network, output, ownership(index))
+                ]);
             } else {
                 table.add_row(row![input, ""]);
             }
         }
     } else {
         for (index, output) in tx.output.iter().enumerate() {
-            let output = output_table_row(network, output);
+            let output = output_table_row(network, output, ownership(index));
             if let Some(input) = tx.input.get(index) {
                 table.add_row(row![format!("{}", input.previous_output), output]);
             } else {
@@ -80,5 +453,87 @@ pub fn print_psbt(psbt: PartiallySignedTransaction, network: Network) {
         }
     }
 
+    let total_output = format!("Total: {} sat", format::number(total_output as usize));
+    match fee {
+        Some((total_input, fee, fee_rate)) => {
+            table.add_row(row![
+                format!("Total: {} sat", format::number(total_input as usize)),
+                total_output
+            ]);
+            table.add_row(row![H2->format!(
+                "Fee: {} sat ({fee_rate:.2} sat/vB)",
+                format::number(fee as usize)
+            )]);
+        }
+        None => {
+            table.add_row(row!["Total: unknown", total_output]);
+            table.add_row(row![H2->"Fee: unknown (missing UTXOs of the inputs)"]);
+        }
+    }
+
     table.printstd();
+
+    if let Some((_, _, fee_rate)) = fee {
+        if fee_rate > max_fee_rate {
+            println!(
+                "{}",
+                style(format!(
+                    "WARNING: the fee rate ({fee_rate:.2} sat/vB) is higher than {max_fee_rate} sat/vB"
+                ))
+                .red()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Copy `text` to the clipboard and clear it after [`CLIPBOARD_CLEAR_AFTER`] seconds
+///
+/// Block until cleared: on some platforms (i.e. X11) the content is available only while the process is alive.
+pub fn copy_to_clipboard<S>(text: S, fingerprint: Fingerprint) -> Result<()>
+where
+    S: Into<String>,
+{
+    let text: String = text.into();
+    let mut clipboard = Clipboard::new()?;
+    clipboard.set_text(text.clone())?;
+    println!("Copied to clipboard (fingerprint: {fingerprint})");
+    println!("The clipboard will be cleared in {CLIPBOARD_CLEAR_AFTER} seconds...");
+    thread::sleep(Duration::from_secs(CLIPBOARD_CLEAR_AFTER));
+
+    // Leave it untouched if something else has been copied in the meantime
+    if clipboard
+        .get_text()
+        .map_or(false, |current| current == text)
+    {
+        clipboard.clear()?;
+        println!("Clipboard cleared");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use keechain_core::bitcoin::ScriptBuf;
+
+    use super::*;
+
+    #[test]
+    fn test_output_table_row_non_address() {
+        let op_return = TxOut {
+            value: 0,
+            script_pubkey: ScriptBuf::new_op_return(b"keechain"),
+        };
+        assert_eq!(
+            format_script(&op_return.script_pubkey),
+            "OP_RETURN 6b6565636861696e"
+        );
+
+        // No ownership label without an address
+        let row = output_table_row(Network::Testnet, &op_return, Some("(payment)"));
+        assert!(row.contains("OP_RETURN 6b6565636861696e"));
+        assert!(!row.contains("(payment)"));
+    }
 }
@@ -0,0 +1,42 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Terminal QR codes
+
+use keechain_core::Result;
+use qrcode::render::unicode::Dense1x2;
+use qrcode::{EcLevel, QrCode};
+
+/// Render `data` as a QR code of unicode half blocks (two rows per line)
+///
+/// Colors are inverted (light modules printed as blocks), so it scans on dark terminals.
+pub fn render<T>(data: T) -> Result<String>
+where
+    T: AsRef<[u8]>,
+{
+    let code = QrCode::with_error_correction_level(data, EcLevel::L)?;
+    Ok(code
+        .render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .quiet_zone(true)
+        .build())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn render_qr() {
+        let qr: String = render("bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu").unwrap();
+        let lines: Vec<&str> = qr.lines().collect();
+        let width: usize = lines[0].chars().count();
+        assert!(lines.iter().all(|line| line.chars().count() == width));
+        // Two rows per line
+        assert_eq!(lines.len(), (width + 1) / 2);
+        assert!(qr
+            .chars()
+            .all(|c| matches!(c, ' ' | '█' | '▀' | '▄' | '\n')));
+    }
+}
@@ -1,9 +1,45 @@
 // Copyright (c) 2022-2023 Yuki Kishimoto
 // Distributed under the MIT software license
 
-use std::io::Error;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io;
 use std::path::{Path, PathBuf};
 
+const WRITE_CHECK_FILE_NAME: &str = ".keechain-write-check";
+const CONFIG_FILE_NAME: &str = "config.json";
+
+/// Environment variable with the default directory for the exported files
+pub const EXPORT_DIR_ENV: &str = "KEECHAIN_EXPORT_DIR";
+
+#[derive(Debug)]
+pub enum Error {
+    IO(io::Error),
+    ReadOnly(PathBuf),
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IO(e) => write!(f, "IO: {e}"),
+            Self::ReadOnly(path) => write!(
+                f,
+                "The keychains directory {} is not writable: check its permissions or move it to a writable location",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Self::IO(e)
+    }
+}
+
 pub fn home() -> PathBuf {
     match dirs::home_dir() {
         Some(path) => path,
@@ -11,12 +47,24 @@ pub fn home() -> PathBuf {
     }
 }
 
+/// Default directory for the exported files: [`EXPORT_DIR_ENV`] if set, otherwise the home directory
+pub fn export_dir() -> PathBuf {
+    export_dir_from(std::env::var_os(EXPORT_DIR_ENV))
+}
+
+fn export_dir_from(value: Option<OsString>) -> PathBuf {
+    match value {
+        Some(path) if !path.is_empty() => PathBuf::from(path),
+        _ => home(),
+    }
+}
+
 pub fn keechain() -> Result<PathBuf, Error> {
     Ok(match dirs::home_dir() {
         Some(path) => {
             let path: PathBuf = path.join(".keechain");
             if !path.exists() {
-                std::fs::create_dir_all(path.as_path())?;
+                fs::create_dir_all(path.as_path())?;
             }
             path
         }
@@ -24,10 +72,98 @@ pub fn keechain() -> Result<PathBuf, Error> {
     })
 }
 
+/// Config file (`~/.keechain/config.json`)
+pub fn config_file() -> Result<PathBuf, Error> {
+    Ok(keechain()?.join(CONFIG_FILE_NAME))
+}
+
 pub fn keychains() -> Result<PathBuf, Error> {
-    let path: PathBuf = keechain()?.join("keychains");
+    keychains_in(keechain()?)
+}
+
+/// Keychains directory of a custom data directory
+pub fn keychains_in<P>(datadir: P) -> Result<PathBuf, Error>
+where
+    P: AsRef<Path>,
+{
+    let path: PathBuf = datadir.as_ref().join("keychains");
     if !path.exists() {
-        std::fs::create_dir_all(path.as_path())?;
+        fs::create_dir_all(path.as_path()).map_err(|e| match e.kind() {
+            io::ErrorKind::PermissionDenied => Error::ReadOnly(path.clone()),
+            _ => Error::IO(e),
+        })?;
     }
     Ok(path)
 }
+
+/// Check if new keychains can be written into `path`
+///
+/// Read-only operations (list, open, sign, ...) don't need this check.
+pub fn ensure_writable<P>(path: P) -> Result<(), Error>
+where
+    P: AsRef<Path>,
+{
+    let path: &Path = path.as_ref();
+
+    if fs::metadata(path)?.permissions().readonly() {
+        return Err(Error::ReadOnly(path.to_path_buf()));
+    }
+
+    // Permissions may not tell the whole story (i.e. read-only mount)
+    let check_file: PathBuf = path.join(WRITE_CHECK_FILE_NAME);
+    match OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&check_file)
+    {
+        Ok(_) => {
+            fs::remove_file(check_file)?;
+            Ok(())
+        }
+        Err(_) => Err(Error::ReadOnly(path.to_path_buf())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_dir() {
+        assert_eq!(export_dir_from(None), home());
+        assert_eq!(export_dir_from(Some(OsString::new())), home());
+        assert_eq!(
+            export_dir_from(Some(OsString::from("/tmp/exports"))),
+            PathBuf::from("/tmp/exports")
+        );
+    }
+
+    #[test]
+    fn test_ensure_writable() {
+        let path: PathBuf =
+            std::env::temp_dir().join(format!("keechain-common-test-{}", std::process::id()));
+        fs::create_dir_all(&path).unwrap();
+
+        assert!(ensure_writable(&path).is_ok());
+        assert!(!path.join(WRITE_CHECK_FILE_NAME).exists());
+
+        // Simulate read-only directory
+        let mut permissions = fs::metadata(&path).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&path, permissions.clone()).unwrap();
+
+        assert!(matches!(
+            ensure_writable(&path),
+            Err(Error::ReadOnly(p)) if p == path
+        ));
+
+        // Read-only operations still work
+        assert!(fs::read_dir(&path).is_ok());
+
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        fs::set_permissions(&path, permissions).unwrap();
+        fs::remove_dir_all(&path).unwrap();
+    }
+}
@@ -39,44 +39,11 @@ pub trait Bip32 {
     }
 }
 
-pub fn account_extended_path(
-    purpose: u32,
-    network: Network,
-    account: Option<u32>,
-) -> Result<DerivationPath, Error> {
-    // Path: m/<purpose>'/<coin>'/<account>'
-    let path: Vec<ChildNumber> = vec![
-        ChildNumber::from_hardened_idx(purpose)?,
-        ChildNumber::from_hardened_idx(match network {
-            Network::Bitcoin => 0,
-            _ => 1,
-        })?,
-        ChildNumber::from_hardened_idx(account.unwrap_or(0))?,
-    ];
-    Ok(DerivationPath::from(path))
-}
-
-pub fn extended_path(
-    purpose: u32,
-    network: Network,
-    account: Option<u32>,
-    change: bool,
-) -> Result<DerivationPath, Error> {
-    // Path: m/<purpose>'/<coin>'/<account>'/<change>
-    let base_path = account_extended_path(purpose, network, account)?;
-    let path: [ChildNumber; 1] = [ChildNumber::from_normal_idx(u32::from(change))?];
-    Ok(base_path.extend(path))
-}
+/// The extended private key is used as root key
+impl Bip32 for ExtendedPrivKey {
+    type Err = Error;
 
-pub fn get_path(
-    purpose: u32,
-    network: Network,
-    account: Option<u32>,
-    change: bool,
-    index: Option<u32>,
-) -> Result<DerivationPath, Error> {
-    // Path: m/<purpose>'/<coin>'/<account>'/<change>/<index>
-    let base_path = extended_path(purpose, network, account, change)?;
-    let path: [ChildNumber; 1] = [ChildNumber::from_normal_idx(index.unwrap_or(0))?];
-    Ok(base_path.extend(path))
+    fn to_bip32_root_key(&self, network: Network) -> Result<ExtendedPrivKey, Self::Err> {
+        Ok(ExtendedPrivKey { network, ..*self })
+    }
 }
@@ -0,0 +1,423 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! BIP322 - Generic Signed Message Format
+//!
+//! <https://github.com/bitcoin/bips/blob/master/bip-0322.mediawiki>
+
+use core::fmt;
+
+use bdk::bitcoin::absolute::LockTime;
+use bdk::bitcoin::blockdata::opcodes::all::OP_RETURN;
+use bdk::bitcoin::blockdata::opcodes::OP_0;
+use bdk::bitcoin::consensus::encode;
+use bdk::bitcoin::hashes::{sha256, Hash, HashEngine};
+use bdk::bitcoin::key::TapTweak;
+use bdk::bitcoin::script::Builder;
+use bdk::bitcoin::secp256k1::{
+    self, KeyPair, Message, PublicKey, Secp256k1, SecretKey, Signing, Verification, XOnlyPublicKey,
+};
+use bdk::bitcoin::sighash::{self, EcdsaSighashType, Prevouts, SighashCache, TapSighashType};
+use bdk::bitcoin::{
+    ecdsa, taproot, OutPoint, Script, ScriptBuf, Sequence, Transaction, TxIn, TxOut, WPubkeyHash,
+    Witness,
+};
+
+use crate::util::base64;
+
+const MESSAGE_TAG: &[u8] = b"BIP0322-signed-message";
+
+#[derive(Debug)]
+pub enum Error {
+    Base64(base64::DecodeError),
+    Encode(encode::Error),
+    Secp256k1(secp256k1::Error),
+    Sighash(sighash::Error),
+    Taproot(taproot::Error),
+    Ecdsa(ecdsa::Error),
+    /// Only P2TR key-path signatures are supported
+    NotTaproot,
+    /// Only P2TR (key-path) and P2WPKH signatures are supported
+    UnsupportedScript,
+    /// The signature witness doesn't have the expected number of elements
+    InvalidWitness,
+    /// The public key in the witness doesn't match the address
+    PublicKeyMismatch,
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Base64(e) => write!(f, "Base64: {e}"),
+            Self::Encode(e) => write!(f, "Encode: {e}"),
+            Self::Secp256k1(e) => write!(f, "Secp256k1: {e}"),
+            Self::Sighash(e) => write!(f, "Sighash: {e}"),
+            Self::Taproot(e) => write!(f, "Taproot: {e}"),
+            Self::Ecdsa(e) => write!(f, "ECDSA: {e}"),
+            Self::NotTaproot => write!(f, "Only P2TR addresses are supported"),
+            Self::UnsupportedScript => write!(f, "Only P2TR and P2WPKH addresses are supported"),
+            Self::InvalidWitness => write!(f, "Invalid signature witness"),
+            Self::PublicKeyMismatch => write!(f, "Public key doesn't match the address"),
+        }
+    }
+}
+
+impl From<base64::DecodeError> for Error {
+    fn from(e: base64::DecodeError) -> Self {
+        Self::Base64(e)
+    }
+}
+
+impl From<encode::Error> for Error {
+    fn from(e: encode::Error) -> Self {
+        Self::Encode(e)
+    }
+}
+
+impl From<secp256k1::Error> for Error {
+    fn from(e: secp256k1::Error) -> Self {
+        Self::Secp256k1(e)
+    }
+}
+
+impl From<sighash::Error> for Error {
+    fn from(e: sighash::Error) -> Self {
+        Self::Sighash(e)
+    }
+}
+
+impl From<taproot::Error> for Error {
+    fn from(e: taproot::Error) -> Self {
+        Self::Taproot(e)
+    }
+}
+
+impl From<ecdsa::Error> for Error {
+    fn from(e: ecdsa::Error) -> Self {
+        Self::Ecdsa(e)
+    }
+}
+
+/// Tagged hash of the message (`BIP0322-signed-message`)
+pub fn message_hash(message: &[u8]) -> sha256::Hash {
+    let tag = sha256::Hash::hash(MESSAGE_TAG);
+    let mut engine = sha256::Hash::engine();
+    engine.input(tag.as_ref());
+    engine.input(tag.as_ref());
+    engine.input(message);
+    sha256::Hash::from_engine(engine)
+}
+
+/// Virtual `to_spend` transaction, committing to the message and the address
+pub fn to_spend(script_pubkey: &Script, message: &[u8]) -> Transaction {
+    let script_sig: ScriptBuf = Builder::new()
+        .push_opcode(OP_0)
+        .push_slice(message_hash(message).to_byte_array())
+        .into_script();
+    Transaction {
+        version: 0,
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint::null(),
+            script_sig,
+            sequence: Sequence::ZERO,
+            witness: Witness::new(),
+        }],
+        output: vec![TxOut {
+            value: 0,
+            script_pubkey: script_pubkey.to_owned(),
+        }],
+    }
+}
+
+/// Virtual `to_sign` transaction, spending the `to_spend` output (witness not filled)
+pub fn to_sign(to_spend: &Transaction) -> Transaction {
+    Transaction {
+        version: 0,
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint::new(to_spend.txid(), 0),
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::ZERO,
+            witness: Witness::new(),
+        }],
+        output: vec![TxOut {
+            value: 0,
+            script_pubkey: Builder::new().push_opcode(OP_RETURN).into_script(),
+        }],
+    }
+}
+
+fn key_spend_message(to_spend: &Transaction, hash_ty: TapSighashType) -> Result<Message, Error> {
+    let to_sign: Transaction = to_sign(to_spend);
+    let sighash = SighashCache::new(&to_sign).taproot_key_spend_signature_hash(
+        0,
+        &Prevouts::All(to_spend.output.as_slice()),
+        hash_ty,
+    )?;
+    Ok(Message::from(sighash))
+}
+
+/// Sign `message` with the key-path of the P2TR output of `keypair` (no script tree, as in BIP86)
+///
+/// Return the base64 encoded BIP322 simple signature.
+pub fn sign_taproot<C>(
+    keypair: &KeyPair,
+    message: &[u8],
+    secp: &Secp256k1<C>,
+) -> Result<String, Error>
+where
+    C: Signing + Verification,
+{
+    let tweaked = keypair.tap_tweak(secp, None);
+    let (output_key, _) = tweaked.public_parts();
+    let script_pubkey = ScriptBuf::new_v1_p2tr_tweaked(output_key);
+
+    let to_spend: Transaction = to_spend(&script_pubkey, message);
+    let msg: Message = key_spend_message(&to_spend, TapSighashType::Default)?;
+    let signature = taproot::Signature {
+        sig: secp.sign_schnorr_no_aux_rand(&msg, &tweaked.to_inner()),
+        hash_ty: TapSighashType::Default,
+    };
+
+    let witness: Witness = Witness::from_slice(&[signature.to_vec()]);
+    Ok(base64::encode(encode::serialize(&witness)))
+}
+
+/// Verify a BIP322 simple signature of a P2TR key-path spend
+pub fn verify_taproot<C>(
+    script_pubkey: &Script,
+    message: &[u8],
+    signature: &str,
+    secp: &Secp256k1<C>,
+) -> Result<(), Error>
+where
+    C: Verification,
+{
+    if !script_pubkey.is_v1_p2tr() {
+        return Err(Error::NotTaproot);
+    }
+    let output_key = XOnlyPublicKey::from_slice(&script_pubkey.as_bytes()[2..])?;
+
+    let witness: Witness = encode::deserialize(&base64::decode(signature)?)?;
+    let signature = match witness.to_vec().as_slice() {
+        [signature] => taproot::Signature::from_slice(signature)?,
+        _ => return Err(Error::InvalidWitness),
+    };
+
+    let to_spend: Transaction = to_spend(script_pubkey, message);
+    let msg: Message = key_spend_message(&to_spend, signature.hash_ty)?;
+    Ok(secp.verify_schnorr(&signature.sig, &msg, &output_key)?)
+}
+
+fn p2wpkh_script_pubkey(public_key: &PublicKey) -> ScriptBuf {
+    ScriptBuf::new_v0_p2wpkh(&WPubkeyHash::hash(&public_key.serialize()))
+}
+
+fn p2wpkh_message(to_spend: &Transaction, hash_ty: EcdsaSighashType) -> Result<Message, Error> {
+    let script_code: ScriptBuf = to_spend.output[0]
+        .script_pubkey
+        .p2wpkh_script_code()
+        .ok_or(Error::UnsupportedScript)?;
+    let to_sign: Transaction = to_sign(to_spend);
+    let sighash = SighashCache::new(&to_sign).segwit_signature_hash(0, &script_code, 0, hash_ty)?;
+    Ok(Message::from(sighash))
+}
+
+/// Sign `message` with the key of the P2WPKH output of `secret_key`
+///
+/// Return the base64 encoded BIP322 simple signature.
+pub fn sign_p2wpkh<C>(
+    secret_key: &SecretKey,
+    message: &[u8],
+    secp: &Secp256k1<C>,
+) -> Result<String, Error>
+where
+    C: Signing,
+{
+    let public_key = PublicKey::from_secret_key(secp, secret_key);
+    let script_pubkey: ScriptBuf = p2wpkh_script_pubkey(&public_key);
+
+    let to_spend: Transaction = to_spend(&script_pubkey, message);
+    let msg: Message = p2wpkh_message(&to_spend, EcdsaSighashType::All)?;
+    let signature = ecdsa::Signature::sighash_all(secp.sign_ecdsa(&msg, secret_key));
+
+    let witness: Witness =
+        Witness::from_slice(&[signature.to_vec(), public_key.serialize().to_vec()]);
+    Ok(base64::encode(encode::serialize(&witness)))
+}
+
+/// Verify a BIP322 simple signature of a P2WPKH output
+pub fn verify_p2wpkh<C>(
+    script_pubkey: &Script,
+    message: &[u8],
+    signature: &str,
+    secp: &Secp256k1<C>,
+) -> Result<(), Error>
+where
+    C: Verification,
+{
+    if !script_pubkey.is_v0_p2wpkh() {
+        return Err(Error::UnsupportedScript);
+    }
+
+    let witness: Witness = encode::deserialize(&base64::decode(signature)?)?;
+    let (signature, public_key) = match witness.to_vec().as_slice() {
+        [signature, public_key] => (
+            ecdsa::Signature::from_slice(signature)?,
+            PublicKey::from_slice(public_key)?,
+        ),
+        _ => return Err(Error::InvalidWitness),
+    };
+    if p2wpkh_script_pubkey(&public_key).as_script() != script_pubkey {
+        return Err(Error::PublicKeyMismatch);
+    }
+
+    let to_spend: Transaction = to_spend(script_pubkey, message);
+    let msg: Message = p2wpkh_message(&to_spend, signature.hash_ty)?;
+    Ok(secp.verify_ecdsa(&msg, &signature.sig, &public_key)?)
+}
+
+/// Verify a BIP322 simple signature (P2TR key-path or P2WPKH)
+pub fn verify<C>(
+    script_pubkey: &Script,
+    message: &[u8],
+    signature: &str,
+    secp: &Secp256k1<C>,
+) -> Result<(), Error>
+where
+    C: Verification,
+{
+    if script_pubkey.is_v1_p2tr() {
+        verify_taproot(script_pubkey, message, signature, secp)
+    } else if script_pubkey.is_v0_p2wpkh() {
+        verify_p2wpkh(script_pubkey, message, signature, secp)
+    } else {
+        Err(Error::UnsupportedScript)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::str::FromStr;
+
+    use bdk::bitcoin::secp256k1::SecretKey;
+    use bdk::bitcoin::{Address, Network};
+
+    use super::*;
+
+    #[test]
+    fn test_message_hash() {
+        assert_eq!(
+            message_hash(b"").to_string(),
+            "c90c269c4f8fcbe6880f72a721ddfbf1914268a794cbb21cfafee13770ae19f1"
+        );
+        assert_eq!(
+            message_hash(b"Hello World").to_string(),
+            "f0eb03b1a75ac6d9847f55c624a99169b5dccba2a31f5b23bea77ba270de0a7a"
+        );
+    }
+
+    #[test]
+    fn test_transactions() {
+        let address = Address::from_str("bc1q9vza2e8x573nczrlzms0wvx3gsqjx7vavgkx0l")
+            .unwrap()
+            .require_network(Network::Bitcoin)
+            .unwrap();
+
+        let tx = to_spend(&address.script_pubkey(), b"");
+        assert_eq!(
+            tx.txid().to_string(),
+            "c5680aa69bb8d860bf82d4e9cd3504b55dde018de765a91bb566283c545a99a7"
+        );
+        assert_eq!(
+            to_sign(&tx).txid().to_string(),
+            "1e9654e951a5ba44c8604c4de6c67fd78a27e81dcadcfe1edf638ba3aaebaed6"
+        );
+
+        let tx = to_spend(&address.script_pubkey(), b"Hello World");
+        assert_eq!(
+            tx.txid().to_string(),
+            "b79d196740ad5217771c1098fc4a4b51e0535c32236c71f1ea4d61a2d603352b"
+        );
+        assert_eq!(
+            to_sign(&tx).txid().to_string(),
+            "88737ae86f2077145f93cc4b153ae9a1cb8d56afa511988c149c5c8c9d93bddf"
+        );
+    }
+
+    #[test]
+    fn test_sign_verify_taproot() {
+        let secp = Secp256k1::new();
+
+        // BIP322 test vector (signed with SIGHASH_ALL)
+        let address =
+            Address::from_str("bc1ppv609nr0vr25u07u95waq5lucwfm6tde4nydujnu8npg4q75mr5sxq8lt3")
+                .unwrap()
+                .require_network(Network::Bitcoin)
+                .unwrap();
+        let script_pubkey = address.script_pubkey();
+        let vector = "AUHd69PrJQEv+oKTfZ8l+WROBHuy9HKrbFCJu7U1iK2iiEy1vMU5EfMtjc+VSHM7aU0SDbak5IUZRVno2P5mjSafAQ==";
+        verify_taproot(&script_pubkey, b"Hello World", vector, &secp).unwrap();
+        assert!(verify_taproot(&script_pubkey, b"Hello World!", vector, &secp).is_err());
+
+        // Same key (L3VFeEujGtevx9w18HD1fhRbCH67Az2dpCymeRE1SoPK6XQtaN2k)
+        let secret_key =
+            SecretKey::from_str("bb051cd0dda0246f33c5a9e133ebd8e7bc02a92af6c41adc131ccd7826c5b004")
+                .unwrap();
+        let keypair = KeyPair::from_secret_key(&secp, &secret_key);
+        let signature = sign_taproot(&keypair, b"Hello World", &secp).unwrap();
+        verify_taproot(&script_pubkey, b"Hello World", &signature, &secp).unwrap();
+        assert!(verify_taproot(&script_pubkey, b"", &signature, &secp).is_err());
+
+        // Only P2TR
+        let address = Address::from_str("bc1q9vza2e8x573nczrlzms0wvx3gsqjx7vavgkx0l")
+            .unwrap()
+            .require_network(Network::Bitcoin)
+            .unwrap();
+        assert!(matches!(
+            verify_taproot(&address.script_pubkey(), b"Hello World", vector, &secp),
+            Err(Error::NotTaproot)
+        ));
+    }
+
+    #[test]
+    fn test_sign_verify_p2wpkh() {
+        let secp = Secp256k1::new();
+
+        // BIP322 test vectors
+        let address = Address::from_str("bc1q9vza2e8x573nczrlzms0wvx3gsqjx7vavgkx0l")
+            .unwrap()
+            .require_network(Network::Bitcoin)
+            .unwrap();
+        let script_pubkey = address.script_pubkey();
+        let empty = "AkcwRAIgM2gBAQqvZX15ZiysmKmQpDrG83avLIT492QBzLnQIxYCIBaTpOaD20qRlEylyxFSeEA2ba9YOixpX8z46TSDtS40ASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI=";
+        let hello = "AkcwRAIgZRfIY3p7/DoVTty6YZbWS71bc5Vct9p9Fia83eRmw2QCICK/ENGfwLtptFluMGs2KsqoNSk89pO7F29zJLUx9a/sASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI=";
+        verify_p2wpkh(&script_pubkey, b"", empty, &secp).unwrap();
+        verify(&script_pubkey, b"Hello World", hello, &secp).unwrap();
+        assert!(verify(&script_pubkey, b"", hello, &secp).is_err());
+
+        // Same key (L3VFeEujGtevx9w18HD1fhRbCH67Az2dpCymeRE1SoPK6XQtaN2k)
+        let secret_key =
+            SecretKey::from_str("bb051cd0dda0246f33c5a9e133ebd8e7bc02a92af6c41adc131ccd7826c5b004")
+                .unwrap();
+        let signature = sign_p2wpkh(&secret_key, b"Hello World", &secp).unwrap();
+        verify(&script_pubkey, b"Hello World", &signature, &secp).unwrap();
+
+        // Taproot witness for a P2WPKH address
+        let other =
+            Address::from_str("bc1ppv609nr0vr25u07u95waq5lucwfm6tde4nydujnu8npg4q75mr5sxq8lt3")
+                .unwrap()
+                .require_network(Network::Bitcoin)
+                .unwrap();
+        let keypair = KeyPair::from_secret_key(&secp, &secret_key);
+        let taproot = sign_taproot(&keypair, b"Hello World", &secp).unwrap();
+        verify(&other.script_pubkey(), b"Hello World", &taproot, &secp).unwrap();
+        assert!(matches!(
+            verify(&script_pubkey, b"Hello World", &taproot, &secp),
+            Err(Error::InvalidWitness)
+        ));
+    }
+}
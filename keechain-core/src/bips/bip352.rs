@@ -0,0 +1,229 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! BIP352 - Silent Payments
+//!
+//! <https://github.com/bitcoin/bips/blob/master/bip-0352.mediawiki>
+
+use core::fmt;
+
+use bdk::bitcoin::bech32::{self, u5, ToBase32, Variant};
+use bdk::bitcoin::secp256k1::{PublicKey, Secp256k1, SecretKey, Signing};
+use bdk::bitcoin::Network;
+
+use super::bip32::{self, Bip32, ChildNumber, DerivationPath, ExtendedPrivKey};
+use super::bip44;
+
+const PURPOSE: u32 = 352;
+const SCAN_KEY_INDEX: u32 = 1;
+const SPEND_KEY_INDEX: u32 = 0;
+const HRP_MAINNET: &str = "sp";
+const HRP_TESTNET: &str = "tsp";
+const VERSION: u8 = 0;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    BIP32(bip32::Error),
+    Bech32(bech32::Error),
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BIP32(e) => write!(f, "BIP32: {e}"),
+            Self::Bech32(e) => write!(f, "Bech32: {e}"),
+        }
+    }
+}
+
+impl From<bip32::Error> for Error {
+    fn from(e: bip32::Error) -> Self {
+        Self::BIP32(e)
+    }
+}
+
+impl From<bech32::Error> for Error {
+    fn from(e: bech32::Error) -> Self {
+        Self::Bech32(e)
+    }
+}
+
+/// Silent payment scan and spend keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SilentPaymentKeys {
+    /// Needed to detect incoming payments (can't spend)
+    pub scan_key: SecretKey,
+    pub spend_key: SecretKey,
+}
+
+impl SilentPaymentKeys {
+    /// Derive keys at `m/352'/<coin>'/<account>'/1'/0` (scan) and `m/352'/<coin>'/<account>'/0'/0` (spend)
+    pub fn from_root<C>(
+        root: &ExtendedPrivKey,
+        network: Network,
+        account: u32,
+        secp: &Secp256k1<C>,
+    ) -> Result<Self, Error>
+    where
+        C: Signing,
+    {
+        let account_path: Vec<ChildNumber> = vec![
+            ChildNumber::from_hardened_idx(PURPOSE)?,
+            ChildNumber::from_hardened_idx(bip44::coin_type(network))?,
+            ChildNumber::from_hardened_idx(account)?,
+        ];
+        let account_key: ExtendedPrivKey =
+            root.derive_priv(secp, &DerivationPath::from(account_path))?;
+
+        let derive = |index: u32| -> Result<SecretKey, Error> {
+            let path: DerivationPath = DerivationPath::from(vec![
+                ChildNumber::from_hardened_idx(index)?,
+                ChildNumber::from_normal_idx(0)?,
+            ]);
+            Ok(account_key.derive_priv(secp, &path)?.private_key)
+        };
+
+        Ok(Self {
+            scan_key: derive(SCAN_KEY_INDEX)?,
+            spend_key: derive(SPEND_KEY_INDEX)?,
+        })
+    }
+
+    pub fn scan_public_key<C>(&self, secp: &Secp256k1<C>) -> PublicKey
+    where
+        C: Signing,
+    {
+        PublicKey::from_secret_key(secp, &self.scan_key)
+    }
+
+    pub fn spend_public_key<C>(&self, secp: &Secp256k1<C>) -> PublicKey
+    where
+        C: Signing,
+    {
+        PublicKey::from_secret_key(secp, &self.spend_key)
+    }
+
+    /// Silent payment address (`sp1q...` on mainnet, `tsp1q...` otherwise)
+    pub fn address<C>(&self, network: Network, secp: &Secp256k1<C>) -> Result<String, Error>
+    where
+        C: Signing,
+    {
+        encode_address(
+            &self.scan_public_key(secp),
+            &self.spend_public_key(secp),
+            network,
+        )
+    }
+}
+
+pub trait Bip352: Sized + Bip32
+where
+    Error: From<<Self as Bip32>::Err>,
+{
+    /// Derive silent payment keys for `account`
+    fn derive_silent_payment_keys<C>(
+        &self,
+        network: Network,
+        account: u32,
+        secp: &Secp256k1<C>,
+    ) -> Result<SilentPaymentKeys, Error>
+    where
+        C: Signing,
+    {
+        let root: ExtendedPrivKey = self.to_bip32_root_key(network)?;
+        SilentPaymentKeys::from_root(&root, network, account, secp)
+    }
+}
+
+/// Encode a version 0 silent payment address
+pub fn encode_address(
+    scan_public_key: &PublicKey,
+    spend_public_key: &PublicKey,
+    network: Network,
+) -> Result<String, Error> {
+    let hrp: &str = match network {
+        Network::Bitcoin => HRP_MAINNET,
+        _ => HRP_TESTNET,
+    };
+
+    let mut payload: Vec<u8> = Vec::with_capacity(66);
+    payload.extend_from_slice(&scan_public_key.serialize());
+    payload.extend_from_slice(&spend_public_key.serialize());
+
+    let mut data: Vec<u5> = vec![u5::try_from_u8(VERSION)?];
+    data.extend(payload.to_base32());
+
+    Ok(bech32::encode(hrp, data, Variant::Bech32m)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bip39::Mnemonic;
+
+    use super::*;
+    use crate::types::Seed;
+
+    #[test]
+    fn test_encode_address() {
+        // BIP352 test vectors
+        let secp = Secp256k1::new();
+        let scan_key =
+            SecretKey::from_str("0f694e068028a717f8af6b9411f9a133dd3565258714cc226594b34db90c1f2c")
+                .unwrap();
+        let spend_key =
+            SecretKey::from_str("9d6ad855ce3417ef84e836892e5a56392bfba05fa5d97ccea30e266f540e08b3")
+                .unwrap();
+        let keys = SilentPaymentKeys {
+            scan_key,
+            spend_key,
+        };
+        assert_eq!(
+            keys.address(Network::Bitcoin, &secp).unwrap(),
+            "sp1qqgste7k9hx0qftg6qmwlkqtwuy6cycyavzmzj85c6qdfhjdpdjtdgqjuexzk6murw56suy3e0rd2cgqvycxttddwsvgxe2usfpxumr70xc9pkqwv"
+        );
+    }
+
+    #[test]
+    fn test_derive_silent_payment_keys() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str(
+            "leader monkey parrot ring guide accident before fence cannon height naive bean",
+        )
+        .unwrap();
+        let seed = Seed::from_mnemonic(mnemonic);
+
+        let keys = seed
+            .derive_silent_payment_keys(Network::Bitcoin, 0, &secp)
+            .unwrap();
+        assert_eq!(
+            keys.scan_key.display_secret().to_string(),
+            "7386d1a53b66efdc83815b8d4dcf2848914c903a204cfa4fd485388c817b87de"
+        );
+        assert_eq!(
+            keys.spend_public_key(&secp).to_string(),
+            "029b93cdffffbc8361c25638447df66443a8511d0058ae5f52328d90fea55c0c23"
+        );
+        assert_eq!(
+            keys.address(Network::Bitcoin, &secp).unwrap(),
+            "sp1qqtpdv3hmm4qw2ujef2mm05y9xwyadlnct9peqrfv5d04nnqdmn03yq5mj0xlllausdsuy43cg37lvezr4pg36qzc4e04yv5djrl22hqvyvh0em2k"
+        );
+
+        let testnet = seed
+            .derive_silent_payment_keys(Network::Testnet, 0, &secp)
+            .unwrap();
+        assert_eq!(
+            testnet.address(Network::Testnet, &secp).unwrap(),
+            "tsp1qqdvsq7e860ejc0nkrs03zere7qqcwxdvncf60mak9j2cl83k9yr22qnuppqs826dxv7679jzpqe4w2xqec0cl6hke3sa5gy4p75v9pls7uu2vcmf"
+        );
+
+        // Different account, different keys
+        let other = seed
+            .derive_silent_payment_keys(Network::Bitcoin, 1, &secp)
+            .unwrap();
+        assert_ne!(keys, other);
+    }
+}
@@ -10,7 +10,7 @@ use bdk::bitcoin::hashes::{sha512, Hash, HashEngine};
 #[cfg(all(feature = "sysinfo", not(target_vendor = "apple")))]
 use bdk::bitcoin::secp256k1::rand;
 use bdk::bitcoin::secp256k1::rand::rngs::OsRng;
-use bdk::bitcoin::secp256k1::rand::{RngCore, SeedableRng};
+use bdk::bitcoin::secp256k1::rand::{CryptoRng, RngCore, SeedableRng};
 pub use bip39::*;
 use rand_chacha::ChaCha20Rng;
 #[cfg(all(feature = "sysinfo", not(target_vendor = "apple")))]
@@ -19,6 +19,40 @@ use sysinfo::{System, SystemExt};
 use crate::types::WordCount;
 use crate::util::time;
 
+/// How custom entropy (i.e. dice rolls) is used to generate a mnemonic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EntropyMode {
+    /// Mix custom entropy with OS, CSPRNG and system entropy
+    #[default]
+    Mixed,
+    /// Use **only** the custom entropy
+    ///
+    /// The same dice rolls always produce the same mnemonic, so users can independently recompute it
+    /// and verify that the RNG has not been backdoored. See [`deterministic_entropy`].
+    Deterministic,
+    /// Like [`EntropyMode::Mixed`], but refuse to generate without at least [`min_dice_rolls`]
+    /// of custom entropy (never rely on the software RNG alone)
+    RequireCustom,
+}
+
+impl EntropyMode {
+    /// Check if at least [`min_dice_rolls`] of custom entropy are required
+    pub fn requires_custom_entropy(&self) -> bool {
+        matches!(self, Self::Deterministic | Self::RequireCustom)
+    }
+}
+
+/// Minimum number of dice rolls (6-sided) needed to cover the entropy of a mnemonic
+pub fn min_dice_rolls(word_count: WordCount) -> usize {
+    let bits: f64 = (word_count.as_u32() * 32 / 3) as f64;
+    (bits / 6f64.log2()).ceil() as usize
+}
+
+/// Bits of entropy of the mnemonic (128 for 12 words, 256 for 24 words)
+pub fn entropy_bits(mnemonic: &Mnemonic) -> usize {
+    mnemonic.word_count() * 32 / 3
+}
+
 pub fn entropy(word_count: WordCount, custom: Option<Vec<u8>>) -> Vec<u8> {
     let mut h = HmacEngine::<sha512::Hash>::new(b"keechain-entropy");
 
@@ -86,7 +120,82 @@ pub fn entropy(word_count: WordCount, custom: Option<Vec<u8>>) -> Vec<u8> {
         h.input(&custom);
     }
 
+    finalize(h, word_count)
+}
+
+/// Deterministic entropy derived **only** from custom entropy (no OS/system entropy)
+///
+/// `entropy = HMAC-SHA512(key = "keechain-deterministic-entropy", msg = custom)[0..len]`,
+/// where `len` is 16, 24 or 32 bytes for 12, 18 or 24 words.
+pub fn deterministic_entropy(word_count: WordCount, custom: &[u8]) -> Vec<u8> {
+    let mut h = HmacEngine::<sha512::Hash>::new(b"keechain-deterministic-entropy");
+    h.input(custom);
+    finalize(h, word_count)
+}
+
+/// Build a mnemonic from raw entropy (16, 20, 24, 28 or 32 bytes)
+pub fn mnemonic_from_bytes<T>(entropy: T) -> Result<Mnemonic, Error>
+where
+    T: AsRef<[u8]>,
+{
+    Mnemonic::from_entropy(entropy.as_ref())
+}
+
+/// Generate a mnemonic using entropy from `rng`
+pub fn generate<R>(word_count: WordCount, rng: &mut R) -> Mnemonic
+where
+    R: RngCore + CryptoRng,
+{
+    let mut entropy: Vec<u8> = vec![0u8; (word_count.as_u32() * 4 / 3) as usize];
+    rng.fill_bytes(&mut entropy);
+    mnemonic_from_bytes(entropy).expect("valid entropy length")
+}
+
+fn finalize(h: HmacEngine<sha512::Hash>, word_count: WordCount) -> Vec<u8> {
     let entropy: [u8; 64] = Hmac::from_engine(h).to_byte_array();
     let len: u32 = word_count.as_u32() * 4 / 3;
     entropy[0..len as usize].to_vec()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mnemonic_from_bytes() {
+        let mnemonic = mnemonic_from_bytes([0u8; 16]).unwrap();
+        assert_eq!(mnemonic.to_string(), "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about");
+        assert!(mnemonic_from_bytes([0u8; 15]).is_err());
+    }
+
+    #[test]
+    fn test_generate() {
+        for word_count in [WordCount::W12, WordCount::W18, WordCount::W24] {
+            let mut rng = ChaCha20Rng::seed_from_u64(42);
+            let mnemonic = generate(word_count, &mut rng);
+            assert_eq!(mnemonic.word_count(), word_count.as_u32() as usize);
+            assert_eq!(entropy_bits(&mnemonic), mnemonic.to_entropy().len() * 8);
+
+            // Same RNG state, same mnemonic
+            let mut rng = ChaCha20Rng::seed_from_u64(42);
+            assert_eq!(generate(word_count, &mut rng), mnemonic);
+        }
+    }
+
+    #[test]
+    fn test_min_dice_rolls() {
+        assert_eq!(min_dice_rolls(WordCount::W12), 50);
+        assert_eq!(min_dice_rolls(WordCount::W18), 75);
+        assert_eq!(min_dice_rolls(WordCount::W24), 100);
+    }
+
+    #[test]
+    fn test_deterministic_entropy() {
+        let rolls: Vec<u8> = (0..100).map(|i| i % 6 + 1).collect();
+        let first = deterministic_entropy(WordCount::W24, &rolls);
+        assert_eq!(first.len(), 32);
+        assert_eq!(first, deterministic_entropy(WordCount::W24, &rolls));
+        assert_eq!(deterministic_entropy(WordCount::W12, &rolls), first[0..16]);
+        assert_ne!(first, entropy(WordCount::W24, Some(rolls)));
+    }
+}
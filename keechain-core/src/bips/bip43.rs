@@ -9,8 +9,9 @@ use std::{fmt, fmt::Display, str::FromStr};
 
 use bdk::bitcoin::Network;
 
-use super::bip32::{self, DerivationPath};
-use super::bip48::{self, ScriptType};
+use super::bip32::{self, DerivationPath, Fingerprint};
+use super::bip44;
+use super::bip48::ScriptType;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 #[derive(Debug)]
@@ -59,12 +60,16 @@ impl Purpose {
         network: Network,
         account: Option<u32>,
     ) -> Result<DerivationPath, bip32::Error> {
-        match self {
-            Self::BIP44 | Self::BIP49 | Self::BIP84 | Self::BIP86 => Ok(
-                bip32::account_extended_path(self.as_u32(), network, account)?,
-            ),
-            Self::BIP48 { script } => Ok(bip48::account_extended_path(network, account, *script)?),
-        }
+        bip44::account_path(*self, network, account)
+    }
+
+    /// Account path with a non-standard coin type (see [`bip44::account_path_with_coin_type`])
+    pub fn to_account_extended_path_with_coin_type(
+        &self,
+        coin_type: u32,
+        account: Option<u32>,
+    ) -> Result<DerivationPath, bip32::Error> {
+        bip44::account_path_with_coin_type(*self, coin_type, account)
     }
 
     pub fn as_u32(&self) -> u32 {
@@ -76,6 +81,37 @@ impl Purpose {
             Self::BIP86 => 86,
         }
     }
+
+    /// Human readable script name
+    pub fn script_name(&self) -> &'static str {
+        match self {
+            Self::BIP44 => "legacy",
+            Self::BIP48 { script } => match script {
+                ScriptType::P2SHWSH => "multisig-nested-segwit",
+                ScriptType::P2WSH => "multisig-native-segwit",
+                ScriptType::P2TR => "multisig-taproot",
+            },
+            Self::BIP49 => "nested-segwit",
+            Self::BIP84 => "native-segwit",
+            Self::BIP86 => "taproot",
+        }
+    }
+
+    /// One-line summary of the derivation used for an export
+    ///
+    /// Example: `native-segwit account 0 -> m/84'/0'/0' [91ef223d] network=bitcoin`
+    pub fn derivation_summary(
+        &self,
+        account: u32,
+        network: Network,
+        root_fingerprint: Fingerprint,
+    ) -> Result<String, bip32::Error> {
+        let path: DerivationPath = self.to_account_extended_path(network, Some(account))?;
+        Ok(format!(
+            "{} account {account} -> {path} [{root_fingerprint}] network={network}",
+            self.script_name()
+        ))
+    }
 }
 
 impl Display for Purpose {
@@ -136,3 +172,25 @@ impl<'de> Deserialize<'de> for Purpose {
         Purpose::from_str(&purpose).map_err(serde::de::Error::custom)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derivation_summary() {
+        let fingerprint = Fingerprint::from_str("91ef223d").unwrap();
+        assert_eq!(
+            Purpose::BIP84
+                .derivation_summary(0, Network::Bitcoin, fingerprint)
+                .unwrap(),
+            "native-segwit account 0 -> m/84'/0'/0' [91ef223d] network=bitcoin".to_string()
+        );
+        assert_eq!(
+            Purpose::BIP86
+                .derivation_summary(2, Network::Testnet, fingerprint)
+                .unwrap(),
+            "taproot account 2 -> m/86'/1'/2' [91ef223d] network=testnet".to_string()
+        );
+    }
+}
@@ -8,9 +8,12 @@
 use core::fmt;
 use core::slice::Iter;
 
+use bdk::bitcoin::Network;
+
 use super::bip32::{self, ChildNumber, DerivationPath};
 use super::bip43::Purpose;
 use super::bip48::ScriptType;
+use crate::types::Index;
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum UnsupportedDerivationPathError {
@@ -68,6 +71,50 @@ impl From<bip32::Error> for Error {
     }
 }
 
+/// Coin type: `0'` for mainnet, `1'` for testnet, signet and regtest
+pub fn coin_type(network: Network) -> u32 {
+    match network {
+        Network::Bitcoin => 0,
+        _ => 1,
+    }
+}
+
+/// Build the account path for a [`Purpose`]
+///
+/// Path: `m/<purpose>'/<coin>'/<account>'` (`m/48'/<coin>'/<account>'/<script_type>'` for BIP48)
+pub fn account_path(
+    purpose: Purpose,
+    network: Network,
+    account: Option<u32>,
+) -> Result<DerivationPath, bip32::Error> {
+    account_path_with_coin_type(purpose, coin_type(network), account)
+}
+
+/// Build the account path for a [`Purpose`] with an explicit coin type
+///
+/// Only to recover wallets that used a non-standard coin type: use [`account_path`] otherwise.
+pub fn account_path_with_coin_type(
+    purpose: Purpose,
+    coin_type: u32,
+    account: Option<u32>,
+) -> Result<DerivationPath, bip32::Error> {
+    // Account is hardened: must be within the 0..=(2^31 - 1) range
+    let account: u32 = account.unwrap_or(0);
+    if Index::new(account).is_err() {
+        return Err(bip32::Error::InvalidChildNumber(account));
+    }
+
+    let mut path: Vec<ChildNumber> = vec![
+        ChildNumber::from_hardened_idx(purpose.as_u32())?,
+        ChildNumber::from_hardened_idx(coin_type)?,
+        ChildNumber::from_hardened_idx(account)?,
+    ];
+    if let Purpose::BIP48 { script } = purpose {
+        path.push(ChildNumber::from_hardened_idx(script.as_u32())?);
+    }
+    Ok(DerivationPath::from(path))
+}
+
 pub struct ExtendedPath {
     pub purpose: Purpose,
     pub coin: u32,
@@ -165,6 +212,79 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_account_path() {
+        let purposes = [
+            (Purpose::BIP44, "m/44'/{coin}'/{account}'"),
+            (Purpose::BIP49, "m/49'/{coin}'/{account}'"),
+            (Purpose::BIP84, "m/84'/{coin}'/{account}'"),
+            (Purpose::BIP86, "m/86'/{coin}'/{account}'"),
+            (
+                Purpose::BIP48 {
+                    script: ScriptType::P2SHWSH,
+                },
+                "m/48'/{coin}'/{account}'/1'",
+            ),
+            (
+                Purpose::BIP48 {
+                    script: ScriptType::P2WSH,
+                },
+                "m/48'/{coin}'/{account}'/2'",
+            ),
+            (
+                Purpose::BIP48 {
+                    script: ScriptType::P2TR,
+                },
+                "m/48'/{coin}'/{account}'/3'",
+            ),
+        ];
+        let networks = [
+            (Network::Bitcoin, "0"),
+            (Network::Testnet, "1"),
+            (Network::Signet, "1"),
+            (Network::Regtest, "1"),
+        ];
+
+        for (purpose, expected) in purposes.into_iter() {
+            for (network, coin) in networks.into_iter() {
+                for account in [None, Some(0), Some(7)] {
+                    let expected = expected
+                        .replace("{coin}", coin)
+                        .replace("{account}", &account.unwrap_or(0).to_string());
+                    let path = account_path(purpose, network, account).unwrap();
+                    assert_eq!(path.to_string(), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_account_path_out_of_range() {
+        assert!(account_path(Purpose::BIP84, Network::Bitcoin, Some(0x7FFFFFFF)).is_ok());
+        assert_eq!(
+            account_path(Purpose::BIP84, Network::Bitcoin, Some(0x80000000)),
+            Err(bip32::Error::InvalidChildNumber(0x80000000))
+        );
+    }
+
+    #[test]
+    fn test_account_path_with_coin_type() {
+        assert_eq!(
+            account_path_with_coin_type(Purpose::BIP84, 0, None).unwrap(),
+            account_path(Purpose::BIP84, Network::Bitcoin, None).unwrap()
+        );
+        assert_eq!(
+            account_path_with_coin_type(Purpose::BIP84, 2, Some(1))
+                .unwrap()
+                .to_string(),
+            "m/84'/2'/1'"
+        );
+        assert_eq!(
+            account_path_with_coin_type(Purpose::BIP84, 0x80000000, None),
+            Err(bip32::Error::InvalidChildNumber(0x80000000))
+        );
+    }
+
     #[test]
     fn test_extended_path_parsing() {
         // BIP44
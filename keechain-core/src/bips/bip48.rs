@@ -8,7 +8,8 @@
 use bdk::bitcoin::bip32::{ChildNumber, DerivationPath, Error};
 use bdk::bitcoin::Network;
 
-use super::bip32;
+use super::bip43::Purpose;
+use super::bip44;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[repr(u8)]
@@ -30,9 +31,13 @@ pub fn account_extended_path(
     script_type: ScriptType,
 ) -> Result<DerivationPath, Error> {
     // Path: m/<purpose>'/<coin_type>'/<account>'/<script_type>'
-    let base_path = bip32::account_extended_path(48, network, account)?;
-    let path: [ChildNumber; 1] = [ChildNumber::from_hardened_idx(script_type.as_u32())?];
-    Ok(base_path.extend(path))
+    bip44::account_path(
+        Purpose::BIP48 {
+            script: script_type,
+        },
+        network,
+        account,
+    )
 }
 
 pub fn extended_path(
@@ -66,6 +71,7 @@ pub fn get_path(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::MAX_INDEX;
     use crate::Result;
 
     #[test]
@@ -98,6 +104,22 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_account_out_of_range() {
+        assert!(get_path(
+            Network::Bitcoin,
+            Some(MAX_INDEX - 1),
+            ScriptType::P2WSH,
+            false,
+            None
+        )
+        .is_ok());
+        assert!(matches!(
+            account_extended_path(Network::Bitcoin, Some(MAX_INDEX), ScriptType::P2WSH),
+            Err(Error::InvalidChildNumber(MAX_INDEX))
+        ));
+    }
+
     #[test]
     fn test_p2shwsh_path() -> Result<()> {
         assert_eq!(
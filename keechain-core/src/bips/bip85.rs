@@ -10,17 +10,25 @@ use core::fmt;
 use bdk::bitcoin::bip32;
 use bdk::bitcoin::hashes::hmac::{Hmac, HmacEngine};
 use bdk::bitcoin::hashes::{sha512, Hash, HashEngine};
-use bdk::bitcoin::secp256k1::{Secp256k1, Signing};
-use bdk::bitcoin::Network;
+use bdk::bitcoin::secp256k1::{self, Secp256k1, SecretKey, Signing};
+use bdk::bitcoin::{Network, PrivateKey};
 use bip39::Mnemonic;
 
-use super::bip32::{Bip32, ChildNumber, DerivationPath, ExtendedPrivKey};
+use super::bip32::{Bip32, ChainCode, ChildNumber, DerivationPath, ExtendedPrivKey, Fingerprint};
 use crate::types::{Index, WordCount};
 
+/// Min length of the HEX application entropy
+pub const MIN_HEX_LEN: usize = 16;
+/// Max length of the HEX application entropy
+pub const MAX_HEX_LEN: usize = 64;
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum Error {
     BIP32(bip32::Error),
     BIP39(bip39::Error),
+    Secp256k1(secp256k1::Error),
+    /// Number of bytes not between [`MIN_HEX_LEN`] and [`MAX_HEX_LEN`]
+    InvalidHexLength(usize),
 }
 
 impl std::error::Error for Error {}
@@ -30,6 +38,11 @@ impl fmt::Display for Error {
         match self {
             Self::BIP32(e) => write!(f, "BIP32: {e}"),
             Self::BIP39(e) => write!(f, "BIP39: {e}"),
+            Self::Secp256k1(e) => write!(f, "Secp256k1: {e}"),
+            Self::InvalidHexLength(len) => write!(
+                f,
+                "Invalid number of bytes: must be between {MIN_HEX_LEN} and {MAX_HEX_LEN}, found {len}"
+            ),
         }
     }
 }
@@ -46,6 +59,75 @@ impl From<bip39::Error> for Error {
     }
 }
 
+impl From<secp256k1::Error> for Error {
+    fn from(e: secp256k1::Error) -> Self {
+        Self::Secp256k1(e)
+    }
+}
+
+const PURPOSE: u32 = 83696968;
+
+/// BIP39 application path: `m/83696968'/39'/0'/<word count>'/<index>'` (english)
+pub fn mnemonic_path(word_count: WordCount, index: Index) -> Result<DerivationPath, Error> {
+    let path: Vec<ChildNumber> = vec![
+        ChildNumber::from_hardened_idx(PURPOSE)?,
+        ChildNumber::from_hardened_idx(39)?,
+        ChildNumber::from_hardened_idx(0)?,
+        ChildNumber::from_hardened_idx(word_count.as_u32())?,
+        ChildNumber::from_hardened_idx(index.as_u32())?,
+    ];
+    Ok(DerivationPath::from(path))
+}
+
+/// HEX application path: `m/83696968'/128169'/<num bytes>'/<index>'`
+pub fn hex_path(num_bytes: usize, index: Index) -> Result<DerivationPath, Error> {
+    if !(MIN_HEX_LEN..=MAX_HEX_LEN).contains(&num_bytes) {
+        return Err(Error::InvalidHexLength(num_bytes));
+    }
+    let path: Vec<ChildNumber> = vec![
+        ChildNumber::from_hardened_idx(PURPOSE)?,
+        ChildNumber::from_hardened_idx(128169)?,
+        ChildNumber::from_hardened_idx(num_bytes as u32)?,
+        ChildNumber::from_hardened_idx(index.as_u32())?,
+    ];
+    Ok(DerivationPath::from(path))
+}
+
+/// WIF application path: `m/83696968'/2'/<index>'`
+pub fn wif_path(index: Index) -> Result<DerivationPath, Error> {
+    let path: Vec<ChildNumber> = vec![
+        ChildNumber::from_hardened_idx(PURPOSE)?,
+        ChildNumber::from_hardened_idx(2)?,
+        ChildNumber::from_hardened_idx(index.as_u32())?,
+    ];
+    Ok(DerivationPath::from(path))
+}
+
+/// XPRV application path: `m/83696968'/32'/<index>'`
+pub fn xprv_path(index: Index) -> Result<DerivationPath, Error> {
+    let path: Vec<ChildNumber> = vec![
+        ChildNumber::from_hardened_idx(PURPOSE)?,
+        ChildNumber::from_hardened_idx(32)?,
+        ChildNumber::from_hardened_idx(index.as_u32())?,
+    ];
+    Ok(DerivationPath::from(path))
+}
+
+/// Derive the 64 bytes of entropy at `path`
+fn derive_entropy<C>(
+    root: &ExtendedPrivKey,
+    path: &DerivationPath,
+    secp: &Secp256k1<C>,
+) -> Result<[u8; 64], Error>
+where
+    C: Signing,
+{
+    let derived: ExtendedPrivKey = root.derive_priv(secp, path)?;
+    let mut h = HmacEngine::<sha512::Hash>::new(b"bip-entropy-from-k");
+    h.input(&derived.private_key.secret_bytes());
+    Ok(Hmac::from_engine(h).to_byte_array())
+}
+
 pub trait FromBip85: Sized {
     fn from_bip85<C>(
         root: &ExtendedPrivKey,
@@ -67,21 +149,9 @@ impl FromBip85 for Mnemonic {
     where
         C: Signing,
     {
-        let word_count: u32 = word_count.as_u32();
-        let path: Vec<ChildNumber> = vec![
-            ChildNumber::from_hardened_idx(83696968)?,
-            ChildNumber::from_hardened_idx(39)?,
-            ChildNumber::from_hardened_idx(0)?,
-            ChildNumber::from_hardened_idx(word_count)?,
-            ChildNumber::from_hardened_idx(index.as_u32())?,
-        ];
-        let path: DerivationPath = DerivationPath::from(path);
-        let derived: ExtendedPrivKey = root.derive_priv(secp, &path)?;
-
-        let mut h = HmacEngine::<sha512::Hash>::new(b"bip-entropy-from-k");
-        h.input(&derived.private_key.secret_bytes());
-        let data: [u8; 64] = Hmac::from_engine(h).to_byte_array();
-        let len: u32 = word_count * 4 / 3;
+        let path: DerivationPath = mnemonic_path(word_count, index)?;
+        let data: [u8; 64] = derive_entropy(root, &path, secp)?;
+        let len: u32 = word_count.as_u32() * 4 / 3;
         Ok(Mnemonic::from_entropy(&data[0..len as usize])?)
     }
 }
@@ -105,8 +175,68 @@ where
         let root: ExtendedPrivKey = self.to_bip32_root_key(Network::Bitcoin)?;
         Mnemonic::from_bip85(&root, word_count, index, secp)
     }
+
+    /// Derive BIP85 HEX entropy of `num_bytes` (between 16 and 64)
+    fn derive_bip85_hex<C>(
+        &self,
+        num_bytes: usize,
+        index: Index,
+        secp: &Secp256k1<C>,
+    ) -> Result<Vec<u8>, Error>
+    where
+        C: Signing,
+    {
+        let path: DerivationPath = hex_path(num_bytes, index)?;
+        let root: ExtendedPrivKey = self.to_bip32_root_key(Network::Bitcoin)?;
+        let data: [u8; 64] = derive_entropy(&root, &path, secp)?;
+        Ok(data[..num_bytes].to_vec())
+    }
+
+    /// Derive BIP85 WIF private key (compressed)
+    fn derive_bip85_wif<C>(
+        &self,
+        index: Index,
+        network: Network,
+        secp: &Secp256k1<C>,
+    ) -> Result<PrivateKey, Error>
+    where
+        C: Signing,
+    {
+        let path: DerivationPath = wif_path(index)?;
+        let root: ExtendedPrivKey = self.to_bip32_root_key(Network::Bitcoin)?;
+        let data: [u8; 64] = derive_entropy(&root, &path, secp)?;
+        let secret_key = SecretKey::from_slice(&data[..32])?;
+        Ok(PrivateKey::new(secret_key, network))
+    }
+
+    /// Derive BIP85 XPRV (master key: the chain code is the first half of the entropy)
+    fn derive_bip85_xprv<C>(
+        &self,
+        index: Index,
+        network: Network,
+        secp: &Secp256k1<C>,
+    ) -> Result<ExtendedPrivKey, Error>
+    where
+        C: Signing,
+    {
+        let path: DerivationPath = xprv_path(index)?;
+        let root: ExtendedPrivKey = self.to_bip32_root_key(Network::Bitcoin)?;
+        let data: [u8; 64] = derive_entropy(&root, &path, secp)?;
+        let mut chain_code = [0u8; 32];
+        chain_code.copy_from_slice(&data[..32]);
+        Ok(ExtendedPrivKey {
+            network,
+            depth: 0,
+            parent_fingerprint: Fingerprint::default(),
+            child_number: ChildNumber::from_normal_idx(0)?,
+            private_key: SecretKey::from_slice(&data[32..])?,
+            chain_code: ChainCode::from(chain_code),
+        })
+    }
 }
 
+impl Bip85 for ExtendedPrivKey {}
+
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
@@ -206,6 +336,65 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_bip85_applications() {
+        let secp = Secp256k1::new();
+        // BIP85 test vectors
+        let root = ExtendedPrivKey::from_str("xprv9s21ZrQH143K2LBWUUQRFXhucrQqBpKdRRxNVq2zBqsx8HVqFk2uYo8kmbaLLHRdqtQpUm98uKfu3vca1LqdGhUtyoFnCNkfmXRyPXLjbKb").unwrap();
+        let index = Index::new(0).unwrap();
+
+        // HEX
+        let path = hex_path(64, index).unwrap();
+        assert_eq!(path.to_string(), "m/83696968'/128169'/64'/0'");
+        assert_eq!(
+            root.derive_bip85_hex(64, index, &secp).unwrap(),
+            crate::util::hex::decode("492db4698cf3b73a5a24998aa3e9d7fa96275d85724a91e71aa2d645442f878555d078fd1f1f67e368976f04137b1f7a0d19232136ca50c44614af72b5582a5c").unwrap()
+        );
+        assert_eq!(root.derive_bip85_hex(16, index, &secp).unwrap().len(), 16);
+        assert_eq!(
+            root.derive_bip85_hex(15, index, &secp),
+            Err(Error::InvalidHexLength(15))
+        );
+        assert_eq!(
+            root.derive_bip85_hex(65, index, &secp),
+            Err(Error::InvalidHexLength(65))
+        );
+
+        // WIF
+        assert_eq!(wif_path(index).unwrap().to_string(), "m/83696968'/2'/0'");
+        assert_eq!(
+            root.derive_bip85_wif(index, Network::Bitcoin, &secp)
+                .unwrap()
+                .to_wif(),
+            "Kzyv4uF39d4Jrw2W7UryTHwZr1zQVNk4dAFyqE6BuMrMh1Za7uhp"
+        );
+
+        // XPRV
+        assert_eq!(xprv_path(index).unwrap().to_string(), "m/83696968'/32'/0'");
+        assert_eq!(
+            root.derive_bip85_xprv(index, Network::Bitcoin, &secp)
+                .unwrap()
+                .to_string(),
+            "xprv9s21ZrQH143K2srSbCSg4m4kLvPMzcWydgmKEnMmoZUurYuBuYG46c6P71UGXMzmriLzCCBvKQWBUv3vPB3m1SATMhp3uEjXHJ42jFg7myX"
+        );
+    }
+
+    #[test]
+    fn test_mnemonic_path() {
+        assert_eq!(
+            mnemonic_path(WordCount::W12, Index::new(0).unwrap())
+                .unwrap()
+                .to_string(),
+            "m/83696968'/39'/0'/12'/0'"
+        );
+        assert_eq!(
+            mnemonic_path(WordCount::W24, Index::new(57).unwrap())
+                .unwrap()
+                .to_string(),
+            "m/83696968'/39'/0'/24'/57'"
+        );
+    }
+
     #[test]
     fn test_eq_bip85_result() {
         let secp = Secp256k1::new();
@@ -6,6 +6,8 @@
 //! <https://github.com/bitcoin/bips>
 
 pub mod bip32;
+pub mod bip322;
+pub mod bip352;
 pub mod bip39;
 pub mod bip43;
 pub mod bip44;
@@ -1,13 +1,15 @@
 // Copyright (c) 2022-2023 Yuki Kishimoto
 // Distributed under the MIT software license
 
-//! AES-256 CBC
+//! AES-256 (CBC and GCM)
 
 use core::fmt;
 
 use aes::cipher::block_padding::Pkcs7;
 use aes::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
 use aes::Aes256;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::Aes256Gcm;
 use bdk::bitcoin::secp256k1::rand;
 use cbc::{Decryptor, Encryptor};
 
@@ -16,11 +18,18 @@ use crate::util::base64;
 type Aes256CbcEnc = Encryptor<Aes256>;
 type Aes256CbcDec = Decryptor<Aes256>;
 
+/// GCM nonce size (96-bit)
+const GCM_NONCE_SIZE: usize = 12;
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum Error {
     InvalidContentFormat,
     Base64Decode,
     WrongBlockMode,
+    /// GCM encryption failed
+    EncryptionFailed,
+    /// GCM authentication failed (wrong key or tampered content)
+    AuthenticationFailed,
 }
 
 impl std::error::Error for Error {}
@@ -31,10 +40,22 @@ impl fmt::Display for Error {
             Self::InvalidContentFormat => write!(f, "invalid content format"),
             Self::Base64Decode => write!(f, "error while decoding from base64"),
             Self::WrongBlockMode => write!(f, "invalid password or block mode"),
+            Self::EncryptionFailed => write!(f, "encryption failed"),
+            Self::AuthenticationFailed => write!(f, "invalid password or tampered content"),
         }
     }
 }
 
+/// AES block mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mode {
+    /// CBC (unauthenticated, kept for backward compatibility)
+    Cbc,
+    /// GCM (authenticated)
+    #[default]
+    Gcm,
+}
+
 /// Encrypt with AES-256 CBC
 pub fn encrypt<T>(key: [u8; 32], content: T) -> String
 where
@@ -68,6 +89,41 @@ where
     Ok(result)
 }
 
+/// Encrypt with AES-256 GCM
+///
+/// Payload: `<nonce (12 bytes)><ciphertext + tag>`
+pub fn encrypt_gcm<T>(key: [u8; 32], content: T) -> Result<Vec<u8>, Error>
+where
+    T: AsRef<[u8]>,
+{
+    let cipher = Aes256Gcm::new(&key.into());
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext: Vec<u8> = cipher
+        .encrypt(&nonce, content.as_ref())
+        .map_err(|_| Error::EncryptionFailed)?;
+
+    let mut payload: Vec<u8> = Vec::with_capacity(GCM_NONCE_SIZE + ciphertext.len());
+    payload.extend_from_slice(nonce.as_slice());
+    payload.extend(ciphertext);
+    Ok(payload)
+}
+
+/// Decrypt with AES-256 GCM
+pub fn decrypt_gcm<T>(key: [u8; 32], payload: T) -> Result<Vec<u8>, Error>
+where
+    T: AsRef<[u8]>,
+{
+    let payload: &[u8] = payload.as_ref();
+    if payload.len() < GCM_NONCE_SIZE {
+        return Err(Error::InvalidContentFormat);
+    }
+    let (nonce, ciphertext) = payload.split_at(GCM_NONCE_SIZE);
+    let cipher = Aes256Gcm::new(&key.into());
+    cipher
+        .decrypt(nonce.into(), ciphertext)
+        .map_err(|_| Error::AuthenticationFailed)
+}
+
 #[deprecated]
 pub trait Aes256Encryption: Sized {
     type Err;
@@ -123,6 +179,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_gcm_encryption_decryption() {
+        let key: [u8; 32] = hash::sha256("supersecretpassword").to_byte_array();
+        let text: &[u8] = b"My Text";
+
+        let encrypted_content: Vec<u8> = encrypt_gcm(key, text).unwrap();
+        assert_eq!(decrypt_gcm(key, &encrypted_content).unwrap(), text.to_vec());
+
+        // Wrong key
+        let wrong_key: [u8; 32] = hash::sha256("wrongpassword").to_byte_array();
+        assert_eq!(
+            decrypt_gcm(wrong_key, &encrypted_content).unwrap_err(),
+            Error::AuthenticationFailed
+        );
+
+        // Tampered nonce, ciphertext and tag
+        for i in [0, GCM_NONCE_SIZE, encrypted_content.len() - 1] {
+            let mut tampered: Vec<u8> = encrypted_content.clone();
+            tampered[i] ^= 0x01;
+            assert_eq!(
+                decrypt_gcm(key, tampered).unwrap_err(),
+                Error::AuthenticationFailed
+            );
+        }
+
+        // Truncated
+        assert_eq!(
+            decrypt_gcm(key, &encrypted_content[..GCM_NONCE_SIZE - 1]).unwrap_err(),
+            Error::InvalidContentFormat
+        );
+    }
+
     #[test]
     fn test_encryption_decryption_seed() {
         let key: &str = "supersecretpassword";
@@ -3,23 +3,40 @@
 
 use core::fmt;
 
+use argon2::{Algorithm, Argon2, Params, Version};
 use bdk::bitcoin::hashes::Hash;
+use bdk::bitcoin::secp256k1::rand;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use zeroize::Zeroizing;
 
 pub mod aes;
 pub mod chacha20;
 pub mod hash;
 
-use crate::util::{self, base64};
+use crate::util::base64;
 
 #[derive(Debug)]
 pub enum Error {
     Aes(aes::Error),
     ChaCha20Poly1305(chacha20::Error),
     Json(serde_json::Error),
+    Bincode(bincode::Error),
+    /// Argon2 key derivation error
+    Kdf(argon2::Error),
     /// Error while decoding from base64
     Base64Decode,
+    /// Error while decompressing the payload
+    Decompress,
+    /// Unknown payload header
+    UnknownHeader(u8),
+    /// Truncated or malformed format header
+    InvalidHeader,
+    UnsupportedKeychainVersion(u8),
+    UnknownCipher(u8),
+    UnknownKdf(u8),
+    /// Argon2 iterations or parallelism above the allowed bounds
+    KdfCostTooHigh,
 }
 
 impl fmt::Display for Error {
@@ -28,7 +45,18 @@ impl fmt::Display for Error {
             Self::Aes(e) => write!(f, "{e}"),
             Self::ChaCha20Poly1305(e) => write!(f, "{e}"),
             Self::Json(e) => write!(f, "Json: {e}"),
+            Self::Bincode(e) => write!(f, "Bincode: {e}"),
+            Self::Kdf(e) => write!(f, "Kdf: {e}"),
             Self::Base64Decode => write!(f, "Error while decoding from base64"),
+            Self::Decompress => write!(f, "Error while decompressing payload"),
+            Self::UnknownHeader(h) => write!(f, "Unknown payload header: {h:#04x}"),
+            Self::InvalidHeader => write!(f, "Invalid format header"),
+            Self::UnsupportedKeychainVersion(v) => {
+                write!(f, "Unsupported keychain format version: {v}")
+            }
+            Self::UnknownCipher(id) => write!(f, "Unknown cipher: {id:#04x}"),
+            Self::UnknownKdf(id) => write!(f, "Unknown KDF: {id:#04x}"),
+            Self::KdfCostTooHigh => write!(f, "KDF iterations or parallelism too high"),
         }
     }
 }
@@ -51,33 +79,961 @@ impl From<serde_json::Error> for Error {
     }
 }
 
-pub(crate) trait MultiEncryption: Sized + Serialize + DeserializeOwned {
-    fn hash_key<K>(key: K) -> [u8; 32]
+impl From<bincode::Error> for Error {
+    fn from(e: bincode::Error) -> Self {
+        Self::Bincode(e)
+    }
+}
+
+impl From<argon2::Error> for Error {
+    fn from(e: argon2::Error) -> Self {
+        Self::Kdf(e)
+    }
+}
+
+/// Serialized payloads smaller than this are never compressed
+const COMPRESSION_THRESHOLD: usize = 1024;
+const COMPRESSION_LEVEL: u8 = 6;
+/// Upper bound of a decompressed payload (avoid decompression bombs)
+const MAX_DECOMPRESSED_SIZE: usize = 16 * 1024 * 1024;
+
+/// Payload header flag: compressed with deflate
+const FLAG_DEFLATE: u8 = 0x01;
+/// Payload header flag: serialized with bincode (JSON otherwise)
+const FLAG_BINCODE: u8 = 0x02;
+const KNOWN_FLAGS: u8 = FLAG_DEFLATE | FLAG_BINCODE;
+/// Payloads written before headers were introduced start with a JSON object
+const LEGACY_JSON_START: u8 = b'{';
+
+/// AES layer header: AES-256 GCM
+///
+/// AES-256 CBC payloads are base64 strings, so they never start with this byte.
+const AES_GCM_HEADER: u8 = 0x01;
+
+/// Format header magic (payloads written before it was introduced start with a random nonce)
+const HEADER_MAGIC: &[u8; 8] = b"KEECHAIN";
+/// Argon2id and AES + XChaCha20-Poly1305, without explicit identifiers
+const FORMAT_VERSION_1: u8 = 1;
+/// Explicit cipher and KDF identifiers
+const FORMAT_VERSION: u8 = 2;
+/// Cipher id: AES-256 (mode in the AES layer header) wrapped in XChaCha20-Poly1305
+const CIPHER_AES_XCHACHA20POLY1305: u8 = 0x01;
+/// KDF id: Argon2id
+const KDF_ARGON2ID: u8 = 0x01;
+const ARGON2ID_SALT_LEN: usize = 16;
+/// `<m_cost, t_cost, p_cost (4 bytes LE each)><salt (16 bytes)>`
+const ARGON2ID_PARAMS_LEN: usize = 3 * 4 + ARGON2ID_SALT_LEN;
+/// Upper bound of the Argon2 memory cost read from a file (1 GiB)
+const MAX_KDF_MEMORY_KIB: u32 = 1024 * 1024;
+/// Upper bound of the Argon2 time cost read from a file
+const MAX_KDF_ITERATIONS: u32 = 64;
+/// Upper bound of the Argon2 lanes read from a file
+const MAX_KDF_PARALLELISM: u32 = 16;
+
+/// Argon2id parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    /// Memory size in KiB
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    /// OWASP recommended parameters (19 MiB, 2 iterations, 1 lane)
+    fn default() -> Self {
+        Self {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+impl KdfParams {
+    /// Stronger parameters for PIN-protected keychains (128 MiB, 4 iterations, 1 lane)
+    ///
+    /// A numeric PIN has a much smaller keyspace than a password: every guess must cost more.
+    pub fn pin() -> Self {
+        Self {
+            memory_kib: 128 * 1024,
+            iterations: 4,
+            parallelism: 1,
+        }
+    }
+}
+
+/// `Argon2id (m=<memory>, t=<iterations>, p=<parallelism>)`
+impl fmt::Display for KdfParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.memory_kib % 1024 == 0 {
+            write!(f, "Argon2id (m={} MiB", self.memory_kib / 1024)?;
+        } else {
+            write!(f, "Argon2id (m={} KiB", self.memory_kib)?;
+        }
+        write!(f, ", t={}, p={})", self.iterations, self.parallelism)
+    }
+}
+
+/// KDF presets, to trade security for speed on slower devices
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KdfProfile {
+    /// 8 MiB, 1 iteration, 1 lane (low-end devices)
+    Fast,
+    /// [`KdfParams::default`]
+    #[default]
+    Balanced,
+    /// 256 MiB, 4 iterations, 1 lane
+    Paranoid,
+}
+
+impl KdfProfile {
+    pub fn params(&self) -> KdfParams {
+        match self {
+            Self::Fast => KdfParams {
+                memory_kib: 8 * 1024,
+                iterations: 1,
+                parallelism: 1,
+            },
+            Self::Balanced => KdfParams::default(),
+            Self::Paranoid => KdfParams {
+                memory_kib: 256 * 1024,
+                iterations: 4,
+                parallelism: 1,
+            },
+        }
+    }
+}
+
+impl From<KdfProfile> for KdfParams {
+    fn from(profile: KdfProfile) -> Self {
+        profile.params()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cipher {
+    AesXChaCha20Poly1305,
+}
+
+impl Cipher {
+    fn id(&self) -> u8 {
+        match self {
+            Self::AesXChaCha20Poly1305 => CIPHER_AES_XCHACHA20POLY1305,
+        }
+    }
+
+    fn from_id(id: u8) -> Result<Self, Error> {
+        match id {
+            CIPHER_AES_XCHACHA20POLY1305 => Ok(Self::AesXChaCha20Poly1305),
+            id => Err(Error::UnknownCipher(id)),
+        }
+    }
+
+    fn encrypt(&self, key: [u8; 32], payload: Vec<u8>, mode: aes::Mode) -> Result<Vec<u8>, Error> {
+        match self {
+            Self::AesXChaCha20Poly1305 => {
+                let first_round: Vec<u8> = aes_encrypt(key, payload, mode)?;
+                Ok(chacha20::encrypt(key, first_round)?)
+            }
+        }
+    }
+
+    /// Decrypt and check if the payload uses a legacy cipher mode (AES-256 CBC)
+    fn decrypt(&self, key: [u8; 32], payload: Vec<u8>) -> Result<(Vec<u8>, bool), Error> {
+        match self {
+            Self::AesXChaCha20Poly1305 => {
+                let first_round: Vec<u8> = chacha20::decrypt(key, payload)?;
+                let is_legacy_aes: bool = first_round.first() != Some(&AES_GCM_HEADER);
+                Ok((aes_decrypt(key, first_round)?, is_legacy_aes))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kdf {
+    Argon2id {
+        params: KdfParams,
+        salt: [u8; ARGON2ID_SALT_LEN],
+    },
+}
+
+impl Kdf {
+    /// Argon2id with a random salt
+    fn argon2id(params: KdfParams) -> Self {
+        Self::Argon2id {
+            params,
+            salt: rand::random(),
+        }
+    }
+
+    fn id(&self) -> u8 {
+        match self {
+            Self::Argon2id { .. } => KDF_ARGON2ID,
+        }
+    }
+
+    fn serialize_params(&self) -> Vec<u8> {
+        match self {
+            Self::Argon2id { params, salt } => {
+                let mut data: Vec<u8> = Vec::with_capacity(ARGON2ID_PARAMS_LEN);
+                data.extend_from_slice(&params.memory_kib.to_le_bytes());
+                data.extend_from_slice(&params.iterations.to_le_bytes());
+                data.extend_from_slice(&params.parallelism.to_le_bytes());
+                data.extend_from_slice(salt);
+                data
+            }
+        }
+    }
+
+    /// Parse the params of KDF `id`, returning the rest of the payload
+    fn parse(id: u8, payload: &[u8]) -> Result<(Self, &[u8]), Error> {
+        match id {
+            KDF_ARGON2ID => {
+                if payload.len() < ARGON2ID_PARAMS_LEN {
+                    return Err(Error::InvalidHeader);
+                }
+                let read_u32 = |offset: usize| -> u32 {
+                    let mut bytes = [0u8; 4];
+                    bytes.copy_from_slice(&payload[offset..offset + 4]);
+                    u32::from_le_bytes(bytes)
+                };
+                let params = KdfParams {
+                    memory_kib: read_u32(0),
+                    iterations: read_u32(4),
+                    parallelism: read_u32(8),
+                };
+                let mut salt = [0u8; ARGON2ID_SALT_LEN];
+                salt.copy_from_slice(&payload[12..ARGON2ID_PARAMS_LEN]);
+                Ok((
+                    Self::Argon2id { params, salt },
+                    &payload[ARGON2ID_PARAMS_LEN..],
+                ))
+            }
+            id => Err(Error::UnknownKdf(id)),
+        }
+    }
+
+    fn derive_key<K>(&self, key: K) -> Result<[u8; 32], Error>
     where
         K: AsRef<[u8]>,
     {
-        hash::sha256(key).to_byte_array()
+        match self {
+            Self::Argon2id { params, salt } => {
+                if params.memory_kib > MAX_KDF_MEMORY_KIB {
+                    return Err(Error::Kdf(argon2::Error::MemoryTooMuch));
+                }
+                if params.iterations > MAX_KDF_ITERATIONS
+                    || params.parallelism > MAX_KDF_PARALLELISM
+                {
+                    return Err(Error::KdfCostTooHigh);
+                }
+                let params = Params::new(
+                    params.memory_kib,
+                    params.iterations,
+                    params.parallelism,
+                    Some(32),
+                )?;
+                let mut output = [0u8; 32];
+                Argon2::new(Algorithm::Argon2id, Version::V0x13, params).hash_password_into(
+                    key.as_ref(),
+                    salt,
+                    &mut output,
+                )?;
+                Ok(output)
+            }
+        }
     }
+}
+
+/// Format header, stored before the ciphertext
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Header {
+    version: u8,
+    cipher: Cipher,
+    kdf: Kdf,
+}
+
+impl Header {
+    fn new(cipher: Cipher, kdf: Kdf) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            cipher,
+            kdf,
+        }
+    }
+
+    /// Serialize with the current format version
+    fn serialize(&self) -> Vec<u8> {
+        let mut header: Vec<u8> = HEADER_MAGIC.to_vec();
+        header.push(FORMAT_VERSION);
+        header.push(self.cipher.id());
+        header.push(self.kdf.id());
+        header.extend(self.kdf.serialize_params());
+        header
+    }
+
+    /// Split the header from the rest of the payload
+    ///
+    /// Return `None` if the payload doesn't start with the header magic (legacy payload).
+    fn parse(payload: &[u8]) -> Result<Option<(Self, &[u8])>, Error> {
+        let payload: &[u8] = match payload.strip_prefix(HEADER_MAGIC.as_slice()) {
+            Some(payload) => payload,
+            None => return Ok(None),
+        };
+
+        let (version, payload) = payload.split_first().ok_or(Error::InvalidHeader)?;
+        let (cipher, kdf, payload) = match *version {
+            FORMAT_VERSION_1 => {
+                let (kdf, payload) = Kdf::parse(KDF_ARGON2ID, payload)?;
+                (Cipher::AesXChaCha20Poly1305, kdf, payload)
+            }
+            FORMAT_VERSION => match payload {
+                [cipher, kdf, payload @ ..] => {
+                    let cipher: Cipher = Cipher::from_id(*cipher)?;
+                    let (kdf, payload) = Kdf::parse(*kdf, payload)?;
+                    (cipher, kdf, payload)
+                }
+                _ => return Err(Error::InvalidHeader),
+            },
+            v => return Err(Error::UnsupportedKeychainVersion(v)),
+        };
+
+        Ok(Some((
+            Self {
+                version: *version,
+                cipher,
+                kdf,
+            },
+            payload,
+        )))
+    }
+}
+
+/// Key used by payloads without the format header
+fn legacy_hash_key<K>(key: K) -> [u8; 32]
+where
+    K: AsRef<[u8]>,
+{
+    hash::sha256(key).to_byte_array()
+}
+
+fn aes_encrypt(key: [u8; 32], payload: Vec<u8>, mode: aes::Mode) -> Result<Vec<u8>, Error> {
+    match mode {
+        aes::Mode::Cbc => Ok(aes::encrypt(key, payload).into_bytes()),
+        aes::Mode::Gcm => {
+            let mut encrypted: Vec<u8> = vec![AES_GCM_HEADER];
+            encrypted.extend(aes::encrypt_gcm(key, payload)?);
+            Ok(encrypted)
+        }
+    }
+}
+
+fn aes_decrypt(key: [u8; 32], payload: Vec<u8>) -> Result<Vec<u8>, Error> {
+    match payload.split_first() {
+        Some((&AES_GCM_HEADER, encrypted)) => Ok(aes::decrypt_gcm(key, encrypted)?),
+        _ => Ok(aes::decrypt(key, payload)?),
+    }
+}
+
+/// Serialize with bincode, add header and, if big enough, compress the payload
+fn pack<T>(data: &T) -> Result<Vec<u8>, Error>
+where
+    T: Serialize,
+{
+    let serialized: Zeroizing<Vec<u8>> = Zeroizing::new(bincode::serialize(data)?);
+    let (header, body): (u8, Zeroizing<Vec<u8>>) = if serialized.len() >= COMPRESSION_THRESHOLD {
+        (
+            FLAG_BINCODE | FLAG_DEFLATE,
+            Zeroizing::new(miniz_oxide::deflate::compress_to_vec(
+                &serialized,
+                COMPRESSION_LEVEL,
+            )),
+        )
+    } else {
+        (FLAG_BINCODE, serialized)
+    };
+    let mut payload: Vec<u8> = Vec::with_capacity(body.len() + 1);
+    payload.push(header);
+    payload.extend_from_slice(&body);
+    Ok(payload)
+}
 
+/// Read header, decompress (if needed) and deserialize the payload
+///
+/// JSON payloads (with or without header) are still supported.
+fn unpack<T>(payload: &[u8]) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+{
+    let header: u8 = match payload.first() {
+        Some(&LEGACY_JSON_START) | None => return Ok(serde_json::from_slice(payload)?),
+        Some(header) if header & !KNOWN_FLAGS != 0 => return Err(Error::UnknownHeader(*header)),
+        Some(header) => *header,
+    };
+
+    let decompressed: Zeroizing<Vec<u8>>;
+    let body: &[u8] = if header & FLAG_DEFLATE != 0 {
+        decompressed = Zeroizing::new(
+            miniz_oxide::inflate::decompress_to_vec_with_limit(
+                &payload[1..],
+                MAX_DECOMPRESSED_SIZE,
+            )
+            .map_err(|_| Error::Decompress)?,
+        );
+        &decompressed
+    } else {
+        &payload[1..]
+    };
+
+    if header & FLAG_BINCODE != 0 {
+        Ok(bincode::deserialize(body)?)
+    } else {
+        Ok(serde_json::from_slice(body)?)
+    }
+}
+
+/// KDF params stored in the header of an encrypted payload (`None` for legacy payloads)
+pub(crate) fn kdf_params(content: &[u8]) -> Option<KdfParams> {
+    let payload: Vec<u8> = base64::decode(content).ok()?;
+    let (header, _) = Header::parse(&payload).ok()??;
+    match header.kdf {
+        Kdf::Argon2id { params, .. } => Some(params),
+    }
+}
+
+/// Compare two byte slices in constant-time (for slices of the same length)
+pub fn constant_time_eq<A, B>(a: A, b: B) -> bool
+where
+    A: AsRef<[u8]>,
+    B: AsRef<[u8]>,
+{
+    let a: &[u8] = a.as_ref();
+    let b: &[u8] = b.as_ref();
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter()
+        .zip(b.iter())
+        .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+        == 0
+}
+
+pub(crate) trait MultiEncryption: Sized + Serialize + DeserializeOwned {
+    /// Encrypt using the default (authenticated) AES mode
     fn encrypt<K>(&self, key: K) -> Result<String, Error>
     where
         K: AsRef<[u8]>,
     {
-        let serialized: Vec<u8> = util::serde::serialize(self)?;
-        let key: [u8; 32] = Self::hash_key(key);
-        let first_round = aes::encrypt(key, serialized);
-        let second_round: Vec<u8> = chacha20::encrypt(key, first_round)?;
-        Ok(base64::encode(second_round))
+        self.encrypt_with_mode(key, aes::Mode::default())
+    }
+
+    fn encrypt_with_mode<K>(&self, key: K, mode: aes::Mode) -> Result<String, Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        self.encrypt_with_params(key, mode, KdfParams::default())
+    }
+
+    /// Encrypt with a fresh salt, storing it (and the KDF params) in the header
+    fn encrypt_with_params<K>(
+        &self,
+        key: K,
+        mode: aes::Mode,
+        params: KdfParams,
+    ) -> Result<String, Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        let header = Header::new(Cipher::AesXChaCha20Poly1305, Kdf::argon2id(params));
+        let key: Zeroizing<[u8; 32]> = Zeroizing::new(header.kdf.derive_key(key)?);
+        let payload: Vec<u8> = pack(self)?;
+        let mut encrypted: Vec<u8> = header.serialize();
+        encrypted.extend(header.cipher.encrypt(*key, payload, mode)?);
+        Ok(base64::encode(encrypted))
     }
 
     fn decrypt<K>(key: K, content: &[u8]) -> Result<Self, Error>
     where
         K: AsRef<[u8]>,
     {
-        let key: [u8; 32] = Self::hash_key(key);
+        let (data, _) = Self::decrypt_and_check_legacy(key, content)?;
+        Ok(data)
+    }
+
+    /// Decrypt and check if the payload uses a legacy format (old format header, sha256 key, AES-256 CBC or JSON serialization)
+    fn decrypt_and_check_legacy<K>(key: K, content: &[u8]) -> Result<(Self, bool), Error>
+    where
+        K: AsRef<[u8]>,
+    {
         let payload: Vec<u8> = base64::decode(content).map_err(|_| Error::Base64Decode)?;
-        let first_round: Vec<u8> = chacha20::decrypt(key, payload)?;
-        let second_round: Vec<u8> = aes::decrypt(key, first_round)?;
-        Ok(util::serde::deserialize(second_round)?)
+        let (second_round, is_legacy_cipher, is_legacy_header): (Vec<u8>, bool, bool) =
+            match Header::parse(&payload)? {
+                Some((header, encrypted)) => {
+                    let key: Zeroizing<[u8; 32]> = Zeroizing::new(header.kdf.derive_key(key)?);
+                    let (decrypted, is_legacy_cipher) =
+                        header.cipher.decrypt(*key, encrypted.to_vec())?;
+                    (decrypted, is_legacy_cipher, header.version < FORMAT_VERSION)
+                }
+                // Before the format header: sha256 key
+                None => {
+                    let key: Zeroizing<[u8; 32]> = Zeroizing::new(legacy_hash_key(key));
+                    let (decrypted, is_legacy_cipher) =
+                        Cipher::AesXChaCha20Poly1305.decrypt(*key, payload)?;
+                    (decrypted, is_legacy_cipher, true)
+                }
+            };
+        // Plaintext, zeroized once deserialized
+        let second_round: Zeroizing<Vec<u8>> = Zeroizing::new(second_round);
+        let is_legacy_serialization: bool = match second_round.first() {
+            Some(&LEGACY_JSON_START) | None => true,
+            Some(header) => header & FLAG_BINCODE == 0,
+        };
+        Ok((
+            unpack(&second_round)?,
+            is_legacy_header || is_legacy_cipher || is_legacy_serialization,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::util;
+
+    const KEY: &str = "password";
+    /// `<magic (8 bytes)><version (1 byte)><cipher id (1 byte)><kdf id (1 byte)><argon2id params>`
+    const HEADER_LEN: usize = HEADER_MAGIC.len() + 3 + ARGON2ID_PARAMS_LEN;
+
+    #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+    struct Payload {
+        data: String,
+    }
+
+    impl MultiEncryption for Payload {}
+
+    fn encrypt_legacy(payload: Vec<u8>) -> String {
+        let key: [u8; 32] = legacy_hash_key(KEY);
+        let first_round = aes::encrypt(key, payload);
+        let second_round: Vec<u8> = chacha20::encrypt(key, first_round).unwrap();
+        base64::encode(second_round)
+    }
+
+    #[test]
+    fn test_encryption_without_compression() {
+        let payload = Payload {
+            data: String::from("keechain"),
+        };
+        assert_eq!(pack(&payload).unwrap()[0], FLAG_BINCODE);
+        let encrypted: String = payload.encrypt(KEY).unwrap();
+        assert_eq!(
+            Payload::decrypt(KEY, encrypted.as_bytes()).unwrap(),
+            payload
+        );
+    }
+
+    #[test]
+    fn test_encryption_aes_modes() {
+        let payload = Payload {
+            data: String::from("keechain"),
+        };
+
+        for mode in [aes::Mode::Cbc, aes::Mode::Gcm] {
+            let encrypted: String = payload.encrypt_with_mode(KEY, mode).unwrap();
+            assert_eq!(
+                Payload::decrypt(KEY, encrypted.as_bytes()).unwrap(),
+                payload
+            );
+
+            // Check the recorded AES mode
+            let decoded: Vec<u8> = base64::decode(encrypted).unwrap();
+            let (header, body) = Header::parse(&decoded).unwrap().unwrap();
+            let key: [u8; 32] = header.kdf.derive_key(KEY).unwrap();
+            let first_round: Vec<u8> = chacha20::decrypt(key, body.to_vec()).unwrap();
+            assert_eq!(first_round[0] == AES_GCM_HEADER, mode == aes::Mode::Gcm);
+        }
+    }
+
+    #[test]
+    fn test_aes_gcm_tamper_detection() {
+        let key: [u8; 32] = legacy_hash_key(KEY);
+        let mut encrypted: Vec<u8> =
+            aes_encrypt(key, b"keechain".to_vec(), aes::Mode::Gcm).unwrap();
+        let last: usize = encrypted.len() - 1;
+        encrypted[last] ^= 0x01;
+        assert!(matches!(
+            aes_decrypt(key, encrypted),
+            Err(Error::Aes(aes::Error::AuthenticationFailed))
+        ));
+    }
+
+    /// Re-encode the outer layer (XChaCha20-Poly1305) after a mutation of the inner one
+    fn wrap_outer(key: [u8; 32], inner: Vec<u8>) -> String {
+        base64::encode(chacha20::encrypt(key, inner).unwrap())
+    }
+
+    fn flip(mut data: Vec<u8>, index: usize) -> Vec<u8> {
+        data[index] ^= 0x01;
+        data
+    }
+
+    #[test]
+    fn test_outer_layer_tamper_resistance() {
+        let payload = Payload {
+            data: String::from("keechain"),
+        };
+        let encrypted: Vec<u8> = base64::decode(payload.encrypt(KEY).unwrap()).unwrap();
+
+        // Outer layer: <kdf header><nonce (24 bytes)><ciphertext><tag (16 bytes)>
+        let last: usize = encrypted.len() - 1;
+        for (region, index) in [
+            ("salt", HEADER_LEN - 1),
+            ("nonce", HEADER_LEN),
+            ("ciphertext", HEADER_LEN + 24),
+            ("tag", last),
+        ] {
+            let tampered: String = base64::encode(flip(encrypted.clone(), index));
+            assert!(
+                matches!(
+                    Payload::decrypt(KEY, tampered.as_bytes()),
+                    Err(Error::ChaCha20Poly1305(chacha20::Error::DecryptionFailed))
+                ),
+                "tampered {region} not detected"
+            );
+        }
+
+        // Truncated
+        let truncated: String = base64::encode(&encrypted[..HEADER_LEN + 20]);
+        assert!(matches!(
+            Payload::decrypt(KEY, truncated.as_bytes()),
+            Err(Error::ChaCha20Poly1305(chacha20::Error::NotFound(_)))
+        ));
+        let truncated: String = base64::encode(&encrypted[..20]);
+        assert!(matches!(
+            Payload::decrypt(KEY, truncated.as_bytes()),
+            Err(Error::InvalidHeader)
+        ));
+
+        // Wrong key
+        assert!(matches!(
+            Payload::decrypt("wrong", base64::encode(&encrypted).as_bytes()),
+            Err(Error::ChaCha20Poly1305(chacha20::Error::DecryptionFailed))
+        ));
+
+        // Not base64
+        assert!(matches!(
+            Payload::decrypt(KEY, b"!keechain!"),
+            Err(Error::Base64Decode)
+        ));
+    }
+
+    #[test]
+    fn test_inner_layer_tamper_resistance() {
+        let payload = Payload {
+            data: String::from("keechain"),
+        };
+        let key: [u8; 32] = legacy_hash_key(KEY);
+        let inner: Vec<u8> = aes_encrypt(key, pack(&payload).unwrap(), aes::Mode::Gcm).unwrap();
+        assert_eq!(
+            Payload::decrypt(KEY, wrap_outer(key, inner.clone()).as_bytes()).unwrap(),
+            payload
+        );
+
+        // Changing the version byte makes the payload parsed as legacy AES-256 CBC
+        let tampered: String = wrap_outer(key, flip(inner.clone(), 0));
+        assert!(matches!(
+            Payload::decrypt(KEY, tampered.as_bytes()),
+            Err(Error::Aes(aes::Error::InvalidContentFormat))
+        ));
+
+        // Inner layer: <version (1 byte)><nonce (12 bytes)><ciphertext><tag (16 bytes)>
+        let last: usize = inner.len() - 1;
+        for (region, index) in [("nonce", 1), ("ciphertext", 13), ("tag", last)] {
+            let tampered: String = wrap_outer(key, flip(inner.clone(), index));
+            assert!(
+                matches!(
+                    Payload::decrypt(KEY, tampered.as_bytes()),
+                    Err(Error::Aes(aes::Error::AuthenticationFailed))
+                ),
+                "tampered {region} not detected"
+            );
+        }
+
+        // Truncated (shorter than the nonce)
+        let tampered: String = wrap_outer(key, inner[..8].to_vec());
+        assert!(matches!(
+            Payload::decrypt(KEY, tampered.as_bytes()),
+            Err(Error::Aes(aes::Error::InvalidContentFormat))
+        ));
+    }
+
+    #[test]
+    fn test_kdf_params() {
+        let payload = Payload {
+            data: String::from("keechain"),
+        };
+        let params = KdfParams {
+            memory_kib: 1024,
+            iterations: 1,
+            parallelism: 1,
+        };
+        let encrypted: String = payload
+            .encrypt_with_params(KEY, aes::Mode::Gcm, params)
+            .unwrap();
+        assert_eq!(
+            Payload::decrypt(KEY, encrypted.as_bytes()).unwrap(),
+            payload
+        );
+
+        // Params and salt are stored in the header
+        let decoded: Vec<u8> = base64::decode(&encrypted).unwrap();
+        let (header, _) = Header::parse(&decoded).unwrap().unwrap();
+        let salt = match header.kdf {
+            Kdf::Argon2id {
+                params: stored,
+                salt,
+            } => {
+                assert_eq!(stored, params);
+                salt
+            }
+        };
+        assert_eq!(decoded[..HEADER_LEN], header.serialize()[..]);
+
+        // Fresh salt for every encryption
+        let other: Vec<u8> = base64::decode(
+            payload
+                .encrypt_with_params(KEY, aes::Mode::Gcm, params)
+                .unwrap(),
+        )
+        .unwrap();
+        let (other_header, _) = Header::parse(&other).unwrap().unwrap();
+        assert_ne!(other_header.kdf, Kdf::Argon2id { params, salt });
+        assert_ne!(
+            header.kdf.derive_key(KEY).unwrap(),
+            other_header.kdf.derive_key(KEY).unwrap()
+        );
+
+        // Memory cost too high
+        let huge = Header::new(
+            Cipher::AesXChaCha20Poly1305,
+            Kdf::Argon2id {
+                params: KdfParams {
+                    memory_kib: MAX_KDF_MEMORY_KIB + 1,
+                    ..params
+                },
+                salt,
+            },
+        );
+        let mut encrypted: Vec<u8> = huge.serialize();
+        encrypted.extend_from_slice(&decoded[HEADER_LEN..]);
+        assert!(matches!(
+            Payload::decrypt(KEY, base64::encode(encrypted).as_bytes()),
+            Err(Error::Kdf(argon2::Error::MemoryTooMuch))
+        ));
+
+        // Time cost and lanes too high
+        for params in [
+            KdfParams {
+                iterations: MAX_KDF_ITERATIONS + 1,
+                ..params
+            },
+            KdfParams {
+                parallelism: MAX_KDF_PARALLELISM + 1,
+                ..params
+            },
+        ] {
+            let huge = Header::new(Cipher::AesXChaCha20Poly1305, Kdf::Argon2id { params, salt });
+            let mut encrypted: Vec<u8> = huge.serialize();
+            encrypted.extend_from_slice(&decoded[HEADER_LEN..]);
+            assert!(matches!(
+                Payload::decrypt(KEY, base64::encode(encrypted).as_bytes()),
+                Err(Error::KdfCostTooHigh)
+            ));
+        }
+
+        // The presets are within the bounds
+        for params in [
+            KdfProfile::Fast.params(),
+            KdfProfile::Balanced.params(),
+            KdfProfile::Paranoid.params(),
+            KdfParams::pin(),
+        ] {
+            assert!(params.memory_kib <= MAX_KDF_MEMORY_KIB);
+            assert!(params.iterations <= MAX_KDF_ITERATIONS);
+            assert!(params.parallelism <= MAX_KDF_PARALLELISM);
+        }
+
+        assert_eq!(
+            kdf_params(
+                payload
+                    .encrypt_with_params(KEY, aes::Mode::Gcm, params)
+                    .unwrap()
+                    .as_bytes()
+            ),
+            Some(params)
+        );
+        assert_eq!(
+            kdf_params(payload.encrypt(KEY).unwrap().as_bytes()),
+            Some(KdfParams::default())
+        );
+        assert_eq!(
+            kdf_params(encrypt_legacy(pack(&payload).unwrap()).as_bytes()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_format_header() {
+        let payload = Payload {
+            data: String::from("keechain"),
+        };
+        let encrypted: Vec<u8> = base64::decode(payload.encrypt(KEY).unwrap()).unwrap();
+        assert!(encrypted.starts_with(HEADER_MAGIC));
+        let (header, body) = Header::parse(&encrypted).unwrap().unwrap();
+        assert_eq!(header.version, FORMAT_VERSION);
+        assert_eq!(header.cipher, Cipher::AesXChaCha20Poly1305);
+        assert_eq!(header.kdf.id(), KDF_ARGON2ID);
+
+        // Version 1: no cipher and KDF identifiers
+        let mut v1: Vec<u8> = HEADER_MAGIC.to_vec();
+        v1.push(FORMAT_VERSION_1);
+        v1.extend(header.kdf.serialize_params());
+        v1.extend_from_slice(body);
+        let (decrypted, is_legacy) =
+            Payload::decrypt_and_check_legacy(KEY, base64::encode(v1).as_bytes()).unwrap();
+        assert_eq!(decrypted, payload);
+        assert!(is_legacy);
+
+        let version: usize = HEADER_MAGIC.len();
+        let tamper = |index: usize, value: u8| -> Result<Payload, Error> {
+            let mut tampered: Vec<u8> = encrypted.clone();
+            tampered[index] = value;
+            Payload::decrypt(KEY, base64::encode(tampered).as_bytes())
+        };
+        assert!(matches!(
+            tamper(version, FORMAT_VERSION + 1),
+            Err(Error::UnsupportedKeychainVersion(3))
+        ));
+        assert!(matches!(
+            tamper(version, 0x00),
+            Err(Error::UnsupportedKeychainVersion(0))
+        ));
+        assert!(matches!(
+            tamper(version + 1, 0xff),
+            Err(Error::UnknownCipher(0xff))
+        ));
+        assert!(matches!(
+            tamper(version + 2, 0xff),
+            Err(Error::UnknownKdf(0xff))
+        ));
+
+        // Truncated header
+        assert!(matches!(
+            Payload::decrypt(KEY, base64::encode(&encrypted[..version + 3]).as_bytes()),
+            Err(Error::InvalidHeader)
+        ));
+    }
+
+    #[test]
+    fn test_decrypt_and_check_legacy() {
+        let payload = Payload {
+            data: String::from("keechain"),
+        };
+
+        let encrypted: String = payload.encrypt(KEY).unwrap();
+        let (decrypted, is_legacy) =
+            Payload::decrypt_and_check_legacy(KEY, encrypted.as_bytes()).unwrap();
+        assert_eq!(decrypted, payload);
+        assert!(!is_legacy);
+
+        let encrypted: String = payload.encrypt_with_mode(KEY, aes::Mode::Cbc).unwrap();
+        let (_, is_legacy) = Payload::decrypt_and_check_legacy(KEY, encrypted.as_bytes()).unwrap();
+        assert!(is_legacy);
+
+        let encrypted: String = encrypt_legacy(util::serde::serialize(&payload).unwrap());
+        let (_, is_legacy) = Payload::decrypt_and_check_legacy(KEY, encrypted.as_bytes()).unwrap();
+        assert!(is_legacy);
+
+        // Current AES mode and serialization, but sha256 key (no KDF header)
+        let key: [u8; 32] = legacy_hash_key(KEY);
+        let inner: Vec<u8> = aes_encrypt(key, pack(&payload).unwrap(), aes::Mode::Gcm).unwrap();
+        let encrypted: String = wrap_outer(key, inner);
+        let (decrypted, is_legacy) =
+            Payload::decrypt_and_check_legacy(KEY, encrypted.as_bytes()).unwrap();
+        assert_eq!(decrypted, payload);
+        assert!(is_legacy);
+    }
+
+    #[test]
+    fn test_encryption_with_compression() {
+        let payload = Payload {
+            data: "keechain".repeat(1000),
+        };
+        let packed: Vec<u8> = pack(&payload).unwrap();
+        assert_eq!(packed[0], FLAG_BINCODE | FLAG_DEFLATE);
+        assert!(packed.len() < bincode::serialize(&payload).unwrap().len());
+        let encrypted: String = payload.encrypt(KEY).unwrap();
+        assert_eq!(
+            Payload::decrypt(KEY, encrypted.as_bytes()).unwrap(),
+            payload
+        );
+    }
+
+    #[test]
+    fn test_binary_smaller_than_json() {
+        let payload = Payload {
+            data: String::from("keechain"),
+        };
+        assert!(pack(&payload).unwrap().len() < util::serde::serialize(&payload).unwrap().len());
+    }
+
+    #[test]
+    fn test_kdf_params_display() {
+        assert_eq!(
+            KdfParams::default().to_string(),
+            "Argon2id (m=19 MiB, t=2, p=1)"
+        );
+        assert_eq!(
+            KdfParams {
+                memory_kib: 1000,
+                iterations: 3,
+                parallelism: 4
+            }
+            .to_string(),
+            "Argon2id (m=1000 KiB, t=3, p=4)"
+        );
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq([1, 2, 3], [1, 2, 3]));
+        assert!(!constant_time_eq([1, 2, 3], [1, 2, 4]));
+        assert!(!constant_time_eq([1, 2, 3], [1, 2]));
+    }
+
+    #[test]
+    fn test_decrypt_legacy_payload() {
+        let payload = Payload {
+            data: String::from("keechain"),
+        };
+
+        // JSON without header
+        let encrypted: String = encrypt_legacy(util::serde::serialize(&payload).unwrap());
+        assert_eq!(
+            Payload::decrypt(KEY, encrypted.as_bytes()).unwrap(),
+            payload
+        );
+
+        // JSON with header
+        let mut json: Vec<u8> = vec![0x00];
+        json.extend(util::serde::serialize(&payload).unwrap());
+        let encrypted: String = encrypt_legacy(json);
+        assert_eq!(
+            Payload::decrypt(KEY, encrypted.as_bytes()).unwrap(),
+            payload
+        );
     }
 }
@@ -2,17 +2,24 @@
 // Distributed under the MIT software license
 
 use core::fmt;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashSet};
+use std::ops::Range;
 use std::str::FromStr;
 
 use bdk::bitcoin::secp256k1::{Secp256k1, Signing};
-use bdk::bitcoin::Network;
-use bdk::miniscript::descriptor::{Descriptor, DescriptorKeyParseError, DescriptorPublicKey};
+use bdk::bitcoin::{Address, Network};
+use bdk::miniscript::descriptor::{
+    ConversionError, Descriptor, DescriptorKeyParseError, DescriptorPublicKey, DescriptorXKey,
+    ShInner, SinglePubKey, Wildcard, Wsh, WshInner,
+};
+use bdk::miniscript::{ForEachKey, ScriptContext, Terminal};
+use serde::Serialize;
 
 use crate::bips::bip32::{
     self, Bip32, ChildNumber, DerivationPath, ExtendedPrivKey, ExtendedPubKey, Fingerprint,
 };
 use crate::bips::bip43::Purpose;
+use crate::bips::bip48::{self, ScriptType};
 use crate::types::Seed;
 
 #[derive(Debug)]
@@ -20,10 +27,26 @@ pub enum Error {
     BIP32(bip32::Error),
     Miniscript(bdk::miniscript::Error),
     DescriptorKeyParse(DescriptorKeyParseError),
+    Conversion(ConversionError),
     UnsupportedDerivationPath,
     PurposePathNotFound,
     CoinPathNotFound,
     DescriptorNotFound,
+    NotMultisig,
+    /// Depth of the extended key doesn't match the length of the origin path
+    KeyOriginDepthMismatch {
+        depth: u8,
+        path_len: usize,
+    },
+    /// Child number of the extended key doesn't match the last step of the origin path
+    KeyOriginChildMismatch,
+    /// Fingerprint of the origin doesn't match the extended key (depth 0) or its parent (depth 1)
+    KeyOriginFingerprintMismatch,
+    /// Threshold must be between 1 and the number of keys
+    InvalidThreshold {
+        threshold: usize,
+        keys: usize,
+    },
 }
 
 impl std::error::Error for Error {}
@@ -34,12 +57,30 @@ impl fmt::Display for Error {
             Self::BIP32(e) => write!(f, "BIP32: {e}"),
             Self::Miniscript(e) => write!(f, "Miniscript: {e}"),
             Self::DescriptorKeyParse(e) => write!(f, "Descriptor Key parse: {e}"),
+            Self::Conversion(e) => write!(f, "Conversion: {e}"),
             Self::UnsupportedDerivationPath => write!(f, "Unsupported derivation path"),
             Self::PurposePathNotFound => write!(f, "Invalid derivation path: purpose not provided"),
             Self::CoinPathNotFound => {
                 write!(f, "Invalid derivation path: invalid coin or not provided")
             }
             Self::DescriptorNotFound => write!(f, "Descriptor not found"),
+            Self::NotMultisig => write!(f, "Descriptor is not a multisig"),
+            Self::KeyOriginDepthMismatch { depth, path_len } => write!(
+                f,
+                "Invalid key origin: extended key has depth {depth} but origin path has {path_len} steps"
+            ),
+            Self::KeyOriginChildMismatch => write!(
+                f,
+                "Invalid key origin: extended key child number doesn't match the origin path"
+            ),
+            Self::KeyOriginFingerprintMismatch => write!(
+                f,
+                "Invalid key origin: fingerprint doesn't match the extended key"
+            ),
+            Self::InvalidThreshold { threshold, keys } => write!(
+                f,
+                "Invalid threshold: {threshold} of {keys} keys (must be between 1 and {keys})"
+            ),
         }
     }
 }
@@ -62,10 +103,86 @@ impl From<DescriptorKeyParseError> for Error {
     }
 }
 
+impl From<ConversionError> for Error {
+    fn from(e: ConversionError) -> Self {
+        Self::Conversion(e)
+    }
+}
+
+/// Address derived from [`Descriptors`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivedAddress {
+    pub address: Address,
+    pub purpose: Purpose,
+    pub change: bool,
+    pub index: u32,
+    pub path: DerivationPath,
+}
+
+/// Result of an address scan (see [`Descriptors::scan_addresses`])
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanResult {
+    /// Derived addresses matching the candidates
+    pub matches: Vec<DerivedAddress>,
+    /// Number of derived addresses
+    pub scanned: u32,
+}
+
+/// Chain of a derived address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Chain {
+    Receive,
+    Change,
+}
+
+impl From<bool> for Chain {
+    fn from(change: bool) -> Self {
+        if change {
+            Self::Change
+        } else {
+            Self::Receive
+        }
+    }
+}
+
+impl fmt::Display for Chain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Receive => write!(f, "receive"),
+            Self::Change => write!(f, "change"),
+        }
+    }
+}
+
+/// Range scanned by [`Descriptors::verify_address`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ScannedRange {
+    pub script_types: Vec<String>,
+    pub chains: Vec<Chain>,
+    /// Indexes from `0` to `max_index` (excluded)
+    pub max_index: u32,
+}
+
+/// Result of [`Descriptors::verify_address`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AddressVerification {
+    pub matched: bool,
+    pub path: Option<DerivationPath>,
+    pub script_type: Option<String>,
+    pub chain: Option<Chain>,
+    /// Set only if not matched
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scanned: Option<ScannedRange>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Descriptors {
-    external: HashMap<Purpose, Descriptor<DescriptorPublicKey>>,
-    internal: HashMap<Purpose, Descriptor<DescriptorPublicKey>>,
+    external: BTreeMap<Purpose, Descriptor<DescriptorPublicKey>>,
+    internal: BTreeMap<Purpose, Descriptor<DescriptorPublicKey>>,
+    multipath: BTreeMap<Purpose, Descriptor<DescriptorPublicKey>>,
+    account: Option<u32>,
+    coin_type: Option<u32>,
 }
 
 impl Descriptors {
@@ -75,6 +192,22 @@ impl Descriptors {
         account: Option<u32>,
         secp: &Secp256k1<C>,
     ) -> Result<Self, Error>
+    where
+        C: Signing,
+    {
+        Self::new_with_coin_type(seed, network, account, None, secp)
+    }
+
+    /// Descriptors with a non-standard coin type (if `coin_type` is `Some`)
+    ///
+    /// Only to recover wallets that used a non-standard coin type.
+    pub fn new_with_coin_type<C>(
+        seed: &Seed,
+        network: Network,
+        account: Option<u32>,
+        coin_type: Option<u32>,
+        secp: &Secp256k1<C>,
+    ) -> Result<Self, Error>
     where
         C: Signing,
     {
@@ -88,15 +221,17 @@ impl Descriptors {
             Purpose::BIP86,
         ];
 
-        let capacity: usize = purposes.len();
         let mut descriptors = Descriptors {
-            external: HashMap::with_capacity(capacity),
-            internal: HashMap::with_capacity(capacity),
+            external: BTreeMap::new(),
+            internal: BTreeMap::new(),
+            multipath: BTreeMap::new(),
+            account,
+            coin_type,
         };
 
         for purpose in purposes.into_iter() {
             // Compose derivation path
-            let path: DerivationPath = purpose.to_account_extended_path(network, account)?;
+            let path: DerivationPath = descriptors.account_path(purpose, network)?;
 
             // Derive key
             let derived_private_key: ExtendedPrivKey = root.derive_priv(secp, &path)?;
@@ -111,19 +246,39 @@ impl Descriptors {
                 purpose,
                 typed_descriptor(root_fingerprint, derived_public_key, &path, true)?,
             );
+            descriptors.multipath.insert(
+                purpose,
+                typed_multipath_descriptor(root_fingerprint, derived_public_key, &path)?,
+            );
         }
 
         Ok(descriptors)
     }
 
+    fn account_path(&self, purpose: Purpose, network: Network) -> Result<DerivationPath, Error> {
+        match self.coin_type {
+            Some(coin_type) => {
+                Ok(purpose.to_account_extended_path_with_coin_type(coin_type, self.account)?)
+            }
+            None => Ok(purpose.to_account_extended_path(network, self.account)?),
+        }
+    }
+
+    /// Receive descriptors, ordered by purpose (BIP44, BIP49, BIP84 and BIP86)
     pub fn external(&self) -> Vec<Descriptor<DescriptorPublicKey>> {
         self.external.clone().into_values().collect()
     }
 
+    /// Change descriptors, ordered by purpose (BIP44, BIP49, BIP84 and BIP86)
     pub fn internal(&self) -> Vec<Descriptor<DescriptorPublicKey>> {
         self.internal.clone().into_values().collect()
     }
 
+    /// Multipath descriptors (`<0;1>`: receive and change in one descriptor), ordered by purpose
+    pub fn multipath(&self) -> Vec<Descriptor<DescriptorPublicKey>> {
+        self.multipath.clone().into_values().collect()
+    }
+
     pub fn get_by_purpose(
         &self,
         purpose: Purpose,
@@ -141,6 +296,135 @@ impl Descriptors {
                 .ok_or(Error::DescriptorNotFound)
         }
     }
+
+    /// Multipath descriptor (`<0;1>`) of `purpose`
+    pub fn get_multipath_by_purpose(
+        &self,
+        purpose: Purpose,
+    ) -> Result<Descriptor<DescriptorPublicKey>, Error> {
+        self.multipath
+            .get(&purpose)
+            .cloned()
+            .ok_or(Error::DescriptorNotFound)
+    }
+
+    /// Derive address at `index`
+    pub fn derive_address(
+        &self,
+        purpose: Purpose,
+        change: bool,
+        index: u32,
+        network: Network,
+    ) -> Result<DerivedAddress, Error> {
+        let descriptor = self.get_by_purpose(purpose, change)?;
+        let address: Address = descriptor.at_derivation_index(index)?.address(network)?;
+        let path: DerivationPath = self.account_path(purpose, network)?.extend([
+            ChildNumber::from_normal_idx(u32::from(change))?,
+            ChildNumber::from_normal_idx(index)?,
+        ]);
+        Ok(DerivedAddress {
+            address,
+            purpose,
+            change,
+            index,
+            path,
+        })
+    }
+
+    /// Derive the addresses in the `range` of indexes
+    pub fn derive_addresses(
+        &self,
+        purpose: Purpose,
+        change: bool,
+        range: Range<u32>,
+        network: Network,
+    ) -> Result<Vec<DerivedAddress>, Error> {
+        range
+            .map(|index| self.derive_address(purpose, change, index, network))
+            .collect()
+    }
+
+    /// Scan the addresses of `purposes` in the `range` of indexes, looking for the `candidates`
+    ///
+    /// Both external and internal addresses are checked. The scan stops when all the candidates are found.
+    pub fn scan_addresses(
+        &self,
+        candidates: &[Address],
+        network: Network,
+        purposes: &[Purpose],
+        range: Range<u32>,
+    ) -> Result<ScanResult, Error> {
+        let mut candidates: HashSet<&Address> = candidates.iter().collect();
+        let mut result = ScanResult::default();
+        for purpose in purposes.iter() {
+            for change in [false, true] {
+                for index in range.clone() {
+                    if candidates.is_empty() {
+                        return Ok(result);
+                    }
+
+                    let derived: DerivedAddress =
+                        self.derive_address(*purpose, change, index, network)?;
+                    result.scanned += 1;
+                    if candidates.remove(&derived.address) {
+                        result.matches.push(derived);
+                    }
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Find which of the `candidates` addresses are derivable from these descriptors
+    ///
+    /// Both external and internal addresses are checked, from index `0` to `max_index` (excluded).
+    pub fn find_addresses(
+        &self,
+        candidates: &[Address],
+        network: Network,
+        max_index: u32,
+    ) -> Result<Vec<DerivedAddress>, Error> {
+        let purposes: Vec<Purpose> = self.external.keys().copied().collect();
+        Ok(self
+            .scan_addresses(candidates, network, &purposes, 0..max_index)?
+            .matches)
+    }
+
+    /// Check if `address` is derivable from these descriptors (see [`Descriptors::find_addresses`])
+    pub fn verify_address(
+        &self,
+        address: &Address,
+        network: Network,
+        max_index: u32,
+    ) -> Result<AddressVerification, Error> {
+        let found = self.find_addresses(&[address.clone()], network, max_index)?;
+        match found.into_iter().next() {
+            Some(derived) => Ok(AddressVerification {
+                matched: true,
+                path: Some(derived.path),
+                script_type: Some(derived.purpose.script_name().to_string()),
+                chain: Some(Chain::from(derived.change)),
+                scanned: None,
+            }),
+            None => {
+                let purposes: Vec<Purpose> = self.external.keys().copied().collect();
+                Ok(AddressVerification {
+                    matched: false,
+                    path: None,
+                    script_type: None,
+                    chain: None,
+                    scanned: Some(ScannedRange {
+                        script_types: purposes
+                            .into_iter()
+                            .map(|p| p.script_name().to_string())
+                            .collect(),
+                        chains: vec![Chain::Receive, Chain::Change],
+                        max_index,
+                    }),
+                })
+            }
+        }
+    }
 }
 
 pub trait ToDescriptor: Bip32
@@ -194,6 +478,29 @@ pub fn descriptor(
     pubkey: ExtendedPubKey,
     path: &DerivationPath,
     change: bool,
+) -> Result<(ChildNumber, DescriptorPublicKey), Error> {
+    descriptor_key(
+        root_fingerprint,
+        pubkey,
+        path,
+        &i32::from(change).to_string(),
+    )
+}
+
+/// Multipath descriptor key (`<0;1>`: receive and change)
+pub fn multipath_descriptor(
+    root_fingerprint: Fingerprint,
+    pubkey: ExtendedPubKey,
+    path: &DerivationPath,
+) -> Result<(ChildNumber, DescriptorPublicKey), Error> {
+    descriptor_key(root_fingerprint, pubkey, path, "<0;1>")
+}
+
+fn descriptor_key(
+    root_fingerprint: Fingerprint,
+    pubkey: ExtendedPubKey,
+    path: &DerivationPath,
+    keychain: &str,
 ) -> Result<(ChildNumber, DescriptorPublicKey), Error> {
     let mut iter_path = path.into_iter();
 
@@ -203,8 +510,7 @@ pub fn descriptor(
     };
 
     let coin: &ChildNumber = match iter_path.next() {
-        Some(ChildNumber::Hardened { index: 0 }) => &ChildNumber::Hardened { index: 0 },
-        Some(ChildNumber::Hardened { index: 1 }) => &ChildNumber::Hardened { index: 1 },
+        Some(coin @ ChildNumber::Hardened { .. }) => coin,
         _ => return Err(Error::CoinPathNotFound),
     };
 
@@ -224,7 +530,7 @@ pub fn descriptor(
             None => String::new(),
         },
         pubkey,
-        i32::from(change)
+        keychain
     );
 
     Ok((*purpose, DescriptorPublicKey::from_str(&desc)?))
@@ -237,6 +543,23 @@ pub fn typed_descriptor(
     change: bool,
 ) -> Result<Descriptor<DescriptorPublicKey>, Error> {
     let (purpose, desc) = descriptor(root_fingerprint, pubkey, path, change)?;
+    to_typed(purpose, desc)
+}
+
+/// Multipath typed descriptor (i.e. `wpkh([fingerprint/84'/0'/0']xpub/<0;1>/*)#checksum`)
+pub fn typed_multipath_descriptor(
+    root_fingerprint: Fingerprint,
+    pubkey: ExtendedPubKey,
+    path: &DerivationPath,
+) -> Result<Descriptor<DescriptorPublicKey>, Error> {
+    let (purpose, desc) = multipath_descriptor(root_fingerprint, pubkey, path)?;
+    to_typed(purpose, desc)
+}
+
+fn to_typed(
+    purpose: ChildNumber,
+    desc: DescriptorPublicKey,
+) -> Result<Descriptor<DescriptorPublicKey>, Error> {
     match purpose {
         ChildNumber::Hardened { index: 44 } => Ok(Descriptor::new_pkh(desc)?),
         ChildNumber::Hardened { index: 49 } => Ok(Descriptor::new_sh_wpkh(desc)?),
@@ -246,6 +569,281 @@ pub fn typed_descriptor(
     }
 }
 
+/// BIP48 P2WSH multisig descriptors (`wsh(sortedmulti(..))`): external and internal
+///
+/// The key of `seed` (at `m/48'/<coin>'/<account>'/2'`) comes first, followed by the `cosigners`
+/// keys (`(master fingerprint, origin path, account xpub)`).
+pub fn multisig_descriptors<C>(
+    seed: &Seed,
+    threshold: usize,
+    cosigners: Vec<(Fingerprint, DerivationPath, ExtendedPubKey)>,
+    network: Network,
+    account: Option<u32>,
+    secp: &Secp256k1<C>,
+) -> Result<
+    (
+        Descriptor<DescriptorPublicKey>,
+        Descriptor<DescriptorPublicKey>,
+    ),
+    Error,
+>
+where
+    C: Signing,
+{
+    let keys: usize = cosigners.len() + 1;
+    if threshold == 0 || threshold > keys {
+        return Err(Error::InvalidThreshold { threshold, keys });
+    }
+
+    let root: ExtendedPrivKey = seed.to_bip32_root_key(network)?;
+    let path: DerivationPath = bip48::account_extended_path(network, account, ScriptType::P2WSH)?;
+    let derived_private_key: ExtendedPrivKey = root.derive_priv(secp, &path)?;
+    let derived_public_key: ExtendedPubKey = ExtendedPubKey::from_priv(secp, &derived_private_key);
+
+    let mut accounts: Vec<(Fingerprint, DerivationPath, ExtendedPubKey)> = Vec::with_capacity(keys);
+    accounts.push((root.fingerprint(secp), path, derived_public_key));
+    accounts.extend(cosigners);
+
+    let multisig = |change: bool| -> Result<Descriptor<DescriptorPublicKey>, Error> {
+        let mut pks: Vec<DescriptorPublicKey> = Vec::with_capacity(keys);
+        for (fingerprint, path, xpub) in accounts.iter() {
+            let key = DescriptorPublicKey::XPub(DescriptorXKey {
+                origin: Some((*fingerprint, path.clone())),
+                xkey: *xpub,
+                derivation_path: DerivationPath::from(vec![ChildNumber::from_normal_idx(
+                    u32::from(change),
+                )?]),
+                wildcard: Wildcard::Unhardened,
+            });
+            validate_key_origin(&key)?;
+            pks.push(key);
+        }
+        Ok(Descriptor::new_wsh_sortedmulti(threshold, pks)?)
+    };
+
+    Ok((multisig(false)?, multisig(true)?))
+}
+
+/// Get the position of the `seed` key in the `descriptor` (if any)
+///
+/// The key is derived at the origin path found in the descriptor and compared with the descriptor's key.
+pub fn key_position<C>(
+    seed: &Seed,
+    descriptor: &Descriptor<DescriptorPublicKey>,
+    network: Network,
+    secp: &Secp256k1<C>,
+) -> Result<Option<usize>, Error>
+where
+    C: Signing,
+{
+    let root: ExtendedPrivKey = seed.to_bip32_root_key(network)?;
+    let root_fingerprint: Fingerprint = root.fingerprint(secp);
+
+    let mut keys: Vec<DescriptorPublicKey> = Vec::new();
+    descriptor.for_each_key(|key| {
+        keys.push(key.clone());
+        true
+    });
+
+    for (index, key) in keys.iter().enumerate() {
+        if is_own_key(&root, root_fingerprint, key, secp)? {
+            return Ok(Some(index));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Check if `key` is derived from `root`, at the origin path found in the key
+fn is_own_key<C>(
+    root: &ExtendedPrivKey,
+    root_fingerprint: Fingerprint,
+    key: &DescriptorPublicKey,
+    secp: &Secp256k1<C>,
+) -> Result<bool, Error>
+where
+    C: Signing,
+{
+    Ok(match key {
+        DescriptorPublicKey::Single(single) => match &single.origin {
+            Some((fingerprint, path)) if *fingerprint == root_fingerprint => {
+                let derived: ExtendedPrivKey = root.derive_priv(secp, path)?;
+                let derived: ExtendedPubKey = ExtendedPubKey::from_priv(secp, &derived);
+                match single.key {
+                    SinglePubKey::FullKey(public_key) => public_key.inner == derived.public_key,
+                    SinglePubKey::XOnly(public_key) => public_key == derived.to_x_only_pub(),
+                }
+            }
+            _ => false,
+        },
+        DescriptorPublicKey::XPub(xkey) => match &xkey.origin {
+            Some((fingerprint, path)) if *fingerprint == root_fingerprint => {
+                let derived: ExtendedPrivKey = root.derive_priv(secp, path)?;
+                let derived: ExtendedPubKey = ExtendedPubKey::from_priv(secp, &derived);
+                derived.public_key == xkey.xkey.public_key
+                    && derived.chain_code == xkey.xkey.chain_code
+            }
+            _ => false,
+        },
+        DescriptorPublicKey::MultiXPub(xkey) => match &xkey.origin {
+            Some((fingerprint, path)) if *fingerprint == root_fingerprint => {
+                let derived: ExtendedPrivKey = root.derive_priv(secp, path)?;
+                let derived: ExtendedPubKey = ExtendedPubKey::from_priv(secp, &derived);
+                derived.public_key == xkey.xkey.public_key
+                    && derived.chain_code == xkey.xkey.chain_code
+            }
+            _ => false,
+        },
+    })
+}
+
+/// Check that the origin (`[fingerprint/path]`) of an extended key is consistent with the key
+///
+/// The depth must match the length of the origin path and the child number its last step.
+/// The fingerprint can be verified only for keys at depth 0 (master key) or 1 (parent fingerprint).
+/// Keys without origin and single keys are accepted as they are.
+pub fn validate_key_origin(key: &DescriptorPublicKey) -> Result<(), Error> {
+    let (origin, xkey) = match key {
+        DescriptorPublicKey::Single(..) => return Ok(()),
+        DescriptorPublicKey::XPub(xkey) => (&xkey.origin, &xkey.xkey),
+        DescriptorPublicKey::MultiXPub(xkey) => (&xkey.origin, &xkey.xkey),
+    };
+
+    if let Some((fingerprint, path)) = origin {
+        if xkey.depth as usize != path.len() {
+            return Err(Error::KeyOriginDepthMismatch {
+                depth: xkey.depth,
+                path_len: path.len(),
+            });
+        }
+
+        match path.as_ref().last() {
+            Some(child) => {
+                if *child != xkey.child_number {
+                    return Err(Error::KeyOriginChildMismatch);
+                }
+                if xkey.depth == 1 && xkey.parent_fingerprint != *fingerprint {
+                    return Err(Error::KeyOriginFingerprintMismatch);
+                }
+            }
+            None => {
+                if xkey.fingerprint() != *fingerprint {
+                    return Err(Error::KeyOriginFingerprintMismatch);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Multisig policy found in a descriptor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultisigInfo {
+    /// Required signatures
+    pub threshold: usize,
+    /// Cosigner keys, in descriptor order
+    pub cosigners: Vec<DescriptorPublicKey>,
+    /// `sortedmulti`: the keys are sorted when building the script
+    pub sorted: bool,
+}
+
+impl MultisigInfo {
+    /// Master fingerprints of the cosigners
+    pub fn fingerprints(&self) -> Vec<Fingerprint> {
+        self.cosigners
+            .iter()
+            .map(|key| key.master_fingerprint())
+            .collect()
+    }
+
+    /// Check the origin of all the cosigner keys (see [`validate_key_origin`])
+    pub fn validate_key_origins(&self) -> Result<(), Error> {
+        self.cosigners.iter().try_for_each(validate_key_origin)
+    }
+
+    /// Get the position of the `seed` key between the cosigners (if any)
+    pub fn cosigner_position<C>(
+        &self,
+        seed: &Seed,
+        network: Network,
+        secp: &Secp256k1<C>,
+    ) -> Result<Option<usize>, Error>
+    where
+        C: Signing,
+    {
+        let root: ExtendedPrivKey = seed.to_bip32_root_key(network)?;
+        let root_fingerprint: Fingerprint = root.fingerprint(secp);
+        for (index, key) in self.cosigners.iter().enumerate() {
+            if is_own_key(&root, root_fingerprint, key, secp)? {
+                return Ok(Some(index));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl fmt::Display for MultisigInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-of-{} ({})",
+            self.threshold,
+            self.cosigners.len(),
+            if self.sorted { "sortedmulti" } else { "multi" }
+        )
+    }
+}
+
+fn multi_from_node<Ctx>(
+    node: &Terminal<DescriptorPublicKey, Ctx>,
+) -> Option<(usize, Vec<DescriptorPublicKey>, bool)>
+where
+    Ctx: ScriptContext,
+{
+    match node {
+        Terminal::Multi(k, pks) | Terminal::MultiA(k, pks) => Some((*k, pks.clone(), false)),
+        _ => None,
+    }
+}
+
+fn multi_from_wsh(
+    wsh: &Wsh<DescriptorPublicKey>,
+) -> Option<(usize, Vec<DescriptorPublicKey>, bool)> {
+    match wsh.as_inner() {
+        WshInner::SortedMulti(smv) => Some((smv.k, smv.pks.clone(), true)),
+        WshInner::Ms(ms) => multi_from_node(&ms.node),
+    }
+}
+
+/// Extract the multisig policy (`multi`, `sortedmulti` or tapscript `multi_a`) from a descriptor
+///
+/// For taproot descriptors the first `multi_a` leaf is used.
+pub fn summarize_multisig(
+    descriptor: &Descriptor<DescriptorPublicKey>,
+) -> Result<MultisigInfo, Error> {
+    let multi = match descriptor {
+        Descriptor::Sh(sh) => match sh.as_inner() {
+            ShInner::SortedMulti(smv) => Some((smv.k, smv.pks.clone(), true)),
+            ShInner::Wsh(wsh) => multi_from_wsh(wsh),
+            ShInner::Ms(ms) => multi_from_node(&ms.node),
+            ShInner::Wpkh(..) => None,
+        },
+        Descriptor::Wsh(wsh) => multi_from_wsh(wsh),
+        Descriptor::Tr(tr) => tr
+            .iter_scripts()
+            .find_map(|(_, ms)| multi_from_node(&ms.node)),
+        _ => None,
+    };
+
+    let (threshold, cosigners, sorted) = multi.ok_or(Error::NotMultisig)?;
+    Ok(MultisigInfo {
+        threshold,
+        cosigners,
+        sorted,
+    })
+}
+
 #[cfg(test)]
 mod test {
     use bip39::Mnemonic;
@@ -305,4 +903,413 @@ mod test {
             .unwrap();
         assert_eq!(desc.to_string(), String::from("wpkh([91ef223d/84'/1'/2345']tpubDCgYuiX1p1eecECkhNc2bLSktmSDoMTj5J3v184ErUXqHTywQ7X5afv51UGfDVSaYzDWvdHhVyJ6UK8fM27EwGByWdczEERfAA9j2nzHUAj/1/*)#tj43jnd8"));
     }
+
+    #[test]
+    fn test_descriptors_order() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("range special tuna oblige own drama trend render harsh army outdoor bulb brisk sing analyst own fork senior stove flash fire bulk umbrella vast").unwrap();
+        let seed = Seed::from_mnemonic(mnemonic);
+        let descriptors = Descriptors::new(&seed, Network::Bitcoin, None, &secp).unwrap();
+
+        let prefixes = [
+            "pkh([91ef223d/44'",
+            "sh(wpkh([91ef223d/49'",
+            "wpkh([91ef223d/84'",
+            "tr([91ef223d/86'",
+        ];
+        for list in [
+            descriptors.external(),
+            descriptors.internal(),
+            descriptors.multipath(),
+        ] {
+            assert_eq!(list.len(), prefixes.len());
+            for (desc, prefix) in list.iter().zip(prefixes) {
+                assert!(desc.to_string().starts_with(prefix));
+            }
+        }
+    }
+
+    #[test]
+    fn test_multipath_descriptors() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("range special tuna oblige own drama trend render harsh army outdoor bulb brisk sing analyst own fork senior stove flash fire bulk umbrella vast").unwrap();
+        let seed = Seed::from_mnemonic(mnemonic);
+        let descriptors = Descriptors::new(&seed, Network::Bitcoin, None, &secp).unwrap();
+
+        for purpose in [
+            Purpose::BIP44,
+            Purpose::BIP49,
+            Purpose::BIP84,
+            Purpose::BIP86,
+        ] {
+            let multipath = descriptors.multipath.get(&purpose).unwrap().clone();
+            assert!(multipath.is_multipath());
+
+            // Checksum
+            let (desc, checksum) = multipath
+                .to_string()
+                .split_once('#')
+                .map(|(d, c)| (d.to_string(), c.to_string()))
+                .unwrap();
+            assert!(desc.contains("/<0;1>/*"));
+            assert_eq!(
+                Descriptor::<DescriptorPublicKey>::from_str(&format!("{desc}#{checksum}")).unwrap(),
+                multipath
+            );
+            assert!(
+                Descriptor::<DescriptorPublicKey>::from_str(&format!("{desc}#00000000")).is_err()
+            );
+
+            // Same as receive + change descriptors
+            assert_eq!(
+                multipath.into_single_descriptors().unwrap(),
+                vec![
+                    descriptors.get_by_purpose(purpose, false).unwrap(),
+                    descriptors.get_by_purpose(purpose, true).unwrap()
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_taproot_descriptors() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("range special tuna oblige own drama trend render harsh army outdoor bulb brisk sing analyst own fork senior stove flash fire bulk umbrella vast").unwrap();
+        let seed = Seed::from_mnemonic(mnemonic);
+        let descriptors = Descriptors::new(&seed, Network::Bitcoin, None, &secp).unwrap();
+
+        let external = descriptors.get_by_purpose(Purpose::BIP86, false).unwrap();
+        assert_eq!(external.to_string(), "tr([91ef223d/86'/0'/0']xpub6CjhhJyrYK83TKQq797CMiNzc4bpoJiYRBeb7iQ99T6dXrEgvg24hDw3ZKDJLNMyiy9Sbwqaw8TtCdaE4xXhnYwy7ptpNVfEAKUCcz8PMtP/0/*)#qkangwzf");
+
+        let internal = descriptors.get_by_purpose(Purpose::BIP86, true).unwrap();
+        assert!(internal.to_string().starts_with("tr([91ef223d/86'/0'/0']xpub6CjhhJyrYK83TKQq797CMiNzc4bpoJiYRBeb7iQ99T6dXrEgvg24hDw3ZKDJLNMyiy9Sbwqaw8TtCdaE4xXhnYwy7ptpNVfEAKUCcz8PMtP/1/*)#"));
+
+        let multipath = descriptors
+            .get_multipath_by_purpose(Purpose::BIP86)
+            .unwrap();
+        assert!(multipath.to_string().starts_with("tr([91ef223d/86'/0'/0']xpub6CjhhJyrYK83TKQq797CMiNzc4bpoJiYRBeb7iQ99T6dXrEgvg24hDw3ZKDJLNMyiy9Sbwqaw8TtCdaE4xXhnYwy7ptpNVfEAKUCcz8PMtP/<0;1>/*)#"));
+
+        // First receive and change addresses
+        let address = descriptors
+            .derive_address(Purpose::BIP86, false, 0, Network::Bitcoin)
+            .unwrap();
+        assert_eq!(address.path.to_string(), "m/86'/0'/0'/0/0");
+        assert!(address.address.to_string().starts_with("bc1p"));
+        let address = descriptors
+            .derive_address(Purpose::BIP86, true, 0, Network::Bitcoin)
+            .unwrap();
+        assert_eq!(address.path.to_string(), "m/86'/0'/0'/1/0");
+
+        // Range of change addresses
+        let addresses = descriptors
+            .derive_addresses(Purpose::BIP86, true, 0..3, Network::Bitcoin)
+            .unwrap();
+        assert_eq!(addresses.len(), 3);
+        assert_eq!(addresses[0], address);
+        assert_eq!(addresses[2].path.to_string(), "m/86'/0'/0'/1/2");
+    }
+
+    #[test]
+    fn test_descriptors_with_coin_type() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("range special tuna oblige own drama trend render harsh army outdoor bulb brisk sing analyst own fork senior stove flash fire bulk umbrella vast").unwrap();
+        let seed = Seed::from_mnemonic(mnemonic);
+
+        // Standard coin type
+        let standard = Descriptors::new(&seed, Network::Bitcoin, None, &secp).unwrap();
+        let descriptors =
+            Descriptors::new_with_coin_type(&seed, Network::Bitcoin, None, Some(0), &secp).unwrap();
+        assert_eq!(
+            descriptors.get_by_purpose(Purpose::BIP86, false).unwrap(),
+            standard.get_by_purpose(Purpose::BIP86, false).unwrap()
+        );
+
+        // Non-standard coin type
+        let descriptors =
+            Descriptors::new_with_coin_type(&seed, Network::Bitcoin, Some(1), Some(2), &secp)
+                .unwrap();
+        let desc = descriptors.get_by_purpose(Purpose::BIP84, false).unwrap();
+        assert!(desc
+            .to_string()
+            .starts_with("wpkh([91ef223d/84'/2'/1']xpub"));
+        let derived = descriptors
+            .derive_address(Purpose::BIP84, true, 3, Network::Bitcoin)
+            .unwrap();
+        assert_eq!(derived.path.to_string(), "m/84'/2'/1'/1/3");
+
+        // Not a valid hardened index
+        assert!(matches!(
+            Descriptors::new_with_coin_type(&seed, Network::Bitcoin, None, Some(0x80000000), &secp),
+            Err(Error::BIP32(bip32::Error::InvalidChildNumber(0x80000000)))
+        ));
+    }
+
+    #[test]
+    fn test_multisig_descriptors() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("range special tuna oblige own drama trend render harsh army outdoor bulb brisk sing analyst own fork senior stove flash fire bulk umbrella vast").unwrap();
+        let seed = Seed::from_mnemonic(mnemonic);
+
+        // Cosigners
+        let mut cosigners = Vec::new();
+        for mnemonic in [
+            "message scissors typical gravity patrol lunch about bacon person focus cry uncover",
+            "easy uncover favorite crystal bless differ energy seat ecology match carry group refuse together chat observe hidden glad brave month diesel sustain depth salt",
+        ] {
+            let cosigner = Seed::from_mnemonic(Mnemonic::from_str(mnemonic).unwrap());
+            let root = cosigner.to_bip32_root_key(Network::Bitcoin).unwrap();
+            let path =
+                bip48::account_extended_path(Network::Bitcoin, None, ScriptType::P2WSH).unwrap();
+            let xpub =
+                ExtendedPubKey::from_priv(&secp, &root.derive_priv(&secp, &path).unwrap());
+            cosigners.push((root.fingerprint(&secp), path, xpub));
+        }
+
+        let (external, internal) =
+            multisig_descriptors(&seed, 2, cosigners.clone(), Network::Bitcoin, None, &secp)
+                .unwrap();
+        assert!(external
+            .to_string()
+            .starts_with("wsh(sortedmulti(2,[91ef223d/48'/0'/0'/2']xpub"));
+        assert_eq!(
+            key_position(&seed, &external, Network::Bitcoin, &secp).unwrap(),
+            Some(0)
+        );
+
+        let info = summarize_multisig(&external).unwrap();
+        assert_eq!(info.threshold, 2);
+        assert_eq!(info.cosigners.len(), 3);
+        assert!(info.sorted);
+        info.validate_key_origins().unwrap();
+
+        // Internal: same keys, change branch
+        assert_eq!(
+            internal.to_string().split('#').next(),
+            external
+                .to_string()
+                .split('#')
+                .next()
+                .map(|desc| desc.replace("/0/*", "/1/*"))
+                .as_deref()
+        );
+
+        // Invalid threshold
+        assert!(matches!(
+            multisig_descriptors(&seed, 4, cosigners.clone(), Network::Bitcoin, None, &secp),
+            Err(Error::InvalidThreshold {
+                threshold: 4,
+                keys: 3
+            })
+        ));
+        assert!(matches!(
+            multisig_descriptors(&seed, 0, cosigners.clone(), Network::Bitcoin, None, &secp),
+            Err(Error::InvalidThreshold { .. })
+        ));
+
+        // Cosigner with inconsistent origin
+        let (fingerprint, _, xpub) = cosigners[0];
+        let wrong_path = DerivationPath::from_str("m/48'/0'/0'").unwrap();
+        assert!(matches!(
+            multisig_descriptors(
+                &seed,
+                2,
+                vec![(fingerprint, wrong_path, xpub)],
+                Network::Bitcoin,
+                None,
+                &secp
+            ),
+            Err(Error::KeyOriginDepthMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_key_position() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("range special tuna oblige own drama trend render harsh army outdoor bulb brisk sing analyst own fork senior stove flash fire bulk umbrella vast").unwrap();
+        let seed = Seed::from_mnemonic(mnemonic);
+
+        // Single key
+        let desc = Descriptor::from_str("tr([91ef223d/86'/0'/0']xpub6CjhhJyrYK83TKQq797CMiNzc4bpoJiYRBeb7iQ99T6dXrEgvg24hDw3ZKDJLNMyiy9Sbwqaw8TtCdaE4xXhnYwy7ptpNVfEAKUCcz8PMtP/0/*)").unwrap();
+        assert_eq!(
+            key_position(&seed, &desc, Network::Bitcoin, &secp).unwrap(),
+            Some(0)
+        );
+
+        // Multisig
+        let desc = Descriptor::from_str("wsh(multi(1,[00000000/48'/0'/0'/2']xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8/0/*,[91ef223d/48'/0'/0'/3']xpub6DaRkmkUCnzQNUYFxbZKDZTxmBaU2mwjHxxhaVd9f5twgMoiPz232PDqEfkKfqTnQeqnGZciVcmWnhTKUxUgp48R8FvCNYiwH4P8oCEk6B8/0/*))").unwrap();
+        assert_eq!(
+            key_position(&seed, &desc, Network::Bitcoin, &secp).unwrap(),
+            Some(1)
+        );
+
+        // Same fingerprint, wrong key
+        let desc = Descriptor::from_str("tr([91ef223d/86'/0'/1']xpub6CjhhJyrYK83TKQq797CMiNzc4bpoJiYRBeb7iQ99T6dXrEgvg24hDw3ZKDJLNMyiy9Sbwqaw8TtCdaE4xXhnYwy7ptpNVfEAKUCcz8PMtP/0/*)").unwrap();
+        assert_eq!(
+            key_position(&seed, &desc, Network::Bitcoin, &secp).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_summarize_multisig() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("range special tuna oblige own drama trend render harsh army outdoor bulb brisk sing analyst own fork senior stove flash fire bulk umbrella vast").unwrap();
+        let seed = Seed::from_mnemonic(mnemonic);
+
+        let desc = Descriptor::from_str("wsh(multi(1,[00000000/48'/0'/0'/2']xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8/0/*,[91ef223d/48'/0'/0'/3']xpub6DaRkmkUCnzQNUYFxbZKDZTxmBaU2mwjHxxhaVd9f5twgMoiPz232PDqEfkKfqTnQeqnGZciVcmWnhTKUxUgp48R8FvCNYiwH4P8oCEk6B8/0/*))").unwrap();
+        let info = summarize_multisig(&desc).unwrap();
+        assert_eq!(info.threshold, 1);
+        assert_eq!(info.cosigners.len(), 2);
+        assert!(!info.sorted);
+        assert_eq!(info.to_string(), "1-of-2 (multi)");
+        assert_eq!(
+            info.fingerprints(),
+            vec![
+                Fingerprint::from_str("00000000").unwrap(),
+                Fingerprint::from_str("91ef223d").unwrap()
+            ]
+        );
+        assert_eq!(
+            info.cosigner_position(&seed, Network::Bitcoin, &secp)
+                .unwrap(),
+            Some(1)
+        );
+
+        let desc = Descriptor::from_str("sh(wsh(sortedmulti(2,[00000000/48'/0'/0'/1']xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8/0/*,[91ef223d/48'/0'/0'/1']xpub6DaRkmkUCnzQNUYFxbZKDZTxmBaU2mwjHxxhaVd9f5twgMoiPz232PDqEfkKfqTnQeqnGZciVcmWnhTKUxUgp48R8FvCNYiwH4P8oCEk6B8/0/*)))").unwrap();
+        let info = summarize_multisig(&desc).unwrap();
+        assert_eq!(info.to_string(), "2-of-2 (sortedmulti)");
+        // Wrong origin path for the seed key
+        assert_eq!(
+            info.cosigner_position(&seed, Network::Bitcoin, &secp)
+                .unwrap(),
+            None
+        );
+
+        // Single key
+        let desc = Descriptor::from_str("tr([91ef223d/86'/0'/0']xpub6CjhhJyrYK83TKQq797CMiNzc4bpoJiYRBeb7iQ99T6dXrEgvg24hDw3ZKDJLNMyiy9Sbwqaw8TtCdaE4xXhnYwy7ptpNVfEAKUCcz8PMtP/0/*)").unwrap();
+        assert!(matches!(summarize_multisig(&desc), Err(Error::NotMultisig)));
+    }
+
+    #[test]
+    fn test_validate_key_origin() {
+        // BIP32 test vector 1
+        let master = DescriptorPublicKey::from_str("[3442193e]xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8").unwrap();
+        assert!(validate_key_origin(&master).is_ok());
+        let child = DescriptorPublicKey::from_str("[3442193e/0']xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw/0/*").unwrap();
+        assert!(validate_key_origin(&child).is_ok());
+
+        let key = DescriptorPublicKey::from_str("[00000000]xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8").unwrap();
+        assert!(matches!(
+            validate_key_origin(&key),
+            Err(Error::KeyOriginFingerprintMismatch)
+        ));
+        let key = DescriptorPublicKey::from_str("[00000000/0']xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw/0/*").unwrap();
+        assert!(matches!(
+            validate_key_origin(&key),
+            Err(Error::KeyOriginFingerprintMismatch)
+        ));
+        let key = DescriptorPublicKey::from_str("[3442193e/1']xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw/0/*").unwrap();
+        assert!(matches!(
+            validate_key_origin(&key),
+            Err(Error::KeyOriginChildMismatch)
+        ));
+
+        // Depth 3
+        let key = DescriptorPublicKey::from_str("[91ef223d/86'/0'/0']xpub6CjhhJyrYK83TKQq797CMiNzc4bpoJiYRBeb7iQ99T6dXrEgvg24hDw3ZKDJLNMyiy9Sbwqaw8TtCdaE4xXhnYwy7ptpNVfEAKUCcz8PMtP/0/*").unwrap();
+        assert!(validate_key_origin(&key).is_ok());
+        let key = DescriptorPublicKey::from_str("[91ef223d/86'/0']xpub6CjhhJyrYK83TKQq797CMiNzc4bpoJiYRBeb7iQ99T6dXrEgvg24hDw3ZKDJLNMyiy9Sbwqaw8TtCdaE4xXhnYwy7ptpNVfEAKUCcz8PMtP/0/*").unwrap();
+        assert!(matches!(
+            validate_key_origin(&key),
+            Err(Error::KeyOriginDepthMismatch {
+                depth: 3,
+                path_len: 2
+            })
+        ));
+
+        // No origin
+        let key = DescriptorPublicKey::from_str("xpub6CjhhJyrYK83TKQq797CMiNzc4bpoJiYRBeb7iQ99T6dXrEgvg24hDw3ZKDJLNMyiy9Sbwqaw8TtCdaE4xXhnYwy7ptpNVfEAKUCcz8PMtP/0/*").unwrap();
+        assert!(validate_key_origin(&key).is_ok());
+    }
+
+    #[test]
+    fn test_find_addresses() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("range special tuna oblige own drama trend render harsh army outdoor bulb brisk sing analyst own fork senior stove flash fire bulk umbrella vast").unwrap();
+        let seed = Seed::from_mnemonic(mnemonic);
+        let descriptors = Descriptors::new(&seed, Network::Testnet, None, &secp).unwrap();
+
+        let mine = descriptors
+            .derive_address(Purpose::BIP84, true, 3, Network::Testnet)
+            .unwrap();
+        assert_eq!(mine.path.to_string(), "m/84'/1'/0'/1/3");
+
+        let not_mine = Address::from_str("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx")
+            .unwrap()
+            .require_network(Network::Testnet)
+            .unwrap();
+
+        let found = descriptors
+            .find_addresses(
+                &[not_mine.clone(), mine.address.clone()],
+                Network::Testnet,
+                10,
+            )
+            .unwrap();
+        assert_eq!(found, vec![mine.clone()]);
+
+        let verification = descriptors
+            .verify_address(&mine.address, Network::Testnet, 10)
+            .unwrap();
+        assert!(verification.matched);
+        assert_eq!(verification.path, Some(mine.path));
+        assert_eq!(verification.script_type.as_deref(), Some("native-segwit"));
+        assert_eq!(verification.chain, Some(Chain::Change));
+        assert_eq!(verification.scanned, None);
+
+        let verification = descriptors
+            .verify_address(&not_mine, Network::Testnet, 10)
+            .unwrap();
+        let json = serde_json::to_value(&verification).unwrap();
+        assert_eq!(json["matched"], false);
+        assert!(json["path"].is_null());
+        assert_eq!(
+            json["scanned"]["chains"],
+            serde_json::json!(["receive", "change"])
+        );
+        assert_eq!(json["scanned"]["max_index"], 10);
+    }
+
+    #[test]
+    fn test_scan_addresses() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("range special tuna oblige own drama trend render harsh army outdoor bulb brisk sing analyst own fork senior stove flash fire bulk umbrella vast").unwrap();
+        let seed = Seed::from_mnemonic(mnemonic);
+        let descriptors = Descriptors::new(&seed, Network::Testnet, None, &secp).unwrap();
+
+        let mine = descriptors
+            .derive_address(Purpose::BIP84, true, 3, Network::Testnet)
+            .unwrap();
+        let not_mine = Address::from_str("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx")
+            .unwrap()
+            .require_network(Network::Testnet)
+            .unwrap();
+
+        // Stops when all the candidates are found: 10 receive and 4 change addresses
+        let result = descriptors
+            .scan_addresses(
+                &[mine.address.clone()],
+                Network::Testnet,
+                &[Purpose::BIP84],
+                0..10,
+            )
+            .unwrap();
+        assert_eq!(result.matches, vec![mine]);
+        assert_eq!(result.scanned, 14);
+
+        let result = descriptors
+            .scan_addresses(&[not_mine], Network::Testnet, &[Purpose::BIP84], 5..10)
+            .unwrap();
+        assert!(result.matches.is_empty());
+        assert_eq!(result.scanned, 10);
+    }
 }
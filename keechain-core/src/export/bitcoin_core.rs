@@ -9,10 +9,13 @@ use bdk::miniscript::descriptor::{Descriptor, DescriptorPublicKey};
 use serde::Serialize;
 use serde_json::json;
 
+use super::WalletExport;
+use crate::bips::bip32::{self, Bip32, Fingerprint};
 use crate::{descriptors, Descriptors, Seed};
 
 #[derive(Debug)]
 pub enum Error {
+    BIP32(bip32::Error),
     Descriptor(descriptors::Error),
 }
 
@@ -21,11 +24,18 @@ impl std::error::Error for Error {}
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            Self::BIP32(e) => write!(f, "BIP32: {e}"),
             Self::Descriptor(e) => write!(f, "Descriptor: {e}"),
         }
     }
 }
 
+impl From<bip32::Error> for Error {
+    fn from(e: bip32::Error) -> Self {
+        Self::BIP32(e)
+    }
+}
+
 impl From<descriptors::Error> for Error {
     fn from(e: descriptors::Error) -> Self {
         Self::Descriptor(e)
@@ -49,10 +59,21 @@ impl BitcoinCoreDescriptor {
             internal,
         }
     }
+
+    pub fn descriptor(&self) -> &Descriptor<DescriptorPublicKey> {
+        &self.desc
+    }
+
+    pub fn is_internal(&self) -> bool {
+        self.internal
+    }
 }
 
 #[derive(Debug)]
-pub struct BitcoinCore(Vec<BitcoinCoreDescriptor>);
+pub struct BitcoinCore {
+    root_fingerprint: Fingerprint,
+    descriptors: Vec<BitcoinCoreDescriptor>,
+}
 
 impl BitcoinCore {
     pub fn new<C>(
@@ -75,12 +96,33 @@ impl BitcoinCore {
             bitcoin_core_descriptors.push(BitcoinCoreDescriptor::new(desc, true));
         }
 
-        Ok(Self(bitcoin_core_descriptors))
+        Ok(Self {
+            root_fingerprint: seed.fingerprint(network, secp)?,
+            descriptors: bitcoin_core_descriptors,
+        })
+    }
+
+    pub fn root_fingerprint(&self) -> Fingerprint {
+        self.root_fingerprint
+    }
+
+    pub fn descriptors(&self) -> &[BitcoinCoreDescriptor] {
+        &self.descriptors
     }
 }
 
 impl ToString for BitcoinCore {
     fn to_string(&self) -> String {
-        format!("\nimportdescriptors '{}'\n", json!(self.0))
+        format!("\nimportdescriptors '{}'\n", self.to_json())
+    }
+}
+
+impl WalletExport for BitcoinCore {
+    fn to_json(&self) -> String {
+        json!(self.descriptors).to_string()
+    }
+
+    fn suggested_filename(&self) -> String {
+        format!("keechain-bitcoin-core-{}.json", self.root_fingerprint)
     }
 }
@@ -0,0 +1,253 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! BSMS signer key record (BIP129, round 1)
+//!
+//! <https://github.com/bitcoin/bips/blob/master/bip-0129.mediawiki>
+
+use core::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use bdk::bitcoin::secp256k1::{Message, Secp256k1, Signing};
+use bdk::bitcoin::sign_message::{self, MessageSignature};
+use bdk::bitcoin::Network;
+use bdk::miniscript::descriptor::{DescriptorPublicKey, DescriptorXKey, Wildcard};
+
+use crate::bips::bip32::{
+    self, Bip32, DerivationPath, ExtendedPrivKey, ExtendedPubKey, Fingerprint,
+};
+use crate::bips::bip48::{self, ScriptType};
+use crate::types::Seed;
+use crate::util::{base64, hex};
+
+const BSMS_VERSION: &str = "BSMS 1.0";
+/// Token of the `NO_ENCRYPTION` mode
+const NO_TOKEN: &str = "00";
+/// Max length of the key description
+pub const MAX_DESCRIPTION_LEN: usize = 80;
+
+#[derive(Debug)]
+pub enum Error {
+    BIP32(bip32::Error),
+    /// Token must be 8 (`STANDARD`) or 16 (`EXTENDED`) bytes
+    InvalidTokenLength(usize),
+    /// Description longer than [`MAX_DESCRIPTION_LEN`] characters
+    DescriptionTooLong(usize),
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BIP32(e) => write!(f, "BIP32: {e}"),
+            Self::InvalidTokenLength(len) => {
+                write!(f, "Invalid token: must be 8 or 16 bytes, found {len} bytes")
+            }
+            Self::DescriptionTooLong(len) => write!(
+                f,
+                "Description too long: max {MAX_DESCRIPTION_LEN} characters, found {len}"
+            ),
+        }
+    }
+}
+
+impl From<bip32::Error> for Error {
+    fn from(e: bip32::Error) -> Self {
+        Self::BIP32(e)
+    }
+}
+
+/// BSMS key record, to send to the coordinator
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bsms {
+    fingerprint: Fingerprint,
+    token: String,
+    key: DescriptorPublicKey,
+    description: String,
+    signature: String,
+}
+
+impl Bsms {
+    /// Key record of the BIP48 account key for `script`
+    ///
+    /// The `token` is provided by the coordinator (`None` if the record is not encrypted).
+    pub fn new<S, C>(
+        seed: &Seed,
+        network: Network,
+        script: ScriptType,
+        account: Option<u32>,
+        token: Option<&[u8]>,
+        description: S,
+        secp: &Secp256k1<C>,
+    ) -> Result<Self, Error>
+    where
+        S: Into<String>,
+        C: Signing,
+    {
+        let token: String = match token {
+            Some(token) => {
+                if token.len() != 8 && token.len() != 16 {
+                    return Err(Error::InvalidTokenLength(token.len()));
+                }
+                hex::encode(token)
+            }
+            None => String::from(NO_TOKEN),
+        };
+
+        let description: String = description.into();
+        let description_len: usize = description.chars().count();
+        if description_len > MAX_DESCRIPTION_LEN {
+            return Err(Error::DescriptionTooLong(description_len));
+        }
+
+        let root: ExtendedPrivKey = seed.to_bip32_root_key(network)?;
+        let path: DerivationPath = bip48::account_extended_path(network, account, script)?;
+        let xpriv: ExtendedPrivKey = root.derive_priv(secp, &path)?;
+        let pubkey: ExtendedPubKey = ExtendedPubKey::from_priv(secp, &xpriv);
+        let key = DescriptorPublicKey::XPub(DescriptorXKey {
+            origin: Some((root.fingerprint(secp), path)),
+            xkey: pubkey,
+            derivation_path: DerivationPath::default(),
+            wildcard: Wildcard::None,
+        });
+
+        // Signed with the private key of the record key (Bitcoin Signed Message)
+        let content: String = record(&token, &key, &description);
+        let msg = Message::from(sign_message::signed_msg_hash(&content));
+        let signature = secp.sign_ecdsa_recoverable(&msg, &xpriv.private_key);
+        let signature = MessageSignature::new(signature, true);
+
+        Ok(Self {
+            fingerprint: pubkey.fingerprint(),
+            token,
+            key,
+            description,
+            signature: base64::encode(signature.serialize()),
+        })
+    }
+
+    /// Key with origin (`[fingerprint/48'/0'/0'/2']xpub...`)
+    pub fn key(&self) -> &DescriptorPublicKey {
+        &self.key
+    }
+
+    /// Base64 encoded signature of the record
+    pub fn signature(&self) -> &str {
+        &self.signature
+    }
+
+    /// Suggested file name (i.e. `keechain-bsms-<fingerprint>.bsms`)
+    pub fn suggested_filename(&self) -> String {
+        format!("keechain-bsms-{}.bsms", self.fingerprint)
+    }
+
+    /// Save the record into `dir`, using the [`Bsms::suggested_filename`]
+    pub fn save_to_file<P>(&self, dir: P) -> Result<PathBuf, io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let path: PathBuf = dir.as_ref().join(self.suggested_filename());
+        fs::write(&path, self.to_string())?;
+        Ok(path)
+    }
+}
+
+/// Record content, without the signature
+fn record(token: &str, key: &DescriptorPublicKey, description: &str) -> String {
+    format!("{BSMS_VERSION}\n{token}\n{key}\n{description}")
+}
+
+impl fmt::Display for Bsms {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", record(&self.token, &self.key, &self.description))?;
+        write!(f, "{}", self.signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bip39::Mnemonic;
+
+    use super::*;
+
+    #[test]
+    fn test_bsms() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("easy uncover favorite crystal bless differ energy seat ecology match carry group refuse together chat observe hidden glad brave month diesel sustain depth salt").unwrap();
+        let seed = Seed::from_mnemonic(mnemonic);
+        let root_fingerprint: Fingerprint = seed.fingerprint(Network::Testnet, &secp).unwrap();
+
+        let bsms = Bsms::new(
+            &seed,
+            Network::Testnet,
+            ScriptType::P2WSH,
+            None,
+            None,
+            "Signer 1 key",
+            &secp,
+        )
+        .unwrap();
+
+        let content: String = bsms.to_string();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], "BSMS 1.0");
+        assert_eq!(lines[1], "00");
+        assert!(lines[2].starts_with(&format!("[{root_fingerprint}/48'/1'/0'/2']tpub")));
+        assert_eq!(lines[3], "Signer 1 key");
+        assert_eq!(lines[4], bsms.signature());
+
+        // Signature recovers the record key
+        let signature =
+            MessageSignature::from_slice(&base64::decode(bsms.signature()).unwrap()).unwrap();
+        let msg_hash = sign_message::signed_msg_hash(&lines[..4].join("\n"));
+        let pubkey = signature.recover_pubkey(&secp, msg_hash).unwrap();
+        match bsms.key() {
+            DescriptorPublicKey::XPub(xkey) => assert_eq!(pubkey.inner, xkey.xkey.public_key),
+            _ => panic!("expected xpub"),
+        }
+
+        let token: Vec<u8> = hex::decode("a54044308ceac9b7").unwrap();
+        let bsms = Bsms::new(
+            &seed,
+            Network::Testnet,
+            ScriptType::P2WSH,
+            None,
+            Some(&token),
+            "Signer 1 key",
+            &secp,
+        )
+        .unwrap();
+        assert_eq!(bsms.to_string().lines().nth(1), Some("a54044308ceac9b7"));
+
+        assert!(matches!(
+            Bsms::new(
+                &seed,
+                Network::Testnet,
+                ScriptType::P2WSH,
+                None,
+                Some(&[0; 4]),
+                "",
+                &secp
+            ),
+            Err(Error::InvalidTokenLength(4))
+        ));
+        assert!(matches!(
+            Bsms::new(
+                &seed,
+                Network::Testnet,
+                ScriptType::P2WSH,
+                None,
+                None,
+                "x".repeat(81),
+                &secp
+            ),
+            Err(Error::DescriptionTooLong(81))
+        ));
+    }
+}
@@ -137,7 +137,7 @@ impl ColdcardGenericJson {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-enum ColdcardGenericJsonNetwork {
+pub(crate) enum ColdcardGenericJsonNetwork {
     /// Mainnet
     Btc,
     /// Testnet
@@ -178,6 +178,16 @@ impl From<ColdcardGenericJsonNetwork> for Network {
     }
 }
 
+impl From<Network> for ColdcardGenericJsonNetwork {
+    fn from(network: Network) -> Self {
+        match network {
+            Network::Bitcoin => Self::Btc,
+            Network::Regtest => Self::Xrt,
+            _ => Self::Xtn,
+        }
+    }
+}
+
 impl Serialize for ColdcardGenericJsonNetwork {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -2,26 +2,51 @@
 // Distributed under the MIT software license
 
 use std::fmt;
-use std::fs::File;
-use std::io::Write;
-use std::path::{Path, PathBuf};
+use std::fs;
+use std::path::Path;
 
 use bdk::bitcoin::secp256k1::{Secp256k1, Signing};
 use bdk::bitcoin::Network;
+use bdk::miniscript::descriptor::{Descriptor, DescriptorPublicKey};
+use bdk::miniscript::ForEachKey;
 use serde::{Deserialize, Serialize};
 
+use super::{BitcoinCore, WalletExport};
 use crate::bips::bip32::{
-    self, Bip32, DerivationPath, ExtendedPrivKey, ExtendedPubKey, Fingerprint,
+    self, Bip32, ChildNumber, DerivationPath, ExtendedPrivKey, ExtendedPubKey, Fingerprint,
 };
-use crate::slips::slip132::{self, ToSlip132};
+use crate::bips::bip43::Purpose;
+use crate::bips::bip44;
+use crate::descriptors::{self, typed_descriptor};
+use crate::slips::slip132::{self, FromSlip132, ToSlip132};
 use crate::types::Seed;
 
+/// Wallet type of the single-signature wallets
+const WALLET_TYPE_STANDARD: &str = "standard";
+
 #[derive(Debug)]
 pub enum Error {
     IO(std::io::Error),
     BIP32(bip32::Error),
     SLIP32(slip132::Error),
     Json(serde_json::Error),
+    Descriptors(descriptors::Error),
+    /// Wallet file encrypted by Electrum (disable the file encryption in Electrum before exporting it)
+    EncryptedWallet,
+    /// Only `standard` wallets are supported (i.e. not multisig)
+    UnsupportedWalletType(String),
+    /// Keystore derivation path doesn't match the script of the xpub
+    DerivationMismatch {
+        script: ElectrumSupportedScripts,
+        derivation: DerivationPath,
+    },
+    /// No Bitcoin Core descriptor with the keystore derivation path
+    KeyNotFound(DerivationPath),
+    /// Keystore xpub doesn't match the Bitcoin Core descriptor key
+    KeyMismatch {
+        electrum: String,
+        bitcoin_core: String,
+    },
 }
 
 impl std::error::Error for Error {}
@@ -33,6 +58,28 @@ impl fmt::Display for Error {
             Self::BIP32(e) => write!(f, "BIP32: {e}"),
             Self::SLIP32(e) => write!(f, "SLIP32: {e}"),
             Self::Json(e) => write!(f, "Json: {e}"),
+            Self::Descriptors(e) => write!(f, "Descriptors: {e}"),
+            Self::EncryptedWallet => write!(
+                f,
+                "Encrypted wallet file: disable the file encryption in Electrum and try again"
+            ),
+            Self::UnsupportedWalletType(wallet_type) => {
+                write!(f, "Unsupported wallet type: {wallet_type}")
+            }
+            Self::DerivationMismatch { script, derivation } => write!(
+                f,
+                "Derivation path {derivation} doesn't match the {script} xpub"
+            ),
+            Self::KeyNotFound(path) => {
+                write!(f, "No Bitcoin Core descriptor found for {path}")
+            }
+            Self::KeyMismatch {
+                electrum,
+                bitcoin_core,
+            } => write!(
+                f,
+                "Electrum xpub {electrum} doesn't match Bitcoin Core xpub {bitcoin_core}"
+            ),
         }
     }
 }
@@ -61,6 +108,12 @@ impl From<serde_json::Error> for Error {
     }
 }
 
+impl From<descriptors::Error> for Error {
+    fn from(e: descriptors::Error) -> Self {
+        Self::Descriptors(e)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[repr(u8)]
 pub enum ElectrumSupportedScripts {
@@ -82,6 +135,45 @@ impl ElectrumSupportedScripts {
     pub fn as_u32(&self) -> u32 {
         *self as u32
     }
+
+    /// Get the script for a [`Purpose`] (`None` if not supported by Electrum)
+    pub fn from_purpose(purpose: Purpose) -> Option<Self> {
+        match purpose {
+            Purpose::BIP44 => Some(Self::Legacy),
+            Purpose::BIP49 => Some(Self::Segwit),
+            Purpose::BIP84 => Some(Self::NativeSegwit),
+            _ => None,
+        }
+    }
+
+    /// Electrum xpub type (i.e. `p2wpkh`)
+    pub fn xpub_type(&self) -> &'static str {
+        match self {
+            Self::Legacy => "standard",
+            Self::Segwit => "p2wpkh-p2sh",
+            Self::NativeSegwit => "p2wpkh",
+        }
+    }
+
+    /// Get the script for an Electrum xpub type (`standard`, `p2wpkh-p2sh` or `p2wpkh`)
+    pub fn from_xpub_type(xpub_type: &str) -> Option<Self> {
+        match xpub_type {
+            "standard" => Some(Self::Legacy),
+            "p2wpkh-p2sh" => Some(Self::Segwit),
+            "p2wpkh" => Some(Self::NativeSegwit),
+            _ => None,
+        }
+    }
+}
+
+impl From<ElectrumSupportedScripts> for Purpose {
+    fn from(value: ElectrumSupportedScripts) -> Self {
+        match value {
+            ElectrumSupportedScripts::Legacy => Self::BIP44,
+            ElectrumSupportedScripts::Segwit => Self::BIP49,
+            ElectrumSupportedScripts::NativeSegwit => Self::BIP84,
+        }
+    }
 }
 
 impl fmt::Display for ElectrumSupportedScripts {
@@ -97,12 +189,20 @@ impl fmt::Display for ElectrumSupportedScripts {
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct ElectrumKeystore {
     xpub: String,
+    /// Only in the wallet files with private keys
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    xprv: Option<String>,
     #[serde(skip)]
     fingerprint: Fingerprint,
+    #[serde(skip)]
+    script: ElectrumSupportedScripts,
     root_fingerprint: Fingerprint,
     #[serde(rename = "type")]
     keystore_type: String,
     derivation: DerivationPath,
+    /// Keystore label, shown by Electrum (i.e. `keechain 91ef223d native-segwit account 0`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -125,36 +225,396 @@ impl Electrum {
         C: Signing,
     {
         let root: ExtendedPrivKey = seed.to_bip32_root_key(network)?;
-        let path: DerivationPath = bip32::account_extended_path(script.as_u32(), network, account)?;
+        let root_fingerprint: Fingerprint = root.fingerprint(secp);
+        let path: DerivationPath = bip44::account_path(script.into(), network, account)?;
         let xpriv: ExtendedPrivKey = root.derive_priv(secp, &path)?;
         let pubkey: ExtendedPubKey = ExtendedPubKey::from_priv(secp, &xpriv);
+        let label: String = format!(
+            "keechain {root_fingerprint} {} account {}",
+            Purpose::from(script).script_name(),
+            account.unwrap_or_default()
+        );
 
         Ok(Self {
             keystore: ElectrumKeystore {
                 xpub: pubkey.to_slip132(&path)?,
+                xprv: None,
                 fingerprint: pubkey.fingerprint(),
-                root_fingerprint: root.fingerprint(secp),
+                script,
+                root_fingerprint,
                 keystore_type: String::from("bip32"),
                 derivation: path,
+                label: Some(label),
             },
-            wallet_type: String::from("standard"),
+            wallet_type: String::from(WALLET_TYPE_STANDARD),
             use_encryption: false,
             seed_version: 20,
         })
     }
 
-    pub fn as_json(&self) -> String {
-        serde_json::json!(self).to_string()
+    /// Read an Electrum wallet file (`standard` wallet, not encrypted)
+    pub fn from_file<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let content: String = fs::read_to_string(path)?;
+        Self::from_json(&content)
     }
 
-    pub fn save_to_file<P>(&self, path: P) -> Result<PathBuf, Error>
+    /// Parse an Electrum wallet JSON, getting the script from the keystore xpub (`xpub`, `ypub` or `zpub`)
+    pub fn from_json<S>(json: S) -> Result<Self, Error>
     where
-        P: AsRef<Path>,
+        S: AsRef<str>,
     {
-        let file_name: String = format!("keechain-{}.json", self.keystore.fingerprint);
-        let path: PathBuf = path.as_ref().join(file_name);
-        let mut file: File = File::options().create(true).write(true).open(&path)?;
-        file.write_all(&serde_json::to_vec(self)?)?;
-        Ok(path)
+        let json: &str = json.as_ref().trim();
+        // Electrum encrypted files are base64 strings
+        if !json.starts_with('{') {
+            return Err(Error::EncryptedWallet);
+        }
+
+        let value: serde_json::Value = serde_json::from_str(json)?;
+        let wallet_type: &str = value
+            .get("wallet_type")
+            .and_then(|t| t.as_str())
+            .unwrap_or_default();
+        if wallet_type != WALLET_TYPE_STANDARD {
+            return Err(Error::UnsupportedWalletType(wallet_type.to_string()));
+        }
+
+        let mut electrum: Self = serde_json::from_value(value)?;
+        let (pubkey, purpose) = ExtendedPubKey::from_slip132(&electrum.keystore.xpub)?;
+        let script = ElectrumSupportedScripts::from_purpose(purpose)
+            .ok_or(slip132::Error::UnknownVersion)?;
+        let derivation: &DerivationPath = &electrum.keystore.derivation;
+        let expected = ChildNumber::Hardened {
+            index: script.as_u32(),
+        };
+        if derivation.into_iter().next() != Some(&expected) {
+            return Err(Error::DerivationMismatch {
+                script,
+                derivation: derivation.clone(),
+            });
+        }
+
+        electrum.keystore.fingerprint = pubkey.fingerprint();
+        electrum.keystore.script = script;
+        Ok(electrum)
+    }
+
+    pub fn script(&self) -> ElectrumSupportedScripts {
+        self.keystore.script
+    }
+
+    pub fn root_fingerprint(&self) -> Fingerprint {
+        self.keystore.root_fingerprint
+    }
+
+    /// Account derivation path (i.e. `m/84'/0'/0'`)
+    pub fn derivation(&self) -> &DerivationPath {
+        &self.keystore.derivation
+    }
+
+    /// Account number, from the [`Electrum::derivation`]
+    pub fn account(&self) -> u32 {
+        match self.keystore.derivation.into_iter().nth(2) {
+            Some(ChildNumber::Hardened { index }) => *index,
+            _ => 0,
+        }
+    }
+
+    /// Keystore label (`None` if not set)
+    pub fn label(&self) -> Option<&str> {
+        self.keystore.label.as_deref()
+    }
+
+    /// Account xpub (SLIP132 encoded, i.e. `zpub`)
+    pub fn xpub(&self) -> &str {
+        &self.keystore.xpub
+    }
+
+    pub fn network(&self) -> Result<Network, Error> {
+        let (pubkey, _) = ExtendedPubKey::from_slip132(&self.keystore.xpub)?;
+        Ok(pubkey.network)
+    }
+
+    /// Account xprv (`None` for watch-only wallets)
+    pub fn xprv(&self) -> Result<Option<ExtendedPrivKey>, Error> {
+        match &self.keystore.xprv {
+            Some(xprv) => {
+                let (xprv, _) = ExtendedPrivKey::from_slip132(xprv)?;
+                Ok(Some(xprv))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Receive and change descriptors of the keystore
+    pub fn descriptors(
+        &self,
+    ) -> Result<
+        (
+            Descriptor<DescriptorPublicKey>,
+            Descriptor<DescriptorPublicKey>,
+        ),
+        Error,
+    > {
+        let (pubkey, _) = ExtendedPubKey::from_slip132(&self.keystore.xpub)?;
+        let root_fingerprint: Fingerprint = self.keystore.root_fingerprint;
+        let path: &DerivationPath = &self.keystore.derivation;
+        Ok((
+            typed_descriptor(root_fingerprint, pubkey, path, false)?,
+            typed_descriptor(root_fingerprint, pubkey, path, true)?,
+        ))
+    }
+
+    /// Check that the keystore matches the key of the [`BitcoinCore`] receive descriptor with the same origin
+    pub fn verify(&self, bitcoin_core: &BitcoinCore) -> Result<(), Error> {
+        let mut keys: Vec<DescriptorPublicKey> = Vec::new();
+        for desc in bitcoin_core
+            .descriptors()
+            .iter()
+            .filter(|d| !d.is_internal())
+        {
+            desc.descriptor().for_each_key(|key| {
+                keys.push(key.clone());
+                true
+            });
+        }
+
+        let xkey = keys
+            .into_iter()
+            .find_map(|key| match key {
+                DescriptorPublicKey::XPub(xkey) => match &xkey.origin {
+                    Some((fingerprint, path))
+                        if *fingerprint == self.keystore.root_fingerprint
+                            && *path == self.keystore.derivation =>
+                    {
+                        Some(xkey.xkey)
+                    }
+                    _ => None,
+                },
+                _ => None,
+            })
+            .ok_or_else(|| Error::KeyNotFound(self.keystore.derivation.clone()))?;
+
+        let bitcoin_core: String = xkey.to_slip132(&self.keystore.derivation)?;
+        if bitcoin_core != self.keystore.xpub {
+            return Err(Error::KeyMismatch {
+                electrum: self.keystore.xpub.clone(),
+                bitcoin_core,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl WalletExport for Electrum {
+    fn to_json(&self) -> String {
+        serde_json::json!(self).to_string()
+    }
+
+    /// `keechain-<fingerprint>-<script>-account-<account>.json`
+    fn suggested_filename(&self) -> String {
+        format!(
+            "keechain-{}-{}-account-{}.json",
+            self.keystore.fingerprint,
+            Purpose::from(self.keystore.script).script_name(),
+            self.account()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bip39::Mnemonic;
+
+    use super::*;
+    use crate::Descriptors;
+
+    #[test]
+    fn test_electrum_verify() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("easy uncover favorite crystal bless differ energy seat ecology match carry group refuse together chat observe hidden glad brave month diesel sustain depth salt").unwrap();
+        let seed = Seed::from_mnemonic(mnemonic);
+
+        for network in [Network::Bitcoin, Network::Testnet] {
+            let bitcoin_core = BitcoinCore::new(&seed, network, Some(1), &secp).unwrap();
+            for script in [
+                ElectrumSupportedScripts::Legacy,
+                ElectrumSupportedScripts::Segwit,
+                ElectrumSupportedScripts::NativeSegwit,
+            ] {
+                let electrum = Electrum::new(&seed, network, script, Some(1), &secp).unwrap();
+                electrum.verify(&bitcoin_core).unwrap();
+            }
+        }
+
+        // Different account
+        let bitcoin_core = BitcoinCore::new(&seed, Network::Bitcoin, None, &secp).unwrap();
+        let electrum = Electrum::new(
+            &seed,
+            Network::Bitcoin,
+            ElectrumSupportedScripts::NativeSegwit,
+            Some(1),
+            &secp,
+        )
+        .unwrap();
+        assert!(matches!(
+            electrum.verify(&bitcoin_core),
+            Err(Error::KeyNotFound(..))
+        ));
+
+        // Tampered keystore
+        let mut electrum = Electrum::new(
+            &seed,
+            Network::Bitcoin,
+            ElectrumSupportedScripts::NativeSegwit,
+            None,
+            &secp,
+        )
+        .unwrap();
+        let other = Electrum::new(
+            &seed,
+            Network::Bitcoin,
+            ElectrumSupportedScripts::Segwit,
+            None,
+            &secp,
+        )
+        .unwrap();
+        electrum.keystore.xpub = other.keystore.xpub;
+        assert!(matches!(
+            electrum.verify(&bitcoin_core),
+            Err(Error::KeyMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_electrum_account_metadata() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("easy uncover favorite crystal bless differ energy seat ecology match carry group refuse together chat observe hidden glad brave month diesel sustain depth salt").unwrap();
+        let seed = Seed::from_mnemonic(mnemonic);
+        let root_fingerprint: Fingerprint = seed.fingerprint(Network::Bitcoin, &secp).unwrap();
+
+        let account_0 = Electrum::new(
+            &seed,
+            Network::Bitcoin,
+            ElectrumSupportedScripts::NativeSegwit,
+            None,
+            &secp,
+        )
+        .unwrap();
+        let account_1 = Electrum::new(
+            &seed,
+            Network::Bitcoin,
+            ElectrumSupportedScripts::NativeSegwit,
+            Some(1),
+            &secp,
+        )
+        .unwrap();
+        assert_eq!(account_0.account(), 0);
+        assert_eq!(account_1.account(), 1);
+        assert_eq!(
+            account_1.label(),
+            Some(format!("keechain {root_fingerprint} native-segwit account 1").as_str())
+        );
+        assert!(account_1
+            .suggested_filename()
+            .ends_with("-native-segwit-account-1.json"));
+        assert_ne!(
+            account_0.suggested_filename(),
+            account_1.suggested_filename()
+        );
+
+        let imported = Electrum::from_json(account_1.to_json()).unwrap();
+        assert_eq!(imported.label(), account_1.label());
+        assert_eq!(
+            imported.suggested_filename(),
+            account_1.suggested_filename()
+        );
+    }
+
+    #[test]
+    fn test_electrum_from_json() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("easy uncover favorite crystal bless differ energy seat ecology match carry group refuse together chat observe hidden glad brave month diesel sustain depth salt").unwrap();
+        let seed = Seed::from_mnemonic(mnemonic);
+
+        for network in [Network::Bitcoin, Network::Testnet] {
+            let descriptors = Descriptors::new(&seed, network, None, &secp).unwrap();
+            for script in [
+                ElectrumSupportedScripts::Legacy,
+                ElectrumSupportedScripts::Segwit,
+                ElectrumSupportedScripts::NativeSegwit,
+            ] {
+                let electrum = Electrum::new(&seed, network, script, None, &secp).unwrap();
+                let imported = Electrum::from_json(electrum.to_json()).unwrap();
+                assert_eq!(imported, electrum);
+                assert_eq!(imported.script(), script);
+                assert_eq!(
+                    ElectrumSupportedScripts::from_xpub_type(script.xpub_type()),
+                    Some(script)
+                );
+                assert_eq!(imported.network().unwrap(), network);
+                assert_eq!(imported.xprv().unwrap(), None);
+
+                let (external, internal) = imported.descriptors().unwrap();
+                assert_eq!(
+                    external,
+                    descriptors.get_by_purpose(script.into(), false).unwrap()
+                );
+                assert_eq!(
+                    internal,
+                    descriptors.get_by_purpose(script.into(), true).unwrap()
+                );
+            }
+        }
+
+        // Electrum file with private keys (and other fields)
+        let root: ExtendedPrivKey = seed.to_bip32_root_key(Network::Bitcoin).unwrap();
+        let path = DerivationPath::from_str("m/44'/0'/0'").unwrap();
+        let xprv: ExtendedPrivKey = root.derive_priv(&secp, &path).unwrap();
+        let electrum = Electrum::new(
+            &seed,
+            Network::Bitcoin,
+            ElectrumSupportedScripts::Legacy,
+            None,
+            &secp,
+        )
+        .unwrap();
+        let mut json: serde_json::Value = serde_json::from_str(&electrum.to_json()).unwrap();
+        json["keystore"]["xprv"] = serde_json::json!(xprv.to_string());
+        json["keystore"]["pw_hash_version"] = serde_json::json!(1);
+        json["addr_history"] = serde_json::json!({});
+        let imported = Electrum::from_json(json.to_string()).unwrap();
+        assert_eq!(imported.xprv().unwrap(), Some(xprv));
+        assert_eq!(imported.root_fingerprint(), root.fingerprint(&secp));
+
+        // Derivation path of another script
+        let mut json: serde_json::Value = serde_json::from_str(&electrum.to_json()).unwrap();
+        json["keystore"]["derivation"] = serde_json::json!("m/84'/0'/0'");
+        assert!(matches!(
+            Electrum::from_json(json.to_string()),
+            Err(Error::DerivationMismatch {
+                script: ElectrumSupportedScripts::Legacy,
+                ..
+            })
+        ));
+
+        // Multisig
+        let mut json: serde_json::Value = serde_json::from_str(&electrum.to_json()).unwrap();
+        json["wallet_type"] = serde_json::json!("2of3");
+        assert!(matches!(
+            Electrum::from_json(json.to_string()),
+            Err(Error::UnsupportedWalletType(wallet_type)) if wallet_type == "2of3"
+        ));
+
+        // Encrypted by Electrum
+        assert!(matches!(
+            Electrum::from_json("QklFMQ"),
+            Err(Error::EncryptedWallet)
+        ));
     }
 }
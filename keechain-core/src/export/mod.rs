@@ -1,12 +1,202 @@
 // Copyright (c) 2022-2023 Yuki Kishimoto
 // Distributed under the MIT software license
 
+use core::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
 pub mod bitcoin_core;
+pub mod bsms;
 pub mod coldcard;
 pub mod electrum;
+pub mod passport;
+pub mod seed_qr;
+pub mod sparrow;
+pub mod summary;
 pub mod wasabi;
 
 pub use self::bitcoin_core::BitcoinCore;
+pub use self::bsms::Bsms;
 pub use self::coldcard::ColdcardGenericJson;
 pub use self::electrum::{Electrum, ElectrumSupportedScripts};
+pub use self::passport::Passport;
+pub use self::seed_qr::{SeedQr, SeedQrFormat};
+pub use self::sparrow::{Sparrow, SparrowScriptType};
+pub use self::summary::Summary;
 pub use self::wasabi::Wasabi;
+
+use crate::crypto::{self, MultiEncryption};
+
+/// Extension appended to the file name of the encrypted exports
+pub const ENCRYPTED_EXTENSION: &str = "enc";
+
+#[derive(Debug)]
+pub enum Error {
+    IO(io::Error),
+    Crypto(crypto::Error),
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IO(e) => write!(f, "IO: {e}"),
+            Self::Crypto(e) => write!(f, "Crypto: {e}"),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Self::IO(e)
+    }
+}
+
+impl From<crypto::Error> for Error {
+    fn from(e: crypto::Error) -> Self {
+        Self::Crypto(e)
+    }
+}
+
+/// Export JSON, encrypted with a user-chosen password
+#[derive(Serialize, Deserialize)]
+struct EncryptedExport {
+    json: String,
+}
+
+impl MultiEncryption for EncryptedExport {}
+
+/// Wallet export format
+pub trait WalletExport {
+    /// Serialize the export
+    fn to_json(&self) -> String;
+
+    /// Suggested file name (i.e. `keechain-wasabi-<fingerprint>-native-segwit-account-0.json`)
+    fn suggested_filename(&self) -> String;
+
+    /// Save the export into `dir`, using the [`WalletExport::suggested_filename`]
+    fn save_to_file<P>(&self, dir: P) -> Result<PathBuf, io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let path: PathBuf = dir.as_ref().join(self.suggested_filename());
+        fs::write(&path, self.to_json())?;
+        Ok(path)
+    }
+
+    /// Encrypt the export with `password` and save it into `dir` (i.e. `keechain-wasabi-<fingerprint>-native-segwit-account-0.json.enc`)
+    ///
+    /// Use [`decrypt_export`] to get back the JSON.
+    fn save_encrypted_to_file<P, S>(&self, dir: P, password: S) -> Result<PathBuf, Error>
+    where
+        P: AsRef<Path>,
+        S: AsRef<[u8]>,
+    {
+        let filename: String = format!("{}.{ENCRYPTED_EXTENSION}", self.suggested_filename());
+        let path: PathBuf = dir.as_ref().join(filename);
+        let export = EncryptedExport {
+            json: self.to_json(),
+        };
+        fs::write(&path, export.encrypt(password)?)?;
+        Ok(path)
+    }
+}
+
+/// Decrypt an export saved with [`WalletExport::save_encrypted_to_file`], returning the JSON
+pub fn decrypt_export<P, S>(path: P, password: S) -> Result<String, Error>
+where
+    P: AsRef<Path>,
+    S: AsRef<[u8]>,
+{
+    let content: String = fs::read_to_string(path)?;
+    let export = EncryptedExport::decrypt(password, content.trim().as_bytes())?;
+    Ok(export.json)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bdk::bitcoin::secp256k1::Secp256k1;
+    use bdk::bitcoin::Network;
+    use bip39::Mnemonic;
+
+    use super::*;
+    use crate::types::Seed;
+
+    #[test]
+    fn test_wallet_export_save_to_file() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("range special tuna oblige own drama trend render harsh army outdoor bulb brisk sing analyst own fork senior stove flash fire bulk umbrella vast").unwrap();
+        let seed = Seed::from_mnemonic(mnemonic);
+        let dir: PathBuf =
+            std::env::temp_dir().join(format!("keechain-export-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        fn check<T>(export: T, dir: &Path)
+        where
+            T: WalletExport,
+        {
+            let path: PathBuf = export.save_to_file(dir).unwrap();
+            assert_eq!(path, dir.join(export.suggested_filename()));
+            assert_eq!(fs::read_to_string(&path).unwrap(), export.to_json());
+        }
+
+        check(
+            Wasabi::new(&seed, Network::Bitcoin, None, &secp).unwrap(),
+            &dir,
+        );
+        check(
+            BitcoinCore::new(&seed, Network::Bitcoin, None, &secp).unwrap(),
+            &dir,
+        );
+        check(
+            Passport::new(&seed, Network::Bitcoin, None, &secp).unwrap(),
+            &dir,
+        );
+        check(
+            Sparrow::new(
+                &seed,
+                Network::Bitcoin,
+                SparrowScriptType::default(),
+                None,
+                &secp,
+            )
+            .unwrap(),
+            &dir,
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_wallet_export_encrypted() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("range special tuna oblige own drama trend render harsh army outdoor bulb brisk sing analyst own fork senior stove flash fire bulk umbrella vast").unwrap();
+        let seed = Seed::from_mnemonic(mnemonic);
+        let dir: PathBuf = std::env::temp_dir().join(format!(
+            "keechain-export-encrypted-test-{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+
+        let wasabi = Wasabi::new(&seed, Network::Bitcoin, None, &secp).unwrap();
+        let path: PathBuf = wasabi.save_encrypted_to_file(&dir, "password").unwrap();
+        assert_eq!(
+            path,
+            dir.join(format!("{}.enc", wasabi.suggested_filename()))
+        );
+        assert_ne!(fs::read_to_string(&path).unwrap(), wasabi.to_json());
+        assert_eq!(decrypt_export(&path, "password").unwrap(), wasabi.to_json());
+        assert!(matches!(
+            decrypt_export(&path, "wrong"),
+            Err(Error::Crypto(_))
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,198 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Foundation Passport single-sig export
+
+use core::fmt;
+use std::collections::BTreeMap;
+
+use bdk::bitcoin::secp256k1::{Secp256k1, Signing};
+use bdk::bitcoin::{Address, Network};
+use serde::{Deserialize, Serialize};
+
+use super::coldcard::ColdcardGenericJsonNetwork;
+use super::WalletExport;
+use crate::bips::bip32::{
+    self, Bip32, DerivationPath, ExtendedPrivKey, ExtendedPubKey, Fingerprint,
+};
+use crate::bips::bip43::Purpose;
+use crate::descriptors::{self, typed_descriptor};
+use crate::slips::slip132::{self, ToSlip132};
+use crate::types::Seed;
+
+const DEVICE_NAME: &str = "Passport";
+
+#[derive(Debug)]
+pub enum Error {
+    IO(std::io::Error),
+    BIP32(bip32::Error),
+    SLIP32(slip132::Error),
+    Descriptors(descriptors::Error),
+    Miniscript(bdk::miniscript::Error),
+    Json(serde_json::Error),
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IO(e) => write!(f, "IO: {e}"),
+            Self::BIP32(e) => write!(f, "BIP32: {e}"),
+            Self::SLIP32(e) => write!(f, "SLIP32: {e}"),
+            Self::Descriptors(e) => write!(f, "Descriptors: {e}"),
+            Self::Miniscript(e) => write!(f, "Miniscript: {e}"),
+            Self::Json(e) => write!(f, "Json: {e}"),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Self::IO(e)
+    }
+}
+
+impl From<bip32::Error> for Error {
+    fn from(e: bip32::Error) -> Self {
+        Self::BIP32(e)
+    }
+}
+
+impl From<slip132::Error> for Error {
+    fn from(e: slip132::Error) -> Self {
+        Self::SLIP32(e)
+    }
+}
+
+impl From<descriptors::Error> for Error {
+    fn from(e: descriptors::Error) -> Self {
+        Self::Descriptors(e)
+    }
+}
+
+impl From<bdk::miniscript::Error> for Error {
+    fn from(e: bdk::miniscript::Error) -> Self {
+        Self::Miniscript(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct PassportChild {
+    name: String,
+    deriv: DerivationPath,
+    xpub: ExtendedPubKey,
+    #[serde(rename = "_pub", skip_serializing_if = "Option::is_none")]
+    slip132: Option<String>,
+    desc: String,
+    first: String,
+}
+
+/// Passport single-sig JSON (Sparrow/Envoy compatible)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Passport {
+    chain: ColdcardGenericJsonNetwork,
+    xfp: Fingerprint,
+    account: u32,
+    xpub: ExtendedPubKey,
+    device: String,
+    #[serde(flatten)]
+    bips: BTreeMap<String, PassportChild>,
+}
+
+impl Passport {
+    pub fn new<C>(
+        seed: &Seed,
+        network: Network,
+        account: Option<u32>,
+        secp: &Secp256k1<C>,
+    ) -> Result<Self, Error>
+    where
+        C: Signing,
+    {
+        let root: ExtendedPrivKey = seed.to_bip32_root_key(network)?;
+        let root_fingerprint: Fingerprint = root.fingerprint(secp);
+
+        let mut bips: BTreeMap<String, PassportChild> = BTreeMap::new();
+        for (purpose, name) in [
+            (Purpose::BIP44, "p2pkh"),
+            (Purpose::BIP49, "p2sh-p2wpkh"),
+            (Purpose::BIP84, "p2wpkh"),
+        ] {
+            let path: DerivationPath = purpose.to_account_extended_path(network, account)?;
+            let xpriv: ExtendedPrivKey = root.derive_priv(secp, &path)?;
+            let pubkey: ExtendedPubKey = ExtendedPubKey::from_priv(secp, &xpriv);
+            let desc = typed_descriptor(root_fingerprint, pubkey, &path, false)?;
+            let first: Address = desc
+                .at_derivation_index(0)
+                .map_err(descriptors::Error::from)?
+                .address(network)?;
+            let slip132: Option<String> = match purpose {
+                Purpose::BIP44 => None,
+                _ => Some(pubkey.to_slip132(&path)?),
+            };
+            bips.insert(
+                purpose.to_string(),
+                PassportChild {
+                    name: name.to_string(),
+                    deriv: path,
+                    xpub: pubkey,
+                    slip132,
+                    desc: desc.to_string(),
+                    first: first.to_string(),
+                },
+            );
+        }
+
+        Ok(Self {
+            chain: network.into(),
+            xfp: root_fingerprint,
+            account: account.unwrap_or_default(),
+            xpub: ExtendedPubKey::from_priv(secp, &root),
+            device: DEVICE_NAME.to_string(),
+            bips,
+        })
+    }
+}
+
+impl WalletExport for Passport {
+    fn to_json(&self) -> String {
+        serde_json::json!(self).to_string()
+    }
+
+    fn suggested_filename(&self) -> String {
+        format!("keechain-passport-{}.json", self.xfp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bip39::Mnemonic;
+
+    use super::*;
+
+    #[test]
+    fn test_passport_export() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("range special tuna oblige own drama trend render harsh army outdoor bulb brisk sing analyst own fork senior stove flash fire bulk umbrella vast").unwrap();
+        let seed = Seed::from_mnemonic(mnemonic);
+        let passport = Passport::new(&seed, Network::Bitcoin, None, &secp).unwrap();
+
+        let json: serde_json::Value = serde_json::from_str(&passport.to_json()).unwrap();
+        assert_eq!(json["chain"], "BTC");
+        assert_eq!(json["xfp"], "91ef223d");
+        assert_eq!(json["device"], "Passport");
+        assert_eq!(json["bip84"]["deriv"], "m/84'/0'/0'");
+        assert!(json["bip84"]["_pub"].as_str().unwrap().starts_with("zpub"));
+        assert!(json["bip44"].get("_pub").is_none());
+        assert!(json["bip49"]["first"].as_str().unwrap().starts_with('3'));
+    }
+}
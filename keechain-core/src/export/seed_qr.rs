@@ -0,0 +1,247 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! SeedQR (SeedSigner)
+//!
+//! <https://github.com/SeedSigner/seedsigner/blob/dev/docs/seed_qr/README.md>
+
+use core::fmt;
+
+use bip39::{Language, Mnemonic};
+
+use crate::util::hex;
+
+#[derive(Debug)]
+pub enum Error {
+    BIP39(bip39::Error),
+    /// SeedQR supports only 12 and 24 words mnemonics
+    UnsupportedWordCount(usize),
+    /// SeedQR defines only the English wordlist
+    UnsupportedLanguage(Language),
+    InvalidStandardSeedQr,
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BIP39(e) => write!(f, "BIP39: {e}"),
+            Self::UnsupportedWordCount(count) => write!(
+                f,
+                "SeedQR supports only 12 or 24 words mnemonics (found {count} words)"
+            ),
+            Self::UnsupportedLanguage(language) => write!(
+                f,
+                "SeedQR supports only English mnemonics (found {language:?})"
+            ),
+            Self::InvalidStandardSeedQr => write!(f, "Invalid standard SeedQR"),
+        }
+    }
+}
+
+impl From<bip39::Error> for Error {
+    fn from(e: bip39::Error) -> Self {
+        Self::BIP39(e)
+    }
+}
+
+/// SeedQR encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SeedQrFormat {
+    /// Word indexes as 4 digits each (numeric QR)
+    #[default]
+    Standard,
+    /// Entropy bytes, 11 bits per word without the checksum (binary QR)
+    Compact,
+}
+
+/// SeedQR payload
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeedQr {
+    format: SeedQrFormat,
+    payload: Vec<u8>,
+}
+
+impl SeedQr {
+    pub fn new(mnemonic: &Mnemonic, format: SeedQrFormat) -> Result<Self, Error> {
+        let word_count: usize = mnemonic.word_count();
+        if word_count != 12 && word_count != 24 {
+            return Err(Error::UnsupportedWordCount(word_count));
+        }
+
+        // Decoded with the English wordlist (also the compact one, from the entropy)
+        let language: Language = mnemonic.language();
+        if language != Language::English {
+            return Err(Error::UnsupportedLanguage(language));
+        }
+
+        let payload: Vec<u8> = match format {
+            SeedQrFormat::Standard => mnemonic
+                .word_iter()
+                .filter_map(|word| language.find_word(word))
+                .map(|index| format!("{index:04}"))
+                .collect::<String>()
+                .into_bytes(),
+            SeedQrFormat::Compact => mnemonic.to_entropy(),
+        };
+
+        Ok(Self { format, payload })
+    }
+
+    /// Parse a standard SeedQR (digits)
+    pub fn from_standard<S>(digits: S) -> Result<Self, Error>
+    where
+        S: AsRef<str>,
+    {
+        let digits: &str = digits.as_ref();
+        if !digits.chars().all(|c| c.is_ascii_digit()) || digits.len() % 4 != 0 {
+            return Err(Error::InvalidStandardSeedQr);
+        }
+        let seed_qr = Self {
+            format: SeedQrFormat::Standard,
+            payload: digits.as_bytes().to_vec(),
+        };
+        // Check that it's a valid mnemonic
+        seed_qr.to_mnemonic()?;
+        Ok(seed_qr)
+    }
+
+    /// Parse a compact SeedQR (entropy bytes)
+    pub fn from_compact(bytes: &[u8]) -> Result<Self, Error> {
+        let seed_qr = Self {
+            format: SeedQrFormat::Compact,
+            payload: bytes.to_vec(),
+        };
+        seed_qr.to_mnemonic()?;
+        Ok(seed_qr)
+    }
+
+    pub fn format(&self) -> SeedQrFormat {
+        self.format
+    }
+
+    /// QR code payload (numeric string for standard, raw bytes for compact)
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.payload
+    }
+
+    pub fn to_mnemonic(&self) -> Result<Mnemonic, Error> {
+        let mnemonic: Mnemonic = match self.format {
+            SeedQrFormat::Standard => {
+                let list = Language::English.word_list();
+                let mut words: Vec<&str> = Vec::new();
+                for chunk in self.payload.chunks(4) {
+                    let index: usize = std::str::from_utf8(chunk)
+                        .ok()
+                        .and_then(|digits| digits.parse().ok())
+                        .ok_or(Error::InvalidStandardSeedQr)?;
+                    words.push(list.get(index).ok_or(Error::InvalidStandardSeedQr)?);
+                }
+                Mnemonic::parse_in_normalized(Language::English, &words.join(" "))?
+            }
+            SeedQrFormat::Compact => Mnemonic::from_entropy(&self.payload)?,
+        };
+
+        let word_count: usize = mnemonic.word_count();
+        if word_count != 12 && word_count != 24 {
+            return Err(Error::UnsupportedWordCount(word_count));
+        }
+
+        Ok(mnemonic)
+    }
+}
+
+impl fmt::Display for SeedQr {
+    /// Digits for standard, hex for compact
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.format {
+            SeedQrFormat::Standard => write!(f, "{}", String::from_utf8_lossy(&self.payload)),
+            SeedQrFormat::Compact => write!(f, "{}", hex::encode(&self.payload)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_seed_qr_12_words() {
+        let mnemonic = Mnemonic::from_str(
+            "forum undo fragile fade shy sign arrest garment culture tube off merit",
+        )
+        .unwrap();
+
+        let standard = SeedQr::new(&mnemonic, SeedQrFormat::Standard).unwrap();
+        assert_eq!(
+            standard.to_string(),
+            "073318950739065415961602009907670428187212261116"
+        );
+        assert_eq!(standard.to_mnemonic().unwrap(), mnemonic);
+
+        let compact = SeedQr::new(&mnemonic, SeedQrFormat::Compact).unwrap();
+        assert_eq!(compact.as_bytes().len(), 16);
+        assert_eq!(compact.to_string(), "5bbd9d71a8ec7990831aff359d426545");
+        assert_eq!(compact.to_mnemonic().unwrap(), mnemonic);
+    }
+
+    #[test]
+    fn test_seed_qr_24_words() {
+        // SeedSigner reference
+        let mnemonic = Mnemonic::from_str("attack pizza motion avocado network gather crop fresh patrol unusual wild holiday candy pony ranch winter theme error hybrid van cereal salon goddess expire").unwrap();
+
+        let standard = SeedQr::new(&mnemonic, SeedQrFormat::Standard).unwrap();
+        assert_eq!(
+            standard.to_string(),
+            "011513251154012711900771041507421289190620080870026613431420201617920614089619290300152408010643"
+        );
+        assert_eq!(
+            SeedQr::from_standard(standard.to_string())
+                .unwrap()
+                .to_mnemonic()
+                .unwrap(),
+            mnemonic
+        );
+
+        let compact = SeedQr::new(&mnemonic, SeedQrFormat::Compact).unwrap();
+        assert_eq!(compact.as_bytes().len(), 32);
+        assert_eq!(
+            compact.to_string(),
+            "0e74b64107f94cc0ccfae6a13dcbec3662154fec67e0e00999c07892597d190a"
+        );
+        assert_eq!(
+            SeedQr::from_compact(compact.as_bytes())
+                .unwrap()
+                .to_mnemonic()
+                .unwrap(),
+            mnemonic
+        );
+    }
+
+    #[test]
+    fn test_seed_qr_invalid() {
+        let mnemonic = Mnemonic::from_entropy(&[0; 24]).unwrap();
+        assert!(matches!(
+            SeedQr::new(&mnemonic, SeedQrFormat::Compact),
+            Err(Error::UnsupportedWordCount(18))
+        ));
+
+        assert!(SeedQr::from_standard("07331895073").is_err());
+        assert!(SeedQr::from_standard("999918950739065415961602009907670428187212261116").is_err());
+        assert!(SeedQr::from_standard("07331895073906541596160200990767042818721226111a").is_err());
+        // Wrong checksum
+        assert!(SeedQr::from_standard("073318950739065415961602009907670428187212261117").is_err());
+
+        // Only English
+        let mnemonic = Mnemonic::from_entropy_in(Language::Spanish, &[0; 16]).unwrap();
+        for format in [SeedQrFormat::Standard, SeedQrFormat::Compact] {
+            assert!(matches!(
+                SeedQr::new(&mnemonic, format),
+                Err(Error::UnsupportedLanguage(Language::Spanish))
+            ));
+        }
+    }
+}
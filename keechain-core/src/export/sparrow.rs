@@ -0,0 +1,288 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Sparrow Wallet single-sig export (watch-only)
+
+use core::fmt;
+
+use bdk::bitcoin::secp256k1::{Secp256k1, Signing};
+use bdk::bitcoin::Network;
+use serde::{Deserialize, Serialize};
+
+use super::WalletExport;
+use crate::bips::bip32::{
+    self, Bip32, DerivationPath, ExtendedPrivKey, ExtendedPubKey, Fingerprint,
+};
+use crate::bips::bip43::Purpose;
+use crate::bips::bip44;
+use crate::types::Seed;
+
+#[derive(Debug)]
+pub enum Error {
+    BIP32(bip32::Error),
+    /// Sparrow single-sig wallets don't support the purpose (i.e. BIP48)
+    UnsupportedPurpose(Purpose),
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BIP32(e) => write!(f, "BIP32: {e}"),
+            Self::UnsupportedPurpose(purpose) => {
+                write!(f, "Purpose {purpose} not supported by Sparrow export")
+            }
+        }
+    }
+}
+
+impl From<bip32::Error> for Error {
+    fn from(e: bip32::Error) -> Self {
+        Self::BIP32(e)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SparrowScriptType {
+    /// P2PKH (BIP44)
+    #[serde(rename = "P2PKH")]
+    Legacy,
+    /// P2SH-P2WPKH (BIP49)
+    #[serde(rename = "P2SH_P2WPKH")]
+    NestedSegwit,
+    /// P2WPKH (BIP84)
+    #[serde(rename = "P2WPKH")]
+    NativeSegwit,
+    /// P2TR (BIP86)
+    #[serde(rename = "P2TR")]
+    Taproot,
+}
+
+impl Default for SparrowScriptType {
+    fn default() -> Self {
+        Self::NativeSegwit
+    }
+}
+
+impl SparrowScriptType {
+    /// Get the script type for a [`Purpose`] (`None` if not supported by Sparrow single-sig wallets)
+    pub fn from_purpose(purpose: Purpose) -> Option<Self> {
+        match purpose {
+            Purpose::BIP44 => Some(Self::Legacy),
+            Purpose::BIP49 => Some(Self::NestedSegwit),
+            Purpose::BIP84 => Some(Self::NativeSegwit),
+            Purpose::BIP86 => Some(Self::Taproot),
+            _ => None,
+        }
+    }
+
+    /// Sparrow policy script (i.e. `wpkh(keystore1)`)
+    fn policy_script(&self) -> String {
+        match self {
+            Self::Legacy => String::from("pkh(keystore1)"),
+            Self::NestedSegwit => String::from("sh(wpkh(keystore1))"),
+            Self::NativeSegwit => String::from("wpkh(keystore1)"),
+            Self::Taproot => String::from("tr(keystore1)"),
+        }
+    }
+}
+
+impl From<SparrowScriptType> for Purpose {
+    fn from(value: SparrowScriptType) -> Self {
+        match value {
+            SparrowScriptType::Legacy => Self::BIP44,
+            SparrowScriptType::NestedSegwit => Self::BIP49,
+            SparrowScriptType::NativeSegwit => Self::BIP84,
+            SparrowScriptType::Taproot => Self::BIP86,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SparrowMiniscript {
+    script: String,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SparrowPolicy {
+    name: String,
+    miniscript: SparrowMiniscript,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SparrowKeyDerivation {
+    master_fingerprint: Fingerprint,
+    derivation_path: DerivationPath,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SparrowKeystore {
+    label: String,
+    source: String,
+    wallet_model: String,
+    key_derivation: SparrowKeyDerivation,
+    extended_public_key: ExtendedPubKey,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sparrow {
+    #[serde(skip)]
+    fingerprint: Fingerprint,
+    name: String,
+    policy_type: String,
+    script_type: SparrowScriptType,
+    default_policy: SparrowPolicy,
+    keystores: Vec<SparrowKeystore>,
+}
+
+impl Sparrow {
+    pub fn new<C>(
+        seed: &Seed,
+        network: Network,
+        script: SparrowScriptType,
+        account: Option<u32>,
+        secp: &Secp256k1<C>,
+    ) -> Result<Self, Error>
+    where
+        C: Signing,
+    {
+        let root: ExtendedPrivKey = seed.to_bip32_root_key(network)?;
+        let root_fingerprint: Fingerprint = root.fingerprint(secp);
+        let path: DerivationPath = bip44::account_path(script.into(), network, account)?;
+        let xpriv: ExtendedPrivKey = root.derive_priv(secp, &path)?;
+        let pubkey: ExtendedPubKey = ExtendedPubKey::from_priv(secp, &xpriv);
+
+        Ok(Self {
+            fingerprint: pubkey.fingerprint(),
+            name: format!("keechain-{root_fingerprint}"),
+            policy_type: String::from("SINGLE"),
+            script_type: script,
+            default_policy: SparrowPolicy {
+                name: String::from("Single Signature"),
+                miniscript: SparrowMiniscript {
+                    script: script.policy_script(),
+                },
+            },
+            keystores: vec![SparrowKeystore {
+                label: String::from("KeeChain"),
+                source: String::from("SW_WATCH"),
+                wallet_model: String::from("SPARROW"),
+                key_derivation: SparrowKeyDerivation {
+                    master_fingerprint: root_fingerprint,
+                    derivation_path: path,
+                },
+                extended_public_key: pubkey,
+            }],
+        })
+    }
+
+    /// Build the export for a [`Purpose`]
+    pub fn from_purpose<C>(
+        seed: &Seed,
+        network: Network,
+        purpose: Purpose,
+        account: Option<u32>,
+        secp: &Secp256k1<C>,
+    ) -> Result<Self, Error>
+    where
+        C: Signing,
+    {
+        let script =
+            SparrowScriptType::from_purpose(purpose).ok_or(Error::UnsupportedPurpose(purpose))?;
+        Self::new(seed, network, script, account, secp)
+    }
+}
+
+impl WalletExport for Sparrow {
+    fn to_json(&self) -> String {
+        serde_json::json!(self).to_string()
+    }
+
+    fn suggested_filename(&self) -> String {
+        format!("keechain-sparrow-{}.json", self.fingerprint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bdk::bitcoin::bip32::ChildNumber;
+    use bip39::Mnemonic;
+    use serde_json::Value;
+
+    use super::*;
+
+    #[test]
+    fn test_sparrow_export() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("easy uncover favorite crystal bless differ energy seat ecology match carry group refuse together chat observe hidden glad brave month diesel sustain depth salt").unwrap();
+        let seed = Seed::from_mnemonic(mnemonic);
+        let root_fingerprint: Fingerprint = seed.fingerprint(Network::Bitcoin, &secp).unwrap();
+
+        let sparrow = Sparrow::new(
+            &seed,
+            Network::Bitcoin,
+            SparrowScriptType::NativeSegwit,
+            Some(1),
+            &secp,
+        )
+        .unwrap();
+        let json: Value = serde_json::from_str(&sparrow.to_json()).unwrap();
+        assert_eq!(json["policyType"], "SINGLE");
+        assert_eq!(json["scriptType"], "P2WPKH");
+        assert_eq!(
+            json["defaultPolicy"]["miniscript"]["script"],
+            "wpkh(keystore1)"
+        );
+
+        let keystore = &json["keystores"][0];
+        assert_eq!(
+            keystore["keyDerivation"]["masterFingerprint"],
+            root_fingerprint.to_string()
+        );
+        assert_eq!(keystore["keyDerivation"]["derivationPath"], "m/84'/0'/1'");
+
+        let xpub =
+            ExtendedPubKey::from_str(keystore["extendedPublicKey"].as_str().unwrap()).unwrap();
+        assert_eq!(xpub.depth, 3);
+        assert_eq!(
+            xpub.child_number,
+            ChildNumber::from_hardened_idx(1).unwrap()
+        );
+        assert_eq!(
+            sparrow.suggested_filename(),
+            format!("keechain-sparrow-{}.json", xpub.fingerprint())
+        );
+
+        let testnet =
+            Sparrow::from_purpose(&seed, Network::Testnet, Purpose::BIP86, None, &secp).unwrap();
+        let json: Value = serde_json::from_str(&testnet.to_json()).unwrap();
+        assert_eq!(json["scriptType"], "P2TR");
+        assert_eq!(
+            json["keystores"][0]["keyDerivation"]["derivationPath"],
+            "m/86'/1'/0'"
+        );
+        assert!(json["keystores"][0]["extendedPublicKey"]
+            .as_str()
+            .unwrap()
+            .starts_with("tpub"));
+
+        assert!(matches!(
+            Sparrow::from_purpose(
+                &seed,
+                Network::Bitcoin,
+                Purpose::BIP48 {
+                    script: crate::bips::bip48::ScriptType::P2WSH
+                },
+                None,
+                &secp
+            ),
+            Err(Error::UnsupportedPurpose(..))
+        ));
+    }
+}
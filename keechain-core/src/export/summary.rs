@@ -0,0 +1,163 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Printable summary of the account xpubs (Coldcard style)
+
+use core::fmt;
+
+use bdk::bitcoin::secp256k1::{Secp256k1, Signing};
+use bdk::bitcoin::Network;
+
+use crate::bips::bip32::{
+    self, Bip32, DerivationPath, ExtendedPrivKey, ExtendedPubKey, Fingerprint,
+};
+use crate::bips::bip43::Purpose;
+use crate::slips::slip132::{self, ToSlip132};
+use crate::types::Seed;
+
+#[derive(Debug)]
+pub enum Error {
+    BIP32(bip32::Error),
+    SLIP32(slip132::Error),
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BIP32(e) => write!(f, "BIP32: {e}"),
+            Self::SLIP32(e) => write!(f, "SLIP32: {e}"),
+        }
+    }
+}
+
+impl From<bip32::Error> for Error {
+    fn from(e: bip32::Error) -> Self {
+        Self::BIP32(e)
+    }
+}
+
+impl From<slip132::Error> for Error {
+    fn from(e: slip132::Error) -> Self {
+        Self::SLIP32(e)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SummaryAccount {
+    title: &'static str,
+    path: DerivationPath,
+    xpub: ExtendedPubKey,
+    slip132: Option<String>,
+}
+
+/// Human readable summary of the standard account xpubs, to store alongside the seed backup
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Summary {
+    network: Network,
+    fingerprint: Fingerprint,
+    account: u32,
+    accounts: Vec<SummaryAccount>,
+}
+
+impl Summary {
+    pub fn new<C>(
+        seed: &Seed,
+        network: Network,
+        account: Option<u32>,
+        secp: &Secp256k1<C>,
+    ) -> Result<Self, Error>
+    where
+        C: Signing,
+    {
+        let root: ExtendedPrivKey = seed.to_bip32_root_key(network)?;
+
+        let mut accounts: Vec<SummaryAccount> = Vec::new();
+        for (purpose, title) in [
+            (Purpose::BIP44, "P2PKH / Legacy"),
+            (Purpose::BIP49, "P2SH-P2WPKH / Nested Segwit"),
+            (Purpose::BIP84, "P2WPKH / Native Segwit"),
+            (Purpose::BIP86, "P2TR / Taproot"),
+        ] {
+            let path: DerivationPath = purpose.to_account_extended_path(network, account)?;
+            let xpriv: ExtendedPrivKey = root.derive_priv(secp, &path)?;
+            let xpub: ExtendedPubKey = ExtendedPubKey::from_priv(secp, &xpriv);
+            let slip132: Option<String> = match purpose {
+                Purpose::BIP49 | Purpose::BIP84 => Some(xpub.to_slip132(&path)?),
+                _ => None,
+            };
+            accounts.push(SummaryAccount {
+                title,
+                path,
+                xpub,
+                slip132,
+            });
+        }
+
+        Ok(Self {
+            network,
+            fingerprint: root.fingerprint(secp),
+            account: account.unwrap_or_default(),
+            accounts,
+        })
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# KeeChain Wallet Summary File")?;
+        writeln!(
+            f,
+            "## For wallet with master key fingerprint: {:X}",
+            self.fingerprint
+        )?;
+        writeln!(f)?;
+        writeln!(f, "Network: {}", self.network)?;
+        writeln!(f, "Account: {}", self.account)?;
+
+        for account in self.accounts.iter() {
+            writeln!(f)?;
+            writeln!(f, "## {}: {}", account.title, account.path)?;
+            writeln!(f)?;
+            writeln!(f, "{}", account.xpub)?;
+            if let Some(slip132) = &account.slip132 {
+                writeln!(f, "{slip132}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bip39::Mnemonic;
+
+    use super::*;
+
+    #[test]
+    fn test_summary_export() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("range special tuna oblige own drama trend render harsh army outdoor bulb brisk sing analyst own fork senior stove flash fire bulk umbrella vast").unwrap();
+        let seed = Seed::from_mnemonic(mnemonic);
+        let summary = Summary::new(&seed, Network::Bitcoin, None, &secp)
+            .unwrap()
+            .to_string();
+
+        let lines: Vec<&str> = summary.lines().collect();
+        assert_eq!(
+            lines[1],
+            "## For wallet with master key fingerprint: 91EF223D"
+        );
+        assert!(lines.contains(&"## P2PKH / Legacy: m/44'/0'/0'"));
+        assert!(lines.contains(&"## P2SH-P2WPKH / Nested Segwit: m/49'/0'/0'"));
+        assert!(lines.contains(&"## P2WPKH / Native Segwit: m/84'/0'/0'"));
+        assert!(lines.contains(&"## P2TR / Taproot: m/86'/0'/0'"));
+        assert_eq!(lines.iter().filter(|l| l.starts_with("xpub")).count(), 4);
+        assert!(lines.iter().any(|l| l.starts_with("ypub")));
+        assert!(lines.iter().any(|l| l.starts_with("zpub")));
+    }
+}
@@ -2,17 +2,17 @@
 // Distributed under the MIT software license
 
 use core::fmt;
-use std::fs::File;
-use std::io::Write;
-use std::path::{Path, PathBuf};
 
 use bdk::bitcoin::secp256k1::{Secp256k1, Signing};
 use bdk::bitcoin::Network;
 use serde::{Deserialize, Serialize};
 
+use super::WalletExport;
 use crate::bips::bip32::{
     self, Bip32, DerivationPath, ExtendedPrivKey, ExtendedPubKey, Fingerprint,
 };
+use crate::bips::bip43::Purpose;
+use crate::bips::bip44;
 use crate::types::Seed;
 
 #[derive(Debug)]
@@ -58,36 +58,90 @@ pub struct Wasabi {
     xpub: ExtendedPubKey,
     #[serde(rename = "MasterFingerprint")]
     root_fingerprint: Fingerprint,
+    /// Account path without the `m/` (i.e. `84'/0'/1'`), as in the Wasabi wallet files
+    #[serde(rename = "AccountKeyPath", default)]
+    account_key_path: String,
+    #[serde(skip)]
+    account: u32,
 }
 
 impl Wasabi {
-    pub fn new<C>(seed: &Seed, network: Network, secp: &Secp256k1<C>) -> Result<Self, Error>
+    /// Native segwit (BIP84) account
+    pub fn new<C>(
+        seed: &Seed,
+        network: Network,
+        account: Option<u32>,
+        secp: &Secp256k1<C>,
+    ) -> Result<Self, Error>
     where
         C: Signing,
     {
         let root: ExtendedPrivKey = seed.to_bip32_root_key(network)?;
-        let path: DerivationPath = bip32::account_extended_path(84, network, None)?;
+        let path: DerivationPath = bip44::account_path(Purpose::BIP84, network, account)?;
         let xpriv: ExtendedPrivKey = root.derive_priv(secp, &path)?;
         let pubkey: ExtendedPubKey = ExtendedPubKey::from_priv(secp, &xpriv);
 
         Ok(Self {
             xpub: pubkey,
             root_fingerprint: root.fingerprint(secp),
+            account_key_path: path.to_string().trim_start_matches("m/").to_string(),
+            account: account.unwrap_or_default(),
         })
     }
 
-    pub fn as_json(&self) -> String {
+    pub fn account(&self) -> u32 {
+        self.account
+    }
+}
+
+impl WalletExport for Wasabi {
+    fn to_json(&self) -> String {
         serde_json::json!(self).to_string()
     }
 
-    pub fn save_to_file<P>(&self, path: P) -> Result<PathBuf, Error>
-    where
-        P: AsRef<Path>,
-    {
-        let file_name: String = format!("keechain-wasabi-{}.json", self.xpub.fingerprint());
-        let path: PathBuf = path.as_ref().join(file_name);
-        let mut file: File = File::options().create(true).write(true).open(&path)?;
-        file.write_all(&serde_json::to_vec(self)?)?;
-        Ok(path)
+    /// `keechain-wasabi-<fingerprint>-native-segwit-account-<account>.json`
+    fn suggested_filename(&self) -> String {
+        format!(
+            "keechain-wasabi-{}-{}-account-{}.json",
+            self.xpub.fingerprint(),
+            Purpose::BIP84.script_name(),
+            self.account
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bip39::Mnemonic;
+
+    use super::*;
+
+    #[test]
+    fn test_wasabi_account() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("easy uncover favorite crystal bless differ energy seat ecology match carry group refuse together chat observe hidden glad brave month diesel sustain depth salt").unwrap();
+        let seed = Seed::from_mnemonic(mnemonic);
+
+        let account_0 = Wasabi::new(&seed, Network::Bitcoin, None, &secp).unwrap();
+        let account_1 = Wasabi::new(&seed, Network::Testnet, Some(1), &secp).unwrap();
+        assert_eq!(account_0.account(), 0);
+        assert_eq!(account_1.account(), 1);
+        assert!(account_0
+            .suggested_filename()
+            .ends_with("-native-segwit-account-0.json"));
+        assert!(account_1
+            .suggested_filename()
+            .ends_with("-native-segwit-account-1.json"));
+
+        let json: serde_json::Value = serde_json::from_str(&account_1.to_json()).unwrap();
+        assert_eq!(json["AccountKeyPath"], "84'/1'/1'");
+        assert_eq!(
+            json["MasterFingerprint"],
+            seed.fingerprint(Network::Testnet, &secp)
+                .unwrap()
+                .to_string()
+        );
     }
 }
@@ -14,15 +14,19 @@ pub mod bips;
 pub mod crypto;
 pub mod descriptors;
 pub mod export;
+pub mod nips;
 pub mod psbt;
+pub mod self_test;
 pub mod slips;
 pub mod types;
+pub mod ur;
 pub mod util;
 
 pub use self::bips::bip43::Purpose;
 pub use self::descriptors::Descriptors;
 pub use self::export::{
-    BitcoinCore, ColdcardGenericJson, Electrum, ElectrumSupportedScripts, Wasabi,
+    BitcoinCore, ColdcardGenericJson, Electrum, ElectrumSupportedScripts, Passport, Sparrow,
+    SparrowScriptType, Summary, WalletExport, Wasabi,
 };
 pub use self::psbt::PsbtUtility;
 pub use self::types::{EncryptedKeychain, Index, KeeChain, Keychain, Secrets, Seed, WordCount};
@@ -0,0 +1,8 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Nostr Implementation Possibilities
+//!
+//! <https://github.com/nostr-protocol/nips>
+
+pub mod nip06;
@@ -0,0 +1,148 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! NIP06
+//!
+//! <https://github.com/nostr-protocol/nips/blob/master/06.md>
+
+use core::fmt;
+
+use bdk::bitcoin::bech32::{self, ToBase32, Variant};
+use bdk::bitcoin::secp256k1::{KeyPair, Secp256k1, SecretKey, Signing, XOnlyPublicKey};
+use bdk::bitcoin::Network;
+
+use crate::bips::bip32::{self, Bip32, ChildNumber, DerivationPath, ExtendedPrivKey};
+
+const PREFIX_BECH32_PUBLIC_KEY: &str = "npub";
+const PREFIX_BECH32_SECRET_KEY: &str = "nsec";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    BIP32(bip32::Error),
+    Bech32(bech32::Error),
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BIP32(e) => write!(f, "BIP32: {e}"),
+            Self::Bech32(e) => write!(f, "Bech32: {e}"),
+        }
+    }
+}
+
+impl From<bip32::Error> for Error {
+    fn from(e: bip32::Error) -> Self {
+        Self::BIP32(e)
+    }
+}
+
+impl From<bech32::Error> for Error {
+    fn from(e: bech32::Error) -> Self {
+        Self::Bech32(e)
+    }
+}
+
+pub trait FromSeed: Sized {
+    fn from_seed<C>(
+        root: &ExtendedPrivKey,
+        account: u32,
+        secp: &Secp256k1<C>,
+    ) -> Result<Self, Error>
+    where
+        C: Signing;
+}
+
+impl FromSeed for KeyPair {
+    fn from_seed<C>(
+        root: &ExtendedPrivKey,
+        account: u32,
+        secp: &Secp256k1<C>,
+    ) -> Result<Self, Error>
+    where
+        C: Signing,
+    {
+        let path: Vec<ChildNumber> = vec![
+            ChildNumber::from_hardened_idx(44)?,
+            ChildNumber::from_hardened_idx(1237)?,
+            ChildNumber::from_hardened_idx(account)?,
+            ChildNumber::from_normal_idx(0)?,
+            ChildNumber::from_normal_idx(0)?,
+        ];
+        let path: DerivationPath = DerivationPath::from(path);
+        let derived: ExtendedPrivKey = root.derive_priv(secp, &path)?;
+        Ok(KeyPair::from_secret_key(secp, &derived.private_key))
+    }
+}
+
+pub trait Nip06: Sized + Bip32
+where
+    Error: From<<Self as Bip32>::Err>,
+{
+    /// Derive nostr keys for `account`
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/06.md>
+    fn derive_nostr_keys<C>(&self, account: u32, secp: &Secp256k1<C>) -> Result<KeyPair, Error>
+    where
+        C: Signing,
+    {
+        let root: ExtendedPrivKey = self.to_bip32_root_key(Network::Bitcoin)?;
+        KeyPair::from_seed(&root, account, secp)
+    }
+}
+
+/// Encode public key as `npub`
+pub fn to_npub(public_key: &XOnlyPublicKey) -> Result<String, Error> {
+    Ok(bech32::encode(
+        PREFIX_BECH32_PUBLIC_KEY,
+        public_key.serialize().to_base32(),
+        Variant::Bech32,
+    )?)
+}
+
+/// Encode secret key as `nsec`
+pub fn to_nsec(secret_key: &SecretKey) -> Result<String, Error> {
+    Ok(bech32::encode(
+        PREFIX_BECH32_SECRET_KEY,
+        secret_key.secret_bytes().to_base32(),
+        Variant::Bech32,
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bip39::Mnemonic;
+
+    use super::*;
+    use crate::types::Seed;
+
+    #[test]
+    fn test_nip06() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str(
+            "leader monkey parrot ring guide accident before fence cannon height naive bean",
+        )
+        .unwrap();
+        let seed = Seed::from_mnemonic(mnemonic);
+
+        let keys: KeyPair = seed.derive_nostr_keys(0, &secp).unwrap();
+        assert_eq!(
+            keys.display_secret().to_string(),
+            "7f7ff03d123792d6ac594bfa67bf6d0c0ab55b6b1fdb6249303fe861f1ccba9a".to_string()
+        );
+
+        let (public_key, _) = keys.x_only_public_key();
+        assert_eq!(
+            to_npub(&public_key).unwrap(),
+            "npub1zutzeysacnf9rru6zqwmxd54mud0k44tst6l70ja5mhv8jjumytsd2x7nu".to_string()
+        );
+
+        // Different account, different keys
+        let other: KeyPair = seed.derive_nostr_keys(1, &secp).unwrap();
+        assert_ne!(keys.secret_bytes(), other.secret_bytes());
+    }
+}
@@ -10,38 +10,83 @@ use std::path::Path;
 use std::str::FromStr;
 use std::sync::Arc;
 
-use bdk::bitcoin::psbt::{self, PartiallySignedTransaction, PsbtParseError};
-use bdk::bitcoin::secp256k1::{Secp256k1, Signing};
-use bdk::bitcoin::{Network, PrivateKey};
-use bdk::miniscript::descriptor::DescriptorKeyParseError;
-use bdk::miniscript::Descriptor;
+use bdk::bitcoin::psbt::{self, PartiallySignedTransaction, PsbtParseError, PsbtSighashType};
+use bdk::bitcoin::script::PushBytes;
+use bdk::bitcoin::secp256k1::{KeyPair, Secp256k1, Signing, Verification};
+use bdk::bitcoin::sighash::EcdsaSighashType;
+use bdk::bitcoin::{Address, Network, PrivateKey, ScriptBuf, Transaction, TxIn, TxOut, Txid};
+use bdk::miniscript::descriptor::{
+    DescriptorKeyParseError, DescriptorPublicKey, DescriptorSecretKey,
+};
+use bdk::miniscript::{self, Descriptor, ForEachKey};
 use bdk::signer::{SignerContext, SignerError, SignerOrdering, SignerWrapper};
 use bdk::{KeychainKind, SignOptions, Wallet};
 
-use crate::bips::bip32::{self, Bip32, DerivationPath, ExtendedPrivKey, Fingerprint};
+use crate::bips::bip32::{
+    self, Bip32, ChildNumber, DerivationPath, ExtendedPrivKey, ExtendedPubKey, Fingerprint,
+};
+use crate::bips::bip322;
 use crate::bips::bip43::Purpose;
 use crate::bips::bip44::{self, ExtendedPath};
 use crate::bips::bip48::ScriptType;
+use crate::descriptors::ScanResult;
 use crate::types::Seed;
-use crate::util::base64;
-use crate::{descriptors, Descriptors};
+use crate::util::{base64, hex};
+use crate::{descriptors, ur, Descriptors};
 
 #[derive(Debug)]
 pub enum Error {
     IO(std::io::Error),
     Base64(base64::DecodeError),
+    Hex(hex::Error),
     BIP32(bip32::Error),
     BIP44(bip44::Error),
+    BIP322(bip322::Error),
     Psbt(psbt::Error),
     PsbtParse(PsbtParseError),
     Descriptors(descriptors::Error),
     DescriptorParse(DescriptorKeyParseError),
     BdkSigner(SignerError),
     BdkDescriptor(bdk::descriptor::DescriptorError),
+    MiniscriptPsbt(miniscript::psbt::Error),
+    Ur(ur::Error),
     FileNotFound,
     InvalidDerivationPath,
     NothingToSign,
     PsbtNotSigned,
+    VersionMismatch,
+    LockTimeMismatch,
+    DuplicateInput,
+    ConflictingGlobalField,
+    AlreadySigned,
+    /// Input already has a different sighash type
+    SighashMismatch(usize),
+    /// `SIGHASH_SINGLE` used for an input without the corresponding output
+    SighashSingleWithoutOutput(usize),
+    /// Descriptor keys are for a different network
+    NetworkMismatch {
+        expected: Network,
+        found: Network,
+    },
+    /// Script type can't be used for single key signing
+    UnsupportedScript(Purpose),
+    /// PSBT has more inputs than allowed
+    TooManyInputs {
+        count: usize,
+        max: usize,
+    },
+    /// Previous transaction not spent by any input
+    PrevTxNotSpent(Txid),
+    /// Input spends an output not found in the previous transaction
+    PrevOutNotFound(usize),
+    /// PSBTs describe different transactions
+    TransactionMismatch {
+        expected: Txid,
+        found: Txid,
+    },
+    NothingToCombine,
+    /// Inputs that can't be finalized
+    NotFinalized(Vec<usize>),
 }
 
 impl std::error::Error for Error {}
@@ -51,18 +96,65 @@ impl fmt::Display for Error {
         match self {
             Self::IO(e) => write!(f, "IO: {e}"),
             Self::Base64(e) => write!(f, "Base64: {e}"),
+            Self::Hex(e) => write!(f, "Hex: {e}"),
             Self::BIP32(e) => write!(f, "BIP32: {e}"),
             Self::BIP44(e) => write!(f, "BIP44: {e}"),
+            Self::BIP322(e) => write!(f, "BIP322: {e}"),
             Self::Psbt(e) => write!(f, "Psbt: {e}"),
             Self::PsbtParse(e) => write!(f, "Psbt parse: {e}"),
             Self::Descriptors(e) => write!(f, "Descriptors: {e}"),
             Self::DescriptorParse(e) => write!(f, "Descriptor parse: {e}"),
             Self::BdkSigner(e) => write!(f, "BDK Signer: {e}"),
             Self::BdkDescriptor(e) => write!(f, "BDK descriptor: {e}"),
+            Self::MiniscriptPsbt(e) => write!(f, "Miniscript PSBT: {e}"),
+            Self::Ur(e) => write!(f, "UR: {e}"),
             Self::FileNotFound => write!(f, "File not found"),
             Self::InvalidDerivationPath => write!(f, "Invalid derivation path"),
             Self::NothingToSign => write!(f, "Nothing to sign here"),
             Self::PsbtNotSigned => write!(f, "PSBT not signed"),
+            Self::VersionMismatch => write!(f, "PSBTs have different versions"),
+            Self::LockTimeMismatch => write!(f, "PSBTs have different lock times"),
+            Self::DuplicateInput => write!(f, "Same input found in more than one PSBT"),
+            Self::ConflictingGlobalField => write!(f, "PSBTs have conflicting global fields"),
+            Self::AlreadySigned => write!(f, "Can't join already signed PSBTs"),
+            Self::SighashMismatch(index) => {
+                write!(f, "Input {index} already has a different sighash type")
+            }
+            Self::SighashSingleWithoutOutput(index) => write!(
+                f,
+                "Can't use SIGHASH_SINGLE for input {index}: no output with the same index"
+            ),
+            Self::NetworkMismatch { expected, found } => write!(
+                f,
+                "Network mismatch: descriptor keys are for {found}, signing on {expected}"
+            ),
+            Self::UnsupportedScript(purpose) => {
+                write!(f, "Unsupported script for single key signing: {purpose}")
+            }
+            Self::TooManyInputs { count, max } => write!(
+                f,
+                "PSBT has {count} inputs, more than the maximum allowed ({max})"
+            ),
+            Self::PrevTxNotSpent(txid) => {
+                write!(f, "Transaction {txid} is not spent by any input")
+            }
+            Self::PrevOutNotFound(index) => write!(
+                f,
+                "Input {index} spends an output not found in the previous transaction"
+            ),
+            Self::TransactionMismatch { expected, found } => write!(
+                f,
+                "Can't combine PSBTs of different transactions: expected {expected}, found {found}"
+            ),
+            Self::NothingToCombine => write!(f, "No PSBT to combine"),
+            Self::NotFinalized(inputs) => {
+                let inputs: Vec<String> = inputs.iter().map(|i| i.to_string()).collect();
+                write!(
+                    f,
+                    "Can't finalize inputs {}: missing signatures",
+                    inputs.join(", ")
+                )
+            }
         }
     }
 }
@@ -79,6 +171,12 @@ impl From<base64::DecodeError> for Error {
     }
 }
 
+impl From<hex::Error> for Error {
+    fn from(e: hex::Error) -> Self {
+        Self::Hex(e)
+    }
+}
+
 impl From<bip32::Error> for Error {
     fn from(e: bip32::Error) -> Self {
         Self::BIP32(e)
@@ -91,12 +189,30 @@ impl From<bip44::Error> for Error {
     }
 }
 
+impl From<bip322::Error> for Error {
+    fn from(e: bip322::Error) -> Self {
+        Self::BIP322(e)
+    }
+}
+
 impl From<psbt::Error> for Error {
     fn from(e: psbt::Error) -> Self {
         Self::Psbt(e)
     }
 }
 
+impl From<miniscript::psbt::Error> for Error {
+    fn from(e: miniscript::psbt::Error) -> Self {
+        Self::MiniscriptPsbt(e)
+    }
+}
+
+impl From<ur::Error> for Error {
+    fn from(e: ur::Error) -> Self {
+        Self::Ur(e)
+    }
+}
+
 impl From<PsbtParseError> for Error {
     fn from(e: PsbtParseError) -> Self {
         Self::PsbtParse(e)
@@ -127,11 +243,83 @@ impl From<bdk::descriptor::DescriptorError> for Error {
     }
 }
 
+/// Signing options
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SigningOptions {
+    /// Sighash type for the inputs signed by the seed (default: `SIGHASH_ALL`, `SIGHASH_DEFAULT` for taproot)
+    pub sighash: Option<EcdsaSighashType>,
+    /// Sign segwit v0 inputs that only provide the `witness_utxo` (default: `false`)
+    ///
+    /// Segwit v0 signatures commit only to the value of the spent output, not to the previous transaction.
+    /// A malicious coordinator could lie about input values across two signing sessions and make
+    /// you pay a huge fee (fee-inflation attack). Requiring the full previous transaction (`non_witness_utxo`)
+    /// prevents it, so enable this only if you trust the PSBT creator.
+    pub trust_witness_utxo: bool,
+    /// Sign inputs with a sighash type other than `SIGHASH_ALL` (or `SIGHASH_DEFAULT` for taproot) (default: `false`)
+    ///
+    /// Non-default sighash types commit only to part of the transaction, so the signature
+    /// could be reused in a transaction with different inputs or outputs.
+    pub allow_all_sighashes: bool,
+    /// Script type (as [`Purpose`]) to use instead of inferring it from the derivation path purpose (default: `None`)
+    ///
+    /// Escape hatch for keys derived at non-standard purposes (i.e. `m/0'/...` or `m/1017'/...`):
+    /// the descriptor is built from the account key (the path without the last two steps).
+    pub assume_script: Option<Purpose>,
+    /// Sign taproot inputs with the script path even if a key-path spend is possible (default: `false`)
+    ///
+    /// A key-path spend looks like any single-key taproot spend, while a script-path spend
+    /// reveals the spent leaf, so by default the script-path signatures are dropped from the inputs
+    /// signed with the internal key.
+    pub force_script_path: bool,
+}
+
+/// Default maximum number of inputs of a PSBT (see [`PsbtUtility::ensure_max_inputs`])
+pub const DEFAULT_MAX_INPUTS: usize = 5000;
+
+/// Default fee rate (sat/vB) above which the fee is considered too high
+pub const DEFAULT_MAX_FEE_RATE: f64 = 500.0;
+
+/// PSBT magic bytes
+const PSBT_MAGIC: &[u8] = b"psbt\xff";
+const PSBT_MAGIC_HEX: &str = "70736274ff";
+const UR_PREFIX: &str = "ur:";
+
+/// PSBT encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PsbtFormat {
+    /// Raw bytes (BIP174 file format)
+    #[default]
+    Binary,
+    Base64,
+    Hex,
+}
+
 pub trait PsbtUtility: Sized {
     fn from_base64<S>(psbt: S) -> Result<Self, Error>
     where
         S: Into<String>;
 
+    /// Decode `ur:crypto-psbt` parts (separated by whitespaces or concatenated)
+    fn from_ur<S>(psbt: S) -> Result<Self, Error>
+    where
+        S: AsRef<str>;
+
+    /// Decode base64, hex or UR (i.e. scanned from a QR code)
+    fn from_text<S>(psbt: S) -> Result<Self, Error>
+    where
+        S: AsRef<str>,
+    {
+        let psbt: &str = psbt.as_ref().trim();
+        let lowercase: String = psbt.to_lowercase();
+        if lowercase.starts_with(UR_PREFIX) {
+            Self::from_ur(psbt)
+        } else if lowercase.starts_with(PSBT_MAGIC_HEX) {
+            Self::from_base64(base64::encode(hex::decode(psbt)?))
+        } else {
+            Self::from_base64(psbt)
+        }
+    }
+
     fn from_file<P>(path: P) -> Result<Self, Error>
     where
         P: AsRef<Path>,
@@ -143,7 +331,19 @@ pub trait PsbtUtility: Sized {
         let mut file: File = File::open(psbt_file)?;
         let mut content: Vec<u8> = Vec::new();
         file.read_to_end(&mut content)?;
-        Self::from_base64(base64::encode(content))
+        Self::from_bytes(content)
+    }
+
+    /// Decode binary, hex, base64 or UR (i.e. the content of a file or of the stdin)
+    fn from_bytes<T>(content: T) -> Result<Self, Error>
+    where
+        T: AsRef<[u8]>,
+    {
+        let content: &[u8] = content.as_ref();
+        if content.starts_with(PSBT_MAGIC) {
+            return Self::from_base64(base64::encode(content));
+        }
+        Self::from_text(String::from_utf8_lossy(content))
     }
 
     fn sign_with_seed<C>(
@@ -179,10 +379,39 @@ pub trait PsbtUtility: Sized {
         network: Network,
         secp: &Secp256k1<C>,
     ) -> Result<bool, Error>
+    where
+        C: Signing,
+    {
+        self.sign_custom_with_options(
+            seed,
+            descriptor,
+            custom_signers,
+            SigningOptions::default(),
+            network,
+            secp,
+        )
+    }
+
+    fn sign_custom_with_options<C>(
+        &mut self,
+        seed: &Seed,
+        descriptor: Option<Descriptor<String>>,
+        custom_signers: Vec<SignerWrapper<PrivateKey>>,
+        options: SigningOptions,
+        network: Network,
+        secp: &Secp256k1<C>,
+    ) -> Result<bool, Error>
     where
         C: Signing;
 
     fn save_to_file<P>(&self, path: P) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        self.save_to_file_with_format(path, PsbtFormat::Binary)
+    }
+
+    fn save_to_file_with_format<P>(&self, path: P, format: PsbtFormat) -> Result<(), Error>
     where
         P: AsRef<Path>,
     {
@@ -191,7 +420,7 @@ pub trait PsbtUtility: Sized {
             .truncate(true)
             .write(true)
             .open(path.as_ref())?;
-        file.write_all(&self.as_bytes()?)?;
+        file.write_all(&self.encode(format)?)?;
         Ok(())
     }
 
@@ -200,6 +429,183 @@ pub trait PsbtUtility: Sized {
     fn as_bytes(&self) -> Result<Vec<u8>, Error> {
         Ok(base64::decode(self.as_base64())?)
     }
+
+    /// Encode the PSBT in the requested [`PsbtFormat`]
+    fn encode(&self, format: PsbtFormat) -> Result<Vec<u8>, Error> {
+        match format {
+            PsbtFormat::Binary => self.as_bytes(),
+            PsbtFormat::Base64 => Ok(self.as_base64().into_bytes()),
+            PsbtFormat::Hex => Ok(hex::encode(self.as_bytes()?).into_bytes()),
+        }
+    }
+
+    /// Merge inputs and outputs of another PSBT (BIP174 joiner)
+    ///
+    /// Unlike [`PartiallySignedTransaction::combine`], the PSBTs must describe **different** parts of a transaction.
+    fn join(&mut self, other: Self) -> Result<(), Error>;
+
+    /// Merge signatures and key origins of another PSBT of the **same** transaction (BIP174 combiner)
+    ///
+    /// Like [`PartiallySignedTransaction::combine`], but previous transactions (`non_witness_utxo`)
+    /// are kept also when the other PSBT adds a `witness_utxo`.
+    fn combine_with(&mut self, other: Self) -> Result<(), Error>;
+
+    /// Refuse PSBTs with more than `max` inputs
+    ///
+    /// Huge PSBTs are slow to process and often a sign of a malformed file.
+    fn ensure_max_inputs(&self, max: usize) -> Result<(), Error>;
+
+    /// Attach the UTXOs of the inputs spending `prev_tx`, if missing
+    ///
+    /// `non_witness_utxo` is always added, `witness_utxo` only for segwit outputs.
+    /// Return the number of updated inputs.
+    fn add_missing_utxo(&mut self, prev_tx: &Transaction) -> Result<usize, Error>;
+
+    /// Finalize the inputs (miniscript) and extract the final transaction
+    ///
+    /// Inputs already finalized are left untouched. If some inputs can't be finalized,
+    /// return [`Error::NotFinalized`] with their indexes.
+    fn finalize<C>(&mut self, secp: &Secp256k1<C>) -> Result<Transaction, Error>
+    where
+        C: Verification;
+
+    /// Sum of the values of the UTXOs spent by the inputs
+    fn total_input_value(&self) -> Result<u64, Error>;
+
+    /// Sum of the values of the outputs
+    fn total_output_value(&self) -> u64;
+
+    /// Absolute fee (sats)
+    fn absolute_fee(&self) -> Result<u64, Error>;
+
+    /// Fee rate (sat/vB), computed with the weight of the extracted transaction
+    ///
+    /// Until the PSBT is finalized the signatures are missing, so the final fee rate will be lower.
+    fn fee_rate(&self) -> Result<f64, Error>;
+
+    /// Check if the output at `index` is derived from the [`Seed`]
+    ///
+    /// The key origins of the output are only hints (the fingerprint is public): the address is looked up,
+    /// at the claimed path, with the same scan used to verify the addresses ([`Descriptors::scan_addresses`]).
+    fn is_own_output<C>(
+        &self,
+        index: usize,
+        seed: &Seed,
+        network: Network,
+        secp: &Secp256k1<C>,
+    ) -> Result<bool, Error>
+    where
+        C: Signing;
+
+    /// Txid of the final transaction, computed from the unsigned transaction
+    ///
+    /// Witnesses are not part of the txid, so it's already fixed for segwit inputs
+    /// (the `scriptSig` of nested segwit inputs is built from the redeem script).
+    /// Check [`PsbtUtility::txid_may_change`] before relying on it.
+    fn predicted_txid(&self) -> Txid;
+
+    /// Check if the txid may still change before finalization (legacy inputs, with signatures in the `scriptSig`)
+    fn txid_may_change(&self) -> bool;
+
+    /// Roles (BIP174) that acted on the PSBT, inferred from its fields
+    fn roles(&self) -> PsbtRoles;
+
+    /// Get summary of the PSBT (outputs, totals and fee)
+    fn summary<C>(
+        &self,
+        seed: &Seed,
+        network: Network,
+        secp: &Secp256k1<C>,
+    ) -> Result<PsbtSummary, Error>
+    where
+        C: Signing;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PsbtOutputSummary {
+    pub address: Option<Address>,
+    pub value: u64,
+    pub is_change: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PsbtSummary {
+    pub outputs: Vec<PsbtOutputSummary>,
+    pub total_input: u64,
+    pub total_output: u64,
+    pub fee: u64,
+}
+
+/// PSBT role (BIP174)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PsbtRole {
+    Creator,
+    Updater,
+    Signer,
+    Combiner,
+    Finalizer,
+    Extractor,
+}
+
+impl fmt::Display for PsbtRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Creator => write!(f, "creator"),
+            Self::Updater => write!(f, "updater"),
+            Self::Signer => write!(f, "signer"),
+            Self::Combiner => write!(f, "combiner"),
+            Self::Finalizer => write!(f, "finalizer"),
+            Self::Extractor => write!(f, "extractor"),
+        }
+    }
+}
+
+/// Lifecycle of a PSBT (see [`PsbtUtility::roles`])
+///
+/// The combiner can't be told apart from the signers, and the extractor leaves no trace in the PSBT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PsbtRoles {
+    pub inputs: usize,
+    /// UTXOs, scripts or key origins added to the inputs or outputs
+    pub updated: bool,
+    /// Inputs with at least a signature (partial or final)
+    pub signed_inputs: usize,
+    /// Inputs with the final `scriptSig` or witness
+    pub finalized_inputs: usize,
+}
+
+impl PsbtRoles {
+    /// Roles that acted on the PSBT (the creator always did)
+    pub fn acted(&self) -> Vec<PsbtRole> {
+        let mut roles: Vec<PsbtRole> = vec![PsbtRole::Creator];
+        if self.updated {
+            roles.push(PsbtRole::Updater);
+        }
+        if self.signed_inputs > 0 {
+            roles.push(PsbtRole::Signer);
+        }
+        if self.finalized_inputs > 0 {
+            roles.push(PsbtRole::Finalizer);
+        }
+        roles
+    }
+
+    /// Role that should act next
+    ///
+    /// [`PsbtRole::Finalizer`] as soon as all the inputs have a signature: multisig inputs may still need more of them.
+    pub fn next(&self) -> PsbtRole {
+        if self.inputs == 0 {
+            PsbtRole::Creator
+        } else if self.finalized_inputs == self.inputs {
+            PsbtRole::Extractor
+        } else if self.signed_inputs == self.inputs {
+            PsbtRole::Finalizer
+        } else if !self.updated {
+            PsbtRole::Updater
+        } else {
+            PsbtRole::Signer
+        }
+    }
 }
 
 impl PsbtUtility for PartiallySignedTransaction {
@@ -210,37 +616,513 @@ impl PsbtUtility for PartiallySignedTransaction {
         Ok(PartiallySignedTransaction::from_str(&psbt.into())?)
     }
 
-    fn sign_custom<C>(
+    fn from_ur<S>(psbt: S) -> Result<Self, Error>
+    where
+        S: AsRef<str>,
+    {
+        Ok(ur::decode_psbt(ur::split_parts(psbt.as_ref()))?)
+    }
+
+    fn sign_custom_with_options<C>(
         &mut self,
         seed: &Seed,
         descriptor: Option<Descriptor<String>>,
         custom_signers: Vec<SignerWrapper<PrivateKey>>,
+        options: SigningOptions,
         network: Network,
         secp: &Secp256k1<C>,
     ) -> Result<bool, Error>
     where
         C: Signing,
     {
-        match sign_psbt(
+        // The leaves are signed in any case: the internal key only adds the key-path signature
+        let use_tr_internal_key: bool = !options.force_script_path;
+        sign_psbt(
             self,
             seed,
-            descriptor.clone(),
-            custom_signers.clone(),
-            false,
+            descriptor,
+            custom_signers,
+            use_tr_internal_key,
+            options,
             network,
             secp,
-        ) {
-            Ok(finalized) => Ok(finalized),
-            Err(Error::PsbtNotSigned) => {
-                sign_psbt(self, seed, descriptor, custom_signers, true, network, secp)
-            }
-            Err(e) => Err(e),
-        }
+        )
     }
 
     fn as_base64(&self) -> String {
         self.to_string()
     }
+
+    fn join(&mut self, other: Self) -> Result<(), Error> {
+        if self.version != other.version || self.unsigned_tx.version != other.unsigned_tx.version {
+            return Err(Error::VersionMismatch);
+        }
+
+        if self.unsigned_tx.lock_time != other.unsigned_tx.lock_time {
+            return Err(Error::LockTimeMismatch);
+        }
+
+        if self.inputs.iter().chain(other.inputs.iter()).any(is_signed) {
+            return Err(Error::AlreadySigned);
+        }
+
+        for txin in other.unsigned_tx.input.iter() {
+            if self
+                .unsigned_tx
+                .input
+                .iter()
+                .any(|i| i.previous_output == txin.previous_output)
+            {
+                return Err(Error::DuplicateInput);
+            }
+        }
+
+        // Check global fields before changing anything
+        let xpub_conflict = other
+            .xpub
+            .iter()
+            .any(|(key, value)| matches!(self.xpub.get(key), Some(v) if v != value));
+        let proprietary_conflict = other
+            .proprietary
+            .iter()
+            .any(|(key, value)| matches!(self.proprietary.get(key), Some(v) if v != value));
+        let unknown_conflict = other
+            .unknown
+            .iter()
+            .any(|(key, value)| matches!(self.unknown.get(key), Some(v) if v != value));
+        if xpub_conflict || proprietary_conflict || unknown_conflict {
+            return Err(Error::ConflictingGlobalField);
+        }
+
+        self.xpub.extend(other.xpub);
+        self.proprietary.extend(other.proprietary);
+        self.unknown.extend(other.unknown);
+        self.unsigned_tx.input.extend(other.unsigned_tx.input);
+        self.unsigned_tx.output.extend(other.unsigned_tx.output);
+        self.inputs.extend(other.inputs);
+        self.outputs.extend(other.outputs);
+
+        Ok(())
+    }
+
+    fn combine_with(&mut self, other: Self) -> Result<(), Error> {
+        let expected: Txid = self.unsigned_tx.txid();
+        let found: Txid = other.unsigned_tx.txid();
+        if expected != found {
+            return Err(Error::TransactionMismatch { expected, found });
+        }
+
+        let non_witness_utxos: Vec<Option<Transaction>> = self
+            .inputs
+            .iter()
+            .zip(other.inputs.iter())
+            .map(|(a, b)| {
+                a.non_witness_utxo
+                    .clone()
+                    .or_else(|| b.non_witness_utxo.clone())
+            })
+            .collect();
+
+        self.combine(other)?;
+
+        for (input, utxo) in self.inputs.iter_mut().zip(non_witness_utxos.into_iter()) {
+            if input.non_witness_utxo.is_none() {
+                input.non_witness_utxo = utxo;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn ensure_max_inputs(&self, max: usize) -> Result<(), Error> {
+        let count: usize = self.inputs.len().max(self.unsigned_tx.input.len());
+        if count > max {
+            return Err(Error::TooManyInputs { count, max });
+        }
+        Ok(())
+    }
+
+    fn add_missing_utxo(&mut self, prev_tx: &Transaction) -> Result<usize, Error> {
+        let txid: Txid = prev_tx.txid();
+        let mut spent: bool = false;
+        let mut updated: usize = 0;
+        for (index, (txin, input)) in self
+            .unsigned_tx
+            .input
+            .iter()
+            .zip(self.inputs.iter_mut())
+            .enumerate()
+        {
+            if txin.previous_output.txid != txid {
+                continue;
+            }
+            spent = true;
+
+            let utxo: &TxOut = prev_tx
+                .output
+                .get(txin.previous_output.vout as usize)
+                .ok_or(Error::PrevOutNotFound(index))?;
+            let is_segwit: bool = utxo.script_pubkey.is_witness_program()
+                || (utxo.script_pubkey.is_p2sh()
+                    && input
+                        .redeem_script
+                        .as_ref()
+                        .map_or(false, |script| script.is_witness_program()));
+
+            let mut changed: bool = false;
+            if input.non_witness_utxo.is_none() {
+                input.non_witness_utxo = Some(prev_tx.clone());
+                changed = true;
+            }
+            if is_segwit && input.witness_utxo.is_none() {
+                input.witness_utxo = Some(utxo.clone());
+                changed = true;
+            }
+            if changed {
+                updated += 1;
+            }
+        }
+
+        if !spent {
+            return Err(Error::PrevTxNotSpent(txid));
+        }
+
+        Ok(updated)
+    }
+
+    fn finalize<C>(&mut self, secp: &Secp256k1<C>) -> Result<Transaction, Error>
+    where
+        C: Verification,
+    {
+        let pending: Vec<usize> = self
+            .inputs
+            .iter()
+            .enumerate()
+            .filter(|(_, input)| {
+                input.final_script_sig.is_none() && input.final_script_witness.is_none()
+            })
+            .map(|(index, _)| index)
+            .collect();
+
+        let mut not_finalized: Vec<usize> = Vec::new();
+        for index in pending.into_iter() {
+            if miniscript::psbt::PsbtExt::finalize_inp_mut(self, secp, index).is_err() {
+                not_finalized.push(index);
+            }
+        }
+
+        if !not_finalized.is_empty() {
+            return Err(Error::NotFinalized(not_finalized));
+        }
+
+        Ok(miniscript::psbt::PsbtExt::extract(self, secp)?)
+    }
+
+    fn total_input_value(&self) -> Result<u64, Error> {
+        let mut total: u64 = 0;
+        for utxo in self.iter_funding_utxos() {
+            total = total
+                .checked_add(utxo?.value)
+                .ok_or(psbt::Error::FeeOverflow)?;
+        }
+        Ok(total)
+    }
+
+    fn total_output_value(&self) -> u64 {
+        self.unsigned_tx.output.iter().map(|o| o.value).sum()
+    }
+
+    fn absolute_fee(&self) -> Result<u64, Error> {
+        Ok(PartiallySignedTransaction::fee(self)?.to_sat())
+    }
+
+    fn fee_rate(&self) -> Result<f64, Error> {
+        let fee: u64 = self.absolute_fee()?;
+        let vsize: usize = self.clone().extract_tx().vsize();
+        Ok(fee as f64 / vsize as f64)
+    }
+
+    fn is_own_output<C>(
+        &self,
+        index: usize,
+        seed: &Seed,
+        network: Network,
+        secp: &Secp256k1<C>,
+    ) -> Result<bool, Error>
+    where
+        C: Signing,
+    {
+        let (output, txout) = match (self.outputs.get(index), self.unsigned_tx.output.get(index)) {
+            (Some(output), Some(txout)) => (output, txout),
+            _ => return Ok(false),
+        };
+
+        let address: Address = match Address::from_script(&txout.script_pubkey, network) {
+            Ok(address) => address,
+            Err(_) => return Ok(false),
+        };
+        let root_fingerprint: Fingerprint = seed.fingerprint(network, secp)?;
+        let paths = output
+            .bip32_derivation
+            .values()
+            .chain(output.tap_key_origins.values().map(|(_, origin)| origin))
+            .filter(|(fingerprint, _)| fingerprint == &root_fingerprint)
+            .map(|(_, path)| path);
+
+        for path in paths {
+            let extended_path = match ExtendedPath::from_derivation_path(path) {
+                Ok(extended_path) => extended_path,
+                Err(_) => continue,
+            };
+            let child: u32 = match path.into_iter().last() {
+                Some(ChildNumber::Normal { index }) => *index,
+                _ => continue,
+            };
+            if let Purpose::BIP48 { .. } = extended_path.purpose {
+                // Multisig: the script can't be rebuilt from the seed alone
+                continue;
+            }
+
+            // Same scan used to verify the addresses
+            let descriptors = Descriptors::new(seed, network, Some(extended_path.account), secp)?;
+            let result: ScanResult = descriptors.scan_addresses(
+                &[address.clone()],
+                network,
+                &[extended_path.purpose],
+                child..child + 1,
+            )?;
+            if result.matches.iter().any(|derived| &derived.path == path) {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    fn predicted_txid(&self) -> Txid {
+        let mut tx = self.unsigned_tx.clone();
+        for (txin, input) in tx.input.iter_mut().zip(self.inputs.iter()) {
+            if let Some(script_sig) = &input.final_script_sig {
+                txin.script_sig = script_sig.clone();
+            } else if let Some(redeem_script) = &input.redeem_script {
+                if redeem_script.is_witness_program() {
+                    if let Ok(push) = <&PushBytes>::try_from(redeem_script.as_bytes()) {
+                        let mut script_sig = ScriptBuf::new();
+                        script_sig.push_slice(push);
+                        txin.script_sig = script_sig;
+                    }
+                }
+            }
+        }
+        tx.txid()
+    }
+
+    fn txid_may_change(&self) -> bool {
+        self.unsigned_tx
+            .input
+            .iter()
+            .zip(self.inputs.iter())
+            .any(|(txin, input)| !is_txid_fixed(txin, input))
+    }
+
+    fn roles(&self) -> PsbtRoles {
+        let updated: bool = self.inputs.iter().any(|input| {
+            input.witness_utxo.is_some()
+                || input.non_witness_utxo.is_some()
+                || input.redeem_script.is_some()
+                || input.witness_script.is_some()
+                || !input.bip32_derivation.is_empty()
+                || !input.tap_key_origins.is_empty()
+        }) || self.outputs.iter().any(|output| {
+            !output.bip32_derivation.is_empty() || !output.tap_key_origins.is_empty()
+        });
+        PsbtRoles {
+            inputs: self.inputs.len(),
+            updated,
+            signed_inputs: self.inputs.iter().filter(|input| is_signed(input)).count(),
+            finalized_inputs: self
+                .inputs
+                .iter()
+                .filter(|input| {
+                    input.final_script_sig.is_some() || input.final_script_witness.is_some()
+                })
+                .count(),
+        }
+    }
+
+    fn summary<C>(
+        &self,
+        seed: &Seed,
+        network: Network,
+        secp: &Secp256k1<C>,
+    ) -> Result<PsbtSummary, Error>
+    where
+        C: Signing,
+    {
+        let outputs: Vec<PsbtOutputSummary> = self
+            .unsigned_tx
+            .output
+            .iter()
+            .enumerate()
+            .map(|(index, output)| {
+                Ok(PsbtOutputSummary {
+                    address: Address::from_script(&output.script_pubkey, network).ok(),
+                    value: output.value,
+                    is_change: self.is_own_output(index, seed, network, secp)?,
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(PsbtSummary {
+            outputs,
+            total_input: self.total_input_value()?,
+            total_output: self.total_output_value(),
+            fee: self.absolute_fee()?,
+        })
+    }
+}
+
+/// Check if the `scriptSig` of the input is already known (finalized or segwit input)
+fn is_txid_fixed(txin: &TxIn, input: &psbt::Input) -> bool {
+    if input.final_script_sig.is_some() {
+        return true;
+    }
+
+    if let Some(redeem_script) = &input.redeem_script {
+        return redeem_script.is_witness_program();
+    }
+
+    let script_pubkey = match (&input.witness_utxo, &input.non_witness_utxo) {
+        (Some(utxo), _) => Some(&utxo.script_pubkey),
+        (None, Some(tx)) => tx
+            .output
+            .get(txin.previous_output.vout as usize)
+            .map(|output| &output.script_pubkey),
+        (None, None) => None,
+    };
+
+    script_pubkey.map_or(false, |script| script.is_witness_program())
+}
+
+fn is_signed(input: &psbt::Input) -> bool {
+    !input.partial_sigs.is_empty()
+        || input.tap_key_sig.is_some()
+        || !input.tap_script_sigs.is_empty()
+        || input.final_script_sig.is_some()
+        || input.final_script_witness.is_some()
+}
+
+/// Set the `sighash` type of the inputs at `indexes`
+///
+/// Fail if an input already has a different sighash type or, for `SIGHASH_SINGLE`, if there isn't an output with the same index.
+fn set_sighash(
+    psbt: &mut PartiallySignedTransaction,
+    indexes: &[usize],
+    sighash: EcdsaSighashType,
+) -> Result<(), Error> {
+    let requested = PsbtSighashType::from(sighash);
+    let is_single: bool = matches!(
+        sighash,
+        EcdsaSighashType::Single | EcdsaSighashType::SinglePlusAnyoneCanPay
+    );
+
+    for index in indexes.iter().copied() {
+        if is_single && index >= psbt.unsigned_tx.output.len() {
+            return Err(Error::SighashSingleWithoutOutput(index));
+        }
+
+        if let Some(current) = psbt.inputs[index].sighash_type {
+            if current != requested {
+                return Err(Error::SighashMismatch(index));
+            }
+        }
+    }
+
+    for index in indexes.iter().copied() {
+        psbt.inputs[index].sighash_type = Some(requested);
+    }
+
+    Ok(())
+}
+
+/// Network of an extended (or WIF) descriptor key
+///
+/// Testnet, signet and regtest share the same version bytes, so all of them are reported as [`Network::Testnet`].
+fn descriptor_key_network(key: &str) -> Option<Network> {
+    if let Ok(key) = DescriptorPublicKey::from_str(key) {
+        return match key {
+            DescriptorPublicKey::XPub(xkey) => Some(xkey.xkey.network),
+            DescriptorPublicKey::MultiXPub(xkey) => Some(xkey.xkey.network),
+            _ => None,
+        };
+    }
+
+    match DescriptorSecretKey::from_str(key) {
+        Ok(DescriptorSecretKey::Single(single)) => Some(single.key.network),
+        Ok(DescriptorSecretKey::XPrv(xkey)) => Some(xkey.xkey.network),
+        Ok(DescriptorSecretKey::MultiXPrv(xkey)) => Some(xkey.xkey.network),
+        _ => None,
+    }
+}
+
+/// Check that the descriptor keys match the signing [`Network`]
+fn check_descriptor_network(
+    descriptor: &Descriptor<String>,
+    network: Network,
+) -> Result<(), Error> {
+    let is_mainnet: bool = network == Network::Bitcoin;
+    let mut found: Option<Network> = None;
+    descriptor.for_each_key(|key| match descriptor_key_network(key) {
+        Some(key_network) if (key_network == Network::Bitcoin) != is_mainnet => {
+            found = Some(key_network);
+            false
+        }
+        _ => true,
+    });
+
+    match found {
+        Some(found) => Err(Error::NetworkMismatch {
+            expected: network,
+            found,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Single key descriptor for the account of `path` (`path` without the last two steps), with the `script` template
+fn assumed_script_descriptor<C>(
+    root: &ExtendedPrivKey,
+    path: &DerivationPath,
+    script: Purpose,
+    secp: &Secp256k1<C>,
+) -> Result<String, Error>
+where
+    C: Signing,
+{
+    let steps: &[ChildNumber] = path.as_ref();
+    if steps.len() < 2 {
+        return Err(Error::InvalidDerivationPath);
+    }
+    let (account_path, keychain) = steps.split_at(steps.len() - 2);
+    let account_path: DerivationPath = DerivationPath::from(account_path.to_vec());
+    let xpriv: ExtendedPrivKey = root.derive_priv(secp, &account_path)?;
+    let xpub: ExtendedPubKey = ExtendedPubKey::from_priv(secp, &xpriv);
+    let origin: String = account_path
+        .into_iter()
+        .map(|child| format!("/{child}"))
+        .collect();
+    let key: String = format!(
+        "[{}{origin}]{xpub}/{}/*",
+        root.fingerprint(secp),
+        keychain[0]
+    );
+
+    match script {
+        Purpose::BIP44 => Ok(format!("pkh({key})")),
+        Purpose::BIP49 => Ok(format!("sh(wpkh({key}))")),
+        Purpose::BIP84 => Ok(format!("wpkh({key})")),
+        Purpose::BIP86 => Ok(format!("tr({key})")),
+        purpose => Err(Error::UnsupportedScript(purpose)),
+    }
 }
 
 fn sign_psbt<C>(
@@ -249,6 +1131,7 @@ fn sign_psbt<C>(
     descriptor: Option<Descriptor<String>>,
     custom_signers: Vec<SignerWrapper<PrivateKey>>,
     use_tr_internal_key: bool,
+    options: SigningOptions,
     network: Network,
     secp: &Secp256k1<C>,
 ) -> Result<bool, Error>
@@ -258,6 +1141,26 @@ where
     let root: ExtendedPrivKey = seed.to_bip32_root_key(network)?;
     let root_fingerprint: Fingerprint = root.fingerprint(secp);
 
+    if let Some(sighash) = options.sighash {
+        let indexes: Vec<usize> = psbt
+            .inputs
+            .iter()
+            .enumerate()
+            .filter(|(_, input)| {
+                input
+                    .bip32_derivation
+                    .values()
+                    .any(|(fingerprint, _)| fingerprint.eq(&root_fingerprint))
+                    || input
+                        .tap_key_origins
+                        .values()
+                        .any(|(_, (fingerprint, _))| fingerprint.eq(&root_fingerprint))
+            })
+            .map(|(index, _)| index)
+            .collect();
+        set_sighash(psbt, &indexes, sighash)?;
+    }
+
     let mut paths: Vec<&DerivationPath> = Vec::new();
 
     for input in psbt.inputs.iter() {
@@ -279,15 +1182,23 @@ where
     }
 
     let descriptor: String = match descriptor {
-        Some(desc) => desc.to_string(),
+        Some(desc) => {
+            check_descriptor_network(&desc, network)?;
+            desc.to_string()
+        }
         None => {
             let path = paths.first().ok_or(Error::NothingToSign)?;
-            let extended_path = ExtendedPath::from_derivation_path(path)?;
-
-            let descriptors = Descriptors::new(seed, network, Some(extended_path.account), secp)?;
-            let descriptor =
-                descriptors.get_by_purpose(extended_path.purpose, extended_path.change)?;
-            descriptor.to_string()
+            match options.assume_script {
+                Some(script) => assumed_script_descriptor(&root, path, script, secp)?,
+                None => {
+                    let extended_path = ExtendedPath::from_derivation_path(path)?;
+                    let descriptors =
+                        Descriptors::new(seed, network, Some(extended_path.account), secp)?;
+                    let descriptor =
+                        descriptors.get_by_purpose(extended_path.purpose, extended_path.change)?;
+                    descriptor.to_string()
+                }
+            }
         }
     };
 
@@ -299,8 +1210,11 @@ where
     for path in paths.into_iter() {
         let child_priv: ExtendedPrivKey = root.derive_priv(secp, path)?;
         let private_key: PrivateKey = PrivateKey::new(child_priv.private_key, network);
-        let extended_path = ExtendedPath::from_derivation_path(path)?;
-        let signer_ctx: SignerContext = match extended_path.purpose {
+        let purpose: Purpose = match options.assume_script {
+            Some(script) => script,
+            None => ExtendedPath::from_derivation_path(path)?.purpose,
+        };
+        let signer_ctx: SignerContext = match purpose {
             Purpose::BIP44 => SignerContext::Legacy,
             Purpose::BIP48 { script } => match script {
                 ScriptType::P2SHWSH | ScriptType::P2WSH => SignerContext::Segwitv0,
@@ -332,7 +1246,22 @@ where
         counter += 1;
     }
 
-    let finalized: bool = wallet.sign(psbt, SignOptions::default())?;
+    let sign_options = SignOptions {
+        // Non-default sighash explicitly requested by the user
+        allow_all_sighashes: options.allow_all_sighashes,
+        trust_witness_utxo: options.trust_witness_utxo,
+        ..Default::default()
+    };
+    let finalized: bool = wallet.sign(psbt, sign_options)?;
+
+    if use_tr_internal_key {
+        // Key-path spend: don't add the script-path signatures, to not reveal the scripts
+        for (input, base_input) in psbt.inputs.iter_mut().zip(base_psbt.inputs.iter()) {
+            if input.tap_key_sig.is_some() {
+                input.tap_script_sigs = base_input.tap_script_sigs.clone();
+            }
+        }
+    }
 
     if base_psbt != *psbt {
         Ok(finalized)
@@ -341,6 +1270,67 @@ where
     }
 }
 
+/// Sign a message with the key at `path` (BIP322 simple signature)
+///
+/// The script is inferred from the purpose of `path`: P2TR (`86'`) or P2WPKH (`84'`).
+/// Return the base64 encoded signature.
+pub fn sign_message<C>(
+    seed: &Seed,
+    path: &DerivationPath,
+    message: &str,
+    network: Network,
+    secp: &Secp256k1<C>,
+) -> Result<String, Error>
+where
+    C: Signing + Verification,
+{
+    let purpose: Purpose = ExtendedPath::from_derivation_path(path)?.purpose;
+    let root: ExtendedPrivKey = seed.to_bip32_root_key(network)?;
+    let derived: ExtendedPrivKey = root.derive_priv(secp, path)?;
+    match purpose {
+        Purpose::BIP86 => {
+            let keypair = KeyPair::from_secret_key(secp, &derived.private_key);
+            Ok(bip322::sign_taproot(&keypair, message.as_bytes(), secp)?)
+        }
+        Purpose::BIP84 => Ok(bip322::sign_p2wpkh(
+            &derived.private_key,
+            message.as_bytes(),
+            secp,
+        )?),
+        purpose => Err(Error::UnsupportedScript(purpose)),
+    }
+}
+
+/// Verify a BIP322 simple signature of `address` (P2TR or P2WPKH)
+pub fn verify_message<C>(
+    address: &Address,
+    message: &str,
+    signature: &str,
+    secp: &Secp256k1<C>,
+) -> Result<(), Error>
+where
+    C: Verification,
+{
+    Ok(bip322::verify(
+        &address.script_pubkey(),
+        message.as_bytes(),
+        signature,
+        secp,
+    )?)
+}
+
+/// Combine the PSBTs of the same transaction signed by different cosigners (BIP174 combiner)
+pub fn combine(
+    psbts: Vec<PartiallySignedTransaction>,
+) -> Result<PartiallySignedTransaction, Error> {
+    let mut psbts = psbts.into_iter();
+    let mut combined: PartiallySignedTransaction = psbts.next().ok_or(Error::NothingToCombine)?;
+    for psbt in psbts {
+        combined.combine_with(psbt)?;
+    }
+    Ok(combined)
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
@@ -363,6 +1353,630 @@ mod tests {
         assert!(finalized);
     }
 
+    #[test]
+    fn test_psbt_sign_with_sighash() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("easy uncover favorite crystal bless differ energy seat ecology match carry group refuse together chat observe hidden glad brave month diesel sustain depth salt").unwrap();
+        let seed = Seed::new::<&str>(mnemonic, None);
+        let mut psbt = PartiallySignedTransaction::from_base64("cHNidP8BAFICAAAAATjFB9Xkau6+MTmNTT9GN6i299X9n9MSQhVVMVegw8qOAAAAAAD9////AcAHAAAAAAAAFgAUAhYIdK3p2Bvf/ZnzIYQcWWZkxCJ4HiUATwEENYfPA+UBpeaAAAAAVd9MbQ78ZD7Ie5K8FXctxNRCrS4DNFhPiSzC2CpygWICsOropyXycdL0H0uI5TUbJL1w8/detLdnP5WxGGUZ+5UQm/Q1S1QAAIABAACAAAAAgAABAHECAAAAAYqdaqOD/k1QaGShhL4ilryMhXgOJu+cFcKFAUMZQ+wrAAAAAAD9////Ai4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUPxCQAAAAAAABYAFO9WcMNPGiI5MjypE7Ku0dT1LOgRI9wkAAEBHy4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUMBAwQBAAAAIgYCyh1DqpGE/SatxQ86lKeUBXZ1BGpZuwNnGiGq9pDdTbkYm/Q1S1QAAIABAACAAAAAgAAAAAAAAAAAAAA=").unwrap();
+        let options = SigningOptions {
+            sighash: Some(EcdsaSighashType::AllPlusAnyoneCanPay),
+            allow_all_sighashes: true,
+            ..Default::default()
+        };
+
+        // The creator already set SIGHASH_ALL
+        let base: PartiallySignedTransaction = psbt.clone();
+        assert!(matches!(
+            psbt.sign_custom_with_options(&seed, None, Vec::new(), options, NETWORK, &secp),
+            Err(Error::SighashMismatch(0))
+        ));
+
+        psbt.inputs[0].sighash_type = None;
+        let finalized = psbt
+            .sign_custom_with_options(&seed, None, Vec::new(), options, NETWORK, &secp)
+            .unwrap();
+        assert!(finalized);
+
+        let witness = psbt.inputs[0]
+            .final_script_witness
+            .clone()
+            .unwrap()
+            .to_vec();
+        assert_eq!(witness[0].last().copied(), Some(0x81));
+
+        // SIGHASH_SINGLE without the corresponding output
+        let mut psbt = base;
+        psbt.inputs[0].sighash_type = None;
+        psbt.unsigned_tx.output.clear();
+        psbt.outputs.clear();
+        let options = SigningOptions {
+            sighash: Some(EcdsaSighashType::Single),
+            ..Default::default()
+        };
+        assert!(matches!(
+            psbt.sign_custom_with_options(&seed, None, Vec::new(), options, NETWORK, &secp),
+            Err(Error::SighashSingleWithoutOutput(0))
+        ));
+    }
+
+    #[test]
+    fn test_psbt_sign_allow_all_sighashes() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("easy uncover favorite crystal bless differ energy seat ecology match carry group refuse together chat observe hidden glad brave month diesel sustain depth salt").unwrap();
+        let seed = Seed::new::<&str>(mnemonic, None);
+        let mut psbt = PartiallySignedTransaction::from_base64("cHNidP8BAFICAAAAATjFB9Xkau6+MTmNTT9GN6i299X9n9MSQhVVMVegw8qOAAAAAAD9////AcAHAAAAAAAAFgAUAhYIdK3p2Bvf/ZnzIYQcWWZkxCJ4HiUATwEENYfPA+UBpeaAAAAAVd9MbQ78ZD7Ie5K8FXctxNRCrS4DNFhPiSzC2CpygWICsOropyXycdL0H0uI5TUbJL1w8/detLdnP5WxGGUZ+5UQm/Q1S1QAAIABAACAAAAAgAABAHECAAAAAYqdaqOD/k1QaGShhL4ilryMhXgOJu+cFcKFAUMZQ+wrAAAAAAD9////Ai4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUPxCQAAAAAAABYAFO9WcMNPGiI5MjypE7Ku0dT1LOgRI9wkAAEBHy4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUMBAwQBAAAAIgYCyh1DqpGE/SatxQ86lKeUBXZ1BGpZuwNnGiGq9pDdTbkYm/Q1S1QAAIABAACAAAAAgAAAAAAAAAAAAAA=").unwrap();
+        psbt.inputs[0].sighash_type = Some(EcdsaSighashType::None.into());
+
+        // Blocked by default
+        assert!(matches!(
+            psbt.clone().sign_with_seed(&seed, NETWORK, &secp),
+            Err(Error::BdkSigner(SignerError::NonStandardSighash))
+        ));
+
+        let options = SigningOptions {
+            allow_all_sighashes: true,
+            ..Default::default()
+        };
+        let finalized = psbt
+            .sign_custom_with_options(&seed, None, Vec::new(), options, NETWORK, &secp)
+            .unwrap();
+        assert!(finalized);
+    }
+
+    #[test]
+    fn test_psbt_sign_assume_script() {
+        use bdk::bitcoin::absolute::LockTime;
+        use bdk::bitcoin::{OutPoint, PublicKey, Sequence, Transaction, TxOut, Witness};
+
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("easy uncover favorite crystal bless differ energy seat ecology match carry group refuse together chat observe hidden glad brave month diesel sustain depth salt").unwrap();
+        let seed = Seed::new::<&str>(mnemonic, None);
+        let root: ExtendedPrivKey = seed.to_bip32_root_key(NETWORK).unwrap();
+
+        // P2WPKH output with a key derived at a non-standard purpose
+        let path = DerivationPath::from_str("m/1017'/1'/0'/0/0").unwrap();
+        let child: ExtendedPrivKey = root.derive_priv(&secp, &path).unwrap();
+        let public_key = PublicKey::new(child.private_key.public_key(&secp));
+        let script_pubkey = ScriptBuf::new_v0_p2wpkh(&public_key.wpubkey_hash().unwrap());
+        let prev_tx = Transaction {
+            version: 2,
+            lock_time: LockTime::ZERO,
+            input: Vec::new(),
+            output: vec![TxOut {
+                value: 10_000,
+                script_pubkey: script_pubkey.clone(),
+            }],
+        };
+        let tx = Transaction {
+            version: 2,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::new(prev_tx.txid(), 0),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                value: 9_000,
+                script_pubkey,
+            }],
+        };
+        let mut psbt = PartiallySignedTransaction::from_unsigned_tx(tx).unwrap();
+        psbt.inputs[0].witness_utxo = Some(prev_tx.output[0].clone());
+        psbt.inputs[0].non_witness_utxo = Some(prev_tx);
+        psbt.inputs[0]
+            .bip32_derivation
+            .insert(public_key.inner, (root.fingerprint(&secp), path));
+
+        // Purpose can't be inferred
+        assert!(psbt.clone().sign_with_seed(&seed, NETWORK, &secp).is_err());
+
+        // Multisig scripts need a descriptor
+        let options = SigningOptions {
+            assume_script: Some(Purpose::BIP48 {
+                script: ScriptType::P2WSH,
+            }),
+            ..Default::default()
+        };
+        assert!(matches!(
+            psbt.clone()
+                .sign_custom_with_options(&seed, None, Vec::new(), options, NETWORK, &secp),
+            Err(Error::UnsupportedScript(..))
+        ));
+
+        let options = SigningOptions {
+            assume_script: Some(Purpose::BIP84),
+            ..Default::default()
+        };
+        let finalized = psbt
+            .sign_custom_with_options(&seed, None, Vec::new(), options, NETWORK, &secp)
+            .unwrap();
+        assert!(finalized);
+        assert!(psbt.inputs[0].final_script_witness.is_some());
+    }
+
+    #[test]
+    fn test_psbt_sign_taproot_key_path() {
+        use bdk::bitcoin::absolute::LockTime;
+        use bdk::bitcoin::{OutPoint, Sequence, TxOut, Witness};
+        use bdk::miniscript::psbt::PsbtExt;
+
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("easy uncover favorite crystal bless differ energy seat ecology match carry group refuse together chat observe hidden glad brave month diesel sustain depth salt").unwrap();
+        let seed = Seed::new::<&str>(mnemonic, None);
+
+        // Internal key and leaf key both derived from the seed
+        let descriptors = Descriptors::new(&seed, NETWORK, None, &secp).unwrap();
+        let key: String = match descriptors.get_by_purpose(Purpose::BIP86, false).unwrap() {
+            Descriptor::Tr(tr) => tr.internal_key().to_string(),
+            _ => panic!("expected taproot descriptor"),
+        };
+        let leaf_key: String = key.replace("/0/*", "/1/*");
+        let descriptor: String = format!("tr({key},pk({leaf_key}))");
+        let derived = Descriptor::<DescriptorPublicKey>::from_str(&descriptor)
+            .unwrap()
+            .at_derivation_index(0)
+            .unwrap();
+
+        let prev_output = TxOut {
+            value: 10_000,
+            script_pubkey: derived.script_pubkey(),
+        };
+        let tx = Transaction {
+            version: 2,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                value: 9_000,
+                script_pubkey: derived.script_pubkey(),
+            }],
+        };
+        let mut psbt = PartiallySignedTransaction::from_unsigned_tx(tx).unwrap();
+        psbt.inputs[0].witness_utxo = Some(prev_output);
+        psbt.update_input_with_descriptor(0, &derived).unwrap();
+        assert!(!psbt.inputs[0].tap_scripts.is_empty());
+        let descriptor = Descriptor::<String>::from_str(&descriptor).unwrap();
+
+        // Key path by default: only the key signature, the leaf is not revealed
+        let mut key_path = psbt.clone();
+        let options = SigningOptions {
+            trust_witness_utxo: true,
+            ..Default::default()
+        };
+        let finalized = key_path
+            .sign_custom_with_options(
+                &seed,
+                Some(descriptor.clone()),
+                Vec::new(),
+                options,
+                NETWORK,
+                &secp,
+            )
+            .unwrap();
+        assert!(finalized);
+        let witness = key_path.inputs[0].final_script_witness.clone().unwrap();
+        assert_eq!(witness.len(), 1);
+
+        // Script path if forced
+        let mut script_path = psbt;
+        let options = SigningOptions {
+            trust_witness_utxo: true,
+            force_script_path: true,
+            ..Default::default()
+        };
+        let finalized = script_path
+            .sign_custom_with_options(&seed, Some(descriptor), Vec::new(), options, NETWORK, &secp)
+            .unwrap();
+        assert!(finalized);
+        let witness = script_path.inputs[0].final_script_witness.clone().unwrap();
+        assert_eq!(witness.len(), 3);
+    }
+
+    #[test]
+    fn test_psbt_formats() {
+        let psbt = PartiallySignedTransaction::from_base64("cHNidP8BAFICAAAAATjFB9Xkau6+MTmNTT9GN6i299X9n9MSQhVVMVegw8qOAAAAAAD9////AcAHAAAAAAAAFgAUAhYIdK3p2Bvf/ZnzIYQcWWZkxCJ4HiUATwEENYfPA+UBpeaAAAAAVd9MbQ78ZD7Ie5K8FXctxNRCrS4DNFhPiSzC2CpygWICsOropyXycdL0H0uI5TUbJL1w8/detLdnP5WxGGUZ+5UQm/Q1S1QAAIABAACAAAAAgAABAHECAAAAAYqdaqOD/k1QaGShhL4ilryMhXgOJu+cFcKFAUMZQ+wrAAAAAAD9////Ai4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUPxCQAAAAAAABYAFO9WcMNPGiI5MjypE7Ku0dT1LOgRI9wkAAEBHy4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUMBAwQBAAAAIgYCyh1DqpGE/SatxQ86lKeUBXZ1BGpZuwNnGiGq9pDdTbkYm/Q1S1QAAIABAACAAAAAgAAAAAAAAAAAAAA=").unwrap();
+        let dir = std::env::temp_dir();
+
+        for format in [PsbtFormat::Binary, PsbtFormat::Base64, PsbtFormat::Hex] {
+            let path = dir.join(format!(
+                "keechain-test-{format:?}-{}.psbt",
+                std::process::id()
+            ));
+            psbt.save_to_file_with_format(&path, format).unwrap();
+            assert_eq!(PartiallySignedTransaction::from_file(&path).unwrap(), psbt);
+            std::fs::remove_file(path).unwrap();
+        }
+
+        assert!(psbt
+            .encode(PsbtFormat::Binary)
+            .unwrap()
+            .starts_with(PSBT_MAGIC));
+        assert_eq!(
+            psbt.encode(PsbtFormat::Base64).unwrap(),
+            psbt.as_base64().into_bytes()
+        );
+        assert!(psbt
+            .encode(PsbtFormat::Hex)
+            .unwrap()
+            .starts_with(PSBT_MAGIC_HEX.as_bytes()));
+    }
+
+    #[test]
+    fn test_psbt_from_text() {
+        let base64: &str = "cHNidP8BAFICAAAAATjFB9Xkau6+MTmNTT9GN6i299X9n9MSQhVVMVegw8qOAAAAAAD9////AcAHAAAAAAAAFgAUAhYIdK3p2Bvf/ZnzIYQcWWZkxCJ4HiUATwEENYfPA+UBpeaAAAAAVd9MbQ78ZD7Ie5K8FXctxNRCrS4DNFhPiSzC2CpygWICsOropyXycdL0H0uI5TUbJL1w8/detLdnP5WxGGUZ+5UQm/Q1S1QAAIABAACAAAAAgAABAHECAAAAAYqdaqOD/k1QaGShhL4ilryMhXgOJu+cFcKFAUMZQ+wrAAAAAAD9////Ai4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUPxCQAAAAAAABYAFO9WcMNPGiI5MjypE7Ku0dT1LOgRI9wkAAEBHy4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUMBAwQBAAAAIgYCyh1DqpGE/SatxQ86lKeUBXZ1BGpZuwNnGiGq9pDdTbkYm/Q1S1QAAIABAACAAAAAgAAAAAAAAAAAAAA=";
+        let psbt = PartiallySignedTransaction::from_base64(base64).unwrap();
+
+        assert_eq!(
+            PartiallySignedTransaction::from_text(format!(" {base64}\n")).unwrap(),
+            psbt
+        );
+        let hex: String = String::from_utf8(psbt.encode(PsbtFormat::Hex).unwrap()).unwrap();
+        assert_eq!(
+            PartiallySignedTransaction::from_text(hex.to_uppercase()).unwrap(),
+            psbt
+        );
+
+        let parts: Vec<String> = ur::encode_psbt(&psbt, 100);
+        assert_eq!(
+            PartiallySignedTransaction::from_ur(parts.join(" ")).unwrap(),
+            psbt
+        );
+        assert_eq!(
+            PartiallySignedTransaction::from_text(parts.join("").to_uppercase()).unwrap(),
+            psbt
+        );
+
+        assert_eq!(
+            PartiallySignedTransaction::from_bytes(psbt.encode(PsbtFormat::Binary).unwrap())
+                .unwrap(),
+            psbt
+        );
+        assert_eq!(
+            PartiallySignedTransaction::from_bytes(format!("{base64}\n")).unwrap(),
+            psbt
+        );
+
+        assert!(matches!(
+            PartiallySignedTransaction::from_text("cHNidP8BAFICAAAAA"),
+            Err(Error::PsbtParse(..))
+        ));
+        assert!(matches!(
+            PartiallySignedTransaction::from_text(&parts[0]),
+            Err(Error::Ur(ur::Error::MissingFragments { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_psbt_sign_trust_witness_utxo() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("easy uncover favorite crystal bless differ energy seat ecology match carry group refuse together chat observe hidden glad brave month diesel sustain depth salt").unwrap();
+        let seed = Seed::new::<&str>(mnemonic, None);
+        let mut psbt = PartiallySignedTransaction::from_base64("cHNidP8BAFICAAAAATjFB9Xkau6+MTmNTT9GN6i299X9n9MSQhVVMVegw8qOAAAAAAD9////AcAHAAAAAAAAFgAUAhYIdK3p2Bvf/ZnzIYQcWWZkxCJ4HiUATwEENYfPA+UBpeaAAAAAVd9MbQ78ZD7Ie5K8FXctxNRCrS4DNFhPiSzC2CpygWICsOropyXycdL0H0uI5TUbJL1w8/detLdnP5WxGGUZ+5UQm/Q1S1QAAIABAACAAAAAgAABAHECAAAAAYqdaqOD/k1QaGShhL4ilryMhXgOJu+cFcKFAUMZQ+wrAAAAAAD9////Ai4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUPxCQAAAAAAABYAFO9WcMNPGiI5MjypE7Ku0dT1LOgRI9wkAAEBHy4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUMBAwQBAAAAIgYCyh1DqpGE/SatxQ86lKeUBXZ1BGpZuwNnGiGq9pDdTbkYm/Q1S1QAAIABAACAAAAAgAAAAAAAAAAAAAA=").unwrap();
+        psbt.inputs[0].non_witness_utxo = None;
+
+        // Safe default: previous transaction required
+        assert!(matches!(
+            psbt.clone().sign_with_seed(&seed, NETWORK, &secp),
+            Err(Error::BdkSigner(SignerError::MissingNonWitnessUtxo))
+        ));
+
+        let options = SigningOptions {
+            trust_witness_utxo: true,
+            ..Default::default()
+        };
+        let finalized = psbt
+            .sign_custom_with_options(&seed, None, Vec::new(), options, NETWORK, &secp)
+            .unwrap();
+        assert!(finalized);
+    }
+
+    #[test]
+    fn test_psbt_predicted_txid() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("easy uncover favorite crystal bless differ energy seat ecology match carry group refuse together chat observe hidden glad brave month diesel sustain depth salt").unwrap();
+        let seed = Seed::new::<&str>(mnemonic, None);
+        let mut psbt = PartiallySignedTransaction::from_base64("cHNidP8BAFICAAAAATjFB9Xkau6+MTmNTT9GN6i299X9n9MSQhVVMVegw8qOAAAAAAD9////AcAHAAAAAAAAFgAUAhYIdK3p2Bvf/ZnzIYQcWWZkxCJ4HiUATwEENYfPA+UBpeaAAAAAVd9MbQ78ZD7Ie5K8FXctxNRCrS4DNFhPiSzC2CpygWICsOropyXycdL0H0uI5TUbJL1w8/detLdnP5WxGGUZ+5UQm/Q1S1QAAIABAACAAAAAgAABAHECAAAAAYqdaqOD/k1QaGShhL4ilryMhXgOJu+cFcKFAUMZQ+wrAAAAAAD9////Ai4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUPxCQAAAAAAABYAFO9WcMNPGiI5MjypE7Ku0dT1LOgRI9wkAAEBHy4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUMBAwQBAAAAIgYCyh1DqpGE/SatxQ86lKeUBXZ1BGpZuwNnGiGq9pDdTbkYm/Q1S1QAAIABAACAAAAAgAAAAAAAAAAAAAA=").unwrap();
+
+        // P2WPKH input
+        let predicted: Txid = psbt.predicted_txid();
+        assert_eq!(predicted, psbt.unsigned_tx.txid());
+        assert!(!psbt.txid_may_change());
+
+        let finalized = psbt.sign_with_seed(&seed, NETWORK, &secp).unwrap();
+        assert!(finalized);
+        assert_eq!(psbt.clone().extract_tx().txid(), predicted);
+
+        // P2PKH input
+        let mut legacy = psbt.clone();
+        legacy.inputs[0] = psbt::Input::default();
+        legacy.inputs[0].non_witness_utxo = psbt.inputs[0].non_witness_utxo.clone();
+        let vout: usize = legacy.unsigned_tx.input[0].previous_output.vout as usize;
+        legacy.inputs[0].non_witness_utxo.as_mut().unwrap().output[vout].script_pubkey =
+            ScriptBuf::from_hex("76a914000000000000000000000000000000000000000088ac").unwrap();
+        assert!(legacy.txid_may_change());
+    }
+
+    #[test]
+    fn test_psbt_add_missing_utxo() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("easy uncover favorite crystal bless differ energy seat ecology match carry group refuse together chat observe hidden glad brave month diesel sustain depth salt").unwrap();
+        let seed = Seed::new::<&str>(mnemonic, None);
+        let original = PartiallySignedTransaction::from_base64("cHNidP8BAFICAAAAATjFB9Xkau6+MTmNTT9GN6i299X9n9MSQhVVMVegw8qOAAAAAAD9////AcAHAAAAAAAAFgAUAhYIdK3p2Bvf/ZnzIYQcWWZkxCJ4HiUATwEENYfPA+UBpeaAAAAAVd9MbQ78ZD7Ie5K8FXctxNRCrS4DNFhPiSzC2CpygWICsOropyXycdL0H0uI5TUbJL1w8/detLdnP5WxGGUZ+5UQm/Q1S1QAAIABAACAAAAAgAABAHECAAAAAYqdaqOD/k1QaGShhL4ilryMhXgOJu+cFcKFAUMZQ+wrAAAAAAD9////Ai4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUPxCQAAAAAAABYAFO9WcMNPGiI5MjypE7Ku0dT1LOgRI9wkAAEBHy4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUMBAwQBAAAAIgYCyh1DqpGE/SatxQ86lKeUBXZ1BGpZuwNnGiGq9pDdTbkYm/Q1S1QAAIABAACAAAAAgAAAAAAAAAAAAAA=").unwrap();
+        let prev_tx: Transaction = original.inputs[0].non_witness_utxo.clone().unwrap();
+
+        let mut psbt = original.clone();
+        psbt.inputs[0].non_witness_utxo = None;
+        psbt.inputs[0].witness_utxo = None;
+        assert!(psbt.absolute_fee().is_err());
+
+        assert_eq!(psbt.add_missing_utxo(&prev_tx).unwrap(), 1);
+        assert_eq!(psbt.inputs[0], original.inputs[0]);
+        assert_eq!(
+            psbt.absolute_fee().unwrap(),
+            original.absolute_fee().unwrap()
+        );
+        assert!(psbt.sign_with_seed(&seed, NETWORK, &secp).unwrap());
+
+        // Nothing missing
+        let mut psbt = original.clone();
+        assert_eq!(psbt.add_missing_utxo(&prev_tx).unwrap(), 0);
+
+        // Not spent by the PSBT
+        let mut other: Transaction = prev_tx.clone();
+        other.lock_time = bdk::bitcoin::absolute::LockTime::from_consensus(1);
+        assert!(matches!(
+            psbt.add_missing_utxo(&other),
+            Err(Error::PrevTxNotSpent(txid)) if txid == other.txid()
+        ));
+    }
+
+    #[test]
+    fn test_sign_message() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("easy uncover favorite crystal bless differ energy seat ecology match carry group refuse together chat observe hidden glad brave month diesel sustain depth salt").unwrap();
+        let seed = Seed::new::<&str>(mnemonic, None);
+        let descriptors = Descriptors::new(&seed, NETWORK, None, &secp).unwrap();
+
+        for purpose in [Purpose::BIP84, Purpose::BIP86] {
+            let derived = descriptors
+                .derive_address(purpose, false, 1, NETWORK)
+                .unwrap();
+            let signature =
+                sign_message(&seed, &derived.path, "Hello World", NETWORK, &secp).unwrap();
+            verify_message(&derived.address, "Hello World", &signature, &secp).unwrap();
+            assert!(verify_message(&derived.address, "Hello", &signature, &secp).is_err());
+        }
+
+        // Only P2TR and P2WPKH
+        let derived = descriptors
+            .derive_address(Purpose::BIP49, false, 0, NETWORK)
+            .unwrap();
+        assert!(matches!(
+            sign_message(&seed, &derived.path, "Hello World", NETWORK, &secp),
+            Err(Error::UnsupportedScript(Purpose::BIP49))
+        ));
+    }
+
+    #[test]
+    fn test_psbt_ensure_max_inputs() {
+        let psbt = PartiallySignedTransaction::from_base64("cHNidP8BAFICAAAAATjFB9Xkau6+MTmNTT9GN6i299X9n9MSQhVVMVegw8qOAAAAAAD9////AcAHAAAAAAAAFgAUAhYIdK3p2Bvf/ZnzIYQcWWZkxCJ4HiUATwEENYfPA+UBpeaAAAAAVd9MbQ78ZD7Ie5K8FXctxNRCrS4DNFhPiSzC2CpygWICsOropyXycdL0H0uI5TUbJL1w8/detLdnP5WxGGUZ+5UQm/Q1S1QAAIABAACAAAAAgAABAHECAAAAAYqdaqOD/k1QaGShhL4ilryMhXgOJu+cFcKFAUMZQ+wrAAAAAAD9////Ai4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUPxCQAAAAAAABYAFO9WcMNPGiI5MjypE7Ku0dT1LOgRI9wkAAEBHy4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUMBAwQBAAAAIgYCyh1DqpGE/SatxQ86lKeUBXZ1BGpZuwNnGiGq9pDdTbkYm/Q1S1QAAIABAACAAAAAgAAAAAAAAAAAAAA=").unwrap();
+        assert!(psbt.ensure_max_inputs(DEFAULT_MAX_INPUTS).is_ok());
+        assert!(psbt.ensure_max_inputs(1).is_ok());
+        assert!(matches!(
+            psbt.ensure_max_inputs(0),
+            Err(Error::TooManyInputs { count: 1, max: 0 })
+        ));
+    }
+
+    #[test]
+    fn test_psbt_summary() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("easy uncover favorite crystal bless differ energy seat ecology match carry group refuse together chat observe hidden glad brave month diesel sustain depth salt").unwrap();
+        let seed = Seed::new::<&str>(mnemonic, None);
+        let psbt = PartiallySignedTransaction::from_base64("cHNidP8BAFICAAAAATjFB9Xkau6+MTmNTT9GN6i299X9n9MSQhVVMVegw8qOAAAAAAD9////AcAHAAAAAAAAFgAUAhYIdK3p2Bvf/ZnzIYQcWWZkxCJ4HiUATwEENYfPA+UBpeaAAAAAVd9MbQ78ZD7Ie5K8FXctxNRCrS4DNFhPiSzC2CpygWICsOropyXycdL0H0uI5TUbJL1w8/detLdnP5WxGGUZ+5UQm/Q1S1QAAIABAACAAAAAgAABAHECAAAAAYqdaqOD/k1QaGShhL4ilryMhXgOJu+cFcKFAUMZQ+wrAAAAAAD9////Ai4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUPxCQAAAAAAABYAFO9WcMNPGiI5MjypE7Ku0dT1LOgRI9wkAAEBHy4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUMBAwQBAAAAIgYCyh1DqpGE/SatxQ86lKeUBXZ1BGpZuwNnGiGq9pDdTbkYm/Q1S1QAAIABAACAAAAAgAAAAAAAAAAAAAA=").unwrap();
+        let summary = psbt.summary(&seed, NETWORK, &secp).unwrap();
+        assert_eq!(summary.total_input, 2094);
+        assert_eq!(summary.total_output, 1984);
+        assert_eq!(summary.fee, 110);
+        assert_eq!(psbt.fee_rate().unwrap(), 110.0 / 82.0);
+        assert_eq!(summary.outputs.len(), 1);
+        assert!(!summary.outputs[0].is_change);
+    }
+
+    #[test]
+    fn test_psbt_is_own_output() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("easy uncover favorite crystal bless differ energy seat ecology match carry group refuse together chat observe hidden glad brave month diesel sustain depth salt").unwrap();
+        let seed = Seed::new::<&str>(mnemonic, None);
+        let mut psbt = PartiallySignedTransaction::from_base64("cHNidP8BAFICAAAAATjFB9Xkau6+MTmNTT9GN6i299X9n9MSQhVVMVegw8qOAAAAAAD9////AcAHAAAAAAAAFgAUAhYIdK3p2Bvf/ZnzIYQcWWZkxCJ4HiUATwEENYfPA+UBpeaAAAAAVd9MbQ78ZD7Ie5K8FXctxNRCrS4DNFhPiSzC2CpygWICsOropyXycdL0H0uI5TUbJL1w8/detLdnP5WxGGUZ+5UQm/Q1S1QAAIABAACAAAAAgAABAHECAAAAAYqdaqOD/k1QaGShhL4ilryMhXgOJu+cFcKFAUMZQ+wrAAAAAAD9////Ai4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUPxCQAAAAAAABYAFO9WcMNPGiI5MjypE7Ku0dT1LOgRI9wkAAEBHy4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUMBAwQBAAAAIgYCyh1DqpGE/SatxQ86lKeUBXZ1BGpZuwNnGiGq9pDdTbkYm/Q1S1QAAIABAACAAAAAgAAAAAAAAAAAAAA=").unwrap();
+
+        let root = seed.to_bip32_root_key(NETWORK).unwrap();
+        let descriptors = Descriptors::new(&seed, NETWORK, None, &secp).unwrap();
+        let change = descriptors
+            .derive_address(Purpose::BIP84, true, 2, NETWORK)
+            .unwrap();
+        let public_key = root
+            .derive_priv(&secp, &change.path)
+            .unwrap()
+            .private_key
+            .public_key(&secp);
+        psbt.outputs[0]
+            .bip32_derivation
+            .insert(public_key, (root.fingerprint(&secp), change.path));
+
+        // Our fingerprint, but a foreign script
+        assert!(!psbt.is_own_output(0, &seed, NETWORK, &secp).unwrap());
+        assert!(!psbt.summary(&seed, NETWORK, &secp).unwrap().outputs[0].is_change);
+
+        psbt.unsigned_tx.output[0].script_pubkey = change.address.script_pubkey();
+        assert!(psbt.is_own_output(0, &seed, NETWORK, &secp).unwrap());
+        assert!(psbt.summary(&seed, NETWORK, &secp).unwrap().outputs[0].is_change);
+
+        // Out of range
+        assert!(!psbt.is_own_output(1, &seed, NETWORK, &secp).unwrap());
+    }
+
+    #[test]
+    fn test_psbt_join() {
+        let base = PartiallySignedTransaction::from_base64("cHNidP8BAFICAAAAATjFB9Xkau6+MTmNTT9GN6i299X9n9MSQhVVMVegw8qOAAAAAAD9////AcAHAAAAAAAAFgAUAhYIdK3p2Bvf/ZnzIYQcWWZkxCJ4HiUATwEENYfPA+UBpeaAAAAAVd9MbQ78ZD7Ie5K8FXctxNRCrS4DNFhPiSzC2CpygWICsOropyXycdL0H0uI5TUbJL1w8/detLdnP5WxGGUZ+5UQm/Q1S1QAAIABAACAAAAAgAABAHECAAAAAYqdaqOD/k1QaGShhL4ilryMhXgOJu+cFcKFAUMZQ+wrAAAAAAD9////Ai4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUPxCQAAAAAAABYAFO9WcMNPGiI5MjypE7Ku0dT1LOgRI9wkAAEBHy4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUMBAwQBAAAAIgYCyh1DqpGE/SatxQ86lKeUBXZ1BGpZuwNnGiGq9pDdTbkYm/Q1S1QAAIABAACAAAAAgAAAAAAAAAAAAAA=").unwrap();
+
+        // Same input: can't be joined
+        let mut psbt = base.clone();
+        assert!(matches!(
+            psbt.join(base.clone()),
+            Err(Error::DuplicateInput)
+        ));
+
+        // Different input
+        let mut other = base.clone();
+        other.unsigned_tx.input[0].previous_output.vout = 1;
+        let mut psbt = base.clone();
+        psbt.join(other.clone()).unwrap();
+        assert_eq!(psbt.unsigned_tx.input.len(), 2);
+        assert_eq!(psbt.inputs.len(), 2);
+        assert_eq!(psbt.unsigned_tx.output.len(), 2);
+        assert_eq!(psbt.outputs.len(), 2);
+
+        // Different lock time
+        other.unsigned_tx.lock_time = bdk::bitcoin::absolute::LockTime::ZERO;
+        let mut psbt = base;
+        assert!(matches!(psbt.join(other), Err(Error::LockTimeMismatch)));
+    }
+
+    #[test]
+    fn test_psbt_combine() {
+        let secp = Secp256k1::new();
+        let base = PartiallySignedTransaction::from_base64("cHNidP8BAFICAAAAATjFB9Xkau6+MTmNTT9GN6i299X9n9MSQhVVMVegw8qOAAAAAAD9////AcAHAAAAAAAAFgAUAhYIdK3p2Bvf/ZnzIYQcWWZkxCJ4HiUATwEENYfPA+UBpeaAAAAAVd9MbQ78ZD7Ie5K8FXctxNRCrS4DNFhPiSzC2CpygWICsOropyXycdL0H0uI5TUbJL1w8/detLdnP5WxGGUZ+5UQm/Q1S1QAAIABAACAAAAAgAABAHECAAAAAYqdaqOD/k1QaGShhL4ilryMhXgOJu+cFcKFAUMZQ+wrAAAAAAD9////Ai4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUPxCQAAAAAAABYAFO9WcMNPGiI5MjypE7Ku0dT1LOgRI9wkAAEBHy4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUMBAwQBAAAAIgYCyh1DqpGE/SatxQ86lKeUBXZ1BGpZuwNnGiGq9pDdTbkYm/Q1S1QAAIABAACAAAAAgAAAAAAAAAAAAAA=").unwrap();
+        let message = bdk::bitcoin::secp256k1::Message::from_slice(&[1; 32]).unwrap();
+        let partial_sig = |secret: [u8; 32]| {
+            let secret_key = bdk::bitcoin::secp256k1::SecretKey::from_slice(&secret).unwrap();
+            let public_key = bdk::bitcoin::PublicKey::new(secret_key.public_key(&secp));
+            let signature =
+                bdk::bitcoin::ecdsa::Signature::sighash_all(secp.sign_ecdsa(&message, &secret_key));
+            (public_key, signature)
+        };
+
+        // Cosigner 1 has only the previous transaction
+        let mut psbt1 = base.clone();
+        psbt1.inputs[0].witness_utxo = None;
+        let (public_key1, signature1) = partial_sig([1; 32]);
+        psbt1.inputs[0].partial_sigs.insert(public_key1, signature1);
+
+        // Cosigner 2 has only the witness UTXO
+        let mut psbt2 = base.clone();
+        psbt2.inputs[0].non_witness_utxo = None;
+        let (public_key2, signature2) = partial_sig([2; 32]);
+        psbt2.inputs[0].partial_sigs.insert(public_key2, signature2);
+
+        let combined = combine(vec![psbt1.clone(), psbt2]).unwrap();
+        assert_eq!(combined.inputs[0].partial_sigs.len(), 2);
+        assert_eq!(
+            combined.inputs[0].partial_sigs.get(&public_key1),
+            Some(&signature1)
+        );
+        assert_eq!(
+            combined.inputs[0].partial_sigs.get(&public_key2),
+            Some(&signature2)
+        );
+        assert_eq!(
+            combined.inputs[0].bip32_derivation,
+            base.inputs[0].bip32_derivation
+        );
+        assert!(combined.inputs[0].witness_utxo.is_some());
+        assert!(combined.inputs[0].non_witness_utxo.is_some());
+
+        // Different transaction
+        let mut other = base.clone();
+        other.unsigned_tx.lock_time = bdk::bitcoin::absolute::LockTime::ZERO;
+        assert!(matches!(
+            psbt1.combine_with(other.clone()),
+            Err(Error::TransactionMismatch { expected, found })
+                if expected == base.unsigned_tx.txid() && found == other.unsigned_tx.txid()
+        ));
+
+        assert!(matches!(combine(Vec::new()), Err(Error::NothingToCombine)));
+    }
+
+    #[test]
+    fn test_psbt_finalize() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("easy uncover favorite crystal bless differ energy seat ecology match carry group refuse together chat observe hidden glad brave month diesel sustain depth salt").unwrap();
+        let seed = Seed::new::<&str>(mnemonic, None);
+        let base = PartiallySignedTransaction::from_base64("cHNidP8BAFICAAAAATjFB9Xkau6+MTmNTT9GN6i299X9n9MSQhVVMVegw8qOAAAAAAD9////AcAHAAAAAAAAFgAUAhYIdK3p2Bvf/ZnzIYQcWWZkxCJ4HiUATwEENYfPA+UBpeaAAAAAVd9MbQ78ZD7Ie5K8FXctxNRCrS4DNFhPiSzC2CpygWICsOropyXycdL0H0uI5TUbJL1w8/detLdnP5WxGGUZ+5UQm/Q1S1QAAIABAACAAAAAgAABAHECAAAAAYqdaqOD/k1QaGShhL4ilryMhXgOJu+cFcKFAUMZQ+wrAAAAAAD9////Ai4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUPxCQAAAAAAABYAFO9WcMNPGiI5MjypE7Ku0dT1LOgRI9wkAAEBHy4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUMBAwQBAAAAIgYCyh1DqpGE/SatxQ86lKeUBXZ1BGpZuwNnGiGq9pDdTbkYm/Q1S1QAAIABAACAAAAAgAAAAAAAAAAAAAA=").unwrap();
+
+        // Not signed
+        let mut psbt = base.clone();
+        assert!(matches!(
+            psbt.finalize(&secp),
+            Err(Error::NotFinalized(inputs)) if inputs == vec![0]
+        ));
+
+        let mut psbt = base;
+        assert!(psbt.sign_with_seed(&seed, NETWORK, &secp).unwrap());
+        let tx: Transaction = psbt.finalize(&secp).unwrap();
+        assert_eq!(tx.txid(), psbt.unsigned_tx.txid());
+        assert!(!tx.input[0].witness.is_empty());
+    }
+
+    #[test]
+    fn test_psbt_roles() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("easy uncover favorite crystal bless differ energy seat ecology match carry group refuse together chat observe hidden glad brave month diesel sustain depth salt").unwrap();
+        let seed = Seed::new::<&str>(mnemonic, None);
+        let mut psbt = PartiallySignedTransaction::from_base64("cHNidP8BAFICAAAAATjFB9Xkau6+MTmNTT9GN6i299X9n9MSQhVVMVegw8qOAAAAAAD9////AcAHAAAAAAAAFgAUAhYIdK3p2Bvf/ZnzIYQcWWZkxCJ4HiUATwEENYfPA+UBpeaAAAAAVd9MbQ78ZD7Ie5K8FXctxNRCrS4DNFhPiSzC2CpygWICsOropyXycdL0H0uI5TUbJL1w8/detLdnP5WxGGUZ+5UQm/Q1S1QAAIABAACAAAAAgAABAHECAAAAAYqdaqOD/k1QaGShhL4ilryMhXgOJu+cFcKFAUMZQ+wrAAAAAAD9////Ai4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUPxCQAAAAAAABYAFO9WcMNPGiI5MjypE7Ku0dT1LOgRI9wkAAEBHy4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUMBAwQBAAAAIgYCyh1DqpGE/SatxQ86lKeUBXZ1BGpZuwNnGiGq9pDdTbkYm/Q1S1QAAIABAACAAAAAgAAAAAAAAAAAAAA=").unwrap();
+
+        // Created only
+        let created =
+            PartiallySignedTransaction::from_unsigned_tx(psbt.unsigned_tx.clone()).unwrap();
+        assert_eq!(created.roles().acted(), vec![PsbtRole::Creator]);
+        assert_eq!(created.roles().next(), PsbtRole::Updater);
+
+        // Updated
+        assert_eq!(
+            psbt.roles(),
+            PsbtRoles {
+                inputs: 1,
+                updated: true,
+                signed_inputs: 0,
+                finalized_inputs: 0,
+            }
+        );
+        assert_eq!(
+            psbt.roles().acted(),
+            vec![PsbtRole::Creator, PsbtRole::Updater]
+        );
+        assert_eq!(psbt.roles().next(), PsbtRole::Signer);
+
+        // Signed
+        assert!(psbt.sign_with_seed(&seed, NETWORK, &secp).unwrap());
+        assert_eq!(psbt.roles().signed_inputs, 1);
+        assert_eq!(
+            psbt.roles().acted(),
+            vec![PsbtRole::Creator, PsbtRole::Updater, PsbtRole::Signer]
+        );
+        assert_eq!(psbt.roles().next(), PsbtRole::Finalizer);
+
+        // Finalized
+        psbt.finalize(&secp).unwrap();
+        assert_eq!(psbt.roles().finalized_inputs, 1);
+        assert_eq!(
+            psbt.roles().acted(),
+            vec![
+                PsbtRole::Creator,
+                PsbtRole::Updater,
+                PsbtRole::Signer,
+                PsbtRole::Finalizer
+            ]
+        );
+        assert_eq!(psbt.roles().next(), PsbtRole::Extractor);
+    }
+
     #[test]
     fn test_psbt_sign_custom_internal() {
         let secp = Secp256k1::new();
@@ -416,4 +2030,24 @@ mod tests {
             wallet.finalize_psbt(&mut psbt, signopts).unwrap();
         }
     }
+
+    #[test]
+    fn test_psbt_sign_descriptor_network_mismatch() {
+        let secp = Secp256k1::new();
+        let descriptor: Descriptor<String> = Descriptor::from_str("tr([5cb492a5/86'/1'/784923']tpubDD56LAR1MR7X5EeZYMpvivk2Lh3HMo4vdDNQ8jAv4oBjLPEddQwxaxNypvrHbMk2qTxAj44YLzqHrzwy5LDNmVyYZBesm6aShhmhYrA8veT/0/*,{pk([76fdbca2/86'/1'/784923']tpubDCDepsNyAPWySAgXx1Por6sHpSWzxsTB9XJp5erEN7NumgdZMhhmycJGMQ1cHZwx66KyZr6psjttDDQ7mV4uJGV2DvB9Mri1nTVmpquvTDR/0/*),pk([3b8ae29b/86'/1'/784923']tpubDDpkQsJQTpHi2bH5Cg7L1pThUxeEStcn9ZsQ53XHkW8Fs81h71XobqpwYf2Jb8ECmW1mUUJxQhZstmwFUg5wQ6EVzH5HmF3cpHcyxjvF1Ep/0/*)})#yxpuntg3").unwrap();
+        let mnemonic = Mnemonic::from_str(
+            "message scissors typical gravity patrol lunch about bacon person focus cry uncover",
+        )
+        .unwrap();
+        let seed = Seed::from_mnemonic(mnemonic);
+        let mut psbt = PartiallySignedTransaction::from_base64("cHNidP8BAIABAAAAAUqvpB5XWB4i2YQXj86NmISEMyu1RfNPxySWaiJOdPvxAAAAAAD9////AtAHAAAAAAAAGXapFFnK2lAxTIKeGfWneG+O4NSYf0KdiKwxBQAAAAAAACJRIJl0wxtydCZWeVTLb8GOUjM1C0cDcnRtsoJJR2p2kkJcnoEmAAABASusDQAAAAAAACJRIDE9g5pAa6WK7b/WGB6d0UWm5sgRkpLbPXpEibVUak7UQhXAI96QaBnrHSbOH9tzer5xHVtMsbzh4fPPTyHnttKY+vkQQ4p+7RhNmkfpEKCicsaRdA2RybE9ldVXWhaDrF7JNiMgKbDNJspJ5wcY9fT6oRoMIdhoYYnuc6Qh5sViE85vARCswEIVwCPekGgZ6x0mzh/bc3q+cR1bTLG84eHzz08h57bSmPr5q2iopqFtsNFdeHEO8dsqx39/tNggFySr31N2paEU2PsjIEIsVoQ9xYIhcgVhulzI+ONq9P7fSu8sj1StIoZwlcDxrMAhFiPekGgZ6x0mzh/bc3q+cR1bTLG84eHzz08h57bSmPr5GQBctJKlVgAAgAEAAIAb+guAAAAAAAMAAAAhFimwzSbKSecHGPX0+qEaDCHYaGGJ7nOkIebFYhPObwEQOQGraKimoW2w0V14cQ7x2yrHf3+02CAXJKvfU3aloRTY+3b9vKJWAACAAQAAgBv6C4AAAAAAAwAAACEWQixWhD3FgiFyBWG6XMj442r0/t9K7yyPVK0ihnCVwPE5ARBDin7tGE2aR+kQoKJyxpF0DZHJsT2V1VdaFoOsXsk2O4rim1YAAIABAACAG/oLgAAAAAADAAAAARcgI96QaBnrHSbOH9tzer5xHVtMsbzh4fPPTyHnttKY+vkBGCAVbTVrthd47iytjVNbPzW50PexUBfrmv0PWXhX4mfhuQAAAQUgWAWKxFtAbv3Fl16WZ669kVHF9qwshO7/YbtVlr1nB6MBBkoBwCIgVCPTDDBosa318t4e3BdC2kVMaL4Tt9ebtKTTLcWoOSqsAcAiIGSjl/ezoaYkgd4EieRdN+h69AxZXtecPEIZlhS13WxcrCEHVCPTDDBosa318t4e3BdC2kVMaL4Tt9ebtKTTLcWoOSo5AQehTj2+yTLCYSuqi1sAacw9645HrMm0vm8JjhZYrB4dO4rim1YAAIABAACAG/oLgAAAAAAEAAAAIQdYBYrEW0Bu/cWXXpZnrr2RUcX2rCyE7v9hu1WWvWcHoxkAXLSSpVYAAIABAACAG/oLgAAAAAAEAAAAIQdko5f3s6GmJIHeBInkXTfoevQMWV7XnDxCGZYUtd1sXDkBWpznxexkx9x3w8Z03kuUS8Ve6LzmrRzSlB1tSACp39p2/byiVgAAgAEAAIAb+guAAAAAAAQAAAAA").unwrap();
+
+        assert!(matches!(
+            psbt.sign_with_descriptor(&seed, descriptor, Network::Bitcoin, &secp),
+            Err(Error::NetworkMismatch {
+                expected: Network::Bitcoin,
+                found: Network::Testnet
+            })
+        ));
+    }
 }
@@ -0,0 +1,220 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Known-answer tests, to check that the build computes the expected values on this platform
+//!
+//! Deterministic and without side effects (no file is read or written).
+
+use core::fmt;
+use core::str::FromStr;
+
+use bdk::bitcoin::secp256k1::{Secp256k1, Signing};
+use bdk::bitcoin::Network;
+
+use crate::bips::bip32::{self, Bip32, DerivationPath, ExtendedPrivKey, ExtendedPubKey};
+use crate::bips::bip39::{self, Mnemonic};
+use crate::bips::bip85::{self, Bip85};
+use crate::crypto::{self, aes, KdfProfile, MultiEncryption};
+use crate::types::{Index, Keychain, Seed};
+use crate::util::hex;
+
+const PASSWORD: &str = "keechain self-test";
+
+#[derive(Debug)]
+pub enum Error {
+    Crypto(crypto::Error),
+    BIP32(bip32::Error),
+    BIP39(bip39::Error),
+    BIP85(bip85::Error),
+    /// Computed value differs from the expected one
+    Mismatch {
+        expected: String,
+        found: String,
+    },
+    /// Payload decrypted with a wrong password
+    WrongPasswordAccepted,
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Crypto(e) => write!(f, "Crypto: {e}"),
+            Self::BIP32(e) => write!(f, "BIP32: {e}"),
+            Self::BIP39(e) => write!(f, "BIP39: {e}"),
+            Self::BIP85(e) => write!(f, "BIP85: {e}"),
+            Self::Mismatch { expected, found } => {
+                write!(f, "Mismatch: expected {expected}, found {found}")
+            }
+            Self::WrongPasswordAccepted => write!(f, "Payload decrypted with a wrong password"),
+        }
+    }
+}
+
+impl From<crypto::Error> for Error {
+    fn from(e: crypto::Error) -> Self {
+        Self::Crypto(e)
+    }
+}
+
+impl From<bip32::Error> for Error {
+    fn from(e: bip32::Error) -> Self {
+        Self::BIP32(e)
+    }
+}
+
+impl From<bip39::Error> for Error {
+    fn from(e: bip39::Error) -> Self {
+        Self::BIP39(e)
+    }
+}
+
+impl From<bip85::Error> for Error {
+    fn from(e: bip85::Error) -> Self {
+        Self::BIP85(e)
+    }
+}
+
+/// Result of a known-answer test
+#[derive(Debug)]
+pub struct Check {
+    pub name: &'static str,
+    pub result: Result<(), Error>,
+}
+
+impl Check {
+    fn new(name: &'static str, result: Result<(), Error>) -> Self {
+        Self { name, result }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+/// Run all the known-answer tests
+pub fn run<C>(secp: &Secp256k1<C>) -> Vec<Check>
+where
+    C: Signing,
+{
+    vec![
+        Check::new("Encryption round-trip", encryption()),
+        Check::new("BIP39 mnemonic -> seed -> fingerprint", bip39_vector(secp)),
+        Check::new("BIP32 derivation", bip32_vector(secp)),
+        Check::new("BIP85 derivation", bip85_vector(secp)),
+    ]
+}
+
+fn check_eq<T>(expected: &str, found: T) -> Result<(), Error>
+where
+    T: ToString,
+{
+    let found: String = found.to_string();
+    if expected == found {
+        Ok(())
+    } else {
+        Err(Error::Mismatch {
+            expected: expected.to_string(),
+            found,
+        })
+    }
+}
+
+/// Encrypt and decrypt a keychain with both the AES modes (fast KDF params)
+fn encryption() -> Result<(), Error> {
+    let mnemonic = Mnemonic::from_entropy(&[0x7f; 16])?;
+    let keychain = Keychain::new(mnemonic, vec![String::from("passphrase")]);
+    for mode in [aes::Mode::Cbc, aes::Mode::Gcm] {
+        let encrypted: String =
+            keychain.encrypt_with_params(PASSWORD, mode, KdfProfile::Fast.params())?;
+        let decrypted = Keychain::decrypt(PASSWORD, encrypted.as_bytes())?;
+        check_eq(&keychain.seed.to_hex(), decrypted.seed.to_hex())?;
+        check_eq(
+            &keychain.passphrases().join(","),
+            decrypted.passphrases().join(","),
+        )?;
+        if Keychain::decrypt("wrong", encrypted.as_bytes()).is_ok() {
+            return Err(Error::WrongPasswordAccepted);
+        }
+    }
+    Ok(())
+}
+
+/// BIP39 test vector (entropy `00..00`)
+fn bip39_vector<C>(secp: &Secp256k1<C>) -> Result<(), Error>
+where
+    C: Signing,
+{
+    let mnemonic = Mnemonic::from_entropy(&[0; 16])?;
+    check_eq(
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+        &mnemonic,
+    )?;
+
+    let seed = Seed::new(mnemonic.clone(), Some("TREZOR"));
+    check_eq(
+        "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+        seed.to_hex(),
+    )?;
+
+    let seed = Seed::from_mnemonic(mnemonic);
+    check_eq("73c5da0a", seed.fingerprint(Network::Bitcoin, secp)?)
+}
+
+/// BIP32 test vector 1 (`m/0'/1/2'/2/1000000000`)
+fn bip32_vector<C>(secp: &Secp256k1<C>) -> Result<(), Error>
+where
+    C: Signing,
+{
+    // 000102030405060708090a0b0c0d0e0f
+    let seed: Vec<u8> = (0..16).collect();
+    let root = ExtendedPrivKey::new_master(Network::Bitcoin, &seed)?;
+    let path = DerivationPath::from_str("m/0'/1/2'/2/1000000000")?;
+    let xprv: ExtendedPrivKey = root.derive_priv(secp, &path)?;
+    check_eq(
+        "xprvA41z7zogVVwxVSgdKUHDy1SKmdb533PjDz7J6N6mV6uS3ze1ai8FHa8kmHScGpWmj4WggLyQjgPie1rFSruoUihUZREPSL39UNdE3BBDu76",
+        xprv,
+    )?;
+    check_eq(
+        "xpub6H1LXWLaKsWFhvm6RVpEL9P4KfRZSW7abD2ttkWP3SSQvnyA8FSVqNTEcYFgJS2UaFcxupHiYkro49S8yGasTvXEYBVPamhGW6cFJodrTHy",
+        ExtendedPubKey::from_priv(secp, &xprv),
+    )
+}
+
+/// BIP85 test vectors (HEX, WIF and XPRV applications)
+fn bip85_vector<C>(secp: &Secp256k1<C>) -> Result<(), Error>
+where
+    C: Signing,
+{
+    let root = ExtendedPrivKey::from_str("xprv9s21ZrQH143K2LBWUUQRFXhucrQqBpKdRRxNVq2zBqsx8HVqFk2uYo8kmbaLLHRdqtQpUm98uKfu3vca1LqdGhUtyoFnCNkfmXRyPXLjbKb")?;
+    let index = Index::default();
+    check_eq(
+        "492db4698cf3b73a5a24998aa3e9d7fa96275d85724a91e71aa2d645442f878555d078fd1f1f67e368976f04137b1f7a0d19232136ca50c44614af72b5582a5c",
+        hex::encode(root.derive_bip85_hex(64, index, secp)?),
+    )?;
+    check_eq(
+        "Kzyv4uF39d4Jrw2W7UryTHwZr1zQVNk4dAFyqE6BuMrMh1Za7uhp",
+        root.derive_bip85_wif(index, Network::Bitcoin, secp)?
+            .to_wif(),
+    )?;
+    check_eq(
+        "xprv9s21ZrQH143K2srSbCSg4m4kLvPMzcWydgmKEnMmoZUurYuBuYG46c6P71UGXMzmriLzCCBvKQWBUv3vPB3m1SATMhp3uEjXHJ42jFg7myX",
+        root.derive_bip85_xprv(index, Network::Bitcoin, secp)?,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_self_test() {
+        let secp = Secp256k1::new();
+        for check in run(&secp).into_iter() {
+            assert!(check.passed(), "{}: {:?}", check.name, check.result);
+        }
+
+        assert!(matches!(check_eq("a", "b"), Err(Error::Mismatch { .. })));
+    }
+}
@@ -4,15 +4,46 @@
 use core::fmt;
 
 use bdk::bitcoin::base58;
-use bdk::bitcoin::bip32::{ChildNumber, DerivationPath, ExtendedPubKey};
+use bdk::bitcoin::bip32::{self, ChildNumber, DerivationPath, ExtendedPrivKey, ExtendedPubKey};
 
+use crate::bips::bip43::Purpose;
 use crate::util::hex;
 
+/// Length of the version prefix of a serialized extended key
+const VERSION_LEN: usize = 4;
+/// `(purpose, xpub mainnet, xpub testnet, xprv mainnet, xprv testnet)` version bytes
+const VERSIONS: [(Purpose, [u8; 4], [u8; 4], [u8; 4], [u8; 4]); 3] = [
+    (
+        Purpose::BIP44,
+        [0x04, 0x88, 0xb2, 0x1e],
+        [0x04, 0x35, 0x87, 0xcf],
+        [0x04, 0x88, 0xad, 0xe4],
+        [0x04, 0x35, 0x83, 0x94],
+    ),
+    (
+        Purpose::BIP49,
+        [0x04, 0x9d, 0x7c, 0xb2],
+        [0x04, 0x4a, 0x52, 0x62],
+        [0x04, 0x9d, 0x78, 0x78],
+        [0x04, 0x4a, 0x4e, 0x28],
+    ),
+    (
+        Purpose::BIP84,
+        [0x04, 0xb2, 0x47, 0x46],
+        [0x04, 0x5f, 0x1c, 0xf6],
+        [0x04, 0xb2, 0x43, 0x0c],
+        [0x04, 0x5f, 0x18, 0xd0],
+    ),
+];
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum Error {
     Base58(base58::Error),
     Hex(hex::Error),
+    BIP32(bip32::Error),
     UnsupportedDerivationPath,
+    /// Unknown SLIP132 version prefix
+    UnknownVersion,
 }
 
 impl std::error::Error for Error {}
@@ -22,7 +53,9 @@ impl fmt::Display for Error {
         match self {
             Self::Base58(e) => write!(f, "Base58: {e}"),
             Self::Hex(e) => write!(f, "Hex: {e}"),
+            Self::BIP32(e) => write!(f, "BIP32: {e}"),
             Self::UnsupportedDerivationPath => write!(f, "Unsupported derivation path"),
+            Self::UnknownVersion => write!(f, "Unknown SLIP132 version"),
         }
     }
 }
@@ -39,6 +72,12 @@ impl From<hex::Error> for Error {
     }
 }
 
+impl From<bip32::Error> for Error {
+    fn from(e: bip32::Error) -> Self {
+        Self::BIP32(e)
+    }
+}
+
 pub trait ToSlip132 {
     type Err;
     fn to_slip132(&self, path: &DerivationPath) -> Result<String, Self::Err>;
@@ -87,6 +126,62 @@ impl ToSlip132 for ExtendedPubKey {
     }
 }
 
+pub trait FromSlip132: Sized {
+    type Err;
+    /// Parse a SLIP132 extended key (i.e. `zpub`), returning also the [`Purpose`] of its version
+    fn from_slip132(s: &str) -> Result<(Self, Purpose), Self::Err>;
+}
+
+impl FromSlip132 for ExtendedPubKey {
+    type Err = Error;
+    fn from_slip132(s: &str) -> Result<(Self, Purpose), Self::Err> {
+        let (data, purpose) = to_bip32_version(s, false)?;
+        Ok((Self::decode(&data)?, purpose))
+    }
+}
+
+impl FromSlip132 for ExtendedPrivKey {
+    type Err = Error;
+    fn from_slip132(s: &str) -> Result<(Self, Purpose), Self::Err> {
+        let (data, purpose) = to_bip32_version(s, true)?;
+        Ok((Self::decode(&data)?, purpose))
+    }
+}
+
+/// Decode `s` replacing the SLIP132 version with the BIP32 one (`xpub`/`tpub` or `xprv`/`tprv`)
+fn to_bip32_version(s: &str, private: bool) -> Result<(Vec<u8>, Purpose), Error> {
+    let mut data: Vec<u8> = base58::decode_check(s)?;
+
+    // (purpose, mainnet, testnet)
+    let versions = VERSIONS.map(
+        |(purpose, xpub_mainnet, xpub_testnet, xprv_mainnet, xprv_testnet)| {
+            if private {
+                (purpose, xprv_mainnet, xprv_testnet)
+            } else {
+                (purpose, xpub_mainnet, xpub_testnet)
+            }
+        },
+    );
+    let (_, bip32_mainnet, bip32_testnet) = versions[0];
+
+    let version: &[u8] = data.get(..VERSION_LEN).ok_or(Error::UnknownVersion)?;
+    let (bip32_version, purpose) = versions
+        .iter()
+        .find_map(|(purpose, mainnet, testnet)| {
+            if version == mainnet {
+                Some((bip32_mainnet, *purpose))
+            } else if version == testnet {
+                Some((bip32_testnet, *purpose))
+            } else {
+                None
+            }
+        })
+        .ok_or(Error::UnknownVersion)?;
+
+    data[..VERSION_LEN].copy_from_slice(&bip32_version);
+    Ok((data, purpose))
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
@@ -130,4 +225,49 @@ mod tests {
             Error::UnsupportedDerivationPath
         );
     }
+
+    #[test]
+    fn test_from_slip132() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("easy uncover favorite crystal bless differ energy seat ecology match carry group refuse together chat observe hidden glad brave month diesel sustain depth salt").unwrap();
+        let seed = Seed::new(mnemonic, Some("mypassphrase"));
+
+        for network in [Network::Bitcoin, Network::Testnet] {
+            let root = ExtendedPrivKey::new_master(network, &seed.to_bytes()).unwrap();
+            let coin: u32 = if network == Network::Bitcoin { 0 } else { 1 };
+            for (purpose, index) in [
+                (Purpose::BIP44, 44),
+                (Purpose::BIP49, 49),
+                (Purpose::BIP84, 84),
+            ] {
+                let path = DerivationPath::from_str(&format!("m/{index}'/{coin}'/0'")).unwrap();
+                let xprv: ExtendedPrivKey = root.derive_priv(&secp, &path).unwrap();
+                let pubkey: ExtendedPubKey = ExtendedPubKey::from_priv(&secp, &xprv);
+                let slip132: String = pubkey.to_slip132(&path).unwrap();
+                assert_eq!(
+                    ExtendedPubKey::from_slip132(&slip132).unwrap(),
+                    (pubkey, purpose)
+                );
+            }
+        }
+
+        // zprv
+        let root = ExtendedPrivKey::new_master(Network::Bitcoin, &seed.to_bytes()).unwrap();
+        let path = DerivationPath::from_str("m/84'/0'/0'").unwrap();
+        let xprv: ExtendedPrivKey = root.derive_priv(&secp, &path).unwrap();
+        let data: Vec<u8> = base58::decode_check(&xprv.to_string()).unwrap();
+        let zprv: String =
+            base58::encode_check(&[&[0x04, 0xb2, 0x43, 0x0c][..], &data[4..]].concat());
+        assert!(zprv.starts_with("zprv"));
+        assert_eq!(
+            ExtendedPrivKey::from_slip132(&zprv).unwrap(),
+            (xprv, Purpose::BIP84)
+        );
+
+        // Private key as public key
+        assert_eq!(
+            ExtendedPubKey::from_slip132(&zprv).unwrap_err(),
+            Error::UnknownVersion
+        );
+    }
 }
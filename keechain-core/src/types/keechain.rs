@@ -4,7 +4,7 @@
 use core::fmt;
 use core::ops::{Deref, DerefMut};
 use std::fs::{self, File};
-use std::io::{Read, Write};
+use std::io::Write;
 use std::path::{Path, PathBuf};
 
 use bdk::bitcoin::hashes::sha256::Hash as Sha256Hash;
@@ -15,20 +15,36 @@ use bdk::bitcoin::{Network, PrivateKey};
 use bdk::miniscript::Descriptor;
 use bdk::signer::SignerWrapper;
 use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
 
 use super::keychain::{self, EncryptedKeychain, Keychain};
 use super::Index;
 use crate::bips::bip32::{self, Bip32, Fingerprint};
-use crate::bips::bip39::{self, Mnemonic};
+use crate::bips::bip39::{self, EntropyMode, Mnemonic};
+use crate::bips::bip43::Purpose;
 use crate::crypto::aes;
-use crate::crypto::{self, hash, MultiEncryption};
+use crate::crypto::{self, hash, KdfParams, MultiEncryption};
 use crate::psbt::{self, PsbtUtility};
 use crate::types::WordCount;
 use crate::util::dir::{self, KEECHAIN_DOT_EXTENSION, KEECHAIN_EXTENSION};
-use crate::util::{self, base64};
+use crate::util::{self, base64, time};
 use crate::{Result, Seed};
 
 const KEECHAIN_FILE_VERSION: u8 = 2;
+const KEECHAIN_TMP_EXTENSION: &str = "tmp";
+
+/// Well-known BIP39 test vector used by the demo mode (**never** use it for real funds)
+pub const DEMO_MNEMONIC: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+/// Password of the demo keechain
+pub const DEMO_PASSWORD: &str = "demo";
+
+pub const MIN_PIN_LEN: usize = 4;
+pub const MAX_PIN_LEN: usize = 12;
+/// Failed PIN attempts allowed before the backoff
+const PIN_FREE_ATTEMPTS: u32 = 3;
+const PIN_BASE_BACKOFF_SECS: u64 = 30;
+const PIN_MAX_BACKOFF_SECS: u64 = 60 * 60;
 
 #[derive(Debug)]
 pub enum Error {
@@ -45,11 +61,42 @@ pub enum Error {
     Generic(String),
     InvalidName,
     FileNotFound,
+    /// Empty or truncated keechain file (i.e. interrupted write)
+    EmptyOrCorruptFile,
     FileAlreadyExists,
     InvalidPassword,
     PasswordNotMatch,
     CurrentPasswordNotMatch,
     UnknownVersion(u8),
+    /// Read-only keechain (i.e. demo)
+    ReadOnly,
+    /// Demo mode is not allowed on mainnet
+    DemoOnMainnet,
+    /// Re-encrypted keychain doesn't match the master fingerprint
+    FingerprintMismatch,
+    /// No keychain with the master fingerprint
+    FingerprintNotFound(Fingerprint),
+    /// More keychains with the same master fingerprint
+    AmbiguousFingerprint {
+        fingerprint: Fingerprint,
+        names: Vec<String>,
+    },
+    /// Not enough custom entropy for the deterministic mode
+    NotEnoughEntropy {
+        required: usize,
+        provided: usize,
+    },
+    /// PIN must be [`MIN_PIN_LEN`] to [`MAX_PIN_LEN`] digits
+    InvalidPin,
+    PinNotMatch,
+    PinAlreadyEnabled,
+    PinNotEnabled,
+    /// Too many failed PIN attempts: wait before retrying
+    PinLocked {
+        retry_after: u64,
+    },
+    /// Too many failed PIN attempts: the keychain file has been wiped
+    PinWiped,
 }
 
 impl std::error::Error for Error {}
@@ -70,6 +117,10 @@ impl fmt::Display for Error {
             Self::Generic(e) => write!(f, "Generic: {e}"),
             Self::InvalidName => write!(f, "Invalid name"),
             Self::FileNotFound => write!(f, "File not found"),
+            Self::EmptyOrCorruptFile => write!(
+                f,
+                "The keychain file is empty or corrupted: restore the keychain from your backup (i.e. mnemonic)"
+            ),
             Self::FileAlreadyExists => write!(
                 f,
                 "There is already a file with the same name! Please, choose another name"
@@ -78,6 +129,39 @@ impl fmt::Display for Error {
             Self::PasswordNotMatch => write!(f, "Password not match"),
             Self::CurrentPasswordNotMatch => write!(f, "Current password not match"),
             Self::UnknownVersion(v) => write!(f, "Unknown keechain file version: {v}"),
+            Self::ReadOnly => write!(f, "Read-only keechain: changes can't be saved"),
+            Self::DemoOnMainnet => write!(f, "Demo mode is not allowed on mainnet"),
+            Self::FingerprintMismatch => write!(
+                f,
+                "Re-encrypted keychain doesn't match the master fingerprint: nothing has been saved"
+            ),
+            Self::FingerprintNotFound(fingerprint) => {
+                write!(f, "No keychain found with fingerprint {fingerprint}")
+            }
+            Self::AmbiguousFingerprint { fingerprint, names } => write!(
+                f,
+                "More keychains with fingerprint {fingerprint}: {}",
+                names.join(", ")
+            ),
+            Self::NotEnoughEntropy { required, provided } => write!(
+                f,
+                "Not enough entropy: at least {required} dice rolls are required, {provided} provided"
+            ),
+            Self::InvalidPin => write!(
+                f,
+                "Invalid PIN: must be {MIN_PIN_LEN} to {MAX_PIN_LEN} digits"
+            ),
+            Self::PinNotMatch => write!(f, "PIN not match"),
+            Self::PinAlreadyEnabled => write!(f, "PIN unlock already enabled"),
+            Self::PinNotEnabled => write!(f, "PIN unlock not enabled"),
+            Self::PinLocked { retry_after } => write!(
+                f,
+                "Too many failed PIN attempts: retry in {retry_after} seconds"
+            ),
+            Self::PinWiped => write!(
+                f,
+                "Too many failed PIN attempts: the keychain has been wiped"
+            ),
         }
     }
 }
@@ -142,17 +226,191 @@ impl From<psbt::Error> for Error {
     }
 }
 
+/// Trim the surrounding whitespaces of a keychain name (empty or whitespace-only names are rejected)
+fn normalize_name<S>(name: S) -> Result<String, Error>
+where
+    S: Into<String>,
+{
+    let name: String = name.into();
+    let name: &str = name.trim();
+    if name.is_empty() {
+        return Err(Error::InvalidName);
+    }
+    Ok(name.to_string())
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum EncryptionKeyType {
     Password,
+    /// Numeric PIN (with stronger KDF params, see [`KdfParams::pin`])
+    ///
+    /// Weaker than a password: use it only for quick unlocks of lower-value keychains.
+    Pin,
     // GPG { key_id: String },
 }
 
+impl EncryptionKeyType {
+    /// KDF params used to encrypt the keychain
+    pub fn kdf_params(&self) -> KdfParams {
+        match self {
+            Self::Password => KdfParams::default(),
+            Self::Pin => KdfParams::pin(),
+        }
+    }
+}
+
+/// PIN settings and failed attempts (stored **not** encrypted in the file header)
+///
+/// The counter slows down guessing on this device, not with a copy of the file:
+/// offline, only the KDF cost protects the PIN.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct PinState {
+    pub failed_attempts: u32,
+    /// Timestamp of the last failed attempt
+    pub last_failure: Option<u64>,
+    /// Wipe the keychain file after this number of failed attempts
+    pub wipe_after: Option<u32>,
+}
+
+impl PinState {
+    fn new(wipe_after: Option<u32>) -> Self {
+        Self {
+            wipe_after,
+            ..Default::default()
+        }
+    }
+
+    /// Seconds to wait after the last failed attempt (doubled at every failure, after [`PIN_FREE_ATTEMPTS`])
+    pub fn backoff(&self) -> u64 {
+        if self.failed_attempts < PIN_FREE_ATTEMPTS {
+            return 0;
+        }
+        let exp: u32 = (self.failed_attempts - PIN_FREE_ATTEMPTS).min(16);
+        (PIN_BASE_BACKOFF_SECS << exp).min(PIN_MAX_BACKOFF_SECS)
+    }
+
+    /// Seconds left before the next attempt is allowed
+    pub fn retry_after(&self, now: u64) -> u64 {
+        match self.last_failure {
+            Some(last_failure) => last_failure
+                .saturating_add(self.backoff())
+                .saturating_sub(now),
+            None => 0,
+        }
+    }
+
+    /// Attempts left before the keychain is wiped
+    pub fn attempts_left(&self) -> Option<u32> {
+        self.wipe_after
+            .map(|wipe_after| wipe_after.saturating_sub(self.failed_attempts))
+    }
+}
+
+/// Check that the PIN has only [`MIN_PIN_LEN`] to [`MAX_PIN_LEN`] digits
+pub fn validate_pin<S>(pin: S) -> Result<(), Error>
+where
+    S: AsRef<str>,
+{
+    let pin: &str = pin.as_ref();
+    if (MIN_PIN_LEN..=MAX_PIN_LEN).contains(&pin.len()) && pin.bytes().all(|b| b.is_ascii_digit()) {
+        Ok(())
+    } else {
+        Err(Error::InvalidPin)
+    }
+}
+
+/// Per-keychain defaults, used when the account or the script are not specified
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct KeychainDefaults {
+    pub account: Option<u32>,
+    pub purpose: Option<Purpose>,
+}
+
+impl MultiEncryption for KeychainDefaults {}
+
 #[derive(Serialize, Deserialize)]
 struct KeeChainRaw {
     version: u8,
     encryption_key_type: EncryptionKeyType,
     keychain: String,
+    /// Master fingerprint (not encrypted), to find the keychain without the password
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fingerprint: Option<Fingerprint>,
+    /// Encrypted [`KeychainDefaults`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    defaults: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pin: Option<PinState>,
+}
+
+impl KeeChainRaw {
+    /// Refuse PIN attempts during the backoff
+    fn ensure_pin_unlocked(&self) -> Result<(), Error> {
+        if let Some(pin) = &self.pin {
+            let retry_after: u64 = pin.retry_after(time::timestamp());
+            if retry_after > 0 {
+                return Err(Error::PinLocked { retry_after });
+            }
+        }
+        Ok(())
+    }
+
+    /// Decrypt the keychain (and check if it has the legacy format)
+    ///
+    /// If unlocked with a PIN, the failed attempt is written to `path` (or the file is wiped, see [`PinState`]).
+    fn decrypt(&mut self, path: &Path, password: &str) -> Result<(Keychain, bool), Error> {
+        // Check keechain file version
+        match self.version {
+            1 => {
+                let content: Vec<u8> = base64::decode(self.keychain.as_bytes())?;
+                let key: Zeroizing<[u8; 32]> =
+                    Zeroizing::new(hash::sha256(password).to_byte_array());
+                let data: Zeroizing<Vec<u8>> = Zeroizing::new(aes::decrypt(*key, content)?);
+                Ok((serde_json::from_slice(&data)?, true))
+            }
+            2 => match Keychain::decrypt_and_check_legacy(password, self.keychain.as_bytes()) {
+                Ok(decrypted) => Ok(decrypted),
+                Err(e) => {
+                    if let Some(pin) = self.pin.as_mut() {
+                        pin.failed_attempts = pin.failed_attempts.saturating_add(1);
+                        pin.last_failure = Some(time::timestamp());
+                        if pin.attempts_left() == Some(0) {
+                            wipe_file(path)?;
+                            return Err(Error::PinWiped);
+                        }
+                        self.write_atomically(path)?;
+                    }
+                    Err(e.into())
+                }
+            },
+            v => Err(Error::UnknownVersion(v)),
+        }
+    }
+
+    /// Write to a temporary file and then rename it, so the previous file is never left half-written
+    fn write_atomically(&self, path: &Path) -> Result<(), Error> {
+        let data: Vec<u8> = util::serde::serialize(self)?;
+
+        let mut tmp: PathBuf = path.to_path_buf();
+        tmp.set_extension(format!("{KEECHAIN_EXTENSION}.{KEECHAIN_TMP_EXTENSION}"));
+        if let Err(e) = write_and_rename(&tmp, path, &data) {
+            // Never leave a partial file behind
+            let _ = fs::remove_file(tmp.as_path());
+            return Err(e.into());
+        }
+        Ok(())
+    }
+}
+
+fn write_and_rename(tmp: &Path, path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let mut file: File = File::options()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(tmp)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    fs::rename(tmp, path)
 }
 
 #[derive(Clone)]
@@ -162,7 +420,12 @@ pub struct KeeChain {
     version: u8,
     encryption_key_type: EncryptionKeyType,
     encrypted_keychain: EncryptedKeychain,
+    defaults: Option<String>,
+    pin: Option<PinState>,
     network: Network,
+    read_only: bool,
+    /// The file on disk uses a legacy encryption format
+    legacy_format: bool,
 }
 
 impl fmt::Debug for KeeChain {
@@ -199,7 +462,36 @@ impl KeeChain {
         S: Into<String>,
         C: Signing,
     {
-        let password: String = password.into();
+        let params: KdfParams = encryption_key_type.kdf_params();
+        Self::new_with_kdf_params(
+            file,
+            password,
+            version,
+            encryption_key_type,
+            keychain,
+            params,
+            network,
+            secp,
+        )
+    }
+
+    /// Same as [`KeeChain::new`], but encrypt the keychain with custom KDF params
+    pub fn new_with_kdf_params<P, S, C>(
+        file: P,
+        password: S,
+        version: u8,
+        encryption_key_type: EncryptionKeyType,
+        keychain: Keychain,
+        params: KdfParams,
+        network: Network,
+        secp: &Secp256k1<C>,
+    ) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+        S: Into<String>,
+        C: Signing,
+    {
+        let password: Zeroizing<String> = Zeroizing::new(password.into());
         Ok(Self {
             file: file.as_ref().to_path_buf(),
             password_hash: Sha256Hash::hash(password.as_bytes()),
@@ -207,13 +499,42 @@ impl KeeChain {
             encryption_key_type,
             encrypted_keychain: EncryptedKeychain::new(
                 keychain.seed.to_bip32_root_pubkey(network, secp)?,
-                keychain.encrypt(&password)?,
+                keychain.encrypt_with_params(password.as_bytes(), aes::Mode::default(), params)?,
                 network,
             ),
+            defaults: None,
+            pin: None,
             network,
+            read_only: false,
+            legacy_format: false,
         })
     }
 
+    /// In-memory, read-only keechain with a well-known test mnemonic ([`DEMO_MNEMONIC`])
+    ///
+    /// Useful for demos and screenshots: refuse to run on mainnet and never write a file.
+    pub fn demo<C>(network: Network, secp: &Secp256k1<C>) -> Result<Self, Error>
+    where
+        C: Signing,
+    {
+        if network == Network::Bitcoin {
+            return Err(Error::DemoOnMainnet);
+        }
+
+        let mnemonic: Mnemonic = Mnemonic::parse_normalized(DEMO_MNEMONIC)?;
+        let mut keechain = Self::new(
+            format!("demo{KEECHAIN_DOT_EXTENSION}"),
+            DEMO_PASSWORD,
+            KEECHAIN_FILE_VERSION,
+            EncryptionKeyType::Password,
+            Keychain::new(mnemonic, Vec::new()),
+            network,
+            secp,
+        )?;
+        keechain.read_only = true;
+        Ok(keechain)
+    }
+
     pub fn open<P, S, PSW, C>(
         base_path: P,
         name: S,
@@ -227,55 +548,155 @@ impl KeeChain {
         PSW: FnOnce() -> Result<String>,
         C: Signing,
     {
-        let name: String = name.into();
-        if name.is_empty() {
-            return Err(Error::InvalidName);
-        }
+        let name: String = normalize_name(name)?;
 
         let keychain_file: PathBuf = dir::get_keychain_file(base_path, name)?;
         if !keychain_file.exists() {
             return Err(Error::FileNotFound);
         }
 
-        let mut file: File = File::open(keychain_file.as_path())?;
-        let mut content: Vec<u8> = Vec::new();
-        file.read_to_end(&mut content)?;
-
-        let password: String = get_password().map_err(|e| Error::Generic(e.to_string()))?;
-
-        let keechain_raw_file: KeeChainRaw = util::serde::deserialize(content)?;
-        let keychain_encrypted: String = keechain_raw_file.keychain;
+        let mut keechain_raw_file: KeeChainRaw = read_raw_file(&keychain_file)?;
+        keechain_raw_file.ensure_pin_unlocked()?;
 
-        // Check keechain file version
-        let keychain: Keychain = match keechain_raw_file.version {
-            1 => {
-                let content: Vec<u8> = base64::decode(keychain_encrypted.as_bytes())?;
-                let key: [u8; 32] = hash::sha256(&password).to_byte_array();
-                let data: Vec<u8> = aes::decrypt(key, content)?;
-                util::serde::deserialize(data)?
-            }
-            2 => Keychain::decrypt(&password, keychain_encrypted.as_bytes())?,
-            v => return Err(Error::UnknownVersion(v)),
-        };
+        let password: Zeroizing<String> =
+            Zeroizing::new(get_password().map_err(|e| Error::Generic(e.to_string()))?);
+        let (keychain, legacy_format): (Keychain, bool) =
+            keechain_raw_file.decrypt(&keychain_file, &password)?;
 
-        let keechain = Self::new(
+        // Keep the KDF params of the file (i.e. the profile chosen at creation)
+        let params: KdfParams = crypto::kdf_params(keechain_raw_file.keychain.as_bytes())
+            .unwrap_or_else(|| keechain_raw_file.encryption_key_type.kdf_params());
+        let mut keechain = Self::new_with_kdf_params(
             keychain_file,
-            &password,
+            password.as_str(),
             KEECHAIN_FILE_VERSION,
             keechain_raw_file.encryption_key_type,
             keychain,
+            params,
             network,
             secp,
         )?;
 
+        keechain.defaults = keechain_raw_file.defaults;
+        keechain.pin = keechain_raw_file
+            .pin
+            .map(|pin| PinState::new(pin.wipe_after));
+
+        // Reset the failed PIN attempts
+        if let Some(pin) = keechain_raw_file.pin {
+            if pin.failed_attempts > 0 {
+                keechain.save()?;
+            }
+        }
+
         // Migrate
         if keechain_raw_file.version < KEECHAIN_FILE_VERSION {
             keechain.save()?;
+        } else {
+            keechain.legacy_format = legacy_format;
+            // Add the fingerprint to the header (legacy files get it when re-encrypted)
+            if keechain_raw_file.fingerprint.is_none() && !legacy_format {
+                keechain.save()?;
+            }
         }
 
         Ok(keechain)
     }
 
+    /// Master fingerprint in the header of the keechain file (the file is not decrypted)
+    ///
+    /// `None` if the file was saved by an older version and not opened since.
+    pub fn fingerprint_in_header<P, S>(base_path: P, name: S) -> Result<Option<Fingerprint>, Error>
+    where
+        P: AsRef<Path>,
+        S: Into<String>,
+    {
+        let keychain_file: PathBuf = dir::get_keychain_file(base_path, normalize_name(name)?)?;
+        if !keychain_file.exists() {
+            return Err(Error::FileNotFound);
+        }
+        let keechain_raw_file: KeeChainRaw = read_raw_file(&keychain_file)?;
+        Ok(keechain_raw_file.fingerprint)
+    }
+
+    /// PIN settings and failed attempts of the keechain file (`None` if unlocked with a password)
+    pub fn pin_state<P, S>(base_path: P, name: S) -> Result<Option<PinState>, Error>
+    where
+        P: AsRef<Path>,
+        S: Into<String>,
+    {
+        let keychain_file: PathBuf = dir::get_keychain_file(base_path, normalize_name(name)?)?;
+        if !keychain_file.exists() {
+            return Err(Error::FileNotFound);
+        }
+        let keechain_raw_file: KeeChainRaw = read_raw_file(&keychain_file)?;
+        Ok(keechain_raw_file.pin)
+    }
+
+    /// Check the password without opening the keechain
+    ///
+    /// The file is not rewritten (no migration or re-encryption), except to update the
+    /// failed attempts of keychains unlocked with a PIN.
+    pub fn verify_password<P, S, PSW>(
+        base_path: P,
+        name: S,
+        get_password: PSW,
+    ) -> Result<bool, Error>
+    where
+        P: AsRef<Path>,
+        S: Into<String>,
+        PSW: FnOnce() -> Result<String>,
+    {
+        let keychain_file: PathBuf = dir::get_keychain_file(base_path, normalize_name(name)?)?;
+        if !keychain_file.exists() {
+            return Err(Error::FileNotFound);
+        }
+        let mut keechain_raw_file: KeeChainRaw = read_raw_file(&keychain_file)?;
+        keechain_raw_file.ensure_pin_unlocked()?;
+
+        let password: Zeroizing<String> =
+            Zeroizing::new(get_password().map_err(|e| Error::Generic(e.to_string()))?);
+        match keechain_raw_file.decrypt(&keychain_file, &password) {
+            Ok(_) => {
+                // Reset the failed PIN attempts
+                if let Some(pin) = keechain_raw_file.pin.as_mut() {
+                    if pin.failed_attempts > 0 {
+                        *pin = PinState::new(pin.wipe_after);
+                        keechain_raw_file.write_atomically(&keychain_file)?;
+                    }
+                }
+                Ok(true)
+            }
+            Err(Error::Crypto(..) | Error::Aes(..) | Error::Base64(..) | Error::Json(..)) => {
+                Ok(false)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Find the name of the keychain with the master `fingerprint` (see [`KeeChain::fingerprint_in_header`])
+    ///
+    /// The files with an unreadable header are skipped.
+    pub fn find_by_fingerprint<P>(base_path: P, fingerprint: Fingerprint) -> Result<String, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let base_path: &Path = base_path.as_ref();
+        let mut names: Vec<String> = Vec::new();
+        for name in dir::get_keychains_list(base_path)?.into_iter() {
+            // Unreadable (i.e. empty or corrupt) files are skipped
+            if Self::fingerprint_in_header(base_path, &name).ok().flatten() == Some(fingerprint) {
+                names.push(name);
+            }
+        }
+
+        match names.len() {
+            0 => Err(Error::FingerprintNotFound(fingerprint)),
+            1 => Ok(names.remove(0)),
+            _ => Err(Error::AmbiguousFingerprint { fingerprint, names }),
+        }
+    }
+
     pub fn generate<P, S, PSW, CPSW, E, C>(
         base_path: P,
         name: S,
@@ -283,6 +704,7 @@ impl KeeChain {
         get_confirm_password: CPSW,
         word_count: WordCount,
         get_custom_entropy: E,
+        entropy_mode: EntropyMode,
         network: Network,
         secp: &Secp256k1<C>,
     ) -> Result<Self, Error>
@@ -294,10 +716,42 @@ impl KeeChain {
         E: FnOnce() -> Result<Option<Vec<u8>>>,
         C: Signing,
     {
-        let name: String = name.into();
-        if name.is_empty() {
-            return Err(Error::InvalidName);
-        }
+        Self::generate_with_kdf_params(
+            base_path,
+            name,
+            get_password,
+            get_confirm_password,
+            word_count,
+            get_custom_entropy,
+            entropy_mode,
+            KdfParams::default(),
+            network,
+            secp,
+        )
+    }
+
+    /// Same as [`KeeChain::generate`], but encrypt the keychain with custom KDF params (see [`KdfProfile`](crate::crypto::KdfProfile))
+    pub fn generate_with_kdf_params<P, S, PSW, CPSW, E, C>(
+        base_path: P,
+        name: S,
+        get_password: PSW,
+        get_confirm_password: CPSW,
+        word_count: WordCount,
+        get_custom_entropy: E,
+        entropy_mode: EntropyMode,
+        kdf_params: KdfParams,
+        network: Network,
+        secp: &Secp256k1<C>,
+    ) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+        S: Into<String>,
+        PSW: FnOnce() -> Result<String>,
+        CPSW: FnOnce() -> Result<String>,
+        E: FnOnce() -> Result<Option<Vec<u8>>>,
+        C: Signing,
+    {
+        let name: String = normalize_name(name)?;
 
         let keychain_file: PathBuf = dir::get_keychain_file(base_path, name)?;
         if keychain_file.exists() {
@@ -321,16 +775,31 @@ impl KeeChain {
 
         let custom_entropy: Option<Vec<u8>> =
             get_custom_entropy().map_err(|e| Error::Generic(e.to_string()))?;
-        let entropy: Vec<u8> = bip39::entropy(word_count, custom_entropy);
-        let mnemonic = Mnemonic::from_entropy(&entropy)?;
+        if entropy_mode.requires_custom_entropy() {
+            let required: usize = bip39::min_dice_rolls(word_count);
+            let provided: usize = custom_entropy.as_ref().map(|c| c.len()).unwrap_or_default();
+            if provided < required {
+                return Err(Error::NotEnoughEntropy { required, provided });
+            }
+        }
+        let entropy: Vec<u8> = match entropy_mode {
+            EntropyMode::Mixed | EntropyMode::RequireCustom => {
+                bip39::entropy(word_count, custom_entropy)
+            }
+            EntropyMode::Deterministic => {
+                bip39::deterministic_entropy(word_count, &custom_entropy.unwrap_or_default())
+            }
+        };
+        let mnemonic: Mnemonic = bip39::mnemonic_from_bytes(entropy)?;
         let keychain = Keychain::new(mnemonic, Vec::new());
 
-        let keechain = Self::new(
+        let keechain = Self::new_with_kdf_params(
             keychain_file,
             &password,
             KEECHAIN_FILE_VERSION,
             EncryptionKeyType::Password,
             keychain,
+            kdf_params,
             network,
             secp,
         )?;
@@ -357,10 +826,38 @@ impl KeeChain {
         M: FnOnce() -> Result<Mnemonic>,
         C: Signing,
     {
-        let name: String = name.into();
-        if name.is_empty() {
-            return Err(Error::InvalidName);
-        }
+        Self::restore_with_kdf_params(
+            base_path,
+            name,
+            get_password,
+            get_confirm_password,
+            get_mnemonic,
+            KdfParams::default(),
+            network,
+            secp,
+        )
+    }
+
+    /// Same as [`KeeChain::restore`], but encrypt the keychain with custom KDF params (see [`KdfProfile`](crate::crypto::KdfProfile))
+    pub fn restore_with_kdf_params<P, S, PSW, CPSW, M, C>(
+        base_path: P,
+        name: S,
+        get_password: PSW,
+        get_confirm_password: CPSW,
+        get_mnemonic: M,
+        kdf_params: KdfParams,
+        network: Network,
+        secp: &Secp256k1<C>,
+    ) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+        PSW: FnOnce() -> Result<String>,
+        CPSW: FnOnce() -> Result<String>,
+        S: Into<String>,
+        M: FnOnce() -> Result<Mnemonic>,
+        C: Signing,
+    {
+        let name: String = normalize_name(name)?;
 
         let keychain_file: PathBuf = dir::get_keychain_file(base_path, name)?;
         if keychain_file.exists() {
@@ -385,12 +882,13 @@ impl KeeChain {
         let mnemonic: Mnemonic = get_mnemonic().map_err(|e| Error::Generic(e.to_string()))?;
         let keychain = Keychain::new(mnemonic, Vec::new());
 
-        let keechain = Self::new(
+        let keechain = Self::new_with_kdf_params(
             keychain_file,
             &password,
             KEECHAIN_FILE_VERSION,
             EncryptionKeyType::Password,
             keychain,
+            kdf_params,
             network,
             secp,
         )?;
@@ -451,6 +949,7 @@ impl KeeChain {
         T: AsRef<[u8]>,
         S: Into<String>,
     {
+        self.ensure_writable()?;
         self.encrypted_keychain
             .add_passphrase(password, passphrase)?;
         self.save()?;
@@ -462,6 +961,7 @@ impl KeeChain {
         T: AsRef<[u8]>,
         S: Into<String>,
     {
+        self.ensure_writable()?;
         self.encrypted_keychain
             .remove_passphrase(password, passphrase)?;
         self.save()?;
@@ -510,28 +1010,103 @@ impl KeeChain {
         self.network
     }
 
+    /// Default account and script of this keychain
+    pub fn defaults<T>(&self, password: T) -> Result<KeychainDefaults, Error>
+    where
+        T: AsRef<[u8]>,
+    {
+        if !self.check_password(&password) {
+            return Err(Error::InvalidPassword);
+        }
+
+        match &self.defaults {
+            Some(defaults) => Ok(KeychainDefaults::decrypt(password, defaults.as_bytes())?),
+            None => Ok(KeychainDefaults::default()),
+        }
+    }
+
+    /// Set default account and script of this keychain
+    ///
+    /// Call [`KeeChain::save`] to persist them.
+    pub fn set_defaults<T>(&mut self, password: T, defaults: KeychainDefaults) -> Result<(), Error>
+    where
+        T: AsRef<[u8]>,
+    {
+        self.ensure_writable()?;
+
+        if !self.check_password(&password) {
+            return Err(Error::InvalidPassword);
+        }
+
+        if let Some(account) = defaults.account {
+            Index::new(account).map_err(|_| keychain::Error::InvalidAccount(account))?;
+        }
+
+        self.defaults = if defaults == KeychainDefaults::default() {
+            None
+        } else {
+            Some(defaults.encrypt(password)?)
+        };
+
+        Ok(())
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    fn ensure_writable(&self) -> Result<(), Error> {
+        if self.read_only {
+            Err(Error::ReadOnly)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Check if the file on disk uses a legacy encryption format
+    pub fn has_legacy_format(&self) -> bool {
+        self.legacy_format
+    }
+
+    /// Rewrite the file with the current encryption format, if it uses a legacy one
+    ///
+    /// Return `true` if the file has been rewritten.
+    pub fn reencrypt_if_legacy(&mut self) -> Result<bool, Error> {
+        if !self.legacy_format {
+            return Ok(false);
+        }
+        self.save()?;
+        self.legacy_format = false;
+        Ok(true)
+    }
+
+    /// Save the keechain file
+    ///
+    /// The content is written to a temporary file and then renamed, so the previous file is never left half-written.
     pub fn save(&self) -> Result<(), Error> {
+        self.ensure_writable()?;
         let raw = KeeChainRaw {
             version: self.version,
             encryption_key_type: self.encryption_key_type.clone(),
             keychain: self.encrypted_keychain.raw(),
+            fingerprint: Some(
+                self.encrypted_keychain
+                    .master_bip32_root_pubkey
+                    .fingerprint(),
+            ),
+            defaults: self.defaults.clone(),
+            pin: self.pin,
         };
-        let data: Vec<u8> = util::serde::serialize(raw)?;
-        let mut file: File = File::options()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(self.file.as_path())?;
-        file.write_all(&data)?;
-        Ok(())
+        raw.write_atomically(&self.file)
     }
 
+    /// Check the password (constant-time comparison of the hashes)
     pub fn check_password<T>(&self, password: T) -> bool
     where
         T: AsRef<[u8]>,
     {
         let password: &[u8] = password.as_ref();
-        self.password_hash == Sha256Hash::hash(password)
+        crypto::constant_time_eq(self.password_hash, Sha256Hash::hash(password))
     }
 
     pub fn sign_psbt<T, C>(
@@ -554,8 +1129,10 @@ impl KeeChain {
     where
         S: Into<String>,
     {
+        let new_name: String = normalize_name(new_name)?;
+        self.ensure_writable()?;
         let mut new: PathBuf = self.file.clone();
-        new.set_file_name(new_name.into());
+        new.set_file_name(new_name);
         new.set_extension(KEECHAIN_EXTENSION);
         if new.exists() {
             Err(Error::FileAlreadyExists)
@@ -566,23 +1143,56 @@ impl KeeChain {
         }
     }
 
-    pub fn change_password<PSW, NPSW, NCPSW>(
+    /// Change the password
+    ///
+    /// Before saving, the re-encrypted keychain is decrypted with the new password and checked against
+    /// the master fingerprint. Return the (unchanged) master fingerprint.
+    pub fn change_password<PSW, NPSW, NCPSW, C>(
         &mut self,
         get_old_password: PSW,
         get_new_password: NPSW,
         get_new_confirm_password: NCPSW,
-    ) -> Result<(), Error>
+        secp: &Secp256k1<C>,
+    ) -> Result<Fingerprint, Error>
     where
         PSW: FnOnce() -> Result<String>,
         NPSW: FnOnce() -> Result<String>,
         NCPSW: FnOnce() -> Result<String>,
+        C: Signing,
     {
+        self.change_password_with_kdf_params(
+            get_old_password,
+            get_new_password,
+            get_new_confirm_password,
+            None,
+            secp,
+        )
+    }
+
+    /// Same as [`KeeChain::change_password`], but re-encrypt with new KDF params (`None` to keep the current ones)
+    ///
+    /// The keychain is re-encrypted also if the password is unchanged but the KDF params differ.
+    pub fn change_password_with_kdf_params<PSW, NPSW, NCPSW, C>(
+        &mut self,
+        get_old_password: PSW,
+        get_new_password: NPSW,
+        get_new_confirm_password: NCPSW,
+        kdf_params: Option<KdfParams>,
+        secp: &Secp256k1<C>,
+    ) -> Result<Fingerprint, Error>
+    where
+        PSW: FnOnce() -> Result<String>,
+        NPSW: FnOnce() -> Result<String>,
+        NCPSW: FnOnce() -> Result<String>,
+        C: Signing,
+    {
+        self.ensure_writable()?;
         let old_password: String = get_old_password().map_err(|e| Error::Generic(e.to_string()))?;
         let new_password: String = get_new_password().map_err(|e| Error::Generic(e.to_string()))?;
         let new_confirm_password: String =
             get_new_confirm_password().map_err(|e| Error::Generic(e.to_string()))?;
 
-        if !self.check_password(old_password) {
+        if !self.check_password(&old_password) {
             return Err(Error::CurrentPasswordNotMatch);
         }
 
@@ -590,28 +1200,926 @@ impl KeeChain {
             return Err(Error::InvalidPassword);
         }
 
+        // PIN-protected: the new password is the new PIN
+        if self.is_pin_enabled() {
+            validate_pin(&new_password)?;
+        }
+
         if new_password != new_confirm_password {
             return Err(Error::PasswordNotMatch);
         }
 
-        let new_password_hash = Sha256Hash::hash(new_password.as_bytes());
+        let current_params: KdfParams = self.encrypted_keychain.kdf_params();
+        let params: KdfParams = kdf_params.unwrap_or(current_params);
+        if self.password_hash != Sha256Hash::hash(new_password.as_bytes())
+            || params != current_params
+        {
+            self.reencrypt(&old_password, &new_password, params, secp)?;
+            self.save()?;
+            self.legacy_format = false;
+        }
 
-        if self.password_hash != new_password_hash {
-            // Set password
-            self.password_hash = new_password_hash;
+        Ok(self
+            .encrypted_keychain
+            .master_bip32_root_pubkey
+            .fingerprint())
+    }
 
-            // Re-save the file
-            self.save()?;
+    /// Re-encrypt keychain and defaults with a new password (not saved)
+    ///
+    /// The re-encrypted keychain is decrypted with the new password and checked against the master fingerprint.
+    fn reencrypt<C>(
+        &mut self,
+        old_password: &str,
+        new_password: &str,
+        params: KdfParams,
+        secp: &Secp256k1<C>,
+    ) -> Result<(), Error>
+    where
+        C: Signing,
+    {
+        let defaults: Option<String> = match &self.defaults {
+            Some(defaults) => Some(
+                KeychainDefaults::decrypt(old_password, defaults.as_bytes())?
+                    .encrypt(new_password)?,
+            ),
+            None => None,
+        };
+        let mut encrypted_keychain: EncryptedKeychain = self.encrypted_keychain.clone();
+        encrypted_keychain.change_password(old_password, new_password, params)?;
+
+        // Check that the new ciphertext decrypts to the same seed
+        let master_fingerprint: Fingerprint = self
+            .encrypted_keychain
+            .master_bip32_root_pubkey
+            .fingerprint();
+        let seed = Seed::from_mnemonic(encrypted_keychain.keychain(new_password)?.mnemonic());
+        if seed.fingerprint(self.encrypted_keychain.network(), secp)? != master_fingerprint {
+            return Err(Error::FingerprintMismatch);
         }
 
+        self.encrypted_keychain = encrypted_keychain;
+        self.defaults = defaults;
+        self.password_hash = Sha256Hash::hash(new_password.as_bytes());
         Ok(())
     }
 
-    pub fn wipe(&self) -> Result<(), Error> {
-        let path = self.file.as_path();
-        let mut file: File = File::options().write(true).truncate(true).open(path)?;
-        file.write_all(&[0u8; 21])?;
-        std::fs::remove_file(path)?;
+    /// Check if the keechain is unlocked with a numeric PIN instead of a password
+    pub fn is_pin_enabled(&self) -> bool {
+        self.encryption_key_type == EncryptionKeyType::Pin
+    }
+
+    /// Unlock with a numeric PIN instead of the password (see [`EncryptionKeyType::Pin`])
+    ///
+    /// The keychain is re-encrypted with the PIN, using stronger KDF params. If `wipe_after` is set,
+    /// the keychain file is wiped after that number of consecutive failed attempts.
+    pub fn enable_pin<PSW, PIN, CPIN, C>(
+        &mut self,
+        get_password: PSW,
+        get_pin: PIN,
+        get_confirm_pin: CPIN,
+        wipe_after: Option<u32>,
+        secp: &Secp256k1<C>,
+    ) -> Result<(), Error>
+    where
+        PSW: FnOnce() -> Result<String>,
+        PIN: FnOnce() -> Result<String>,
+        CPIN: FnOnce() -> Result<String>,
+        C: Signing,
+    {
+        self.ensure_writable()?;
+        if self.is_pin_enabled() {
+            return Err(Error::PinAlreadyEnabled);
+        }
+
+        let password: String = get_password().map_err(|e| Error::Generic(e.to_string()))?;
+        if !self.check_password(&password) {
+            return Err(Error::CurrentPasswordNotMatch);
+        }
+
+        let pin: String = get_pin().map_err(|e| Error::Generic(e.to_string()))?;
+        validate_pin(&pin)?;
+        let confirm_pin: String = get_confirm_pin().map_err(|e| Error::Generic(e.to_string()))?;
+        if pin != confirm_pin {
+            return Err(Error::PinNotMatch);
+        }
+
+        let encryption_key_type = EncryptionKeyType::Pin;
+        self.reencrypt(&password, &pin, encryption_key_type.kdf_params(), secp)?;
+        self.encryption_key_type = encryption_key_type;
+        self.pin = Some(PinState::new(wipe_after.filter(|n| *n > 0)));
+        self.save()?;
+        self.legacy_format = false;
         Ok(())
     }
+
+    /// Go back to unlock with a password
+    pub fn disable_pin<PIN, NPSW, NCPSW, C>(
+        &mut self,
+        get_pin: PIN,
+        get_new_password: NPSW,
+        get_new_confirm_password: NCPSW,
+        secp: &Secp256k1<C>,
+    ) -> Result<(), Error>
+    where
+        PIN: FnOnce() -> Result<String>,
+        NPSW: FnOnce() -> Result<String>,
+        NCPSW: FnOnce() -> Result<String>,
+        C: Signing,
+    {
+        self.ensure_writable()?;
+        if !self.is_pin_enabled() {
+            return Err(Error::PinNotEnabled);
+        }
+
+        let pin: String = get_pin().map_err(|e| Error::Generic(e.to_string()))?;
+        if !self.check_password(&pin) {
+            return Err(Error::CurrentPasswordNotMatch);
+        }
+
+        let new_password: String = get_new_password().map_err(|e| Error::Generic(e.to_string()))?;
+        if new_password.is_empty() {
+            return Err(Error::InvalidPassword);
+        }
+        let new_confirm_password: String =
+            get_new_confirm_password().map_err(|e| Error::Generic(e.to_string()))?;
+        if new_password != new_confirm_password {
+            return Err(Error::PasswordNotMatch);
+        }
+
+        let encryption_key_type = EncryptionKeyType::Password;
+        self.reencrypt(&pin, &new_password, encryption_key_type.kdf_params(), secp)?;
+        self.encryption_key_type = encryption_key_type;
+        self.pin = None;
+        self.save()?;
+        Ok(())
+    }
+
+    /// PIN settings (`None` if unlocked with a password)
+    pub fn pin(&self) -> Option<PinState> {
+        self.pin
+    }
+
+    pub fn wipe(&self) -> Result<(), Error> {
+        self.ensure_writable()?;
+        wipe_file(&self.file)
+    }
+}
+
+/// Read a keechain file, detecting the empty and truncated ones
+fn read_raw_file(path: &Path) -> Result<KeeChainRaw, Error> {
+    let content: Vec<u8> = fs::read(path)?;
+    if content.iter().all(u8::is_ascii_whitespace) {
+        return Err(Error::EmptyOrCorruptFile);
+    }
+    util::serde::deserialize(content).map_err(|e| {
+        if e.is_eof() || e.is_syntax() {
+            Error::EmptyOrCorruptFile
+        } else {
+            Error::Json(e)
+        }
+    })
+}
+
+/// Overwrite the beginning of the file and remove it
+fn wipe_file(path: &Path) -> Result<(), Error> {
+    let mut file: File = File::options().write(true).truncate(true).open(path)?;
+    file.write_all(&[0u8; 21])?;
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use core::str::FromStr;
+
+    use super::*;
+    use crate::crypto::KdfProfile;
+    use crate::types::MAX_INDEX;
+
+    #[test]
+    fn test_demo() {
+        let secp = Secp256k1::new();
+
+        assert!(matches!(
+            KeeChain::demo(Network::Bitcoin, &secp),
+            Err(Error::DemoOnMainnet)
+        ));
+
+        let mut keechain = KeeChain::demo(Network::Testnet, &secp).unwrap();
+        assert!(keechain.is_read_only());
+        assert_eq!(keechain.name(), Some(String::from("demo")));
+        assert_eq!(
+            keechain.identity(),
+            Fingerprint::from_str("73c5da0a").unwrap()
+        );
+        assert!(keechain.check_password(DEMO_PASSWORD));
+        assert_eq!(
+            keechain
+                .keychain(DEMO_PASSWORD)
+                .unwrap()
+                .mnemonic()
+                .to_string(),
+            DEMO_MNEMONIC
+        );
+
+        assert!(matches!(keechain.save(), Err(Error::ReadOnly)));
+        assert!(matches!(keechain.wipe(), Err(Error::ReadOnly)));
+        assert!(matches!(keechain.rename("renamed"), Err(Error::ReadOnly)));
+        assert!(matches!(
+            keechain.add_passphrase(DEMO_PASSWORD, "test"),
+            Err(Error::ReadOnly)
+        ));
+        assert!(keechain.passphrases(DEMO_PASSWORD).unwrap().is_empty());
+    }
+
+    fn temp_keychains_dir(name: &str) -> PathBuf {
+        let path: PathBuf =
+            std::env::temp_dir().join(format!("keechain-test-{name}-{}", std::process::id()));
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_reencrypt_if_legacy() {
+        let secp = Secp256k1::new();
+        let path: PathBuf = temp_keychains_dir("reencrypt");
+        let password: &str = "test";
+
+        // Write a file with the legacy AES-256 CBC format
+        let mnemonic: Mnemonic = Mnemonic::parse_normalized(DEMO_MNEMONIC).unwrap();
+        let keychain = Keychain::new(mnemonic, Vec::new());
+        let raw = KeeChainRaw {
+            version: KEECHAIN_FILE_VERSION,
+            encryption_key_type: EncryptionKeyType::Password,
+            keychain: keychain
+                .encrypt_with_mode(password, aes::Mode::Cbc)
+                .unwrap(),
+            fingerprint: None,
+            defaults: None,
+            pin: None,
+        };
+        fs::write(
+            path.join("legacy.keechain"),
+            util::serde::serialize(raw).unwrap(),
+        )
+        .unwrap();
+
+        let mut keechain = KeeChain::open(
+            &path,
+            "legacy",
+            || Ok(password.to_string()),
+            Network::Testnet,
+            &secp,
+        )
+        .unwrap();
+        assert!(keechain.has_legacy_format());
+        assert_eq!(
+            KeeChain::fingerprint_in_header(&path, "legacy").unwrap(),
+            None
+        );
+        assert!(keechain.reencrypt_if_legacy().unwrap());
+        assert!(!keechain.has_legacy_format());
+        assert_eq!(
+            KeeChain::fingerprint_in_header(&path, "legacy").unwrap(),
+            Some(keechain.identity())
+        );
+
+        // Already current: skip
+        let mut keechain = KeeChain::open(
+            &path,
+            "legacy",
+            || Ok(password.to_string()),
+            Network::Testnet,
+            &secp,
+        )
+        .unwrap();
+        assert!(!keechain.has_legacy_format());
+        assert!(!keechain.reencrypt_if_legacy().unwrap());
+        assert_eq!(
+            keechain.identity(),
+            Fingerprint::from_str("73c5da0a").unwrap()
+        );
+
+        fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn test_no_partial_file_on_error() {
+        let secp = Secp256k1::new();
+        let path: PathBuf = temp_keychains_dir("partial");
+        let generate = |entropy: Option<Vec<u8>>| {
+            KeeChain::generate(
+                &path,
+                "partial",
+                || Ok(String::from("test")),
+                || Ok(String::from("test")),
+                WordCount::W12,
+                || Ok(entropy),
+                EntropyMode::RequireCustom,
+                Network::Testnet,
+                &secp,
+            )
+        };
+
+        // Fail before saving: the name is still available
+        assert!(matches!(
+            generate(None),
+            Err(Error::NotEnoughEntropy { .. })
+        ));
+        assert!(dir::get_keychains_list(&path).unwrap().is_empty());
+        generate(Some(vec![1; 50])).unwrap();
+        assert_eq!(
+            dir::get_keychains_list(&path).unwrap(),
+            vec![String::from("partial")]
+        );
+
+        // Fail while saving (the destination is a directory): the temporary file is removed
+        let blocked: PathBuf = path.join("blocked.keechain");
+        fs::create_dir_all(blocked.join("content")).unwrap();
+        let raw: KeeChainRaw =
+            util::serde::deserialize(fs::read(path.join("partial.keechain")).unwrap()).unwrap();
+        assert!(matches!(raw.write_atomically(&blocked), Err(Error::IO(..))));
+        assert!(blocked.is_dir());
+        assert!(!path.join("blocked.keechain.tmp").exists());
+
+        fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn test_open_empty_or_corrupt_file() {
+        let secp = Secp256k1::new();
+        let path: PathBuf = temp_keychains_dir("empty-or-corrupt");
+        KeeChain::restore(
+            &path,
+            "corrupt",
+            || Ok(String::from("test")),
+            || Ok(String::from("test")),
+            || Ok(Mnemonic::parse_normalized(DEMO_MNEMONIC).unwrap()),
+            Network::Testnet,
+            &secp,
+        )
+        .unwrap();
+        let file: PathBuf = path.join("corrupt.keechain");
+        let content: Vec<u8> = fs::read(&file).unwrap();
+
+        let open = || {
+            KeeChain::open(
+                &path,
+                "corrupt",
+                || Ok(String::from("test")),
+                Network::Testnet,
+                &secp,
+            )
+        };
+
+        // Empty
+        fs::write(&file, b"").unwrap();
+        assert!(matches!(open(), Err(Error::EmptyOrCorruptFile)));
+        assert!(matches!(
+            KeeChain::verify_password(&path, "corrupt", || Ok(String::from("test"))),
+            Err(Error::EmptyOrCorruptFile)
+        ));
+
+        // Truncated
+        fs::write(&file, &content[..content.len() / 2]).unwrap();
+        assert!(matches!(open(), Err(Error::EmptyOrCorruptFile)));
+
+        // Zeroed
+        fs::write(&file, vec![0u8; content.len()]).unwrap();
+        assert!(matches!(open(), Err(Error::EmptyOrCorruptFile)));
+
+        fs::write(&file, &content).unwrap();
+        open().unwrap();
+
+        fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn test_find_by_fingerprint() {
+        let secp = Secp256k1::new();
+        let path: PathBuf = temp_keychains_dir("find-by-fingerprint");
+        let save = |name: &str| {
+            let mnemonic: Mnemonic = Mnemonic::parse_normalized(DEMO_MNEMONIC).unwrap();
+            KeeChain::new(
+                path.join(format!("{name}{KEECHAIN_DOT_EXTENSION}")),
+                "test",
+                KEECHAIN_FILE_VERSION,
+                EncryptionKeyType::Password,
+                Keychain::new(mnemonic, Vec::new()),
+                Network::Testnet,
+                &secp,
+            )
+            .unwrap()
+            .save()
+            .unwrap();
+        };
+        let fingerprint = Fingerprint::from_str("73c5da0a").unwrap();
+
+        save("wallet");
+        assert_eq!(
+            KeeChain::fingerprint_in_header(&path, "wallet").unwrap(),
+            Some(fingerprint)
+        );
+        assert_eq!(
+            KeeChain::fingerprint_in_header(&path, " wallet ").unwrap(),
+            Some(fingerprint)
+        );
+        assert_eq!(
+            KeeChain::find_by_fingerprint(&path, fingerprint).unwrap(),
+            "wallet"
+        );
+
+        // An empty file doesn't break the lookup
+        fs::write(path.join(format!("empty{KEECHAIN_DOT_EXTENSION}")), "").unwrap();
+        assert!(matches!(
+            KeeChain::fingerprint_in_header(&path, "empty"),
+            Err(Error::EmptyOrCorruptFile)
+        ));
+        assert_eq!(
+            KeeChain::find_by_fingerprint(&path, fingerprint).unwrap(),
+            "wallet"
+        );
+        assert!(matches!(
+            KeeChain::find_by_fingerprint(&path, Fingerprint::from_str("01020304").unwrap()),
+            Err(Error::FingerprintNotFound(..))
+        ));
+
+        save("copy");
+        match KeeChain::find_by_fingerprint(&path, fingerprint) {
+            Err(Error::AmbiguousFingerprint { names, .. }) => {
+                assert_eq!(names, vec![String::from("copy"), String::from("wallet")])
+            }
+            _ => panic!("expected ambiguous fingerprint"),
+        }
+
+        fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn test_names() {
+        let secp = Secp256k1::new();
+        let path: PathBuf = temp_keychains_dir("names");
+        let restore = |name: &str| {
+            KeeChain::restore(
+                &path,
+                name,
+                || Ok(String::from("test")),
+                || Ok(String::from("test")),
+                || Ok(Mnemonic::parse_normalized(DEMO_MNEMONIC).unwrap()),
+                Network::Testnet,
+                &secp,
+            )
+        };
+        let open = |name: &str| {
+            KeeChain::open(
+                &path,
+                name,
+                || Ok(String::from("test")),
+                Network::Testnet,
+                &secp,
+            )
+        };
+
+        // Empty or whitespace-only
+        for name in ["", " ", "\t\n"] {
+            assert!(matches!(restore(name), Err(Error::InvalidName)));
+            assert!(matches!(open(name), Err(Error::InvalidName)));
+        }
+
+        // Trimmed
+        let mut keechain = restore(" wallet ").unwrap();
+        assert_eq!(keechain.name(), Some(String::from("wallet")));
+        assert!(path.join("wallet.keechain").exists());
+        assert_eq!(
+            open("wallet\n").unwrap().name(),
+            Some(String::from("wallet"))
+        );
+        assert!(matches!(restore("wallet  "), Err(Error::FileAlreadyExists)));
+
+        assert!(matches!(keechain.rename("  "), Err(Error::InvalidName)));
+        keechain.rename(" renamed ").unwrap();
+        assert_eq!(keechain.name(), Some(String::from("renamed")));
+        assert!(path.join("renamed.keechain").exists());
+
+        fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn test_verify_password() {
+        let secp = Secp256k1::new();
+        let path: PathBuf = temp_keychains_dir("verify-password");
+        KeeChain::restore(
+            &path,
+            "verify",
+            || Ok(String::from("test")),
+            || Ok(String::from("test")),
+            || Ok(Mnemonic::parse_normalized(DEMO_MNEMONIC).unwrap()),
+            Network::Testnet,
+            &secp,
+        )
+        .unwrap();
+        let file: PathBuf = path.join("verify.keechain");
+        let content: Vec<u8> = fs::read(&file).unwrap();
+
+        assert!(KeeChain::verify_password(&path, "verify", || Ok(String::from("test"))).unwrap());
+        assert!(!KeeChain::verify_password(&path, "verify", || Ok(String::from("wrong"))).unwrap());
+        assert_eq!(fs::read(&file).unwrap(), content);
+        assert!(matches!(
+            KeeChain::verify_password(&path, "missing", || Ok(String::from("test"))),
+            Err(Error::FileNotFound)
+        ));
+
+        fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn test_pin() {
+        let secp = Secp256k1::new();
+        let path: PathBuf = temp_keychains_dir("pin");
+        let file: PathBuf = path.join("pin.keechain");
+        let open = |password: &str| {
+            KeeChain::open(
+                &path,
+                "pin",
+                || Ok(password.to_string()),
+                Network::Testnet,
+                &secp,
+            )
+        };
+        // Skip the backoff
+        let set_pin_state = |pin: PinState| {
+            let mut raw: KeeChainRaw = util::serde::deserialize(fs::read(&file).unwrap()).unwrap();
+            raw.pin = Some(pin);
+            raw.write_atomically(&file).unwrap();
+        };
+
+        let mut keechain = KeeChain::restore(
+            &path,
+            "pin",
+            || Ok(String::from("test")),
+            || Ok(String::from("test")),
+            || Ok(Mnemonic::parse_normalized(DEMO_MNEMONIC).unwrap()),
+            Network::Testnet,
+            &secp,
+        )
+        .unwrap();
+        assert!(!keechain.is_pin_enabled());
+        assert_eq!(KeeChain::pin_state(&path, "pin").unwrap(), None);
+
+        let enable = |keechain: &mut KeeChain, password: &str, pin: &str, confirm: &str| {
+            keechain.enable_pin(
+                || Ok(password.to_string()),
+                || Ok(pin.to_string()),
+                || Ok(confirm.to_string()),
+                Some(5),
+                &secp,
+            )
+        };
+        assert!(matches!(
+            enable(&mut keechain, "wrong", "1234", "1234"),
+            Err(Error::CurrentPasswordNotMatch)
+        ));
+        for invalid in ["123", "12a4", "1234567890123", " 1234"] {
+            assert!(matches!(
+                enable(&mut keechain, "test", invalid, invalid),
+                Err(Error::InvalidPin)
+            ));
+        }
+        assert!(matches!(
+            enable(&mut keechain, "test", "1234", "4321"),
+            Err(Error::PinNotMatch)
+        ));
+        enable(&mut keechain, "test", "1234", "1234").unwrap();
+        assert!(keechain.is_pin_enabled());
+        assert!(keechain.check_password("1234"));
+        assert_eq!(keechain.encrypted_keychain.kdf_params(), KdfParams::pin());
+        assert!(matches!(
+            enable(&mut keechain, "1234", "5678", "5678"),
+            Err(Error::PinAlreadyEnabled)
+        ));
+
+        // Failed attempts and backoff
+        for failed_attempts in 1..=PIN_FREE_ATTEMPTS {
+            assert!(matches!(open("0000"), Err(Error::Crypto(..))));
+            assert_eq!(
+                KeeChain::pin_state(&path, "pin")
+                    .unwrap()
+                    .unwrap()
+                    .failed_attempts,
+                failed_attempts
+            );
+        }
+        assert!(matches!(
+            open("1234"),
+            Err(Error::PinLocked { retry_after }) if retry_after > 0 && retry_after <= PIN_BASE_BACKOFF_SECS
+        ));
+
+        // Successful attempt resets the counter
+        set_pin_state(PinState {
+            failed_attempts: PIN_FREE_ATTEMPTS,
+            last_failure: Some(0),
+            wipe_after: Some(5),
+        });
+        let mut keechain = open("1234").unwrap();
+        assert!(keechain.is_pin_enabled());
+        assert_eq!(
+            KeeChain::pin_state(&path, "pin").unwrap(),
+            Some(PinState::new(Some(5)))
+        );
+        assert_eq!(keechain.encrypted_keychain.kdf_params(), KdfParams::pin());
+
+        // Back to password
+        keechain
+            .disable_pin(
+                || Ok(String::from("1234")),
+                || Ok(String::from("password")),
+                || Ok(String::from("password")),
+                &secp,
+            )
+            .unwrap();
+        assert!(!keechain.is_pin_enabled());
+        assert_eq!(KeeChain::pin_state(&path, "pin").unwrap(), None);
+        let mut keechain = open("password").unwrap();
+        assert_eq!(
+            keechain.encrypted_keychain.kdf_params(),
+            KdfParams::default()
+        );
+
+        // Wipe after N failures
+        enable(&mut keechain, "password", "1234", "1234").unwrap();
+        set_pin_state(PinState {
+            failed_attempts: 4,
+            last_failure: Some(0),
+            wipe_after: Some(5),
+        });
+        assert!(matches!(open("0000"), Err(Error::PinWiped)));
+        assert!(!file.exists());
+
+        fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn test_pin_backoff() {
+        let mut pin = PinState::new(Some(10));
+        assert_eq!(pin.backoff(), 0);
+        assert_eq!(pin.retry_after(100), 0);
+
+        pin.failed_attempts = PIN_FREE_ATTEMPTS;
+        pin.last_failure = Some(100);
+        assert_eq!(pin.backoff(), PIN_BASE_BACKOFF_SECS);
+        assert_eq!(pin.retry_after(110), PIN_BASE_BACKOFF_SECS - 10);
+        assert_eq!(pin.retry_after(1000), 0);
+        assert_eq!(pin.attempts_left(), Some(10 - PIN_FREE_ATTEMPTS));
+
+        pin.failed_attempts = PIN_FREE_ATTEMPTS + 2;
+        assert_eq!(pin.backoff(), PIN_BASE_BACKOFF_SECS * 4);
+        pin.failed_attempts = u32::MAX;
+        assert_eq!(pin.backoff(), PIN_MAX_BACKOFF_SECS);
+        assert_eq!(pin.attempts_left(), Some(0));
+    }
+
+    #[test]
+    fn test_change_password() {
+        let secp = Secp256k1::new();
+        let path: PathBuf = temp_keychains_dir("change-password");
+        let mnemonic: Mnemonic = Mnemonic::parse_normalized(DEMO_MNEMONIC).unwrap();
+        let keechain = KeeChain::new(
+            path.join("wallet.keechain"),
+            "old",
+            KEECHAIN_FILE_VERSION,
+            EncryptionKeyType::Password,
+            Keychain::new(mnemonic, Vec::new()),
+            Network::Testnet,
+            &secp,
+        )
+        .unwrap();
+        keechain.save().unwrap();
+
+        let mut keechain = KeeChain::open(
+            &path,
+            "wallet",
+            || Ok(String::from("old")),
+            Network::Testnet,
+            &secp,
+        )
+        .unwrap();
+        let fingerprint: Fingerprint = keechain
+            .change_password(
+                || Ok(String::from("old")),
+                || Ok(String::from("new")),
+                || Ok(String::from("new")),
+                &secp,
+            )
+            .unwrap();
+        assert_eq!(fingerprint, Fingerprint::from_str("73c5da0a").unwrap());
+
+        assert!(KeeChain::open(
+            &path,
+            "wallet",
+            || Ok(String::from("old")),
+            Network::Testnet,
+            &secp
+        )
+        .is_err());
+        let keechain = KeeChain::open(
+            &path,
+            "wallet",
+            || Ok(String::from("new")),
+            Network::Testnet,
+            &secp,
+        )
+        .unwrap();
+        assert_eq!(
+            keechain.identity(),
+            Fingerprint::from_str("73c5da0a").unwrap()
+        );
+        assert!(!path.join("wallet.keechain.tmp").exists());
+
+        fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn test_kdf_profiles() {
+        let secp = Secp256k1::new();
+        let path: PathBuf = temp_keychains_dir("kdf-profiles");
+        let open = |name: &str| {
+            KeeChain::open(
+                &path,
+                name,
+                || Ok(String::from("test")),
+                Network::Testnet,
+                &secp,
+            )
+            .unwrap()
+        };
+
+        for (name, profile) in [
+            ("fast", KdfProfile::Fast),
+            ("balanced", KdfProfile::Balanced),
+            ("paranoid", KdfProfile::Paranoid),
+        ] {
+            let keechain = KeeChain::restore_with_kdf_params(
+                &path,
+                name,
+                || Ok(String::from("test")),
+                || Ok(String::from("test")),
+                || Ok(Mnemonic::parse_normalized(DEMO_MNEMONIC).unwrap()),
+                profile.params(),
+                Network::Testnet,
+                &secp,
+            )
+            .unwrap();
+            assert_eq!(keechain.encrypted_keychain.kdf_params(), profile.params());
+
+            // Open and save keep the params of the file
+            let keechain = open(name);
+            assert_eq!(keechain.encrypted_keychain.kdf_params(), profile.params());
+            keechain.save().unwrap();
+            let keechain = open(name);
+            assert_eq!(keechain.encrypted_keychain.kdf_params(), profile.params());
+            assert_eq!(
+                keechain.identity(),
+                Fingerprint::from_str("73c5da0a").unwrap()
+            );
+        }
+
+        // Change the profile, keeping the same password
+        let mut keechain = open("fast");
+        keechain
+            .change_password_with_kdf_params(
+                || Ok(String::from("test")),
+                || Ok(String::from("test")),
+                || Ok(String::from("test")),
+                Some(KdfProfile::Balanced.params()),
+                &secp,
+            )
+            .unwrap();
+        assert_eq!(
+            open("fast").encrypted_keychain.kdf_params(),
+            KdfParams::default()
+        );
+
+        // Unchanged if no params are provided
+        let mut keechain = open("paranoid");
+        keechain
+            .change_password(
+                || Ok(String::from("test")),
+                || Ok(String::from("test")),
+                || Ok(String::from("test")),
+                &secp,
+            )
+            .unwrap();
+        assert_eq!(
+            open("paranoid").encrypted_keychain.kdf_params(),
+            KdfProfile::Paranoid.params()
+        );
+
+        fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn test_generate_require_custom_entropy() {
+        let secp = Secp256k1::new();
+        let path: PathBuf = temp_keychains_dir("require-custom-entropy");
+        let generate = |name: &str, rolls: Vec<u8>| {
+            KeeChain::generate(
+                &path,
+                name,
+                || Ok(String::from("password")),
+                || Ok(String::from("password")),
+                WordCount::W12,
+                || Ok(Some(rolls)),
+                EntropyMode::RequireCustom,
+                Network::Testnet,
+                &secp,
+            )
+        };
+
+        let required: usize = bip39::min_dice_rolls(WordCount::W12);
+        assert!(matches!(
+            generate("not-enough", vec![1; required - 1]),
+            Err(Error::NotEnoughEntropy { provided, .. }) if provided == required - 1
+        ));
+        assert!(!path.join("not-enough.keechain").exists());
+
+        // Same rolls, different mnemonics: the OS/system entropy is still mixed in
+        let first = generate("first", vec![3; required]).unwrap();
+        let second = generate("second", vec![3; required]).unwrap();
+        assert_ne!(first.identity(), second.identity());
+
+        fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn test_defaults() {
+        let secp = Secp256k1::new();
+        let path: PathBuf = temp_keychains_dir("defaults");
+        let mnemonic: Mnemonic = Mnemonic::parse_normalized(DEMO_MNEMONIC).unwrap();
+        let mut keechain = KeeChain::new(
+            path.join("wallet.keechain"),
+            "old",
+            KEECHAIN_FILE_VERSION,
+            EncryptionKeyType::Password,
+            Keychain::new(mnemonic, Vec::new()),
+            Network::Testnet,
+            &secp,
+        )
+        .unwrap();
+        assert_eq!(
+            keechain.defaults("old").unwrap(),
+            KeychainDefaults::default()
+        );
+
+        let defaults = KeychainDefaults {
+            account: Some(2),
+            purpose: Some(Purpose::BIP86),
+        };
+        assert!(matches!(
+            keechain.set_defaults("wrong", defaults),
+            Err(Error::InvalidPassword)
+        ));
+        assert!(matches!(
+            keechain.set_defaults(
+                "old",
+                KeychainDefaults {
+                    account: Some(MAX_INDEX),
+                    purpose: None
+                }
+            ),
+            Err(Error::Keychain(keychain::Error::InvalidAccount(MAX_INDEX)))
+        ));
+        keechain.set_defaults("old", defaults).unwrap();
+        keechain.save().unwrap();
+
+        // Defaults are kept across open and change of password
+        let mut keechain = KeeChain::open(
+            &path,
+            "wallet",
+            || Ok(String::from("old")),
+            Network::Testnet,
+            &secp,
+        )
+        .unwrap();
+        assert_eq!(keechain.defaults("old").unwrap(), defaults);
+        keechain
+            .change_password(
+                || Ok(String::from("old")),
+                || Ok(String::from("new")),
+                || Ok(String::from("new")),
+                &secp,
+            )
+            .unwrap();
+        let keechain = KeeChain::open(
+            &path,
+            "wallet",
+            || Ok(String::from("new")),
+            Network::Testnet,
+            &secp,
+        )
+        .unwrap();
+        assert_eq!(keechain.defaults("new").unwrap(), defaults);
+
+        fs::remove_dir_all(path).unwrap();
+    }
 }
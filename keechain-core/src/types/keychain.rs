@@ -2,20 +2,25 @@
 // Distributed under the MIT software license
 
 use core::fmt;
-use core::ops::Deref;
+use core::ops::{Deref, Range};
 
-use bdk::bitcoin::secp256k1::{Secp256k1, Signing};
-use bdk::bitcoin::Network;
+use bdk::bitcoin::secp256k1::{Secp256k1, Signing, Verification};
+use bdk::bitcoin::{Address, Network};
+use bdk::miniscript::descriptor::{Descriptor, DescriptorPublicKey};
 use serde::de::Deserializer;
 use serde::{Deserialize, Serialize};
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
-use crate::bips::bip32::{self, Bip32, ExtendedPubKey, Fingerprint};
+use crate::bips::bip32::{
+    self, Bip32, DerivationPath, ExtendedPrivKey, ExtendedPubKey, Fingerprint,
+};
 use crate::bips::bip39::Mnemonic;
+use crate::bips::bip43::Purpose;
 use crate::bips::bip85::{self, Bip85};
-use crate::crypto::{self, MultiEncryption};
-use crate::types::{Index, Secrets, Seed, WordCount};
-use crate::{descriptors, Descriptors, Result};
+use crate::crypto::{self, aes, KdfParams, MultiEncryption};
+use crate::descriptors::ScanResult;
+use crate::types::{Index, Secrets, Seed, WordCount, MAX_INDEX};
+use crate::{descriptors, psbt, Descriptors, Result};
 
 #[derive(Debug)]
 pub enum Error {
@@ -23,6 +28,11 @@ pub enum Error {
     BIP85(bip85::Error),
     Crypto(crypto::Error),
     Descriptors(descriptors::Error),
+    Psbt(psbt::Error),
+    /// Account out of the hardened range
+    InvalidAccount(u32),
+    /// Coin type out of the hardened range
+    InvalidCoinType(u32),
 }
 
 impl std::error::Error for Error {}
@@ -34,6 +44,17 @@ impl fmt::Display for Error {
             Self::BIP85(e) => write!(f, "BIP85: {e}"),
             Self::Crypto(e) => write!(f, "Crypto: {e}"),
             Self::Descriptors(e) => write!(f, "Descriptors: {e}"),
+            Self::Psbt(e) => write!(f, "Psbt: {e}"),
+            Self::InvalidAccount(account) => write!(
+                f,
+                "Invalid account {account}: must be between 0 and {}",
+                MAX_INDEX - 1
+            ),
+            Self::InvalidCoinType(coin_type) => write!(
+                f,
+                "Invalid coin type {coin_type}: must be between 0 and {}",
+                MAX_INDEX - 1
+            ),
         }
     }
 }
@@ -62,6 +83,12 @@ impl From<descriptors::Error> for Error {
     }
 }
 
+impl From<psbt::Error> for Error {
+    fn from(e: psbt::Error) -> Self {
+        Self::Psbt(e)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EncryptedKeychain {
     pub(crate) master_bip32_root_pubkey: ExtendedPubKey,
@@ -117,6 +144,27 @@ impl EncryptedKeychain {
         Ok(Keychain::decrypt(password, self.raw.as_bytes())?)
     }
 
+    /// KDF params of the encrypted keychain (default ones for legacy formats)
+    pub fn kdf_params(&self) -> KdfParams {
+        crypto::kdf_params(self.raw.as_bytes()).unwrap_or_default()
+    }
+
+    /// Re-encrypt the keychain with a new password (and the current encryption format)
+    pub(crate) fn change_password<T, N>(
+        &mut self,
+        password: T,
+        new_password: N,
+        params: KdfParams,
+    ) -> Result<(), Error>
+    where
+        T: AsRef<[u8]>,
+        N: AsRef<[u8]>,
+    {
+        let keychain: Keychain = self.keychain(password)?;
+        self.raw = keychain.encrypt_with_params(new_password, aes::Mode::default(), params)?;
+        Ok(())
+    }
+
     pub fn add_passphrase<T, S>(&mut self, password: T, passphrase: S) -> Result<(), Error>
     where
         T: AsRef<[u8]>,
@@ -124,7 +172,8 @@ impl EncryptedKeychain {
     {
         let mut keychain: Keychain = self.keychain(&password)?;
         keychain.add_passphrase(passphrase);
-        self.raw = keychain.encrypt(password)?;
+        self.raw =
+            keychain.encrypt_with_params(password, aes::Mode::default(), self.kdf_params())?;
         Ok(())
     }
 
@@ -135,7 +184,8 @@ impl EncryptedKeychain {
     {
         let mut keychain: Keychain = self.keychain(&password)?;
         keychain.remove_passphrase(passphrase);
-        self.raw = keychain.encrypt(password)?;
+        self.raw =
+            keychain.encrypt_with_params(password, aes::Mode::default(), self.kdf_params())?;
         Ok(())
     }
 
@@ -219,10 +269,20 @@ impl Keychain {
         }
     }
 
+    /// BIP39 mnemonic
+    ///
+    /// **Sensitive**: never log or display it without explicit user request.
     pub fn mnemonic(&self) -> Mnemonic {
         self.mnemonic.clone()
     }
 
+    /// Currently applied BIP39 passphrase (if any)
+    ///
+    /// **Sensitive**: never log or display it without explicit user request.
+    pub fn passphrase(&self) -> Option<String> {
+        self.seed.passphrase()
+    }
+
     pub fn passphrases(&self) -> Vec<String> {
         self.passphrases.clone()
     }
@@ -252,7 +312,29 @@ impl Keychain {
     where
         C: Signing,
     {
-        Ok(Descriptors::new(&self.seed, network, account, secp)?)
+        self.descriptors_with_coin_type(network, account, None, secp)
+    }
+
+    /// Descriptors with a non-standard coin type (if `coin_type` is `Some`)
+    pub fn descriptors_with_coin_type<C>(
+        &self,
+        network: Network,
+        account: Option<u32>,
+        coin_type: Option<u32>,
+        secp: &Secp256k1<C>,
+    ) -> Result<Descriptors, Error>
+    where
+        C: Signing,
+    {
+        if let Some(account) = account {
+            Index::new(account).map_err(|_| Error::InvalidAccount(account))?;
+        }
+        if let Some(coin_type) = coin_type {
+            Index::new(coin_type).map_err(|_| Error::InvalidCoinType(coin_type))?;
+        }
+        Ok(Descriptors::new_with_coin_type(
+            &self.seed, network, account, coin_type, secp,
+        )?)
     }
 
     pub fn secrets<C>(&self, network: Network, secp: &Secp256k1<C>) -> Result<Secrets, Error>
@@ -262,6 +344,89 @@ impl Keychain {
         Ok(Secrets::new(&self.seed, network, secp)?)
     }
 
+    /// Check if this keychain is a signer of the `descriptor`
+    pub fn validate_against_descriptor<C>(
+        &self,
+        descriptor: &Descriptor<DescriptorPublicKey>,
+        network: Network,
+        secp: &Secp256k1<C>,
+    ) -> Result<bool, Error>
+    where
+        C: Signing,
+    {
+        Ok(self
+            .descriptor_key_position(descriptor, network, secp)?
+            .is_some())
+    }
+
+    /// Get the position of this keychain key in the `descriptor` (i.e. in a multisig)
+    pub fn descriptor_key_position<C>(
+        &self,
+        descriptor: &Descriptor<DescriptorPublicKey>,
+        network: Network,
+        secp: &Secp256k1<C>,
+    ) -> Result<Option<usize>, Error>
+    where
+        C: Signing,
+    {
+        Ok(descriptors::key_position(
+            &self.seed, descriptor, network, secp,
+        )?)
+    }
+
+    /// Scan the addresses of the `accounts` and `purposes` in the `range` of indexes, looking for the `candidates`
+    ///
+    /// See [`Descriptors::scan_addresses`]: the scan stops when all the candidates are found.
+    pub fn scan_addresses<C>(
+        &self,
+        candidates: &[Address],
+        network: Network,
+        accounts: &[u32],
+        purposes: &[Purpose],
+        range: Range<u32>,
+        secp: &Secp256k1<C>,
+    ) -> Result<ScanResult, Error>
+    where
+        C: Signing,
+    {
+        let mut result = ScanResult::default();
+        for account in accounts.iter() {
+            let remaining: Vec<Address> = candidates
+                .iter()
+                .filter(|c| !result.matches.iter().any(|m| &m.address == *c))
+                .cloned()
+                .collect();
+            if remaining.is_empty() {
+                break;
+            }
+
+            let descriptors: Descriptors = self.descriptors(network, Some(*account), secp)?;
+            let account_result: ScanResult =
+                descriptors.scan_addresses(&remaining, network, purposes, range.clone())?;
+            result.matches.extend(account_result.matches);
+            result.scanned += account_result.scanned;
+        }
+        Ok(result)
+    }
+
+    /// Sign a message with the key at `path` (P2TR or P2WPKH, BIP322 simple signature)
+    ///
+    /// Return the base64 encoded signature.
+    pub fn sign_message<C>(
+        &self,
+        path: &DerivationPath,
+        message: &str,
+        network: Network,
+        secp: &Secp256k1<C>,
+    ) -> Result<String, Error>
+    where
+        C: Signing + Verification,
+    {
+        Ok(psbt::sign_message(
+            &self.seed, path, message, network, secp,
+        )?)
+    }
+
     pub(crate) fn add_passphrase<S>(&mut self, passphrase: S)
     where
         S: Into<String>,
@@ -295,3 +460,78 @@ impl Keychain {
 }
 
 impl MultiEncryption for Keychain {}
+
+#[cfg(test)]
+mod tests {
+    use core::str::FromStr;
+
+    use super::*;
+    use crate::bips::bip322;
+
+    #[test]
+    fn test_sign_message() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("range special tuna oblige own drama trend render harsh army outdoor bulb brisk sing analyst own fork senior stove flash fire bulk umbrella vast").unwrap();
+        let keychain = Keychain::new(mnemonic, Vec::new());
+
+        let derived = keychain
+            .descriptors(Network::Testnet, Some(0), &secp)
+            .unwrap()
+            .derive_address(Purpose::BIP86, false, 3, Network::Testnet)
+            .unwrap();
+        let signature = keychain
+            .sign_message(&derived.path, "Hello World", Network::Testnet, &secp)
+            .unwrap();
+
+        let script_pubkey = derived.address.script_pubkey();
+        bip322::verify_taproot(&script_pubkey, b"Hello World", &signature, &secp).unwrap();
+        assert!(bip322::verify_taproot(&script_pubkey, b"Hello", &signature, &secp).is_err());
+    }
+
+    #[test]
+    fn test_scan_addresses() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("range special tuna oblige own drama trend render harsh army outdoor bulb brisk sing analyst own fork senior stove flash fire bulk umbrella vast").unwrap();
+        let keychain = Keychain::new(mnemonic, Vec::new());
+
+        let account_0 = keychain
+            .descriptors(Network::Testnet, Some(0), &secp)
+            .unwrap()
+            .derive_address(Purpose::BIP84, false, 2, Network::Testnet)
+            .unwrap();
+        let account_1 = keychain
+            .descriptors(Network::Testnet, Some(1), &secp)
+            .unwrap()
+            .derive_address(Purpose::BIP86, true, 4, Network::Testnet)
+            .unwrap();
+
+        let result = keychain
+            .scan_addresses(
+                &[account_1.address.clone(), account_0.address.clone()],
+                Network::Testnet,
+                &[0, 1],
+                &[Purpose::BIP84, Purpose::BIP86],
+                0..5,
+                &secp,
+            )
+            .unwrap();
+        assert_eq!(result.matches, vec![account_0, account_1.clone()]);
+        assert_eq!(result.matches[1].path.to_string(), "m/86'/1'/1'/1/4");
+        // 20 addresses for each account: the match of account 1 is the last change address
+        assert_eq!(result.scanned, 40);
+
+        // Not in the range
+        let result = keychain
+            .scan_addresses(
+                &[account_1.address],
+                Network::Testnet,
+                &[0, 1],
+                &[Purpose::BIP86],
+                0..4,
+                &secp,
+            )
+            .unwrap();
+        assert!(result.matches.is_empty());
+        assert_eq!(result.scanned, 16);
+    }
+}
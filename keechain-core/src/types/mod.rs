@@ -8,15 +8,16 @@ use std::str::FromStr;
 use bdk::bitcoin::secp256k1::{Secp256k1, Signing};
 use bdk::bitcoin::Network;
 use bip39::Mnemonic;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 pub mod keechain;
 pub mod keychain;
 pub mod seed;
 
-pub use self::keechain::KeeChain;
+pub use self::keechain::{KeeChain, KeychainDefaults, PinState};
 pub use self::keychain::{EncryptedKeychain, Keychain};
 pub use self::seed::Seed;
-use crate::bips::bip32::{self, Bip32, ExtendedPrivKey, Fingerprint};
+use crate::bips::bip32::{self, Bip32, ChainCode, ExtendedPrivKey, Fingerprint};
 use crate::util::hex;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -146,6 +147,25 @@ impl Secrets {
     }
 }
 
+impl Zeroize for Secrets {
+    fn zeroize(&mut self) {
+        self.entropy.zeroize();
+        self.mnemonic.zeroize();
+        self.passphrase.zeroize();
+        self.seed_hex.zeroize();
+        self.root_key.private_key.non_secure_erase();
+        self.root_key.chain_code = ChainCode::from([0u8; 32]);
+    }
+}
+
+impl Drop for Secrets {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for Secrets {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -157,4 +177,26 @@ mod tests {
         assert!(Index::new(2147483647).is_ok());
         assert!(Index::new(2147483648).is_err());
     }
+
+    #[test]
+    fn test_secrets_zeroize() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::parse_normalized("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about").unwrap();
+        let seed = Seed::new(mnemonic, Some("TREZOR"));
+        let mut secrets = Secrets::new(&seed, Network::Testnet, &secp).unwrap();
+        assert_eq!(secrets.entropy, "00000000000000000000000000000000");
+        assert!(secrets.seed_hex.starts_with("c55257c360c07c72"));
+
+        secrets.zeroize();
+        assert!(secrets.entropy.is_empty());
+        assert!(secrets.passphrase.is_none());
+        assert!(secrets.seed_hex.is_empty());
+        assert_ne!(
+            secrets.root_key.private_key,
+            seed.to_bip32_root_key(Network::Testnet)
+                .unwrap()
+                .private_key
+        );
+        assert_eq!(secrets.root_key.chain_code.to_bytes(), [0u8; 32]);
+    }
 }
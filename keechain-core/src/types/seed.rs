@@ -3,22 +3,129 @@
 
 use core::fmt;
 
+use bdk::bitcoin::secp256k1::{Secp256k1, Signing};
 use bdk::bitcoin::Network;
-use bip39::Mnemonic;
+use bip39::{Language, Mnemonic};
 use serde::{Deserialize, Serialize};
-use zeroize::{Zeroize, ZeroizeOnDrop};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
-use crate::bips::bip32::{self, Bip32, ExtendedPrivKey};
+use crate::bips::bip32::{self, Bip32, ExtendedPrivKey, Fingerprint};
+use crate::bips::bip352::Bip352;
 use crate::bips::bip85::Bip85;
+use crate::crypto;
 use crate::descriptors::ToDescriptor;
+use crate::nips::nip06::Nip06;
 use crate::util::hex;
 
-#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    BIP39(bip39::Error),
+    EmptyMnemonic,
+    /// Word not in the wordlist (position starting from 1)
+    UnknownWord(usize),
+    BadWordCount(usize),
+    InvalidChecksum,
+    Hex(hex::Error),
+    /// Entropy must be 16, 24 or 32 bytes
+    InvalidEntropyLength(usize),
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BIP39(e) => write!(f, "BIP39: {e}"),
+            Self::EmptyMnemonic => write!(f, "Empty mnemonic"),
+            Self::UnknownWord(position) => {
+                write!(f, "Word #{position} is not in the wordlist")
+            }
+            Self::BadWordCount(count) => write!(
+                f,
+                "Invalid number of words: {count} (must be 12, 15, 18, 21 or 24)"
+            ),
+            Self::InvalidChecksum => write!(f, "Invalid checksum: check the words order"),
+            Self::Hex(e) => write!(f, "Hex: {e}"),
+            Self::InvalidEntropyLength(len) => write!(
+                f,
+                "Invalid entropy: must be 16, 24 or 32 bytes, found {len} bytes"
+            ),
+        }
+    }
+}
+
+impl From<bip39::Error> for Error {
+    fn from(e: bip39::Error) -> Self {
+        match e {
+            bip39::Error::UnknownWord(index) => Self::UnknownWord(index + 1),
+            bip39::Error::BadWordCount(count) => Self::BadWordCount(count),
+            bip39::Error::InvalidChecksum => Self::InvalidChecksum,
+            e => Self::BIP39(e),
+        }
+    }
+}
+
+impl From<hex::Error> for Error {
+    fn from(e: hex::Error) -> Self {
+        Self::Hex(e)
+    }
+}
+
+/// Parse a mnemonic typed by the user
+///
+/// Extra whitespaces and uppercase letters are ignored, words are NFKD normalized.
+pub fn parse_mnemonic<S>(mnemonic: S, language: Language) -> Result<Mnemonic, Error>
+where
+    S: AsRef<str>,
+{
+    let words: Vec<String> = mnemonic
+        .as_ref()
+        .split_whitespace()
+        .map(|word| word.to_lowercase())
+        .collect();
+    if words.is_empty() {
+        return Err(Error::EmptyMnemonic);
+    }
+    Ok(Mnemonic::parse_in(language, words.join(" "))?)
+}
+
+/// Build the mnemonic of hex encoded entropy (16, 24 or 32 bytes: 12, 18 or 24 words)
+pub fn mnemonic_from_hex_entropy<S>(entropy: S) -> Result<Mnemonic, Error>
+where
+    S: AsRef<str>,
+{
+    let entropy: Vec<u8> = hex::decode(entropy.as_ref().trim())?;
+    if ![16, 24, 32].contains(&entropy.len()) {
+        return Err(Error::InvalidEntropyLength(entropy.len()));
+    }
+    Ok(Mnemonic::from_entropy(&entropy)?)
+}
+
+#[derive(Clone, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
 pub struct Seed {
     mnemonic: Mnemonic,
     passphrase: Option<String>,
 }
 
+/// Constant-time comparison of the mnemonic (entropy and language) and of the passphrase
+///
+/// Cheaper than [`Seed::same_as`], that runs the BIP39 key stretching on both sides.
+impl PartialEq for Seed {
+    fn eq(&self, other: &Self) -> bool {
+        let entropy: Zeroizing<Vec<u8>> = Zeroizing::new(self.mnemonic.to_entropy());
+        let other_entropy: Zeroizing<Vec<u8>> = Zeroizing::new(other.mnemonic.to_entropy());
+        let same_mnemonic: bool = crypto::constant_time_eq(&*entropy, &*other_entropy)
+            && self.mnemonic.language() == other.mnemonic.language();
+        let same_passphrase: bool = crypto::constant_time_eq(
+            self.passphrase.as_deref().unwrap_or_default(),
+            other.passphrase.as_deref().unwrap_or_default(),
+        );
+        same_mnemonic & same_passphrase
+    }
+}
+
+impl Eq for Seed {}
+
 impl fmt::Debug for Seed {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "<sensitive>")
@@ -43,6 +150,20 @@ impl Seed {
         }
     }
 
+    /// Validate the `mnemonic` (see [`parse_mnemonic`]) and build the seed
+    pub fn from_mnemonic_str<S, P>(
+        mnemonic: S,
+        passphrase: Option<P>,
+        language: Language,
+    ) -> Result<Self, Error>
+    where
+        S: AsRef<str>,
+        P: Into<String>,
+    {
+        let mnemonic: Mnemonic = parse_mnemonic(mnemonic, language)?;
+        Ok(Self::new(mnemonic, passphrase))
+    }
+
     pub fn mnemonic(&self) -> Mnemonic {
         self.mnemonic.clone()
     }
@@ -51,14 +172,31 @@ impl Seed {
         self.passphrase.clone()
     }
 
+    /// Raw 512-bit BIP39 seed (mnemonic + passphrase)
+    ///
+    /// **Sensitive**: anyone with these bytes can derive all the keys, don't log or store it unencrypted.
     pub fn to_bytes(&self) -> [u8; 64] {
         self.mnemonic
             .to_seed(self.passphrase.clone().unwrap_or_default())
     }
 
+    /// Hex of [`Seed::to_bytes`] (**sensitive**)
     pub fn to_hex(&self) -> String {
         hex::encode(self.to_bytes())
     }
+
+    /// Check if two seeds are the same (constant-time comparison of the seed bytes)
+    pub fn same_as(&self, other: &Seed) -> bool {
+        crypto::constant_time_eq(self.to_bytes(), other.to_bytes())
+    }
+
+    /// Master fingerprint, network independent. Usable as dedup key.
+    pub fn identity_fingerprint<C>(&self, secp: &Secp256k1<C>) -> Result<Fingerprint, bip32::Error>
+    where
+        C: Signing,
+    {
+        self.fingerprint(Network::Bitcoin, secp)
+    }
 }
 
 impl Bip32 for Seed {
@@ -69,7 +207,9 @@ impl Bip32 for Seed {
 }
 
 impl Bip85 for Seed {}
+impl Bip352 for Seed {}
 impl ToDescriptor for Seed {}
+impl Nip06 for Seed {}
 
 #[cfg(test)]
 mod tests {
@@ -84,4 +224,127 @@ mod tests {
         let seed = Seed::new(mnemonic, passphrase);
         assert_eq!(&seed.to_hex(), "fb826595a0d679f5e9f8c799bd1decb8dc2ad3fb4e39a1ffaa4708a150e0e81ae55d3f340a188cd6188a2b76601aeae16945b36ae0ecfced9645029796c33713")
     }
+
+    #[test]
+    fn test_seed_to_bytes() {
+        // BIP39 test vector
+        let mnemonic = Mnemonic::from_str("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about").unwrap();
+        let seed = Seed::new(mnemonic, Some("TREZOR"));
+        let bytes: [u8; 64] = seed.to_bytes();
+        assert_eq!(hex::encode(bytes), "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04");
+        assert_eq!(hex::encode(bytes), seed.to_hex());
+    }
+
+    #[test]
+    fn test_seed_from_mnemonic_str() {
+        let expected = Seed::new(Mnemonic::from_str("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about").unwrap(), Some("TREZOR"));
+
+        let seed = Seed::from_mnemonic_str(
+            "  Abandon abandon ABANDON abandon abandon abandon\nabandon abandon abandon abandon abandon  about ",
+            Some("TREZOR"),
+            Language::English,
+        )
+        .unwrap();
+        assert_eq!(seed, expected);
+
+        assert_eq!(
+            Seed::from_mnemonic_str(" ", None::<String>, Language::English),
+            Err(Error::EmptyMnemonic)
+        );
+        assert_eq!(
+            Seed::from_mnemonic_str(
+                "abandon abandon abandom abandon abandon abandon abandon abandon abandon abandon abandon about",
+                None::<String>,
+                Language::English
+            ),
+            Err(Error::UnknownWord(3))
+        );
+        assert_eq!(
+            Seed::from_mnemonic_str(
+                "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+                None::<String>,
+                Language::English
+            ),
+            Err(Error::BadWordCount(11))
+        );
+        assert_eq!(
+            Seed::from_mnemonic_str(
+                "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon",
+                None::<String>,
+                Language::English
+            ),
+            Err(Error::InvalidChecksum)
+        );
+    }
+
+    #[test]
+    fn test_mnemonic_from_hex_entropy() {
+        // BIP39 test vectors
+        assert_eq!(
+            mnemonic_from_hex_entropy("00000000000000000000000000000000")
+                .unwrap()
+                .to_string(),
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about"
+        );
+        assert_eq!(
+            mnemonic_from_hex_entropy(" 7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f\n")
+                .unwrap()
+                .to_string(),
+            "legal winner thank year wave sausage worth useful legal winner thank yellow"
+        );
+        assert_eq!(
+            mnemonic_from_hex_entropy("00".repeat(32))
+                .unwrap()
+                .word_count(),
+            24
+        );
+
+        assert_eq!(
+            mnemonic_from_hex_entropy("0g000000000000000000000000000000"),
+            Err(Error::Hex(hex::Error::InvalidHexCharacter {
+                c: 'g',
+                index: 1
+            }))
+        );
+        assert_eq!(
+            mnemonic_from_hex_entropy("000"),
+            Err(Error::Hex(hex::Error::OddLength))
+        );
+        assert_eq!(
+            mnemonic_from_hex_entropy("00".repeat(20)),
+            Err(Error::InvalidEntropyLength(20))
+        );
+        assert_eq!(
+            mnemonic_from_hex_entropy("00".repeat(15)),
+            Err(Error::InvalidEntropyLength(15))
+        );
+    }
+
+    #[test]
+    fn test_seed_same_as() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("easy uncover favorite crystal bless differ energy seat ecology match carry group refuse together chat observe hidden glad brave month diesel sustain depth salt").unwrap();
+        let seed = Seed::new(mnemonic.clone(), Some("mypassphrase"));
+        let same = Seed::new(mnemonic.clone(), Some("mypassphrase"));
+        let other = Seed::from_mnemonic(mnemonic.clone());
+
+        assert!(seed.same_as(&same));
+        assert!(!seed.same_as(&other));
+        assert_eq!(seed, same);
+        assert_ne!(seed, other);
+
+        // Empty passphrase, same seed bytes
+        let empty = Seed::new(mnemonic, Some(""));
+        assert!(other.same_as(&empty));
+        assert_eq!(other, empty);
+
+        assert_eq!(
+            seed.identity_fingerprint(&secp).unwrap(),
+            same.identity_fingerprint(&secp).unwrap()
+        );
+        assert_ne!(
+            seed.identity_fingerprint(&secp).unwrap(),
+            other.identity_fingerprint(&secp).unwrap()
+        );
+    }
 }
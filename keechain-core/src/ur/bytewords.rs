@@ -0,0 +1,137 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Bytewords (minimal style)
+//!
+//! <https://github.com/BlockchainCommons/Research/blob/master/papers/bcr-2020-012-bytewords.md>
+
+use core::fmt;
+
+const WORDS: [&str; 256] = [
+    "able", "acid", "also", "apex", "aqua", "arch", "atom", "aunt", "away", "axis", "back", "bald",
+    "barn", "belt", "beta", "bias", "blue", "body", "brag", "brew", "bulb", "buzz", "calm", "cash",
+    "cats", "chef", "city", "claw", "code", "cola", "cook", "cost", "crux", "curl", "cusp", "cyan",
+    "dark", "data", "days", "deli", "dice", "diet", "door", "down", "draw", "drop", "drum", "dull",
+    "duty", "each", "easy", "echo", "edge", "epic", "even", "exam", "exit", "eyes", "fact", "fair",
+    "fern", "figs", "film", "fish", "fizz", "flap", "flew", "flux", "foxy", "free", "frog", "fuel",
+    "fund", "gala", "game", "gear", "gems", "gift", "girl", "glow", "good", "gray", "grim", "guru",
+    "gush", "gyro", "half", "hang", "hard", "hawk", "heat", "help", "high", "hill", "holy", "hope",
+    "horn", "huts", "iced", "idea", "idle", "inch", "inky", "into", "iris", "iron", "item", "jade",
+    "jazz", "join", "jolt", "jowl", "judo", "jugs", "jump", "junk", "jury", "keep", "keno", "kept",
+    "keys", "kick", "kiln", "king", "kite", "kiwi", "knob", "lamb", "lava", "lazy", "leaf", "legs",
+    "liar", "limp", "lion", "list", "logo", "loud", "love", "luau", "luck", "lung", "main", "many",
+    "math", "maze", "memo", "menu", "meow", "mild", "mint", "miss", "monk", "nail", "navy", "need",
+    "news", "next", "noon", "note", "numb", "obey", "oboe", "omit", "onyx", "open", "oval", "owls",
+    "paid", "part", "peck", "play", "plus", "poem", "pool", "pose", "puff", "puma", "purr", "quad",
+    "quiz", "race", "ramp", "real", "redo", "rich", "road", "rock", "roof", "ruby", "ruin", "runs",
+    "rust", "safe", "saga", "scar", "sets", "silk", "skew", "slot", "soap", "solo", "song", "stub",
+    "surf", "swan", "taco", "task", "taxi", "tent", "tied", "time", "tiny", "toil", "tomb", "toys",
+    "trip", "tuna", "twin", "ugly", "undo", "unit", "urge", "user", "vast", "very", "veto", "vial",
+    "vibe", "view", "visa", "void", "vows", "wall", "wand", "warm", "wasp", "wave", "waxy", "webs",
+    "what", "when", "whiz", "wolf", "work", "yank", "yawn", "yell", "yoga", "yurt", "zaps", "zero",
+    "zest", "zinc", "zone", "zoom",
+];
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    InvalidLength,
+    InvalidWord(String),
+    InvalidChecksum,
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLength => write!(f, "Invalid length"),
+            Self::InvalidWord(word) => write!(f, "Invalid byteword: {word}"),
+            Self::InvalidChecksum => write!(f, "Invalid checksum"),
+        }
+    }
+}
+
+/// CRC-32 (ISO-HDLC), used as checksum by bytewords and by the multi-part URs
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc: u32 = 0xffff_ffff;
+    for byte in data.iter() {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask: u32 = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Encode `data` with the checksum appended (first and last letter of each word)
+pub fn encode_minimal(data: &[u8]) -> String {
+    let checksum: [u8; 4] = crc32(data).to_be_bytes();
+    data.iter()
+        .chain(checksum.iter())
+        .map(|byte| {
+            let word: &[u8] = WORDS[*byte as usize].as_bytes();
+            format!("{}{}", word[0] as char, word[3] as char)
+        })
+        .collect()
+}
+
+/// Decode and verify the checksum (case insensitive)
+pub fn decode_minimal(encoded: &str) -> Result<Vec<u8>, Error> {
+    let encoded: Vec<u8> = encoded.to_ascii_lowercase().into_bytes();
+    if encoded.len() % 2 != 0 || encoded.len() < 10 {
+        return Err(Error::InvalidLength);
+    }
+
+    let mut bytes: Vec<u8> = Vec::with_capacity(encoded.len() / 2);
+    for pair in encoded.chunks(2) {
+        let index: usize = WORDS
+            .iter()
+            .position(|word| {
+                let word: &[u8] = word.as_bytes();
+                word[0] == pair[0] && word[3] == pair[1]
+            })
+            .ok_or_else(|| Error::InvalidWord(String::from_utf8_lossy(pair).to_string()))?;
+        bytes.push(index as u8);
+    }
+
+    let (data, checksum) = bytes.split_at(bytes.len() - 4);
+    if crc32(data).to_be_bytes() != checksum {
+        return Err(Error::InvalidChecksum);
+    }
+
+    Ok(data.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"Hello, world!"), 0xebe6c6e6);
+        assert_eq!(crc32(b"Wolf"), 0x598c84dc);
+    }
+
+    #[test]
+    fn test_bytewords() {
+        // BCR-2020-012 test vector
+        let data: Vec<u8> = vec![0, 1, 2, 128, 255];
+        assert_eq!(encode_minimal(&data), "aeadaolazmjendeoti");
+        assert_eq!(decode_minimal("aeadaolazmjendeoti").unwrap(), data);
+        assert_eq!(decode_minimal("AEADAOLAZMJENDEOTI").unwrap(), data);
+
+        assert_eq!(
+            decode_minimal("aeadaolazmjendeotj"),
+            Err(Error::InvalidWord(String::from("tj")))
+        );
+        assert_eq!(
+            decode_minimal("aeadaolazmjendeoto"),
+            Err(Error::InvalidChecksum)
+        );
+        assert_eq!(
+            decode_minimal("aeadaolazmjendeot"),
+            Err(Error::InvalidLength)
+        );
+    }
+}
@@ -0,0 +1,250 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Minimal CBOR (RFC 8949), only the definite-length items used by the UR types
+
+use core::fmt;
+
+const MAJOR_UINT: u8 = 0;
+const MAJOR_BYTES: u8 = 2;
+const MAJOR_ARRAY: u8 = 4;
+const MAJOR_MAP: u8 = 5;
+const MAJOR_TAG: u8 = 6;
+const FALSE: u8 = 0xf4;
+const TRUE: u8 = 0xf5;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    UnexpectedEnd,
+    /// Unexpected major type
+    UnexpectedType {
+        expected: u8,
+        found: u8,
+    },
+    /// Indefinite lengths and reserved additional info are not supported
+    Unsupported,
+    TrailingData,
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd => write!(f, "Unexpected end of data"),
+            Self::UnexpectedType { expected, found } => write!(
+                f,
+                "Unexpected major type: expected {expected}, found {found}"
+            ),
+            Self::Unsupported => write!(f, "Unsupported item"),
+            Self::TrailingData => write!(f, "Trailing data"),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Encoder {
+    buf: Vec<u8>,
+}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn head(&mut self, major: u8, value: u64) -> &mut Self {
+        let major: u8 = major << 5;
+        if value < 24 {
+            self.buf.push(major | value as u8);
+        } else if value <= u8::MAX as u64 {
+            self.buf.push(major | 24);
+            self.buf.push(value as u8);
+        } else if value <= u16::MAX as u64 {
+            self.buf.push(major | 25);
+            self.buf.extend_from_slice(&(value as u16).to_be_bytes());
+        } else if value <= u32::MAX as u64 {
+            self.buf.push(major | 26);
+            self.buf.extend_from_slice(&(value as u32).to_be_bytes());
+        } else {
+            self.buf.push(major | 27);
+            self.buf.extend_from_slice(&value.to_be_bytes());
+        }
+        self
+    }
+
+    pub fn uint(&mut self, value: u64) -> &mut Self {
+        self.head(MAJOR_UINT, value)
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        self.head(MAJOR_BYTES, bytes.len() as u64);
+        self.buf.extend_from_slice(bytes);
+        self
+    }
+
+    pub fn array(&mut self, len: usize) -> &mut Self {
+        self.head(MAJOR_ARRAY, len as u64)
+    }
+
+    pub fn map(&mut self, len: usize) -> &mut Self {
+        self.head(MAJOR_MAP, len as u64)
+    }
+
+    pub fn tag(&mut self, tag: u64) -> &mut Self {
+        self.head(MAJOR_TAG, tag)
+    }
+
+    pub fn bool(&mut self, value: bool) -> &mut Self {
+        self.buf.push(if value { TRUE } else { FALSE });
+        self
+    }
+
+    pub fn finish(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.buf)
+    }
+}
+
+pub struct Decoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let end: usize = self.pos.checked_add(len).ok_or(Error::UnexpectedEnd)?;
+        let slice: &'a [u8] = self.data.get(self.pos..end).ok_or(Error::UnexpectedEnd)?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn head(&mut self, expected: u8) -> Result<u64, Error> {
+        let initial: u8 = self.take(1)?[0];
+        let major: u8 = initial >> 5;
+        if major != expected {
+            return Err(Error::UnexpectedType {
+                expected,
+                found: major,
+            });
+        }
+        let value: u64 = match initial & 0x1f {
+            info @ 0..=23 => info as u64,
+            24 => self.take(1)?[0] as u64,
+            25 => {
+                let mut buf = [0u8; 2];
+                buf.copy_from_slice(self.take(2)?);
+                u16::from_be_bytes(buf) as u64
+            }
+            26 => {
+                let mut buf = [0u8; 4];
+                buf.copy_from_slice(self.take(4)?);
+                u32::from_be_bytes(buf) as u64
+            }
+            27 => {
+                let mut buf = [0u8; 8];
+                buf.copy_from_slice(self.take(8)?);
+                u64::from_be_bytes(buf)
+            }
+            _ => return Err(Error::Unsupported),
+        };
+        Ok(value)
+    }
+
+    pub fn uint(&mut self) -> Result<u64, Error> {
+        self.head(MAJOR_UINT)
+    }
+
+    pub fn bytes(&mut self) -> Result<&'a [u8], Error> {
+        let len: u64 = self.head(MAJOR_BYTES)?;
+        let len: usize = usize::try_from(len).map_err(|_| Error::UnexpectedEnd)?;
+        self.take(len)
+    }
+
+    pub fn array(&mut self) -> Result<u64, Error> {
+        self.head(MAJOR_ARRAY)
+    }
+
+    /// Check that all the data has been read
+    pub fn finish(&self) -> Result<(), Error> {
+        if self.pos == self.data.len() {
+            Ok(())
+        } else {
+            Err(Error::TrailingData)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::hex;
+
+    #[test]
+    fn test_encode() {
+        // RFC 8949 appendix A
+        assert_eq!(hex::encode(Encoder::new().uint(0).finish()), "00");
+        assert_eq!(hex::encode(Encoder::new().uint(23).finish()), "17");
+        assert_eq!(hex::encode(Encoder::new().uint(24).finish()), "1818");
+        assert_eq!(hex::encode(Encoder::new().uint(1000).finish()), "1903e8");
+        assert_eq!(
+            hex::encode(Encoder::new().uint(1000000).finish()),
+            "1a000f4240"
+        );
+        assert_eq!(
+            hex::encode(Encoder::new().uint(1000000000000).finish()),
+            "1b000000e8d4a51000"
+        );
+        assert_eq!(
+            hex::encode(Encoder::new().bytes(&[1, 2, 3, 4]).finish()),
+            "4401020304"
+        );
+        assert_eq!(
+            hex::encode(
+                Encoder::new()
+                    .array(4)
+                    .uint(1)
+                    .uint(2)
+                    .uint(3)
+                    .bool(false)
+                    .finish()
+            ),
+            "84010203f4"
+        );
+        assert_eq!(
+            hex::encode(Encoder::new().tag(303).map(0).finish()),
+            "d9012fa0"
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        let data: Vec<u8> = hex::decode("83011903e84401020304").unwrap();
+        let mut decoder = Decoder::new(&data);
+        assert_eq!(decoder.array(), Ok(3));
+        assert_eq!(decoder.uint(), Ok(1));
+        assert_eq!(decoder.uint(), Ok(1000));
+        assert_eq!(decoder.bytes(), Ok(&[1, 2, 3, 4][..]));
+        assert_eq!(decoder.finish(), Ok(()));
+
+        let data: Vec<u8> = hex::decode("4401020304").unwrap();
+        let mut decoder = Decoder::new(&data);
+        assert_eq!(
+            decoder.uint(),
+            Err(Error::UnexpectedType {
+                expected: 0,
+                found: 2
+            })
+        );
+
+        let data: Vec<u8> = hex::decode("440102").unwrap();
+        assert_eq!(Decoder::new(&data).bytes(), Err(Error::UnexpectedEnd));
+
+        let data: Vec<u8> = hex::decode("0000").unwrap();
+        let mut decoder = Decoder::new(&data);
+        decoder.uint().unwrap();
+        assert_eq!(decoder.finish(), Err(Error::TrailingData));
+    }
+}
@@ -0,0 +1,586 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Uniform Resources (UR), for animated QR codes
+//!
+//! <https://github.com/BlockchainCommons/Research/blob/master/papers/bcr-2020-005-ur.md>
+//!
+//! Multi-part URs are encoded with the "pure" fragments only (sequence number up to the
+//! sequence length): when decoding, the mixed fountain parts are skipped.
+
+use core::fmt;
+
+use bdk::bitcoin::psbt::{self, PartiallySignedTransaction};
+use bdk::bitcoin::secp256k1::{Secp256k1, Signing};
+use bdk::bitcoin::Network;
+
+pub mod bytewords;
+mod cbor;
+
+use self::cbor::{Decoder, Encoder};
+use crate::bips::bip32::{
+    self, Bip32, ChildNumber, DerivationPath, ExtendedPrivKey, ExtendedPubKey, Fingerprint,
+};
+use crate::bips::bip43::Purpose;
+use crate::types::Seed;
+
+const UR_SCHEME: &str = "ur:";
+pub const CRYPTO_PSBT: &str = "crypto-psbt";
+pub const CRYPTO_ACCOUNT: &str = "crypto-account";
+/// Max fragment length of the multi-part URs (bytes)
+pub const DEFAULT_MAX_FRAGMENT_LEN: usize = 200;
+/// Max sequence length (number of fragments) accepted when decoding multi-part URs
+const MAX_SEQUENCE_LEN: usize = 10_000;
+
+// Registered CBOR tags (BCR-2020-006)
+const TAG_CRYPTO_HDKEY: u64 = 303;
+const TAG_CRYPTO_KEYPATH: u64 = 304;
+const TAG_CRYPTO_COIN_INFO: u64 = 305;
+const TAG_CRYPTO_ACCOUNT: u64 = 311;
+const TAG_SCRIPT_HASH: u64 = 400;
+const TAG_PUBLIC_KEY_HASH: u64 = 403;
+const TAG_WITNESS_PUBLIC_KEY_HASH: u64 = 404;
+const TAG_TAPROOT: u64 = 409;
+
+#[derive(Debug)]
+pub enum Error {
+    BIP32(bip32::Error),
+    Bytewords(bytewords::Error),
+    Cbor(cbor::Error),
+    Psbt(psbt::Error),
+    InvalidScheme,
+    InvalidSequence,
+    NoParts,
+    UnexpectedType {
+        expected: String,
+        found: String,
+    },
+    /// Parts of different URs
+    InconsistentParts,
+    MissingFragments {
+        received: usize,
+        total: usize,
+    },
+    ChecksumMismatch,
+    UnsupportedPurpose(Purpose),
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BIP32(e) => write!(f, "BIP32: {e}"),
+            Self::Bytewords(e) => write!(f, "Bytewords: {e}"),
+            Self::Cbor(e) => write!(f, "CBOR: {e}"),
+            Self::Psbt(e) => write!(f, "Psbt: {e}"),
+            Self::InvalidScheme => write!(f, "Invalid UR: must start with `ur:`"),
+            Self::InvalidSequence => write!(f, "Invalid UR sequence"),
+            Self::NoParts => write!(f, "No UR parts"),
+            Self::UnexpectedType { expected, found } => {
+                write!(f, "Unexpected UR type: expected {expected}, found {found}")
+            }
+            Self::InconsistentParts => write!(f, "UR parts of different messages"),
+            Self::MissingFragments { received, total } => {
+                write!(f, "Missing UR parts: received {received} of {total}")
+            }
+            Self::ChecksumMismatch => write!(f, "UR checksum mismatch"),
+            Self::UnsupportedPurpose(purpose) => {
+                write!(f, "Purpose {purpose} not supported by crypto-account")
+            }
+        }
+    }
+}
+
+impl From<bip32::Error> for Error {
+    fn from(e: bip32::Error) -> Self {
+        Self::BIP32(e)
+    }
+}
+
+impl From<bytewords::Error> for Error {
+    fn from(e: bytewords::Error) -> Self {
+        Self::Bytewords(e)
+    }
+}
+
+impl From<cbor::Error> for Error {
+    fn from(e: cbor::Error) -> Self {
+        Self::Cbor(e)
+    }
+}
+
+impl From<psbt::Error> for Error {
+    fn from(e: psbt::Error) -> Self {
+        Self::Psbt(e)
+    }
+}
+
+/// Encode the CBOR `message` as UR (more parts if longer than `max_fragment_len`)
+pub fn encode(ur_type: &str, message: &[u8], max_fragment_len: usize) -> Vec<String> {
+    let max_fragment_len: usize = max_fragment_len.max(1);
+    if message.len() <= max_fragment_len {
+        return vec![format!(
+            "{UR_SCHEME}{ur_type}/{}",
+            bytewords::encode_minimal(message)
+        )];
+    }
+
+    let count: usize = (message.len() + max_fragment_len - 1) / max_fragment_len;
+    let fragment_len: usize = (message.len() + count - 1) / count;
+    let checksum: u32 = bytewords::crc32(message);
+
+    message
+        .chunks(fragment_len)
+        .enumerate()
+        .map(|(index, chunk)| {
+            // Last fragment is padded with zeros
+            let mut fragment: Vec<u8> = chunk.to_vec();
+            fragment.resize(fragment_len, 0);
+
+            let part: Vec<u8> = Encoder::new()
+                .array(5)
+                .uint(index as u64 + 1)
+                .uint(count as u64)
+                .uint(message.len() as u64)
+                .uint(checksum as u64)
+                .bytes(&fragment)
+                .finish();
+            format!(
+                "{UR_SCHEME}{ur_type}/{}-{count}/{}",
+                index + 1,
+                bytewords::encode_minimal(&part)
+            )
+        })
+        .collect()
+}
+
+struct Part {
+    seq: usize,
+    len: usize,
+    message_len: usize,
+    checksum: u32,
+    fragment: Vec<u8>,
+}
+
+impl Part {
+    fn decode(data: &[u8]) -> Result<Self, Error> {
+        let mut decoder = Decoder::new(data);
+        if decoder.array()? != 5 {
+            return Err(Error::InvalidSequence);
+        }
+        let seq: u64 = decoder.uint()?;
+        let len: u64 = decoder.uint()?;
+        let message_len: u64 = decoder.uint()?;
+        let checksum: u64 = decoder.uint()?;
+        let fragment: Vec<u8> = decoder.bytes()?.to_vec();
+        decoder.finish()?;
+        Ok(Self {
+            seq: usize::try_from(seq).map_err(|_| Error::InvalidSequence)?,
+            len: usize::try_from(len).map_err(|_| Error::InvalidSequence)?,
+            message_len: usize::try_from(message_len).map_err(|_| Error::InvalidSequence)?,
+            checksum: u32::try_from(checksum).map_err(|_| Error::InvalidSequence)?,
+            fragment,
+        })
+    }
+
+    /// Number of fragments of `fragment.len()` bytes needed for the message
+    fn expected_len(&self) -> usize {
+        let fragment_len: usize = self.fragment.len();
+        if fragment_len == 0 {
+            return 0;
+        }
+        self.message_len / fragment_len + usize::from(self.message_len % fragment_len != 0)
+    }
+}
+
+/// Decode single or multi-part UR (case insensitive)
+///
+/// Return the UR type and the CBOR message.
+pub fn decode<I, S>(parts: I) -> Result<(String, Vec<u8>), Error>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut ur_type: Option<String> = None;
+    let mut first: Option<Part> = None;
+    let mut fragments: Vec<Option<Vec<u8>>> = Vec::new();
+
+    for part in parts.into_iter() {
+        let part: String = part.as_ref().trim().to_ascii_lowercase();
+        let body: &str = part.strip_prefix(UR_SCHEME).ok_or(Error::InvalidScheme)?;
+        let components: Vec<&str> = body.split('/').collect();
+
+        match ur_type.as_deref() {
+            Some(t) if t != components[0] => return Err(Error::InconsistentParts),
+            Some(_) => (),
+            None => ur_type = Some(components[0].to_string()),
+        }
+
+        match components.as_slice() {
+            [t, payload] => {
+                if first.is_some() {
+                    return Err(Error::InconsistentParts);
+                }
+                return Ok((t.to_string(), bytewords::decode_minimal(payload)?));
+            }
+            [_, sequence, payload] => {
+                let (seq, len) = sequence.split_once('-').ok_or(Error::InvalidSequence)?;
+                let seq: usize = seq.parse().map_err(|_| Error::InvalidSequence)?;
+                let len: usize = len.parse().map_err(|_| Error::InvalidSequence)?;
+                let part = Part::decode(&bytewords::decode_minimal(payload)?)?;
+                if part.seq != seq || part.len != len || seq == 0 || len == 0 {
+                    return Err(Error::InvalidSequence);
+                }
+
+                // The sequence length is untrusted: check it before allocating the fragments
+                if len > MAX_SEQUENCE_LEN || len != part.expected_len() {
+                    return Err(Error::InvalidSequence);
+                }
+
+                match &first {
+                    Some(f) => {
+                        if f.len != part.len
+                            || f.message_len != part.message_len
+                            || f.checksum != part.checksum
+                            || f.fragment.len() != part.fragment.len()
+                        {
+                            return Err(Error::InconsistentParts);
+                        }
+                    }
+                    None => fragments = vec![None; part.len],
+                }
+
+                // Mixed fountain parts are skipped
+                if part.seq <= part.len {
+                    fragments[part.seq - 1] = Some(part.fragment.clone());
+                }
+
+                if first.is_none() {
+                    first = Some(part);
+                }
+            }
+            _ => return Err(Error::InvalidSequence),
+        }
+    }
+
+    let first: Part = first.ok_or(Error::NoParts)?;
+    let received: usize = fragments.iter().filter(|f| f.is_some()).count();
+    if received != first.len {
+        return Err(Error::MissingFragments {
+            received,
+            total: first.len,
+        });
+    }
+
+    let mut message: Vec<u8> = fragments.into_iter().flatten().flatten().collect();
+    if message.len() < first.message_len {
+        return Err(Error::InconsistentParts);
+    }
+    message.truncate(first.message_len);
+    if bytewords::crc32(&message) != first.checksum {
+        return Err(Error::ChecksumMismatch);
+    }
+
+    Ok((ur_type.unwrap_or_default(), message))
+}
+
+/// Split text with one or more URs (separated by whitespaces or concatenated)
+pub fn split_parts(text: &str) -> Vec<String> {
+    text.to_ascii_lowercase()
+        .split(UR_SCHEME)
+        .map(|part| part.split_whitespace().collect::<String>())
+        .filter(|part| !part.is_empty())
+        .map(|part| format!("{UR_SCHEME}{part}"))
+        .collect()
+}
+
+fn check_type(expected: &str, found: String) -> Result<(), Error> {
+    if found == expected {
+        Ok(())
+    } else {
+        Err(Error::UnexpectedType {
+            expected: expected.to_string(),
+            found,
+        })
+    }
+}
+
+/// Encode PSBT as `ur:crypto-psbt`
+pub fn encode_psbt(psbt: &PartiallySignedTransaction, max_fragment_len: usize) -> Vec<String> {
+    let message: Vec<u8> = Encoder::new().bytes(&psbt.serialize()).finish();
+    encode(CRYPTO_PSBT, &message, max_fragment_len)
+}
+
+/// Decode `ur:crypto-psbt` parts
+pub fn decode_psbt<I, S>(parts: I) -> Result<PartiallySignedTransaction, Error>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let (ur_type, message) = decode(parts)?;
+    check_type(CRYPTO_PSBT, ur_type)?;
+    let mut decoder = Decoder::new(&message);
+    let psbt = PartiallySignedTransaction::deserialize(decoder.bytes()?)?;
+    decoder.finish()?;
+    Ok(psbt)
+}
+
+fn fingerprint_to_u64(fingerprint: Fingerprint) -> u64 {
+    u32::from_be_bytes(fingerprint.to_bytes()) as u64
+}
+
+/// `crypto-hdkey` of an account key
+fn encode_hdkey(
+    encoder: &mut Encoder,
+    root_fingerprint: Fingerprint,
+    path: &DerivationPath,
+    pubkey: &ExtendedPubKey,
+    network: Network,
+) {
+    let is_mainnet: bool = network == Network::Bitcoin;
+    encoder
+        .tag(TAG_CRYPTO_HDKEY)
+        .map(if is_mainnet { 4 } else { 5 })
+        .uint(3)
+        .bytes(&pubkey.public_key.serialize())
+        .uint(4)
+        .bytes(pubkey.chain_code.as_bytes());
+
+    if !is_mainnet {
+        // Coin info: bitcoin (default type), testnet
+        encoder
+            .uint(5)
+            .tag(TAG_CRYPTO_COIN_INFO)
+            .map(1)
+            .uint(2)
+            .uint(1);
+    }
+
+    encoder
+        .uint(6)
+        .tag(TAG_CRYPTO_KEYPATH)
+        .map(3)
+        .uint(1)
+        .array(path.len() * 2);
+    for child in path.into_iter() {
+        match child {
+            ChildNumber::Normal { index } => encoder.uint(*index as u64).bool(false),
+            ChildNumber::Hardened { index } => encoder.uint(*index as u64).bool(true),
+        };
+    }
+    encoder
+        .uint(2)
+        .uint(fingerprint_to_u64(root_fingerprint))
+        .uint(3)
+        .uint(path.len() as u64);
+
+    encoder
+        .uint(8)
+        .uint(fingerprint_to_u64(pubkey.parent_fingerprint));
+}
+
+/// Encode the single-sig account keys of `purposes` as `ur:crypto-account`
+pub fn encode_account<C>(
+    seed: &Seed,
+    network: Network,
+    account: Option<u32>,
+    purposes: &[Purpose],
+    max_fragment_len: usize,
+    secp: &Secp256k1<C>,
+) -> Result<Vec<String>, Error>
+where
+    C: Signing,
+{
+    let root: ExtendedPrivKey = seed.to_bip32_root_key(network)?;
+    let root_fingerprint: Fingerprint = root.fingerprint(secp);
+
+    let mut encoder = Encoder::new();
+    encoder
+        .tag(TAG_CRYPTO_ACCOUNT)
+        .map(2)
+        .uint(1)
+        .uint(fingerprint_to_u64(root_fingerprint))
+        .uint(2)
+        .array(purposes.len());
+
+    for purpose in purposes.iter() {
+        match purpose {
+            Purpose::BIP44 => encoder.tag(TAG_PUBLIC_KEY_HASH),
+            Purpose::BIP49 => encoder
+                .tag(TAG_SCRIPT_HASH)
+                .tag(TAG_WITNESS_PUBLIC_KEY_HASH),
+            Purpose::BIP84 => encoder.tag(TAG_WITNESS_PUBLIC_KEY_HASH),
+            Purpose::BIP86 => encoder.tag(TAG_TAPROOT),
+            purpose => return Err(Error::UnsupportedPurpose(*purpose)),
+        };
+
+        let path: DerivationPath = purpose.to_account_extended_path(network, account)?;
+        let xpriv: ExtendedPrivKey = root.derive_priv(secp, &path)?;
+        let pubkey: ExtendedPubKey = ExtendedPubKey::from_priv(secp, &xpriv);
+        encode_hdkey(&mut encoder, root_fingerprint, &path, &pubkey, network);
+    }
+
+    Ok(encode(CRYPTO_ACCOUNT, &encoder.finish(), max_fragment_len))
+}
+
+#[cfg(test)]
+mod tests {
+    use core::str::FromStr;
+
+    use bip39::Mnemonic;
+
+    use super::*;
+    use crate::psbt::PsbtUtility;
+
+    const PSBT: &str = "cHNidP8BAFICAAAAATjFB9Xkau6+MTmNTT9GN6i299X9n9MSQhVVMVegw8qOAAAAAAD9////AcAHAAAAAAAAFgAUAhYIdK3p2Bvf/ZnzIYQcWWZkxCJ4HiUATwEENYfPA+UBpeaAAAAAVd9MbQ78ZD7Ie5K8FXctxNRCrS4DNFhPiSzC2CpygWICsOropyXycdL0H0uI5TUbJL1w8/detLdnP5WxGGUZ+5UQm/Q1S1QAAIABAACAAAAAgAABAHECAAAAAYqdaqOD/k1QaGShhL4ilryMhXgOJu+cFcKFAUMZQ+wrAAAAAAD9////Ai4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUPxCQAAAAAAABYAFO9WcMNPGiI5MjypE7Ku0dT1LOgRI9wkAAEBHy4IAAAAAAAAFgAUqjLdU2PqfvD/lSvnNLJZ0ab4kUMBAwQBAAAAIgYCyh1DqpGE/SatxQ86lKeUBXZ1BGpZuwNnGiGq9pDdTbkYm/Q1S1QAAIABAACAAAAAgAAAAAAAAAAAAAA=";
+
+    #[test]
+    fn test_crypto_psbt() {
+        let psbt = PartiallySignedTransaction::from_base64(PSBT).unwrap();
+
+        let single: Vec<String> = encode_psbt(&psbt, 1000);
+        assert_eq!(single.len(), 1);
+        assert!(single[0].starts_with("ur:crypto-psbt/"));
+        assert_eq!(decode_psbt(&single).unwrap(), psbt);
+
+        let parts: Vec<String> = encode_psbt(&psbt, 100);
+        assert!(parts.len() > 1);
+        assert!(parts[0].starts_with(&format!("ur:crypto-psbt/1-{}/", parts.len())));
+        assert_eq!(decode_psbt(&parts).unwrap(), psbt);
+
+        // Any order, uppercase (QR alphanumeric mode) and concatenated
+        let mut reversed: Vec<String> = parts.iter().rev().map(|p| p.to_uppercase()).collect();
+        assert_eq!(decode_psbt(&reversed).unwrap(), psbt);
+        assert_eq!(decode_psbt(split_parts(&parts.join(""))).unwrap(), psbt);
+        assert_eq!(decode_psbt(split_parts(&parts.join("\n"))).unwrap(), psbt);
+
+        reversed.remove(0);
+        assert!(matches!(
+            decode_psbt(&reversed),
+            Err(Error::MissingFragments { .. })
+        ));
+        assert!(matches!(
+            decode_psbt(Vec::<String>::new()),
+            Err(Error::NoParts)
+        ));
+        assert!(matches!(
+            decode_psbt(["crypto-psbt/aeadaolazmjendeoti"]),
+            Err(Error::InvalidScheme)
+        ));
+    }
+
+    #[test]
+    fn test_decode_huge_sequence_len() {
+        let part = |len: u64, message_len: u64, fragment: &[u8]| -> String {
+            let part: Vec<u8> = Encoder::new()
+                .array(5)
+                .uint(1)
+                .uint(len)
+                .uint(message_len)
+                .uint(0)
+                .bytes(fragment)
+                .finish();
+            format!(
+                "ur:crypto-psbt/1-{len}/{}",
+                bytewords::encode_minimal(&part)
+            )
+        };
+
+        // Not matching the message length
+        assert!(matches!(
+            decode([part(4_000_000_000, 10, &[0; 10])]),
+            Err(Error::InvalidSequence)
+        ));
+
+        // Matching the message length, but too long
+        assert!(matches!(
+            decode([part(4_000_000_000, 4_000_000_000, &[0])]),
+            Err(Error::InvalidSequence)
+        ));
+
+        // Empty fragment
+        assert!(matches!(
+            decode([part(1, 0, &[])]),
+            Err(Error::InvalidSequence)
+        ));
+    }
+
+    #[test]
+    fn test_crypto_account() {
+        let secp = Secp256k1::new();
+        let mnemonic = Mnemonic::from_str("easy uncover favorite crystal bless differ energy seat ecology match carry group refuse together chat observe hidden glad brave month diesel sustain depth salt").unwrap();
+        let seed = Seed::from_mnemonic(mnemonic);
+        let root_fingerprint: Fingerprint = seed.fingerprint(Network::Bitcoin, &secp).unwrap();
+
+        let parts: Vec<String> = encode_account(
+            &seed,
+            Network::Bitcoin,
+            None,
+            &[Purpose::BIP84],
+            DEFAULT_MAX_FRAGMENT_LEN,
+            &secp,
+        )
+        .unwrap();
+        assert_eq!(parts.len(), 1);
+
+        let (ur_type, message) = decode(&parts).unwrap();
+        assert_eq!(ur_type, CRYPTO_ACCOUNT);
+
+        // 311({1: fingerprint, 2: [404(303({3: key, 4: chain code, 6: 304({1: [84, true, 0, true, 0, true], 2: fingerprint, 3: 3}), 8: parent fingerprint}))]})
+        let path = DerivationPath::from_str("m/84'/0'/0'").unwrap();
+        let xpriv = seed
+            .to_bip32_root_key(Network::Bitcoin)
+            .unwrap()
+            .derive_priv(&secp, &path)
+            .unwrap();
+        let xpub = ExtendedPubKey::from_priv(&secp, &xpriv);
+        let mut expected = Encoder::new();
+        expected
+            .tag(311)
+            .map(2)
+            .uint(1)
+            .uint(fingerprint_to_u64(root_fingerprint))
+            .uint(2)
+            .array(1)
+            .tag(404)
+            .tag(303)
+            .map(4)
+            .uint(3)
+            .bytes(&xpub.public_key.serialize())
+            .uint(4)
+            .bytes(xpub.chain_code.as_bytes())
+            .uint(6)
+            .tag(304)
+            .map(3)
+            .uint(1)
+            .array(6)
+            .uint(84)
+            .bool(true)
+            .uint(0)
+            .bool(true)
+            .uint(0)
+            .bool(true)
+            .uint(2)
+            .uint(fingerprint_to_u64(root_fingerprint))
+            .uint(3)
+            .uint(3)
+            .uint(8)
+            .uint(fingerprint_to_u64(xpub.parent_fingerprint));
+        assert_eq!(message, expected.finish());
+
+        assert!(matches!(
+            encode_account(
+                &seed,
+                Network::Bitcoin,
+                None,
+                &[Purpose::BIP48 {
+                    script: crate::bips::bip48::ScriptType::P2WSH
+                }],
+                DEFAULT_MAX_FRAGMENT_LEN,
+                &secp,
+            ),
+            Err(Error::UnsupportedPurpose(..))
+        ));
+    }
+}
@@ -4,5 +4,6 @@
 pub mod base64;
 pub mod dir;
 pub mod hex;
+pub mod passphrase;
 pub mod serde;
 pub mod time;
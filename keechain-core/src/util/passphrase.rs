@@ -0,0 +1,85 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! BIP39 passphrase strength estimation
+
+use core::fmt;
+
+/// Passphrases shorter than this are [`PassphraseStrength::Weak`]
+pub const MIN_PASSPHRASE_LEN: usize = 8;
+/// Passphrases at least this long are [`PassphraseStrength::Strong`]
+pub const STRONG_PASSPHRASE_LEN: usize = 16;
+
+/// Rough strength of a passphrase
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PassphraseStrength {
+    Weak,
+    Fair,
+    Strong,
+}
+
+impl fmt::Display for PassphraseStrength {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Weak => write!(f, "weak"),
+            Self::Fair => write!(f, "fair"),
+            Self::Strong => write!(f, "strong"),
+        }
+    }
+}
+
+/// Estimate the strength of a passphrase from its length and character classes
+/// (lowercase, uppercase, digits and others)
+///
+/// * [`PassphraseStrength::Weak`]: shorter than [`MIN_PASSPHRASE_LEN`] or a single class (i.e. all lowercase)
+/// * [`PassphraseStrength::Strong`]: at least [`STRONG_PASSPHRASE_LEN`] characters, or 12 characters of 3 classes
+/// * [`PassphraseStrength::Fair`]: otherwise
+///
+/// This is only a hint: it doesn't detect common words or known passphrases.
+pub fn strength<S>(passphrase: S) -> PassphraseStrength
+where
+    S: AsRef<str>,
+{
+    let passphrase: &str = passphrase.as_ref();
+    let len: usize = passphrase.chars().count();
+
+    let classes: usize = [
+        passphrase.chars().any(char::is_lowercase),
+        passphrase.chars().any(char::is_uppercase),
+        passphrase.chars().any(|c| c.is_ascii_digit()),
+        passphrase
+            .chars()
+            .any(|c| !c.is_lowercase() && !c.is_uppercase() && !c.is_ascii_digit()),
+    ]
+    .into_iter()
+    .filter(|class| *class)
+    .count();
+
+    if len < MIN_PASSPHRASE_LEN || classes < 2 {
+        PassphraseStrength::Weak
+    } else if len >= STRONG_PASSPHRASE_LEN || (len >= 12 && classes >= 3) {
+        PassphraseStrength::Strong
+    } else {
+        PassphraseStrength::Fair
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strength() {
+        assert_eq!(strength(""), PassphraseStrength::Weak);
+        assert_eq!(strength("Ab1!"), PassphraseStrength::Weak);
+        assert_eq!(strength("alllowercase"), PassphraseStrength::Weak);
+        assert_eq!(strength("12345678901234567890"), PassphraseStrength::Weak);
+        assert_eq!(strength("Lowercase"), PassphraseStrength::Fair);
+        assert_eq!(strength("correct horse"), PassphraseStrength::Fair);
+        assert_eq!(strength("Passphrase12"), PassphraseStrength::Strong);
+        assert_eq!(
+            strength("correct horse battery staple"),
+            PassphraseStrength::Strong
+        );
+    }
+}
@@ -2,10 +2,10 @@
 // Distributed under the MIT software license
 
 use std::ops::Deref;
-use std::str::FromStr;
 use std::sync::Arc;
 
 use keechain_core::bips::bip39;
+use keechain_core::types::seed;
 use uniffi::Object;
 
 use crate::error::Result;
@@ -33,7 +33,7 @@ impl Mnemonic {
     #[uniffi::constructor]
     pub fn from_string(mnemonic: String) -> Result<Arc<Self>> {
         Ok(Arc::new(Self {
-            inner: bip39::Mnemonic::from_str(&mnemonic)?,
+            inner: seed::parse_mnemonic(mnemonic, bip39::Language::English)?,
         }))
     }
 
@@ -0,0 +1,115 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+use std::ops::Deref;
+use std::sync::Arc;
+
+use keechain_core::bitcoin::secp256k1::Secp256k1;
+use keechain_core::{descriptors, Purpose};
+use uniffi::{Enum, Record};
+
+use crate::error::Result;
+use crate::types::seed::Seed;
+use crate::types::Network;
+
+/// Single-sig script type
+#[derive(Enum)]
+pub enum ScriptType {
+    /// P2PKH (BIP44)
+    Legacy,
+    /// P2SH-WPKH (BIP49)
+    NestedSegwit,
+    /// P2WPKH (BIP84)
+    NativeSegwit,
+    /// P2TR (BIP86)
+    Taproot,
+}
+
+impl From<ScriptType> for Purpose {
+    fn from(value: ScriptType) -> Self {
+        match value {
+            ScriptType::Legacy => Self::BIP44,
+            ScriptType::NestedSegwit => Self::BIP49,
+            ScriptType::NativeSegwit => Self::BIP84,
+            ScriptType::Taproot => Self::BIP86,
+        }
+    }
+}
+
+#[derive(Record)]
+pub struct Descriptors {
+    /// Receive descriptors, ordered by purpose (BIP44, BIP49, BIP84 and BIP86)
+    pub external: Vec<String>,
+    /// Change descriptors, ordered by purpose (BIP44, BIP49, BIP84 and BIP86)
+    pub internal: Vec<String>,
+}
+
+impl From<descriptors::Descriptors> for Descriptors {
+    fn from(value: descriptors::Descriptors) -> Self {
+        Self {
+            external: value.external().iter().map(|d| d.to_string()).collect(),
+            internal: value.internal().iter().map(|d| d.to_string()).collect(),
+        }
+    }
+}
+
+#[derive(Record)]
+pub struct AddressMatch {
+    pub address: String,
+    /// Derivation path (i.e. `m/84'/0'/0'/0/3`)
+    pub path: String,
+}
+
+#[derive(Record)]
+pub struct ScanResult {
+    pub matches: Vec<AddressMatch>,
+    /// Number of derived addresses
+    pub scanned: u32,
+}
+
+impl From<descriptors::ScanResult> for ScanResult {
+    fn from(value: descriptors::ScanResult) -> Self {
+        Self {
+            matches: value
+                .matches
+                .into_iter()
+                .map(|m| AddressMatch {
+                    address: m.address.to_string(),
+                    path: m.path.to_string(),
+                })
+                .collect(),
+            scanned: value.scanned,
+        }
+    }
+}
+
+/// Export the single-sig descriptors of the `account` (default `0`)
+#[uniffi::export]
+pub fn export_descriptors(
+    seed: Arc<Seed>,
+    network: Network,
+    account: Option<u32>,
+) -> Result<Descriptors> {
+    let secp = Secp256k1::new();
+    let descriptors =
+        descriptors::Descriptors::new(seed.as_ref().deref(), network.into(), account, &secp)?;
+    Ok(descriptors.into())
+}
+
+/// Derive the receive (or change) address at `index`
+#[uniffi::export]
+pub fn derive_address(
+    seed: Arc<Seed>,
+    network: Network,
+    script_type: ScriptType,
+    account: Option<u32>,
+    change: bool,
+    index: u32,
+) -> Result<String> {
+    let secp = Secp256k1::new();
+    let network = network.into();
+    let descriptors =
+        descriptors::Descriptors::new(seed.as_ref().deref(), network, account, &secp)?;
+    let derived = descriptors.derive_address(script_type.into(), change, index, network)?;
+    Ok(derived.address.to_string())
+}
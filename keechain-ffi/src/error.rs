@@ -26,8 +26,44 @@ impl From<keechain_core::bips::bip39::Error> for KeechainError {
     }
 }
 
+impl From<keechain_core::types::seed::Error> for KeechainError {
+    fn from(e: keechain_core::types::seed::Error) -> KeechainError {
+        Self::Generic { err: e.to_string() }
+    }
+}
+
 impl From<keechain_core::types::keychain::Error> for KeechainError {
     fn from(e: keechain_core::types::keychain::Error) -> KeechainError {
         Self::Generic { err: e.to_string() }
     }
 }
+
+impl From<keechain_core::psbt::Error> for KeechainError {
+    fn from(e: keechain_core::psbt::Error) -> KeechainError {
+        Self::Generic { err: e.to_string() }
+    }
+}
+
+impl From<keechain_core::nips::nip06::Error> for KeechainError {
+    fn from(e: keechain_core::nips::nip06::Error) -> KeechainError {
+        Self::Generic { err: e.to_string() }
+    }
+}
+
+impl From<keechain_core::secp256k1::Error> for KeechainError {
+    fn from(e: keechain_core::secp256k1::Error) -> KeechainError {
+        Self::Generic { err: e.to_string() }
+    }
+}
+
+impl From<keechain_core::descriptors::Error> for KeechainError {
+    fn from(e: keechain_core::descriptors::Error) -> KeechainError {
+        Self::Generic { err: e.to_string() }
+    }
+}
+
+impl From<keechain_core::bitcoin::address::Error> for KeechainError {
+    fn from(e: keechain_core::bitcoin::address::Error) -> KeechainError {
+        Self::Generic { err: e.to_string() }
+    }
+}
@@ -2,13 +2,21 @@
 // Distributed under the MIT software license
 
 mod bips;
+mod descriptors;
 mod error;
+mod nips;
+mod psbt;
 mod types;
 
 pub use self::bips::bip39::Mnemonic;
+pub use self::descriptors::{
+    derive_address, export_descriptors, AddressMatch, Descriptors, ScanResult, ScriptType,
+};
 pub use self::error::KeechainError;
+pub use self::nips::nip06::NostrKeys;
+pub use self::psbt::{inspect_psbt, sign_psbt, PsbtOutput, PsbtSummary, SignedPsbt};
 pub use self::types::keychain::Keychain;
 pub use self::types::seed::Seed;
-pub use self::types::WordCount;
+pub use self::types::{Network, WordCount};
 
 uniffi::include_scaffolding!("keechain");
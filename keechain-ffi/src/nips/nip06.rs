@@ -0,0 +1,45 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+use std::sync::Arc;
+
+use keechain_core::nips::nip06::{self, Nip06};
+use keechain_core::secp256k1::rand::rngs::OsRng;
+use keechain_core::secp256k1::{KeyPair, Message, Secp256k1};
+use uniffi::Object;
+
+use crate::error::Result;
+use crate::types::seed::Seed;
+
+/// Nostr keys derived from seed (NIP06)
+///
+/// The secret key never leaves this object: only the `npub` and signatures are exposed.
+#[derive(Object)]
+pub struct NostrKeys {
+    inner: KeyPair,
+}
+
+#[uniffi::export]
+impl NostrKeys {
+    #[uniffi::constructor]
+    pub fn from_seed(seed: Arc<Seed>, account: u32) -> Result<Arc<Self>> {
+        let secp = Secp256k1::new();
+        Ok(Arc::new(Self {
+            inner: seed.derive_nostr_keys(account, &secp)?,
+        }))
+    }
+
+    /// Get bech32 encoded public key (`npub`)
+    pub fn public_key(&self) -> Result<String> {
+        let (public_key, _) = self.inner.x_only_public_key();
+        Ok(nip06::to_npub(&public_key)?)
+    }
+
+    /// Schnorr sign a 32-byte message (i.e. the nostr event ID)
+    pub fn sign(&self, message: Vec<u8>) -> Result<String> {
+        let secp = Secp256k1::new();
+        let message = Message::from_slice(&message)?;
+        let sig = secp.sign_schnorr_with_rng(&message, &self.inner, &mut OsRng);
+        Ok(sig.to_string())
+    }
+}
@@ -0,0 +1,79 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+use std::ops::Deref;
+use std::sync::Arc;
+
+use keechain_core::bitcoin::psbt::PartiallySignedTransaction;
+use keechain_core::bitcoin::secp256k1::Secp256k1;
+use keechain_core::psbt;
+use keechain_core::PsbtUtility;
+use uniffi::Record;
+
+use crate::error::Result;
+use crate::types::seed::Seed;
+use crate::types::Network;
+
+#[derive(Record)]
+pub struct PsbtOutput {
+    pub address: Option<String>,
+    pub value: u64,
+    pub is_change: bool,
+}
+
+impl From<psbt::PsbtOutputSummary> for PsbtOutput {
+    fn from(value: psbt::PsbtOutputSummary) -> Self {
+        Self {
+            address: value.address.map(|a| a.to_string()),
+            value: value.value,
+            is_change: value.is_change,
+        }
+    }
+}
+
+#[derive(Record)]
+pub struct PsbtSummary {
+    pub outputs: Vec<PsbtOutput>,
+    pub total_input: u64,
+    pub total_output: u64,
+    pub fee: u64,
+}
+
+impl From<psbt::PsbtSummary> for PsbtSummary {
+    fn from(value: psbt::PsbtSummary) -> Self {
+        Self {
+            outputs: value.outputs.into_iter().map(|o| o.into()).collect(),
+            total_input: value.total_input,
+            total_output: value.total_output,
+            fee: value.fee,
+        }
+    }
+}
+
+/// Inspect a base64 PSBT before signing it
+#[uniffi::export]
+pub fn inspect_psbt(seed: Arc<Seed>, psbt: String, network: Network) -> Result<PsbtSummary> {
+    let secp = Secp256k1::new();
+    let psbt = PartiallySignedTransaction::from_base64(psbt)?;
+    let summary = psbt.summary(seed.as_ref().deref(), network.into(), &secp)?;
+    Ok(summary.into())
+}
+
+#[derive(Record)]
+pub struct SignedPsbt {
+    /// Base64 PSBT
+    pub psbt: String,
+    pub finalized: bool,
+}
+
+/// Sign a base64 PSBT
+#[uniffi::export]
+pub fn sign_psbt(seed: Arc<Seed>, psbt: String, network: Network) -> Result<SignedPsbt> {
+    let secp = Secp256k1::new();
+    let mut psbt = PartiallySignedTransaction::from_base64(psbt)?;
+    let finalized: bool = psbt.sign_with_seed(seed.as_ref().deref(), network.into(), &secp)?;
+    Ok(SignedPsbt {
+        psbt: psbt.as_base64(),
+        finalized,
+    })
+}
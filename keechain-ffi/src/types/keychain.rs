@@ -2,13 +2,20 @@
 // Distributed under the MIT software license
 
 use std::ops::Deref;
+use std::str::FromStr;
 use std::sync::Arc;
 
+use keechain_core::bitcoin::secp256k1::Secp256k1;
+use keechain_core::bitcoin::{self, Address};
 use keechain_core::types::keychain;
+use keechain_core::Purpose;
 use uniffi::Object;
 
 use super::seed::Seed;
+use super::Network;
 use crate::bips::bip39::Mnemonic;
+use crate::descriptors::{ScanResult, ScriptType};
+use crate::error::Result;
 
 #[derive(Object)]
 pub struct Keychain {
@@ -28,6 +35,10 @@ impl Keychain {
         Arc::new(self.inner.mnemonic().into())
     }
 
+    pub fn passphrase(&self) -> Option<String> {
+        self.inner.passphrase()
+    }
+
     pub fn passphrases(&self) -> Vec<String> {
         self.inner.passphrases()
     }
@@ -36,6 +47,35 @@ impl Keychain {
         Arc::new(self.inner.seed().clone().into())
     }
 
+    /// Look for the `candidates` addresses, from index `start` to `end` (excluded) of each account,
+    /// script type and chain
+    pub fn scan_addresses(
+        &self,
+        candidates: Vec<String>,
+        network: Network,
+        accounts: Vec<u32>,
+        script_types: Vec<ScriptType>,
+        start: u32,
+        end: u32,
+    ) -> Result<ScanResult> {
+        let secp = Secp256k1::new();
+        let network: bitcoin::Network = network.into();
+        let candidates: Vec<Address> = candidates
+            .into_iter()
+            .map(|c| Ok(Address::from_str(&c)?.require_network(network)?))
+            .collect::<Result<_>>()?;
+        let purposes: Vec<Purpose> = script_types.into_iter().map(Purpose::from).collect();
+        let result = self.inner.scan_addresses(
+            &candidates,
+            network,
+            &accounts,
+            &purposes,
+            start..end,
+            &secp,
+        )?;
+        Ok(result.into())
+    }
+
     /* pub fn identity(&self, network: Network) -> Result<Fingerprint, Error> {
         Ok(self.seed.fingerprint(network)?)
     } */
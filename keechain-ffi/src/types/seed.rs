@@ -4,16 +4,25 @@
 use std::ops::Deref;
 use std::sync::Arc;
 
+use keechain_core::bips::bip39::Language;
 use keechain_core::types::seed;
 use uniffi::Object;
 
 use crate::bips::bip39::Mnemonic;
+use crate::error::Result;
 
 #[derive(Object)]
 pub struct Seed {
     inner: seed::Seed,
 }
 
+impl Deref for Seed {
+    type Target = seed::Seed;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
 impl From<seed::Seed> for Seed {
     fn from(inner: seed::Seed) -> Self {
         Self { inner }
@@ -29,6 +38,14 @@ impl Seed {
         })
     }
 
+    /// Validate and normalize an english mnemonic and build the seed
+    #[uniffi::constructor]
+    pub fn from_mnemonic_str(mnemonic: String, passphrase: Option<String>) -> Result<Arc<Self>> {
+        Ok(Arc::new(Self {
+            inner: seed::Seed::from_mnemonic_str(mnemonic, passphrase, Language::English)?,
+        }))
+    }
+
     pub fn mnemonic(&self) -> String {
         self.inner.mnemonic().to_string()
     }
@@ -7,6 +7,9 @@ mod heading;
 mod identity;
 mod input_field;
 mod mnemonic;
+mod network_banner;
+#[cfg(feature = "pin")]
+mod pin_pad;
 mod read_only_field;
 mod version;
 mod view;
@@ -17,6 +20,9 @@ pub use self::heading::Heading;
 pub use self::identity::Identity;
 pub use self::input_field::InputField;
 pub use self::mnemonic::MnemonicViewer;
+pub use self::network_banner::NetworkBanner;
+#[cfg(feature = "pin")]
+pub use self::pin_pad::PinPad;
 pub use self::read_only_field::ReadOnlyField;
 pub use self::version::Version;
 pub use self::view::View;
@@ -0,0 +1,36 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+use eframe::egui::{Context, Frame, RichText, TopBottomPanel};
+use keechain_core::bitcoin::Network;
+
+use crate::theme::color::{self, WHITE};
+
+/// Persistent banner with the active network, to avoid signing on the wrong one
+pub struct NetworkBanner {
+    network: Network,
+}
+
+impl NetworkBanner {
+    pub fn new(network: Network) -> Self {
+        Self { network }
+    }
+
+    pub fn show(self, ctx: &Context) {
+        let text: String = match self.network {
+            Network::Bitcoin => String::from("MAINNET"),
+            network => network.to_string().to_uppercase(),
+        };
+        TopBottomPanel::top("network_banner")
+            .frame(
+                Frame::none()
+                    .fill(color::network(self.network))
+                    .inner_margin(4.0),
+            )
+            .show(ctx, |ui| {
+                ui.vertical_centered(|ui| {
+                    ui.label(RichText::new(text).small().strong().color(WHITE));
+                });
+            });
+    }
+}
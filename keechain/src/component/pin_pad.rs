@@ -0,0 +1,107 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+use eframe::egui::{self, Align, Event, Grid, Key, Layout, RichText, Ui, WidgetText};
+use eframe::epaint::Vec2;
+use keechain_core::types::keechain::MAX_PIN_LEN;
+
+use crate::theme::color::DARK_GRAY;
+
+const KEY_SIZE: Vec2 = egui::vec2(64.0, 40.0);
+const KEYS: [[&str; 3]; 4] = [
+    ["1", "2", "3"],
+    ["4", "5", "6"],
+    ["7", "8", "9"],
+    ["C", "0", "⌫"],
+];
+
+/// Numeric keypad
+pub struct PinPad {
+    label: WidgetText,
+    enabled: bool,
+    keyboard: bool,
+}
+
+impl PinPad {
+    pub fn new<T>(label: T) -> Self
+    where
+        T: Into<WidgetText>,
+    {
+        Self {
+            label: label.into(),
+            enabled: true,
+            keyboard: false,
+        }
+    }
+
+    pub fn enabled(self, enabled: bool) -> Self {
+        Self { enabled, ..self }
+    }
+
+    /// Accept the digits typed on the keyboard (only if no other field is on the screen)
+    pub fn keyboard(self) -> Self {
+        Self {
+            keyboard: true,
+            ..self
+        }
+    }
+
+    pub fn render(self, ui: &mut Ui, pin: &mut String) {
+        ui.with_layout(Layout::top_down(Align::Center), |ui| {
+            ui.label(self.label.clone());
+            ui.add_space(0.5);
+            let masked: String = if pin.is_empty() {
+                String::from("-")
+            } else {
+                "●".repeat(pin.len())
+            };
+            ui.label(RichText::new(masked).size(24.0));
+            ui.add_space(5.0);
+
+            if !self.enabled {
+                return;
+            }
+
+            if self.keyboard {
+                ui.input(|i| {
+                    for event in i.events.iter() {
+                        if let Event::Text(text) = event {
+                            for c in text.chars().filter(char::is_ascii_digit) {
+                                push(pin, c);
+                            }
+                        }
+                    }
+                    if i.key_pressed(Key::Backspace) {
+                        pin.pop();
+                    }
+                });
+            }
+
+            Grid::new(self.label.text())
+                .spacing(egui::vec2(5.0, 5.0))
+                .show(ui, |ui| {
+                    for row in KEYS.iter() {
+                        for key in row.iter() {
+                            let button = egui::Button::new(*key).fill(DARK_GRAY);
+                            if ui.add_sized(KEY_SIZE, button).clicked() {
+                                match *key {
+                                    "C" => pin.clear(),
+                                    "⌫" => {
+                                        pin.pop();
+                                    }
+                                    digit => digit.chars().for_each(|c| push(pin, c)),
+                                }
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+}
+
+fn push(pin: &mut String, c: char) {
+    if pin.len() < MAX_PIN_LEN {
+        pin.push(c);
+    }
+}
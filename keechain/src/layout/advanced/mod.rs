@@ -3,3 +3,4 @@
 
 pub mod danger;
 pub mod deterministic_entropy;
+pub mod verify_address;
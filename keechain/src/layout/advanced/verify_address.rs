@@ -0,0 +1,159 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+use std::str::FromStr;
+
+use eframe::egui::{Key, RichText, Ui};
+use keechain_core::bitcoin::Address;
+use keechain_core::descriptors::{Chain, ScanResult};
+use keechain_core::{Purpose, Result};
+
+use crate::component::{Button, Error, Heading, InputField, View};
+use crate::theme::color::{DARK_GREEN, ORANGE, RED};
+use crate::{AppState, Menu, Stage, SECP256K1};
+
+const PURPOSES: [Purpose; 4] = [
+    Purpose::BIP44,
+    Purpose::BIP49,
+    Purpose::BIP84,
+    Purpose::BIP86,
+];
+const DEFAULT_MAX_INDEX: u32 = 1000;
+
+#[derive(Default)]
+pub struct VerifyAddressState {
+    password: String,
+    address: String,
+    account: String,
+    max_index: String,
+    result: Option<ScanResult>,
+    error: Option<String>,
+}
+
+impl VerifyAddressState {
+    pub fn clear(&mut self) {
+        self.password.clear();
+        self.address = String::new();
+        self.account = String::new();
+        self.max_index = String::new();
+        self.result = None;
+        self.error = None;
+    }
+}
+
+pub fn update(app: &mut AppState, ui: &mut Ui) {
+    if app.keechain.is_none() {
+        app.set_stage(Stage::Start);
+    }
+
+    View::show(ui, |ui| {
+        Heading::new("Verify address").render(ui);
+
+        InputField::new("Password")
+            .placeholder("Password")
+            .is_password()
+            .render(ui, &mut app.layouts.verify_address.password);
+
+        ui.add_space(7.0);
+
+        InputField::new("Address")
+            .placeholder("Address")
+            .render(ui, &mut app.layouts.verify_address.address);
+
+        ui.add_space(7.0);
+
+        InputField::new("Account")
+            .placeholder("0")
+            .render(ui, &mut app.layouts.verify_address.account);
+
+        ui.add_space(7.0);
+
+        InputField::new("Addresses to scan (for each script type and chain)")
+            .placeholder(DEFAULT_MAX_INDEX.to_string())
+            .render(ui, &mut app.layouts.verify_address.max_index);
+
+        ui.add_space(7.0);
+
+        if let Some(result) = &app.layouts.verify_address.result {
+            match result.matches.first() {
+                Some(derived) => {
+                    ui.label(
+                        RichText::new("The address belongs to the keychain").color(DARK_GREEN),
+                    );
+                    ui.label(format!(
+                        "Path: {} ({}, {})",
+                        derived.path,
+                        derived.purpose.script_name(),
+                        Chain::from(derived.change)
+                    ));
+                }
+                None => {
+                    ui.label(
+                        RichText::new(format!(
+                            "Address not found ({} addresses scanned)",
+                            result.scanned
+                        ))
+                        .color(RED),
+                    );
+                }
+            }
+            ui.add_space(7.0);
+        }
+
+        if let Some(error) = &app.layouts.verify_address.error {
+            Error::new(error).render(ui);
+        }
+
+        ui.add_space(15.0);
+
+        let is_ready: bool = !app.layouts.verify_address.password.is_empty()
+            && !app.layouts.verify_address.address.is_empty();
+
+        let button = Button::new("Verify")
+            .background_color(ORANGE)
+            .enabled(is_ready)
+            .render(ui);
+
+        if is_ready && (ui.input(|i| i.key_pressed(Key::Enter)) || button.clicked()) {
+            match scan(app) {
+                Ok(result) => {
+                    app.layouts.verify_address.error = None;
+                    app.layouts.verify_address.result = Some(result);
+                }
+                Err(e) => {
+                    app.layouts.verify_address.result = None;
+                    app.layouts.verify_address.error = Some(e.to_string());
+                }
+            }
+        }
+
+        ui.add_space(5.0);
+        if Button::new("Back").render(ui).clicked() {
+            app.layouts.verify_address.clear();
+            app.stage = Stage::Menu(Menu::Advanced);
+        }
+    });
+}
+
+fn scan(app: &AppState) -> Result<ScanResult> {
+    let state = &app.layouts.verify_address;
+    let keechain = app.keechain.as_ref().ok_or("Impossible to get keechain")?;
+    let address: Address = Address::from_str(state.address.trim())?.require_network(app.network)?;
+    let account: u32 = match state.account.trim() {
+        "" => 0,
+        account => account.parse()?,
+    };
+    let max_index: u32 = match state.max_index.trim() {
+        "" => DEFAULT_MAX_INDEX,
+        max_index => max_index.parse()?,
+    };
+    let keychain = keechain.keychain(state.password.clone())?;
+    Ok(keychain.scan_addresses(
+        &[address],
+        app.network,
+        &[account],
+        &PURPOSES,
+        0..max_index,
+        &SECP256K1,
+    )?)
+}
@@ -6,7 +6,7 @@ use std::str::FromStr;
 
 use eframe::egui::{Align, ComboBox, Layout, RichText, Ui};
 use keechain_core::bitcoin::Network;
-use keechain_core::{Electrum, ElectrumSupportedScripts, Index, KeeChain, Result};
+use keechain_core::{Electrum, ElectrumSupportedScripts, Index, KeeChain, Result, WalletExport};
 
 use crate::component::{Button, Error, Heading, Identity, InputField, View};
 use crate::theme::color::{DARK_GREEN, ORANGE};
@@ -21,8 +21,8 @@ fn export_electrum(
 ) -> Result<PathBuf> {
     let seed = keechain.seed(password)?;
     let electrum_json_wallet = Electrum::new(&seed, network, script, account, &SECP256K1)?;
-    let home_dir: PathBuf = keechain_common::home();
-    Ok(electrum_json_wallet.save_to_file(home_dir)?)
+    let export_dir: PathBuf = keechain_common::export_dir();
+    Ok(electrum_json_wallet.save_to_file(export_dir)?)
 }
 
 const WALLET_TYPES: [(ElectrumSupportedScripts, &str); 3] = [
@@ -0,0 +1,161 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use eframe::egui::{Align, ComboBox, Layout, RichText, Ui};
+use keechain_core::bitcoin::Network;
+use keechain_core::{Index, KeeChain, Result, Sparrow, SparrowScriptType, WalletExport};
+
+use crate::component::{Button, Error, Heading, Identity, InputField, View};
+use crate::theme::color::{DARK_GREEN, ORANGE};
+use crate::{AppState, Menu, Stage, SECP256K1};
+
+fn export_sparrow(
+    keechain: &KeeChain,
+    password: String,
+    network: Network,
+    script: SparrowScriptType,
+    account: Option<u32>,
+) -> Result<PathBuf> {
+    let seed = keechain.seed(password)?;
+    let sparrow = Sparrow::new(&seed, network, script, account, &SECP256K1)?;
+    let export_dir: PathBuf = keechain_common::export_dir();
+    Ok(sparrow.save_to_file(export_dir)?)
+}
+
+const WALLET_TYPES: [(SparrowScriptType, &str); 4] = [
+    (SparrowScriptType::Legacy, "Legacy (BIP44)"),
+    (SparrowScriptType::NestedSegwit, "Nested Segwit (BIP49)"),
+    (SparrowScriptType::NativeSegwit, "Native Segwit (BIP84)"),
+    (SparrowScriptType::Taproot, "Taproot (BIP86)"),
+];
+
+#[derive(Default)]
+pub struct ExportSparrowState {
+    password: String,
+    script: SparrowScriptType,
+    account: String,
+    result: Option<String>,
+    error: Option<String>,
+}
+
+impl ExportSparrowState {
+    pub fn clear(&mut self) {
+        self.password.clear();
+        self.script = SparrowScriptType::default();
+        self.account.clear();
+        self.result = None;
+        self.error = None;
+    }
+}
+
+pub fn update(app: &mut AppState, ui: &mut Ui) {
+    if app.keechain.is_none() {
+        app.set_stage(Stage::Start);
+    }
+
+    View::show(ui, |ui| {
+        Heading::new("Export Sparrow").render(ui);
+
+        if let Some(keechain) = &app.keechain {
+            Identity::new(keechain.identity(), keechain.passphrase()).render(ui);
+            ui.add_space(15.0);
+        }
+
+        InputField::new("Password")
+            .placeholder("Password")
+            .is_password()
+            .render(ui, &mut app.layouts.export_sparrow.password);
+
+        ui.add_space(7.0);
+
+        ui.with_layout(Layout::top_down(Align::Min), |ui| {
+            ui.add_space(1.0);
+            ui.label("Type");
+            ui.horizontal_wrapped(|ui| {
+                ComboBox::from_id_source("type")
+                    .width(ui.available_width())
+                    .selected_text(
+                        WALLET_TYPES
+                            .iter()
+                            .find(|&&t| t.0 == app.layouts.export_sparrow.script)
+                            .map(|t| t.1)
+                            .unwrap_or("Impossible to get value"),
+                    )
+                    .show_ui(ui, |ui| {
+                        for (script, label) in WALLET_TYPES.into_iter() {
+                            ui.selectable_value(
+                                &mut app.layouts.export_sparrow.script,
+                                script,
+                                label,
+                            );
+                        }
+                    });
+            })
+        });
+
+        ui.add_space(7.0);
+
+        InputField::new("Account")
+            .placeholder("Account (between 0 and 2^31 - 1)")
+            .render(ui, &mut app.layouts.export_sparrow.account);
+
+        if let Some(result) = &app.layouts.export_sparrow.result {
+            ui.add_space(7.0);
+            ui.label(RichText::new(result).color(DARK_GREEN));
+        }
+
+        if let Some(error) = &app.layouts.export_sparrow.error {
+            ui.add_space(7.0);
+            Error::new(error).render(ui);
+        }
+
+        ui.add_space(15.0);
+
+        let is_ready: bool = !app.layouts.export_sparrow.account.is_empty();
+
+        let button = Button::new("Export")
+            .background_color(ORANGE)
+            .enabled(is_ready)
+            .render(ui);
+
+        if is_ready && button.clicked() {
+            match app.keechain.as_mut() {
+                Some(keechain) => {
+                    match Index::from_str(app.layouts.export_sparrow.account.as_str()) {
+                        Ok(index) => {
+                            match export_sparrow(
+                                keechain,
+                                app.layouts.export_sparrow.password.clone(),
+                                app.network,
+                                app.layouts.export_sparrow.script,
+                                Some(index.as_u32()),
+                            ) {
+                                Ok(path) => {
+                                    app.layouts.export_sparrow.error = None;
+                                    app.layouts.export_sparrow.result =
+                                        Some(format!("File exported to {}", path.display()));
+                                }
+                                Err(e) => app.layouts.export_sparrow.error = Some(e.to_string()),
+                            }
+                        }
+                        Err(e) => app.layouts.export_sparrow.error = Some(e.to_string()),
+                    }
+                }
+                None => {
+                    app.layouts.export_sparrow.error =
+                        Some("Impossible to get keechain".to_string())
+                }
+            }
+        }
+
+        ui.add_space(5.0);
+
+        if Button::new("Back").render(ui).clicked() {
+            app.layouts.export_sparrow.clear();
+            app.stage = Stage::Menu(Menu::Export);
+        }
+    });
+}
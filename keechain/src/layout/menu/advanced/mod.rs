@@ -26,6 +26,10 @@ pub fn update(app: &mut AppState, ui: &mut Ui) {
             app.stage = Stage::Command(Command::DeterministicEntropy);
         }
         ui.add_space(5.0);
+        if Button::new("Verify address").render(ui).clicked() {
+            app.stage = Stage::Command(Command::VerifyAddress);
+        }
+        ui.add_space(5.0);
         if Button::new("Danger")
             .background_color(DARK_RED)
             .render(ui)
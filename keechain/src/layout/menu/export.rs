@@ -39,6 +39,10 @@ pub fn update(app: &mut AppState, ui: &mut Ui) {
             app.set_stage(Stage::Command(Command::Export(ExportTypes::Electrum)));
         }
         ui.add_space(5.0);
+        if Button::new("Sparrow").render(ui).clicked() {
+            app.set_stage(Stage::Command(Command::Export(ExportTypes::Sparrow)));
+        }
+        ui.add_space(5.0);
         if Button::new("Back").render(ui).clicked() {
             app.stage = Stage::Menu(Menu::Main);
         }
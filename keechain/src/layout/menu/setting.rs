@@ -26,6 +26,13 @@ pub fn update(app: &mut AppState, ui: &mut Ui) {
         if Button::new("Change password").render(ui).clicked() {
             app.stage = Stage::Command(Command::ChangePassword);
         }
+        #[cfg(feature = "pin")]
+        {
+            ui.add_space(5.0);
+            if Button::new("PIN unlock").render(ui).clicked() {
+                app.stage = Stage::Command(Command::PinUnlock);
+            }
+        }
         ui.add_space(5.0);
         if Button::new("Back").render(ui).clicked() {
             app.stage = Stage::Menu(Menu::Main);
@@ -14,11 +14,15 @@ pub mod start;
 pub use self::advanced::danger::view_secrets::ViewSecretsState;
 pub use self::advanced::danger::wipe::WipeKeychainState;
 pub use self::advanced::deterministic_entropy::DeterministicEntropyState;
+pub use self::advanced::verify_address::VerifyAddressState;
 pub use self::export::electrum::ExportElectrumState;
+pub use self::export::sparrow::ExportSparrowState;
 pub use self::new_keychain::NewKeychainState;
 pub use self::passphrase::PassphraseState;
 pub use self::restore::RestoreState;
 pub use self::setting::change_password::ChangePasswordState;
+#[cfg(feature = "pin")]
+pub use self::setting::pin::PinUnlockState;
 pub use self::setting::rename::RenameKeychainState;
 pub use self::sign::SignState;
 pub use self::start::StartState;
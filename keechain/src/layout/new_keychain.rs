@@ -1,8 +1,8 @@
 // Copyright (c) 2022-2023 Yuki Kishimoto
 // Distributed under the MIT software license
 
-use eframe::egui::{Align, ComboBox, Key, Layout, Ui};
-use keechain_core::bips::bip39::Mnemonic;
+use eframe::egui::{Align, ComboBox, Event, Key, Layout, Pos2, ProgressBar, Ui};
+use keechain_core::bips::bip39::{self, EntropyMode, Mnemonic};
 use keechain_core::types::{KeeChain, WordCount};
 
 use crate::component::{Button, Error, Heading, InputField, MnemonicViewer, View};
@@ -10,6 +10,8 @@ use crate::theme::color::ORANGE;
 use crate::{AppState, Menu, Stage, KEYCHAINS_PATH, SECP256K1};
 
 const WORD_COUNT_OPTIONS: [WordCount; 3] = [WordCount::W12, WordCount::W18, WordCount::W24];
+/// Number of mouse/keyboard events to collect before generating
+const USER_ENTROPY_SAMPLES: usize = 256;
 
 #[derive(Default)]
 pub struct NewKeychainState {
@@ -17,6 +19,11 @@ pub struct NewKeychainState {
     password: String,
     confirm_password: String,
     word_count: WordCount,
+    add_user_entropy: bool,
+    collecting_entropy: bool,
+    entropy: Vec<u8>,
+    entropy_samples: usize,
+    last_pointer_pos: Option<Pos2>,
     keechain: Option<KeeChain>,
     mnemonic: Option<Mnemonic>,
     confirm_saved_mnemonic: bool,
@@ -29,6 +36,11 @@ impl NewKeychainState {
         self.password = String::new();
         self.confirm_password = String::new();
         self.word_count = WordCount::default();
+        self.add_user_entropy = false;
+        self.collecting_entropy = false;
+        self.entropy = Vec::new();
+        self.entropy_samples = 0;
+        self.last_pointer_pos = None;
         self.keechain = None;
         self.mnemonic = None;
         self.confirm_saved_mnemonic = false;
@@ -42,6 +54,8 @@ pub fn update(app: &mut AppState, ui: &mut Ui) {
 
         if let Some(keechain) = app.layouts.new_keychain.keechain.clone() {
             show_mnemonic_layout(app, keechain, app.layouts.new_keychain.mnemonic.clone(), ui);
+        } else if app.layouts.new_keychain.collecting_entropy {
+            collect_entropy_layout(app, ui);
         } else {
             generate_layout(app, ui);
         }
@@ -90,6 +104,15 @@ fn generate_layout(app: &mut AppState, ui: &mut Ui) {
 
     ui.add_space(7.0);
 
+    ui.with_layout(Layout::top_down(Align::Min), |ui| {
+        ui.checkbox(
+            &mut app.layouts.new_keychain.add_user_entropy,
+            "Add entropy from mouse movements and key presses",
+        );
+    });
+
+    ui.add_space(7.0);
+
     if let Some(error) = &app.layouts.new_keychain.error {
         Error::new(error).render(ui);
     }
@@ -114,21 +137,101 @@ fn generate_layout(app: &mut AppState, ui: &mut Ui) {
     }
 
     if is_ready && (ui.input(|i| i.key_pressed(Key::Enter)) || button.clicked()) {
-        match KeeChain::generate(
-            KEYCHAINS_PATH.as_path(),
-            app.layouts.new_keychain.name.clone(),
-            || Ok(app.layouts.new_keychain.password.clone()),
-            || Ok(app.layouts.new_keychain.confirm_password.clone()),
-            app.layouts.new_keychain.word_count,
-            || Ok(None),
-            app.network,
-            &SECP256K1,
-        ) {
-            Ok(keechain) => {
-                app.layouts.new_keychain.keechain = Some(keechain);
+        if let Err(e) = keechain_common::ensure_writable(KEYCHAINS_PATH.as_path()) {
+            app.layouts.new_keychain.error = Some(e.to_string());
+            return;
+        }
+
+        if app.layouts.new_keychain.add_user_entropy {
+            app.layouts.new_keychain.collecting_entropy = true;
+        } else {
+            generate(app, None);
+        }
+    }
+}
+
+fn collect_entropy_layout(app: &mut AppState, ui: &mut Ui) {
+    ui.label("Move your mouse and mash keys randomly until the bar is full");
+
+    ui.add_space(15.0);
+
+    let time: f64 = ui.input(|i| i.time);
+    let events: Vec<Event> = ui.input(|i| i.events.clone());
+    let state = &mut app.layouts.new_keychain;
+
+    for event in events.into_iter() {
+        if state.entropy_samples >= USER_ENTROPY_SAMPLES {
+            break;
+        }
+
+        match event {
+            Event::PointerMoved(pos) => {
+                // Skip samples without movement
+                if state.last_pointer_pos == Some(pos) {
+                    continue;
+                }
+                state.last_pointer_pos = Some(pos);
+                state.entropy.extend(pos.x.to_le_bytes());
+                state.entropy.extend(pos.y.to_le_bytes());
             }
-            Err(e) => app.layouts.new_keychain.error = Some(e.to_string()),
+            Event::Key {
+                key, pressed: true, ..
+            } => state.entropy.extend((key as u32).to_le_bytes()),
+            Event::Text(text) => state.entropy.extend(text.as_bytes()),
+            _ => continue,
+        }
+
+        // Timing jitter
+        state.entropy.extend(time.to_le_bytes());
+        state.entropy_samples += 1;
+    }
+
+    let progress: f32 = state.entropy_samples as f32 / USER_ENTROPY_SAMPLES as f32;
+    ui.add(ProgressBar::new(progress).show_percentage());
+
+    ui.add_space(15.0);
+
+    let is_ready: bool = state.entropy_samples >= USER_ENTROPY_SAMPLES;
+
+    let button = Button::new("Generate")
+        .background_color(ORANGE)
+        .enabled(is_ready)
+        .render(ui);
+
+    ui.add_space(5.0);
+
+    if Button::new("Back").render(ui).clicked() {
+        state.collecting_entropy = false;
+        state.entropy = Vec::new();
+        state.entropy_samples = 0;
+        state.last_pointer_pos = None;
+    }
+
+    if is_ready && button.clicked() {
+        let entropy: Vec<u8> = std::mem::take(&mut app.layouts.new_keychain.entropy);
+        app.layouts.new_keychain.collecting_entropy = false;
+        app.layouts.new_keychain.entropy_samples = 0;
+        app.layouts.new_keychain.last_pointer_pos = None;
+        generate(app, Some(entropy));
+    }
+}
+
+fn generate(app: &mut AppState, custom_entropy: Option<Vec<u8>>) {
+    match KeeChain::generate(
+        KEYCHAINS_PATH.as_path(),
+        app.layouts.new_keychain.name.clone(),
+        || Ok(app.layouts.new_keychain.password.clone()),
+        || Ok(app.layouts.new_keychain.confirm_password.clone()),
+        app.layouts.new_keychain.word_count,
+        || Ok(custom_entropy),
+        EntropyMode::Mixed,
+        app.network,
+        &SECP256K1,
+    ) {
+        Ok(keechain) => {
+            app.layouts.new_keychain.keechain = Some(keechain);
         }
+        Err(e) => app.layouts.new_keychain.error = Some(e.to_string()),
     }
 }
 
@@ -139,6 +242,13 @@ fn show_mnemonic_layout(
     ui: &mut Ui,
 ) {
     if let Some(mnemonic) = mnemonic {
+        ui.label(format!("Language: {}", mnemonic.language()));
+        ui.label(format!(
+            "Word count: {} ({} bits of entropy)",
+            mnemonic.word_count(),
+            bip39::entropy_bits(&mnemonic)
+        ));
+        ui.add_space(5.0);
         MnemonicViewer::new(mnemonic).render(ui);
     }
 
@@ -3,9 +3,10 @@
 
 use eframe::egui::{Align, Key, Layout, RichText, Ui};
 use eframe::epaint::Color32;
+use keechain_core::util::passphrase::{self, PassphraseStrength};
 
 use crate::component::{Button, Error, Heading, Identity, InputField, View};
-use crate::theme::color::{DARK_RED, ORANGE};
+use crate::theme::color::{DARK_GREEN, DARK_RED, ORANGE, RED};
 use crate::{AppState, Menu, Stage, SECP256K1};
 
 #[derive(Default)]
@@ -90,6 +91,17 @@ pub fn apply_new_layout(app: &mut AppState, ui: &mut Ui) {
         .placeholder("Passphrase")
         .render(ui, &mut app.layouts.passphrase.passphrase);
 
+    if !app.layouts.passphrase.passphrase.is_empty() {
+        let strength = passphrase::strength(&app.layouts.passphrase.passphrase);
+        let color: Color32 = match strength {
+            PassphraseStrength::Weak => RED,
+            PassphraseStrength::Fair => ORANGE,
+            PassphraseStrength::Strong => DARK_GREEN,
+        };
+        ui.add_space(3.0);
+        ui.label(RichText::new(format!("Strength: {strength}")).color(color));
+    }
+
     ui.add_space(7.0);
 
     if let Some(error) = &app.layouts.passphrase.error {
@@ -1,12 +1,10 @@
 // Copyright (c) 2022-2023 Yuki Kishimoto
 // Distributed under the MIT software license
 
-use std::str::FromStr;
-
 use eframe::egui::{Key, RichText, Ui};
 use eframe::epaint::Color32;
-use keechain_core::bips::bip39::Mnemonic;
-use keechain_core::types::KeeChain;
+use keechain_core::bips::bip39::Language;
+use keechain_core::types::{seed, KeeChain};
 
 use crate::component::{Button, Heading, InputField, View};
 use crate::theme::color::ORANGE;
@@ -86,7 +84,12 @@ pub fn update(app: &mut AppState, ui: &mut Ui) {
         }
 
         if is_ready && (ui.input(|i| i.key_pressed(Key::Enter)) || button.clicked()) {
-            match Mnemonic::from_str(&app.layouts.restore.mnemonic) {
+            if let Err(e) = keechain_common::ensure_writable(KEYCHAINS_PATH.as_path()) {
+                app.layouts.restore.error = Some(e.to_string());
+                return;
+            }
+
+            match seed::parse_mnemonic(&app.layouts.restore.mnemonic, Language::English) {
                 Ok(mnemonic) => match KeeChain::restore(
                     KEYCHAINS_PATH.as_path(),
                     app.layouts.restore.name.clone(),
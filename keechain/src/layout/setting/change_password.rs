@@ -5,7 +5,7 @@ use eframe::egui::{Key, Ui};
 
 use crate::component::{Button, Error, Heading, InputField, View};
 use crate::theme::color::ORANGE;
-use crate::{AppState, Menu, Stage};
+use crate::{AppState, Menu, Stage, SECP256K1};
 
 #[derive(Default)]
 pub struct ChangePasswordState {
@@ -74,6 +74,7 @@ pub fn update(app: &mut AppState, ui: &mut Ui) {
                         || Ok(app.layouts.change_password.current_password.clone()),
                         || Ok(app.layouts.change_password.new_password.clone()),
                         || Ok(app.layouts.change_password.confirm_new_password.clone()),
+                        &SECP256K1,
                     ) {
                         Ok(_) => {
                             app.layouts.change_password.clear();
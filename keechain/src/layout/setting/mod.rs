@@ -2,4 +2,6 @@
 // Distributed under the MIT software license
 
 pub mod change_password;
+#[cfg(feature = "pin")]
+pub mod pin;
 pub mod rename;
@@ -0,0 +1,194 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+use eframe::egui::{Key, RichText, Ui};
+use keechain_core::types::keechain::{MAX_PIN_LEN, MIN_PIN_LEN};
+
+use crate::component::{Button, Error, Heading, InputField, PinPad, View};
+use crate::theme::color::{ORANGE, RED};
+use crate::{AppState, Menu, Stage, SECP256K1};
+
+#[derive(Default)]
+pub struct PinUnlockState {
+    password: String,
+    pin: String,
+    confirm_pin: String,
+    wipe_after: String,
+    error: Option<String>,
+}
+
+impl PinUnlockState {
+    pub fn clear(&mut self) {
+        self.password = String::new();
+        self.pin = String::new();
+        self.confirm_pin = String::new();
+        self.wipe_after = String::new();
+        self.error = None;
+    }
+}
+
+pub fn update(app: &mut AppState, ui: &mut Ui) {
+    if app.keechain.is_none() {
+        app.set_stage(Stage::Start);
+    }
+
+    let is_pin_enabled: bool = app
+        .keechain
+        .as_ref()
+        .map(|k| k.is_pin_enabled())
+        .unwrap_or_default();
+
+    View::show(ui, |ui| {
+        Heading::new("PIN unlock").render(ui);
+
+        if is_pin_enabled {
+            disable(app, ui)
+        } else {
+            enable(app, ui)
+        }
+
+        ui.add_space(5.0);
+        if Button::new("Back").render(ui).clicked() {
+            app.layouts.pin_unlock.clear();
+            app.stage = Stage::Menu(Menu::Setting);
+        }
+    });
+}
+
+fn enable(app: &mut AppState, ui: &mut Ui) {
+    ui.label(
+        RichText::new(
+            "A PIN is much weaker than a password: anyone with a copy of the keychain file can try all the PINs. Use it only for lower-value keychains.",
+        )
+        .color(RED),
+    );
+
+    ui.add_space(10.0);
+
+    InputField::new("Current password")
+        .placeholder("Current password")
+        .is_password()
+        .render(ui, &mut app.layouts.pin_unlock.password);
+
+    ui.add_space(7.0);
+
+    PinPad::new(format!("New PIN ({MIN_PIN_LEN} to {MAX_PIN_LEN} digits)"))
+        .render(ui, &mut app.layouts.pin_unlock.pin);
+
+    ui.add_space(7.0);
+
+    PinPad::new("Confirm new PIN").render(ui, &mut app.layouts.pin_unlock.confirm_pin);
+
+    ui.add_space(7.0);
+
+    InputField::new("Wipe the keychain after failed attempts")
+        .placeholder("Never")
+        .render(ui, &mut app.layouts.pin_unlock.wipe_after);
+
+    ui.add_space(7.0);
+
+    if let Some(error) = &app.layouts.pin_unlock.error {
+        Error::new(error).render(ui);
+    }
+
+    ui.add_space(15.0);
+
+    let is_ready: bool = !app.layouts.pin_unlock.password.is_empty()
+        && !app.layouts.pin_unlock.pin.is_empty()
+        && !app.layouts.pin_unlock.confirm_pin.is_empty();
+
+    let button = Button::new("Enable PIN")
+        .background_color(ORANGE)
+        .enabled(is_ready)
+        .render(ui);
+
+    if is_ready && (ui.input(|i| i.key_pressed(Key::Enter)) || button.clicked()) {
+        let wipe_after: Option<u32> = match app.layouts.pin_unlock.wipe_after.trim() {
+            "" => None,
+            value => match value.parse() {
+                Ok(wipe_after) => Some(wipe_after),
+                Err(_) => {
+                    app.layouts.pin_unlock.error = Some("Invalid number of attempts".to_string());
+                    return;
+                }
+            },
+        };
+
+        match app.keechain.as_mut() {
+            Some(keechain) => match keechain.enable_pin(
+                || Ok(app.layouts.pin_unlock.password.clone()),
+                || Ok(app.layouts.pin_unlock.pin.clone()),
+                || Ok(app.layouts.pin_unlock.confirm_pin.clone()),
+                wipe_after,
+                &SECP256K1,
+            ) {
+                Ok(_) => {
+                    app.layouts.pin_unlock.clear();
+                    app.stage = Stage::Menu(Menu::Setting);
+                }
+                Err(e) => app.layouts.pin_unlock.error = Some(e.to_string()),
+            },
+            None => app.layouts.pin_unlock.error = Some("Impossible to get keechain".to_string()),
+        }
+    }
+}
+
+fn disable(app: &mut AppState, ui: &mut Ui) {
+    if let Some(wipe_after) = app.keechain.as_ref().and_then(|k| k.pin()?.wipe_after) {
+        ui.label(format!(
+            "The keychain is wiped after {wipe_after} failed attempts"
+        ));
+        ui.add_space(10.0);
+    }
+
+    PinPad::new("Current PIN").render(ui, &mut app.layouts.pin_unlock.pin);
+
+    ui.add_space(7.0);
+
+    InputField::new("New password")
+        .placeholder("New password")
+        .is_password()
+        .render(ui, &mut app.layouts.pin_unlock.password);
+
+    ui.add_space(7.0);
+
+    InputField::new("Confirm new password")
+        .placeholder("Confirm new password")
+        .is_password()
+        .render(ui, &mut app.layouts.pin_unlock.confirm_pin);
+
+    ui.add_space(7.0);
+
+    if let Some(error) = &app.layouts.pin_unlock.error {
+        Error::new(error).render(ui);
+    }
+
+    ui.add_space(15.0);
+
+    let is_ready: bool = !app.layouts.pin_unlock.pin.is_empty()
+        && !app.layouts.pin_unlock.password.is_empty()
+        && !app.layouts.pin_unlock.confirm_pin.is_empty();
+
+    let button = Button::new("Disable PIN")
+        .background_color(ORANGE)
+        .enabled(is_ready)
+        .render(ui);
+
+    if is_ready && (ui.input(|i| i.key_pressed(Key::Enter)) || button.clicked()) {
+        match app.keechain.as_mut() {
+            Some(keechain) => match keechain.disable_pin(
+                || Ok(app.layouts.pin_unlock.pin.clone()),
+                || Ok(app.layouts.pin_unlock.password.clone()),
+                || Ok(app.layouts.pin_unlock.confirm_pin.clone()),
+                &SECP256K1,
+            ) {
+                Ok(_) => {
+                    app.layouts.pin_unlock.clear();
+                    app.stage = Stage::Menu(Menu::Setting);
+                }
+                Err(e) => app.layouts.pin_unlock.error = Some(e.to_string()),
+            },
+            None => app.layouts.pin_unlock.error = Some("Impossible to get keechain".to_string()),
+        }
+    }
+}
@@ -8,6 +8,7 @@ use eframe::egui::{RichText, Ui};
 use keechain_core::bdk::miniscript::Descriptor;
 use keechain_core::bitcoin::psbt::PartiallySignedTransaction;
 use keechain_core::bitcoin::Network;
+use keechain_core::psbt::DEFAULT_MAX_INPUTS;
 use keechain_core::util::dir;
 use keechain_core::{KeeChain, PsbtUtility, Seed};
 use rfd::FileDialog;
@@ -29,6 +30,7 @@ where
     let seed: Seed = keechain.keychain(password.clone())?.seed();
     let psbt_file = path.as_ref();
     let mut psbt: PartiallySignedTransaction = PartiallySignedTransaction::from_file(psbt_file)?;
+    psbt.ensure_max_inputs(DEFAULT_MAX_INPUTS)?;
     let finalized: bool = if descriptor.is_empty() {
         psbt.sign_with_seed(&seed, network, &SECP256K1)?
     } else {
@@ -3,12 +3,20 @@
 
 use std::path::Path;
 use std::sync::Arc;
+#[cfg(feature = "pin")]
+use std::time::Duration;
 
 use eframe::egui::{self, Align, ComboBox, Key, Layout, Ui};
 use egui_extras::RetainedImage;
 use keechain_core::types::KeeChain;
+#[cfg(feature = "pin")]
+use keechain_core::types::PinState;
 use keechain_core::util::dir;
+#[cfg(feature = "pin")]
+use keechain_core::util::time;
 
+#[cfg(feature = "pin")]
+use crate::component::PinPad;
 use crate::component::{Button, Error, InputField, View};
 use crate::theme::color::ORANGE;
 use crate::{AppState, Menu, Stage, KEYCHAINS_PATH, SECP256K1};
@@ -20,6 +28,9 @@ pub struct StartState {
     password: String,
     error: Option<String>,
     logo: Arc<RetainedImage>,
+    /// PIN state of the selected keychain (the name is the one it was loaded for)
+    #[cfg(feature = "pin")]
+    pin: Option<(String, Option<PinState>)>,
 }
 
 impl Default for StartState {
@@ -31,6 +42,8 @@ impl Default for StartState {
             logo: Arc::new(
                 RetainedImage::from_image_bytes("logo.png", LOGO).expect("Impossible to load logo"),
             ),
+            #[cfg(feature = "pin")]
+            pin: None,
         }
     }
 }
@@ -40,6 +53,76 @@ impl StartState {
         self.name = String::new();
         self.password = String::new();
         self.error = None;
+        #[cfg(feature = "pin")]
+        {
+            self.pin = None;
+        }
+    }
+
+    /// PIN state of the selected keychain (read from the file header when the selection changes)
+    #[cfg(feature = "pin")]
+    fn pin_state(&mut self) -> Option<PinState> {
+        match &self.pin {
+            Some((name, pin)) if name == &self.name => *pin,
+            _ => {
+                let pin: Option<PinState> = if self.name.is_empty() {
+                    None
+                } else {
+                    KeeChain::pin_state(KEYCHAINS_PATH.as_path(), &self.name)
+                        .ok()
+                        .flatten()
+                };
+                self.password = String::new();
+                self.pin = Some((self.name.clone(), pin));
+                pin
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "pin"))]
+fn unlock_field(app: &mut AppState, ui: &mut Ui) -> bool {
+    InputField::new("Password")
+        .placeholder("Password")
+        .is_password()
+        .render(ui, &mut app.layouts.start.password);
+    true
+}
+
+/// PIN pad if the keychain is unlocked with a PIN, otherwise the password field
+///
+/// Return `false` if the keychain is locked after too many failed PIN attempts.
+#[cfg(feature = "pin")]
+fn unlock_field(app: &mut AppState, ui: &mut Ui) -> bool {
+    match app.layouts.start.pin_state() {
+        Some(pin) => {
+            let retry_after: u64 = pin.retry_after(time::timestamp());
+            PinPad::new("PIN")
+                .enabled(retry_after == 0)
+                .keyboard()
+                .render(ui, &mut app.layouts.start.password);
+            if retry_after > 0 {
+                Error::new(format!(
+                    "Too many failed attempts: retry in {retry_after} seconds"
+                ))
+                .render(ui);
+                ui.ctx().request_repaint_after(Duration::from_secs(1));
+                return false;
+            }
+            if let Some(attempts_left) = pin.attempts_left() {
+                ui.label(format!(
+                    "{attempts_left} attempts left before the keychain is wiped"
+                ));
+            }
+            true
+        }
+        None => {
+            InputField::new("Password")
+                .placeholder("Password")
+                .is_password()
+                .render(ui, &mut app.layouts.start.password);
+            true
+        }
     }
 }
 
@@ -82,10 +165,7 @@ pub fn update(app: &mut AppState, ui: &mut Ui) {
 
         ui.add_space(7.0);
 
-        InputField::new("Password")
-            .placeholder("Password")
-            .is_password()
-            .render(ui, &mut app.layouts.start.password);
+        let unlockable: bool = unlock_field(app, ui);
 
         ui.add_space(7.0);
 
@@ -95,8 +175,9 @@ pub fn update(app: &mut AppState, ui: &mut Ui) {
 
         ui.add_space(15.0);
 
-        let is_ready: bool =
-            !app.layouts.start.name.is_empty() && !app.layouts.start.password.is_empty();
+        let is_ready: bool = unlockable
+            && !app.layouts.start.name.is_empty()
+            && !app.layouts.start.password.is_empty();
         let button = Button::new("Open")
             .background_color(ORANGE)
             .enabled(is_ready)
@@ -131,7 +212,14 @@ pub fn update(app: &mut AppState, ui: &mut Ui) {
                     app.set_keechain(Some(keechain));
                     app.set_stage(Stage::Menu(Menu::Main));
                 }
-                Err(e) => app.layouts.start.error = Some(e.to_string()),
+                Err(e) => {
+                    // Failed PIN attempts are counted in the file header
+                    #[cfg(feature = "pin")]
+                    {
+                        app.layouts.start.pin = None;
+                    }
+                    app.layouts.start.error = Some(e.to_string());
+                }
             }
         }
     });
@@ -11,6 +11,7 @@ use eframe::{App, Frame, NativeOptions, Theme};
 use egui::TextStyle::{Body, Button, Heading, Monospace, Small};
 use keechain_core::bitcoin::secp256k1::{rand, All, Secp256k1};
 use keechain_core::bitcoin::Network;
+use keechain_core::types::keechain::DEMO_PASSWORD;
 use keechain_core::types::KeeChain;
 use keechain_core::Result;
 use once_cell::sync::Lazy;
@@ -19,10 +20,13 @@ mod component;
 mod layout;
 mod theme;
 
+use self::component::NetworkBanner;
+#[cfg(feature = "pin")]
+use self::layout::PinUnlockState;
 use self::layout::{
-    ChangePasswordState, DeterministicEntropyState, ExportElectrumState, NewKeychainState,
-    PassphraseState, RenameKeychainState, RestoreState, SignState, StartState, ViewSecretsState,
-    WipeKeychainState,
+    ChangePasswordState, DeterministicEntropyState, ExportElectrumState, ExportSparrowState,
+    NewKeychainState, PassphraseState, RenameKeychainState, RestoreState, SignState, StartState,
+    VerifyAddressState, ViewSecretsState, WipeKeychainState,
 };
 
 const MIN_WINDOWS_SIZE: Vec2 = egui::vec2(350.0, 530.0);
@@ -37,18 +41,26 @@ static SECP256K1: Lazy<Secp256k1<All>> = Lazy::new(|| {
 static KEYCHAINS_PATH: Lazy<PathBuf> =
     Lazy::new(|| keechain_common::keychains().expect("Can't get keychains path"));
 
-fn parse_network(args: Vec<String>) -> Result<Network> {
-    for (i, arg) in args.iter().enumerate() {
-        if arg.contains("--") {
-            let network = Network::from_str(args[i].trim_start_matches("--"))?;
-            return Ok(network);
+const DEMO_ARG: &str = "--demo";
+
+fn parse_network(args: &[String]) -> Result<Option<Network>> {
+    for arg in args.iter() {
+        if arg.contains("--") && arg != DEMO_ARG {
+            let network = Network::from_str(arg.trim_start_matches("--"))?;
+            return Ok(Some(network));
         }
     }
-    Ok(Network::Bitcoin)
+    Ok(None)
 }
 
 pub fn main() -> Result<()> {
-    let network: Network = parse_network(std::env::args().collect())?;
+    let args: Vec<String> = std::env::args().collect();
+    let demo: bool = args.iter().any(|arg| arg == DEMO_ARG);
+    let network: Network = match parse_network(&args)? {
+        Some(network) => network,
+        None if demo => Network::Testnet,
+        None => Network::Bitcoin,
+    };
     let options = NativeOptions {
         fullscreen: false,
         resizable: true,
@@ -60,13 +72,22 @@ pub fn main() -> Result<()> {
         drag_and_drop_support: false,
         ..Default::default()
     };
-    let app = AppState::new(&network);
+    let mut app = AppState::new(&network);
+    if demo {
+        app.set_keechain(Some(KeeChain::demo(network, &SECP256K1)?));
+        app.set_stage(Stage::Menu(Menu::Main));
+    }
     let app_name = format!(
-        "KeeChain{}",
+        "KeeChain{}{}",
         if network.ne(&Network::Bitcoin) {
             format!(" [{network}]")
         } else {
             String::new()
+        },
+        if demo {
+            format!(" [demo, password: {DEMO_PASSWORD}]")
+        } else {
+            String::new()
         }
     );
     Ok(eframe::run_native(
@@ -81,6 +102,7 @@ pub enum ExportTypes {
     Descriptors,
     BitcoinCore,
     Electrum,
+    Sparrow,
 }
 
 pub enum Command {
@@ -89,9 +111,12 @@ pub enum Command {
     Export(ExportTypes),
     RenameKeychain,
     ChangePassword,
+    #[cfg(feature = "pin")]
+    PinUnlock,
     ViewSecrets,
     WipeKeychain,
     DeterministicEntropy,
+    VerifyAddress,
 }
 
 #[derive(Clone)]
@@ -126,10 +151,14 @@ pub struct AppLayoutStates {
     passphrase: PassphraseState,
     rename_keychain: RenameKeychainState,
     change_password: ChangePasswordState,
+    #[cfg(feature = "pin")]
+    pin_unlock: PinUnlockState,
     view_secrets: ViewSecretsState,
     wipe_keychain: WipeKeychainState,
     deterministic_entropy: DeterministicEntropyState,
+    verify_address: VerifyAddressState,
     export_electrum: ExportElectrumState,
+    export_sparrow: ExportSparrowState,
 }
 
 pub struct AppState {
@@ -171,6 +200,8 @@ impl App for AppState {
         .into();
         ctx.set_style(style);
 
+        NetworkBanner::new(self.network).show(ctx);
+
         CentralPanel::default().show(ctx, |ui| match &self.stage {
             Stage::Start => layout::start::update(self, ui),
             Stage::NewKeychain => layout::new_keychain::update(self, ui),
@@ -184,11 +215,14 @@ impl App for AppState {
                 }
                 Command::RenameKeychain => layout::setting::rename::update(self, ui),
                 Command::ChangePassword => layout::setting::change_password::update(self, ui),
+                #[cfg(feature = "pin")]
+                Command::PinUnlock => layout::setting::pin::update(self, ui),
                 Command::ViewSecrets => layout::advanced::danger::view_secrets::update(self, ui),
                 Command::WipeKeychain => layout::advanced::danger::wipe::update(self, ui),
                 Command::DeterministicEntropy => {
                     layout::advanced::deterministic_entropy::update(self, ui)
                 }
+                Command::VerifyAddress => layout::advanced::verify_address::update(self, ui),
             },
         });
     }
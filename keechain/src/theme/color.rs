@@ -2,10 +2,22 @@
 // Distributed under the MIT software license
 
 use eframe::epaint::Color32;
+use keechain_core::bitcoin::Network;
 
 pub const DARK_GRAY: Color32 = Color32::DARK_GRAY;
 pub const DARK_GREEN: Color32 = Color32::DARK_GREEN;
 pub const DARK_RED: Color32 = Color32::DARK_RED;
 pub const ORANGE: Color32 = Color32::from_rgb(222, 113, 0);
+pub const PURPLE: Color32 = Color32::from_rgb(120, 60, 170);
 pub const RED: Color32 = Color32::RED;
 pub const WHITE: Color32 = Color32::WHITE;
+
+/// Color of the network banner (green for mainnet)
+pub fn network(network: Network) -> Color32 {
+    match network {
+        Network::Bitcoin => DARK_GREEN,
+        Network::Testnet => ORANGE,
+        Network::Signet => PURPLE,
+        _ => DARK_GRAY,
+    }
+}